@@ -3,15 +3,16 @@ use openkrab::commands::{
     bridge_command, channels_add_command, channels_list_command, channels_logs_command,
     channels_remove_command, channels_status_command, config_edit_command, config_get_command,
     config_set_command, config_show_command, configure_command_interactive, cron_add_command,
-    cron_list_command, daemon_command, devices_command, directory_command, discord_send_command,
+    cron_disable_command, cron_enable_command, cron_list_command, cron_remove_command,
+    cron_runs_command, daemon_command, devices_command, directory_command, discord_send_command,
     discord_send_dry_run_command, dns_command, docs_command, doctor_simple, exec_approvals_command,
     hooks_command, is_remote_environment, login_github_copilot, login_minimax_oauth,
     login_openai_codex_oauth_interactive, login_qwen_oauth, logs_tail_command,
-    memory_search_command, memory_sync_command, mission_control_command, models_auth_add_command,
-    models_auth_get_command, models_auth_list_command, models_auth_remove_command,
-    models_list_command, nodes_command, onboard_quick, onboard_wizard, pairing_approve_command,
-    pairing_generate_command, pairing_list_command, run_interactive_shell, sandbox_command,
-    send_whatsapp_media, send_whatsapp_message, skills_command, slack_send_command,
+    memory_search_command, memory_stats_command, memory_sync_command, mission_control_command,
+    models_auth_add_command, models_auth_get_command, models_auth_list_command,
+    models_auth_remove_command, models_list_command, nodes_command, onboard_quick, onboard_wizard,
+    pairing_approve_command, pairing_generate_command, pairing_list_command, run_interactive_shell,
+    sandbox_command, send_whatsapp_media, send_whatsapp_message, skills_command, slack_send_command,
     slack_send_dry_run_command, status_simple, system_command, telegram_send_command,
     telegram_send_dry_run_command, update_command, webhooks_command,
 };
@@ -99,6 +100,19 @@ enum CliCommand {
         #[command(subcommand)]
         sub: MemorySub,
     },
+    /// Token usage and cost report
+    Usage {
+        /// Group by session, agent, provider or model
+        #[arg(long, default_value = "provider")]
+        by: String,
+        /// Only include the last N days
+        #[arg(long)]
+        days: Option<u32>,
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        #[arg(long)]
+        db: Option<String>,
+    },
     Gateway {
         #[command(subcommand)]
         sub: GatewaySub,
@@ -236,6 +250,16 @@ enum MemorySub {
         query: String,
         #[arg(long)]
         db: Option<String>,
+        /// Only search these sources (memory, session or a collection name)
+        #[arg(long = "collection")]
+        collections: Vec<String>,
+    },
+    /// Chunk counts, embedding cache hit rate and database size
+    Stats {
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        #[arg(long)]
+        db: Option<String>,
     },
 }
 
@@ -245,6 +269,26 @@ enum GatewaySub {
         #[arg(long)]
         db: Option<String>,
     },
+    /// Scoped API tokens for the gateway
+    Token {
+        #[command(subcommand)]
+        sub: GatewayTokenSub,
+    },
+}
+
+#[derive(Subcommand)]
+enum GatewayTokenSub {
+    Issue {
+        #[arg(long)]
+        name: String,
+        /// status, chat or admin
+        #[arg(long, default_value = "chat")]
+        scope: String,
+    },
+    List,
+    Revoke {
+        id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -313,10 +357,36 @@ enum ConfigSub {
 #[derive(Subcommand)]
 enum CronSub {
     List,
-    Add { schedule: String, command: String },
-    Remove { id: String },
-    Enable { id: String },
-    Disable { id: String },
+    /// Schedule a task: a cron expression, `every 30m`, or an RFC 3339 time
+    Add {
+        schedule: String,
+        command: String,
+        /// IANA time zone for the cron expression
+        #[arg(long)]
+        tz: Option<String>,
+        /// Agent that runs the task (default agent if unset)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Send each run's reply to `channel:to` (e.g. telegram:12345)
+        #[arg(long)]
+        deliver: Option<String>,
+        /// Missed runs: skip, latest or all
+        #[arg(long)]
+        catch_up: Option<String>,
+    },
+    Remove {
+        id: String,
+    },
+    Enable {
+        id: String,
+    },
+    Disable {
+        id: String,
+    },
+    /// Show a job's run history
+    Runs {
+        id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -504,15 +574,39 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
             }
-            MemorySub::Search { query, db } => {
-                let out = memory_search_command(&query, db.as_deref()).await?;
+            MemorySub::Search {
+                query,
+                db,
+                collections,
+            } => {
+                let out = memory_search_command(&query, db.as_deref(), &collections).await?;
+                println!("{out}");
+            }
+            MemorySub::Stats { json, db } => {
+                let out = memory_stats_command(db.as_deref(), json)?;
                 println!("{out}");
             }
         },
+        CliCommand::Usage { by, days, json, db } => {
+            let out = openkrab::commands::usage_report_command(db.as_deref(), &by, days, json)?;
+            println!("{out}");
+        }
         CliCommand::Gateway { sub } => match sub {
             GatewaySub::Start { db } => {
                 openkrab::commands::gateway_start_command(db.as_deref()).await?;
             }
+            GatewaySub::Token { sub } => {
+                let out = match sub {
+                    GatewayTokenSub::Issue { name, scope } => {
+                        openkrab::commands::gateway_token_issue_command(&name, &scope)?
+                    }
+                    GatewayTokenSub::List => openkrab::commands::gateway_token_list_command()?,
+                    GatewayTokenSub::Revoke { id } => {
+                        openkrab::commands::gateway_token_revoke_command(&id)?
+                    }
+                };
+                println!("{out}");
+            }
         },
         CliCommand::Models { provider } => {
             let out = models_list_command(&provider)?;
@@ -609,12 +703,28 @@ async fn main() -> anyhow::Result<()> {
         },
         CliCommand::Cron { sub } => match sub {
             CronSub::List => println!("{}", cron_list_command()),
-            CronSub::Add { schedule, command } => {
-                println!("{}", cron_add_command(&schedule, &command))
-            }
-            CronSub::Remove { id } => println!("Removing cron job: {}", id),
-            CronSub::Enable { id } => println!("Enabling cron job: {}", id),
-            CronSub::Disable { id } => println!("Disabling cron job: {}", id),
+            CronSub::Add {
+                schedule,
+                command,
+                tz,
+                agent,
+                deliver,
+                catch_up,
+            } => println!(
+                "{}",
+                cron_add_command(openkrab::commands::CronAddOptions {
+                    schedule,
+                    command,
+                    timezone: tz,
+                    agent,
+                    deliver,
+                    catch_up,
+                })
+            ),
+            CronSub::Remove { id } => println!("{}", cron_remove_command(&id)),
+            CronSub::Enable { id } => println!("{}", cron_enable_command(&id)),
+            CronSub::Disable { id } => println!("{}", cron_disable_command(&id)),
+            CronSub::Runs { id } => println!("{}", cron_runs_command(&id)),
         },
         CliCommand::Pairing { sub } => match sub {
             PairingSub::List => println!("{}", pairing_list_command()),
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -49,6 +50,47 @@ pub enum ChatMessage {
 pub struct ChatCompletionResponse {
     pub message: ChatMessage,
     pub finish_reason: String,
+    /// Set when a fallback model produced this reply instead of the primary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<crate::agents::fallback::FallbackNotice>,
+    /// Token counts reported by the provider, when it reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// Prompt/completion token counts for one model call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn new(input_tokens: u64, output_tokens: u64) -> Self {
+        Self {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// OpenAI-style `{"prompt_tokens", "completion_tokens"}` object.
+    pub fn from_openai(usage: &Value) -> Option<Self> {
+        let input = usage["prompt_tokens"].as_u64()?;
+        Some(Self::new(
+            input,
+            usage["completion_tokens"].as_u64().unwrap_or(0),
+        ))
+    }
+}
+
+/// Per-request options derived from the session (thinking budget, etc.).
+#[derive(Debug, Clone, Default)]
+pub struct ChatOptions {
+    pub thinking_level: Option<ThinkingLevel>,
 }
 
 #[async_trait]
@@ -65,14 +107,50 @@ pub trait ChatProvider: Send + Sync {
         tools: Option<&[ToolDefinition]>,
         handler: StreamHandler,
     ) -> Result<ChatCompletionResponse>;
+
+    /// Like `complete`, but with session-level options. Providers that have no
+    /// use for the options keep the default, which ignores them.
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        self.complete(messages, tools).await
+    }
+
+    /// Like `stream`, but with session-level options.
+    async fn stream_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        self.stream(messages, tools, handler).await
+    }
+
+    /// Model id requests are sent to, for catalog lookups (context window,
+    /// capabilities). `None` when the provider does not know.
+    fn model_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Provider id (`openai`, `anthropic`, ...) for usage accounting.
+    fn provider_name(&self) -> Option<String> {
+        None
+    }
 }
 
 use crate::agents::tool::{ToolCall, ToolDefinition};
+use crate::sessions::ThinkingLevel;
 
 pub struct OpenAiChatProvider {
     api_key: String,
     model: String,
     base_url: String,
+    provider_name: String,
+    stream_usage: bool,
 }
 
 impl OpenAiChatProvider {
@@ -81,12 +159,35 @@ impl OpenAiChatProvider {
             api_key,
             base_url: base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
             model: model.unwrap_or_else(|| "gpt-4o".to_string()),
+            provider_name: "openai".to_string(),
+            stream_usage: true,
         }
     }
+
+    /// Report a different provider id for OpenAI-compatible endpoints.
+    pub fn with_provider_name(mut self, name: impl Into<String>) -> Self {
+        self.provider_name = name.into();
+        self
+    }
+
+    /// Whether to ask for a final usage chunk on streams
+    /// (`stream_options.include_usage`). Some compatible servers reject it.
+    pub fn with_stream_usage(mut self, enabled: bool) -> Self {
+        self.stream_usage = enabled;
+        self
+    }
 }
 
 #[async_trait]
 impl ChatProvider for OpenAiChatProvider {
+    fn model_id(&self) -> Option<String> {
+        Some(self.model.clone())
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        Some(self.provider_name.clone())
+    }
+
     async fn complete(
         &self,
         messages: Vec<ChatMessage>,
@@ -174,6 +275,8 @@ impl ChatProvider for OpenAiChatProvider {
         Ok(ChatCompletionResponse {
             message,
             finish_reason,
+            fallback: None,
+            usage: TokenUsage::from_openai(&json["usage"]),
         })
     }
 
@@ -191,6 +294,9 @@ impl ChatProvider for OpenAiChatProvider {
             "messages": messages,
             "stream": true,
         });
+        if self.stream_usage {
+            body["stream_options"] = serde_json::json!({ "include_usage": true });
+        }
 
         if let Some(t) = tools {
             if !t.is_empty() {
@@ -225,6 +331,9 @@ impl ChatProvider for OpenAiChatProvider {
 
         let mut stream = res.bytes_stream();
         let mut buffer = String::new();
+        let mut tool_ids_by_index: HashMap<u64, String> = HashMap::new();
+        let mut finish_reason = "stop".to_string();
+        let mut usage = None;
 
         while let Some(item) = stream.next().await {
             let chunk = item?;
@@ -244,6 +353,9 @@ impl ChatProvider for OpenAiChatProvider {
 
                 if let Some(data) = line.strip_prefix("data: ") {
                     let json: Value = serde_json::from_str(data)?;
+                    if let Some(u) = TokenUsage::from_openai(&json["usage"]) {
+                        usage = Some(u);
+                    }
                     let choice = &json["choices"][0];
                     let delta = &choice["delta"];
 
@@ -257,41 +369,36 @@ impl ChatProvider for OpenAiChatProvider {
 
                     if let Some(tool_calls) = delta["tool_calls"].as_array() {
                         for call in tool_calls {
-                            let _index = call["index"].as_u64().unwrap_or(0);
-                            let id = call["id"].as_str();
+                            let index = call["index"].as_u64().unwrap_or(0);
                             let name = call["function"]["name"].as_str();
                             let args = call["function"]["arguments"].as_str();
 
-                            if let Some(id_str) = id {
-                                if let Some(name_str) = name {
-                                    handler.start_tool_call(id_str, name_str)?;
-                                }
+                            // The id is only sent on the first chunk of each call;
+                            // later argument chunks refer to it by index.
+                            if let (Some(id_str), Some(name_str)) = (call["id"].as_str(), name) {
+                                handler.start_tool_call(id_str, name_str)?;
+                                tool_ids_by_index.insert(index, id_str.to_string());
                             }
 
-                            if let Some(args_str) = args {
-                                // Need to track which tool call this corresponds to.
-                                // Simplified for now: assume index-based tracking or just use the last one.
-                                // In streaming.rs we use tool_call_id.
-                                if let Some(tc_id) = id.or_else(|| {
-                                    // Fallback: finding the ID from the accumulator might be needed if OpenAI doesn't repeat it
-                                    None
-                                }) {
+                            if let (Some(args_str), Some(tc_id)) =
+                                (args, tool_ids_by_index.get(&index))
+                            {
+                                if !args_str.is_empty() {
                                     handler.push_tool_arguments(tc_id, args_str)?;
-                                } else {
-                                    // If ID is missing in subsequent chunks (common in OpenAI SSE),
-                                    // we'd need to track it by index.
-                                    // For now, let's assume we can get it or use a simpler mapping.
                                 }
                             }
                         }
                     }
 
-                    if let Some(finish_reason) = choice["finish_reason"].as_str() {
-                        handler.finish(finish_reason, None)?;
+                    if let Some(reason) = choice["finish_reason"].as_str() {
+                        finish_reason = reason.to_string();
                     }
                 }
             }
         }
+        // Usage arrives in a trailing chunk after `finish_reason`, so the
+        // stream is only finished once the body is fully read.
+        handler.finish(&finish_reason, usage.map(|u| u.total_tokens() as usize))?;
 
         // Return the final accumulated message
         let acc = handler.accumulator.lock().unwrap();
@@ -321,7 +428,9 @@ impl ChatProvider for OpenAiChatProvider {
                 content,
                 tool_calls,
             },
-            finish_reason: "stop".to_string(), // Simplified, should get from acc
+            finish_reason,
+            fallback: None,
+            usage,
         })
     }
 }
@@ -4,8 +4,12 @@
 //! Provides methods to split, chunk, and summarize long transcripts so
 //! that they stay within a model's context window.
 
-use crate::agents::chat::{ChatMessage, ContentPart, UserContent};
+use crate::agents::chat::{ChatMessage, ChatProvider, ContentPart, UserContent};
 use crate::agents::session_repair::{repair_tool_use_result_pairing, strip_tool_result_details};
+use crate::sessions::Session;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 // ─── Constants ────────────────────────────────────────────────────────────────
 
@@ -29,14 +33,20 @@ pub fn estimate_tokens(msg: &ChatMessage) -> usize {
         ChatMessage::User { content } => {
             let len = match content {
                 UserContent::Text(t) => t.len(),
-                UserContent::Parts(p) => p.iter().map(|part| match part {
-                    ContentPart::Text { text } => text.len(),
-                    _ => 0,
-                }).sum(),
+                UserContent::Parts(p) => p
+                    .iter()
+                    .map(|part| match part {
+                        ContentPart::Text { text } => text.len(),
+                        _ => 0,
+                    })
+                    .sum(),
             };
             (len + 7) / 4
         }
-        ChatMessage::Assistant { content, tool_calls } => {
+        ChatMessage::Assistant {
+            content,
+            tool_calls,
+        } => {
             let mut len = content.as_ref().map(|c| c.len()).unwrap_or(0);
             if let Some(calls) = tool_calls {
                 for call in calls {
@@ -262,17 +272,23 @@ pub fn format_messages_as_summary(messages: &[ChatMessage]) -> String {
             ChatMessage::User { content } => {
                 let text = match content {
                     UserContent::Text(t) => t.clone(),
-                    UserContent::Parts(p) => p.iter().filter_map(|part| match part {
-                        ContentPart::Text { text } => Some(text.clone()),
-                        _ => None,
-                    }).collect::<Vec<_>>().join(" "),
+                    UserContent::Parts(p) => p
+                        .iter()
+                        .filter_map(|part| match part {
+                            ContentPart::Text { text } => Some(text.clone()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" "),
                 };
                 ("USER", text)
             }
-            ChatMessage::Assistant { content, .. } => ("ASSISTANT", content.clone().unwrap_or_default()),
+            ChatMessage::Assistant { content, .. } => {
+                ("ASSISTANT", content.clone().unwrap_or_default())
+            }
             ChatMessage::Tool { content, .. } => ("TOOL", content.clone()),
         };
-        
+
         let display_content = if content.len() > 500 {
             format!("{}…", &content[..500])
         } else {
@@ -298,7 +314,14 @@ pub fn compact_transcript(
 
     // Build summary of dropped messages
     let summary = format_messages_as_summary(&prune.dropped_messages);
+    with_summary_prefix(summary, prune, custom_instructions)
+}
 
+fn with_summary_prefix(
+    summary: String,
+    prune: PruneResult,
+    custom_instructions: Option<&str>,
+) -> Vec<ChatMessage> {
     let mut instructions = String::from("Previous conversation summary:\n\n");
     instructions.push_str(&summary);
 
@@ -312,14 +335,251 @@ pub fn compact_transcript(
         prune.dropped_count, prune.dropped_tokens
     ));
 
-    let mut result = vec![ChatMessage::System { content: instructions }];
+    let mut result = vec![ChatMessage::System {
+        content: instructions,
+    }];
     result.extend(prune.messages);
     result
 }
 
-/// Resolve context window token count with fallback.
-pub fn resolve_context_window_tokens(context_window: Option<usize>) -> usize {
-    context_window.unwrap_or(128_000).max(1)
+// ─── LLM summarization ────────────────────────────────────────────────────────
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You condense chat history for an assistant that will continue the conversation. \
+Write a concise summary that keeps decisions, facts the user stated, open tasks, tool results that still matter \
+and commitments made. Do not add commentary or invent details.";
+
+/// Session metadata key holding the cached compaction summary.
+pub const SUMMARY_CACHE_META_KEY: &str = "compaction_summary_cache";
+
+/// A summary of the oldest part of a transcript, keyed by per-message
+/// fingerprints so later turns can extend it instead of starting over.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SummaryCache {
+    pub fingerprints: Vec<String>,
+    pub summary: String,
+}
+
+impl SummaryCache {
+    pub fn load(session: &Session) -> Option<Self> {
+        serde_json::from_str(session.get_meta(SUMMARY_CACHE_META_KEY)?).ok()
+    }
+
+    pub fn store(&self, session: &mut Session) {
+        if let Ok(json) = serde_json::to_string(self) {
+            session.set_meta(SUMMARY_CACHE_META_KEY, json);
+        }
+    }
+
+    /// How many leading `fingerprints` this summary already covers. The cached
+    /// run may start before `fingerprints` does when old messages have since
+    /// been trimmed from the transcript; the summary still covers them.
+    pub fn covered_prefix(&self, fingerprints: &[String]) -> usize {
+        let Some(first) = fingerprints.first() else {
+            return 0;
+        };
+        self.fingerprints
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| *f == first)
+            .map(|(start, _)| &self.fingerprints[start..])
+            .find(|tail| tail.len() <= fingerprints.len() && fingerprints[..tail.len()] == **tail)
+            .map(|tail| tail.len())
+            .unwrap_or(0)
+    }
+}
+
+/// Stable short hash of a message, used as the summary cache key.
+pub fn message_fingerprint(msg: &ChatMessage) -> String {
+    let json = serde_json::to_string(msg).unwrap_or_default();
+    let digest = Sha256::digest(json.as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Render messages as a plain transcript for the summarizer. Messages too
+/// large to summarize safely are replaced by a placeholder.
+fn render_for_summary(messages: &[ChatMessage], context_window: usize) -> String {
+    let mut lines = Vec::with_capacity(messages.len());
+    for msg in messages {
+        if is_oversized_for_summary(msg, context_window) {
+            lines.push(format!(
+                "[omitted: oversized message, ~{} tokens]",
+                estimate_tokens(msg)
+            ));
+            continue;
+        }
+        match msg {
+            ChatMessage::System { content } => lines.push(format!("SYSTEM: {}", content)),
+            ChatMessage::User { content } => {
+                let text = match content {
+                    UserContent::Text(t) => t.clone(),
+                    UserContent::Parts(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ContentPart::Text { text } => text.clone(),
+                            ContentPart::ImageUrl { .. } => "[image]".to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                };
+                lines.push(format!("USER: {}", text));
+            }
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                if let Some(text) = content.as_deref().filter(|t| !t.is_empty()) {
+                    lines.push(format!("ASSISTANT: {}", text));
+                }
+                for call in tool_calls.iter().flatten() {
+                    lines.push(format!(
+                        "ASSISTANT called {}({})",
+                        call.name,
+                        truncate_chars(&call.arguments, 500)
+                    ));
+                }
+            }
+            ChatMessage::Tool { content, .. } => {
+                lines.push(format!("TOOL RESULT: {}", truncate_chars(content, 2000)))
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Summarize `messages` with an LLM. Messages are split into chunks sized by
+/// `compute_adaptive_chunk_ratio`, and each chunk refines the running summary
+/// (seeded with `previous_summary` when extending a cached one).
+pub async fn summarize_with_provider(
+    provider: &dyn ChatProvider,
+    messages: &[ChatMessage],
+    context_window: usize,
+    previous_summary: Option<&str>,
+    custom_instructions: Option<&str>,
+) -> Result<String> {
+    let ratio = compute_adaptive_chunk_ratio(messages, context_window);
+    let max_chunk_tokens = ((context_window as f64 * ratio) as usize).max(1);
+    let mut summary = previous_summary.map(|s| s.to_string());
+
+    for chunk in chunk_messages_by_max_tokens(messages, max_chunk_tokens) {
+        let mut prompt = match &summary {
+            Some(prev) => format!(
+                "Summary so far:\n{}\n\nUpdate the summary with these later messages:\n\n",
+                prev
+            ),
+            None => "Summarize these messages:\n\n".to_string(),
+        };
+        prompt.push_str(&render_for_summary(&chunk, context_window));
+        if let Some(custom) = custom_instructions {
+            prompt.push_str("\n\nFocus: ");
+            prompt.push_str(custom);
+        }
+
+        let response = provider
+            .complete(
+                vec![
+                    ChatMessage::System {
+                        content: SUMMARY_SYSTEM_PROMPT.to_string(),
+                    },
+                    ChatMessage::User {
+                        content: UserContent::Text(prompt),
+                    },
+                ],
+                None,
+            )
+            .await?;
+        match response.message {
+            ChatMessage::Assistant {
+                content: Some(text),
+                ..
+            } if !text.trim().is_empty() => {
+                summary = Some(text.trim().to_string());
+            }
+            _ => bail!("Summarizer returned an empty summary"),
+        }
+    }
+
+    Ok(summary.unwrap_or_else(|| DEFAULT_SUMMARY_FALLBACK.to_string()))
+}
+
+/// Async variant of `compact_transcript` for a live session.
+///
+/// Dropped messages are summarized by `summarizer` and the result is cached in
+/// the session metadata, so a later turn only summarizes messages dropped since.
+/// Without a summarizer, or if it fails, the heuristic summary is used.
+pub async fn compact_session_history(
+    messages: &[ChatMessage],
+    max_context_tokens: usize,
+    summarizer: Option<&dyn ChatProvider>,
+    session: &mut Session,
+    custom_instructions: Option<&str>,
+) -> Vec<ChatMessage> {
+    let prune = prune_history_for_context_share(messages, max_context_tokens, None, None);
+    if prune.dropped_messages.is_empty() {
+        return prune.messages;
+    }
+
+    let summary = match summarizer {
+        Some(provider) => {
+            let fingerprints: Vec<String> = prune
+                .dropped_messages
+                .iter()
+                .map(message_fingerprint)
+                .collect();
+            let cache = SummaryCache::load(session).unwrap_or_default();
+            let covered = cache.covered_prefix(&fingerprints);
+
+            if covered > 0 && covered == fingerprints.len() {
+                Some(cache.summary)
+            } else {
+                let previous = (covered > 0).then_some(cache.summary.as_str());
+                match summarize_with_provider(
+                    provider,
+                    &prune.dropped_messages[covered..],
+                    max_context_tokens,
+                    previous,
+                    custom_instructions,
+                )
+                .await
+                {
+                    Ok(summary) => {
+                        SummaryCache {
+                            fingerprints,
+                            summary: summary.clone(),
+                        }
+                        .store(session);
+                        Some(summary)
+                    }
+                    Err(e) => {
+                        tracing::warn!("LLM compaction failed, using heuristic summary: {}", e);
+                        None
+                    }
+                }
+            }
+        }
+        None => None,
+    };
+
+    let summary = summary.unwrap_or_else(|| format_messages_as_summary(&prune.dropped_messages));
+    with_summary_prefix(summary, prune, custom_instructions)
+}
+
+/// Resolve context window token count: explicit value, then the model
+/// catalog entry for `model` (`provider/model` or bare id), then 128k.
+pub fn resolve_context_window_tokens(context_window: Option<usize>, model: Option<&str>) -> usize {
+    context_window
+        .or_else(|| {
+            let catalog = crate::agents::model_catalog::load_model_catalog();
+            crate::agents::model_catalog::find_model_by_ref(&catalog, model?)?.context_window
+        })
+        .unwrap_or(128_000)
+        .max(1)
 }
 
 // ─── Tests ────────────────────────────────────────────────────────────────────
@@ -330,15 +590,17 @@ mod tests {
 
     fn make_msgs(count: usize, content_len: usize) -> Vec<ChatMessage> {
         (0..count)
-            .map(|i| ChatMessage::User { 
-                content: UserContent::Text("x".repeat(content_len) + &format!(" msg{}", i))
+            .map(|i| ChatMessage::User {
+                content: UserContent::Text("x".repeat(content_len) + &format!(" msg{}", i)),
             })
             .collect()
     }
 
     #[test]
     fn estimate_tokens_basic() {
-        let msg = ChatMessage::User { content: UserContent::Text("hello world".into()) };
+        let msg = ChatMessage::User {
+            content: UserContent::Text("hello world".into()),
+        };
         let tokens = estimate_tokens(&msg);
         assert!(tokens > 0);
         assert!(tokens < 20);
@@ -384,7 +646,7 @@ mod tests {
 
     #[test]
     fn adaptive_ratio_large_messages() {
-        let msgs = make_msgs(5, 50000); 
+        let msgs = make_msgs(5, 50000);
         let ratio = compute_adaptive_chunk_ratio(&msgs, 128000);
         assert!(ratio < BASE_CHUNK_RATIO);
         assert!(ratio >= MIN_CHUNK_RATIO);
@@ -392,10 +654,14 @@ mod tests {
 
     #[test]
     fn oversized_check() {
-        let small = ChatMessage::User { content: UserContent::Text("hello".into()) };
+        let small = ChatMessage::User {
+            content: UserContent::Text("hello".into()),
+        };
         assert!(!is_oversized_for_summary(&small, 128000));
 
-        let huge = ChatMessage::User { content: UserContent::Text("x".repeat(300000)) };
+        let huge = ChatMessage::User {
+            content: UserContent::Text("x".repeat(300000)),
+        };
         assert!(is_oversized_for_summary(&huge, 128000));
     }
 
@@ -443,16 +709,141 @@ mod tests {
 
     #[test]
     fn format_summary_truncates_long() {
-        let msg = ChatMessage::User { content: UserContent::Text("x".repeat(1000)) };
+        let msg = ChatMessage::User {
+            content: UserContent::Text("x".repeat(1000)),
+        };
         let summary = format_messages_as_summary(&[msg]);
         assert!(summary.contains("…"));
     }
 
     #[test]
     fn resolve_context_default() {
-        assert_eq!(resolve_context_window_tokens(None), 128_000);
-        assert_eq!(resolve_context_window_tokens(Some(32000)), 32000);
-        assert_eq!(resolve_context_window_tokens(Some(0)), 1);
+        assert_eq!(resolve_context_window_tokens(None, None), 128_000);
+        assert_eq!(resolve_context_window_tokens(Some(32000), None), 32000);
+        assert_eq!(resolve_context_window_tokens(Some(0), None), 1);
+    }
+
+    #[test]
+    fn resolve_context_from_catalog() {
+        assert_eq!(resolve_context_window_tokens(None, Some("llama3.1")), 8192);
+        assert_eq!(
+            resolve_context_window_tokens(None, Some("anthropic/claude-sonnet-4-5")),
+            200_000
+        );
+        assert_eq!(
+            resolve_context_window_tokens(Some(4096), Some("gpt-4o")),
+            4096
+        );
+        assert_eq!(
+            resolve_context_window_tokens(None, Some("unknown-model")),
+            128_000
+        );
+    }
+
+    /// Counts calls and echoes how many messages each prompt covered.
+    struct CountingSummarizer {
+        calls: std::sync::atomic::AtomicUsize,
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl ChatProvider for CountingSummarizer {
+        async fn complete(
+            &self,
+            messages: Vec<ChatMessage>,
+            _tools: Option<&[crate::agents::tool::ToolDefinition]>,
+        ) -> Result<crate::agents::chat::ChatCompletionResponse> {
+            let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if self.fail {
+                bail!("summarizer offline");
+            }
+            let extends = matches!(
+                &messages[1],
+                ChatMessage::User { content: UserContent::Text(t) } if t.starts_with("Summary so far")
+            );
+            Ok(crate::agents::chat::ChatCompletionResponse {
+                message: ChatMessage::Assistant {
+                    content: Some(format!("summary #{} (extends: {})", n, extends)),
+                    tool_calls: None,
+                },
+                finish_reason: "stop".to_string(),
+                fallback: None,
+                usage: None,
+            })
+        }
+
+        async fn stream(
+            &self,
+            messages: Vec<ChatMessage>,
+            tools: Option<&[crate::agents::tool::ToolDefinition]>,
+            _handler: crate::agents::streaming::StreamHandler,
+        ) -> Result<crate::agents::chat::ChatCompletionResponse> {
+            self.complete(messages, tools).await
+        }
+    }
+
+    fn summarizer(fail: bool) -> CountingSummarizer {
+        CountingSummarizer {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            fail,
+        }
+    }
+
+    fn system_text(msg: &ChatMessage) -> &str {
+        match msg {
+            ChatMessage::System { content } => content,
+            _ => panic!("expected system summary"),
+        }
     }
-}
 
+    #[tokio::test]
+    async fn llm_summary_is_cached_and_extended() {
+        let provider = summarizer(false);
+        let mut session = Session::new("s");
+        let msgs = make_msgs(40, 400);
+
+        let first = compact_session_history(&msgs, 2000, Some(&provider), &mut session, None).await;
+        assert!(system_text(&first[0]).contains("summary #"));
+        let calls_after_first = provider.calls.load(std::sync::atomic::Ordering::SeqCst);
+        assert!(calls_after_first >= 1);
+
+        // Same history: served from the cache.
+        let again = compact_session_history(&msgs, 2000, Some(&provider), &mut session, None).await;
+        assert_eq!(system_text(&again[0]), system_text(&first[0]));
+        assert_eq!(
+            provider.calls.load(std::sync::atomic::Ordering::SeqCst),
+            calls_after_first
+        );
+
+        // More history: only the newly dropped messages are summarized.
+        let longer = make_msgs(60, 400);
+        let extended =
+            compact_session_history(&longer, 2000, Some(&provider), &mut session, None).await;
+        assert!(system_text(&extended[0]).contains("extends: true"));
+    }
+
+    #[tokio::test]
+    async fn failing_summarizer_falls_back_to_heuristic() {
+        let provider = summarizer(true);
+        let mut session = Session::new("s");
+        let msgs = make_msgs(40, 400);
+
+        let result =
+            compact_session_history(&msgs, 2000, Some(&provider), &mut session, None).await;
+        assert!(system_text(&result[0]).contains("Conversation Summary"));
+        assert!(SummaryCache::load(&session).is_none());
+    }
+
+    #[test]
+    fn cache_prefix_survives_trimmed_history() {
+        let fps: Vec<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let cache = SummaryCache {
+            fingerprints: fps[..3].to_vec(),
+            summary: "s".into(),
+        };
+        assert_eq!(cache.covered_prefix(&fps), 3);
+        // "a" was trimmed from the transcript since the cache was written.
+        assert_eq!(cache.covered_prefix(&fps[1..]), 2);
+        assert_eq!(cache.covered_prefix(&["x".to_string()]), 0);
+    }
+}
@@ -1,15 +1,67 @@
-use crate::agents::chat::{ChatMessage, ChatProvider};
+use crate::agents::chat::{ChatCompletionResponse, ChatMessage, ChatOptions, ChatProvider};
 use crate::agents::identity::AgentIdentity;
-use crate::agents::tool::{Tool, ToolDefinition};
-use crate::memory::MemoryManager;
+use crate::agents::tool::{Tool, ToolCall, ToolDefinition};
+use crate::agents::usage::{add_session_cost, format_usage_footer, TurnUsage, UsageRecord};
+use crate::memory::{MemoryBackend, MemoryManager};
+use crate::sessions::TranscriptEntry;
 use anyhow::Result;
+use futures::future::join_all;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Per-turn limits for the model/tool interaction loop.
+#[derive(Debug, Clone)]
+pub struct AgentLoopConfig {
+    /// Model calls allowed in one turn before giving up.
+    pub max_iterations: usize,
+    /// Wall-clock budget for the whole turn, tools included.
+    pub turn_timeout: Duration,
+    /// Tool calls from one assistant message that may run at once.
+    pub tool_concurrency: usize,
+}
+
+impl Default for AgentLoopConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 16,
+            turn_timeout: Duration::from_secs(300),
+            tool_concurrency: 4,
+        }
+    }
+}
+
+impl AgentLoopConfig {
+    /// Overlay `agents.defaults` settings on the built-in defaults.
+    pub fn from_agent_defaults(defaults: Option<&crate::OPENKRAB_CONFIG::AgentDefaults>) -> Self {
+        let mut config = Self::default();
+        if let Some(d) = defaults {
+            if let Some(n) = d.max_iterations {
+                config.max_iterations = n.max(1);
+            }
+            if let Some(secs) = d.turn_timeout_seconds {
+                config.turn_timeout = Duration::from_secs(secs.max(1));
+            }
+            if let Some(n) = d.tool_concurrency {
+                config.tool_concurrency = n.max(1);
+            }
+        }
+        config
+    }
+}
 
 pub struct Agent {
     pub identity: AgentIdentity,
     pub provider: Box<dyn ChatProvider>,
     pub memory: Option<Arc<MemoryManager>>,
     pub tools: Vec<Box<dyn Tool>>,
+    pub loop_config: AgentLoopConfig,
+    /// Cheaper model used to summarize compacted history; heuristic if unset.
+    pub summarizer: Option<Box<dyn ChatProvider>>,
+    /// Id this agent is routed under; scopes its memory searches.
+    pub agent_id: Option<String>,
+    /// Serves recall instead of `memory` when set (e.g. the QMD sidecar).
+    pub memory_backend: Option<Arc<dyn MemoryBackend>>,
 }
 
 impl std::fmt::Debug for Agent {
@@ -19,6 +71,13 @@ impl std::fmt::Debug for Agent {
             .field("provider", &"...")
             .field("memory", &self.memory.is_some())
             .field("tools_count", &self.tools.len())
+            .field("loop_config", &self.loop_config)
+            .field("summarizer", &self.summarizer.is_some())
+            .field("agent_id", &self.agent_id)
+            .field(
+                "memory_backend",
+                &self.memory_backend.as_ref().map(|b| b.id().to_string()),
+            )
             .finish()
     }
 }
@@ -35,12 +94,54 @@ impl Agent {
             provider,
             memory,
             tools,
+            loop_config: AgentLoopConfig::default(),
+            summarizer: None,
+            agent_id: None,
+            memory_backend: None,
         }
     }
 
+    pub fn with_agent_id(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    pub fn with_memory_backend(mut self, backend: Arc<dyn MemoryBackend>) -> Self {
+        self.memory_backend = Some(backend);
+        self
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Box<dyn ChatProvider>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    pub fn with_loop_config(mut self, loop_config: AgentLoopConfig) -> Self {
+        self.loop_config = loop_config;
+        self
+    }
+
+    /// Catalog entry of the provider's model, if the catalog knows it.
+    pub fn catalog_entry(&self) -> Option<crate::agents::model_catalog::ModelCatalogEntry> {
+        use crate::agents::model_catalog::{
+            find_model_by_ref, find_model_in_catalog, load_model_catalog,
+        };
+        let model = self.provider.model_id()?;
+        let catalog = load_model_catalog();
+        let provider = self.provider.provider_name().unwrap_or_default();
+        find_model_in_catalog(&catalog, &provider, &model)
+            .or_else(|| find_model_by_ref(&catalog, &model))
+    }
+
+    /// Whether the model accepts image input.
+    pub fn supports_vision(&self) -> bool {
+        self.catalog_entry()
+            .is_some_and(|entry| crate::agents::model_catalog::model_supports_vision(&entry))
+    }
+
     pub async fn answer(&self, query: &str) -> Result<String> {
         let mut session = crate::sessions::Session::new("manual-session");
-        session.append_transcript(crate::sessions::TranscriptEntry::user(query));
+        session.append_transcript(TranscriptEntry::user(query));
         self.answer_session(&mut session, None).await
     }
 
@@ -59,6 +160,15 @@ impl Agent {
 
         match &res {
             Ok(text) => {
+                if let Some(backend) = &self.memory_backend {
+                    if let Err(e) = backend
+                        .record_session(session, self.agent_id.as_deref())
+                        .await
+                    {
+                        tracing::warn!("Failed to record session in memory: {}", e);
+                    }
+                }
+
                 // Emit Complete Hook
                 let mut complete_payload = crate::hooks::HookPayload::new();
                 complete_payload.set("session_id", session.id.clone());
@@ -84,13 +194,26 @@ impl Agent {
         stream_handler: Option<crate::agents::streaming::StreamHandler>,
     ) -> Result<String> {
         // 1. Convert transcript to ChatMessages
+        //    Trimming can orphan tool results at the head of the transcript, so
+        //    re-pair tool calls with their results before sending.
         let history: Vec<ChatMessage> =
             session.transcript.iter().cloned().map(Into::into).collect();
+        let history =
+            crate::agents::session_repair::repair_tool_use_result_pairing(&history).messages;
 
         // 2. Apply Compaction to history
-        let context_window = crate::agents::compaction::resolve_context_window_tokens(None);
-        let compacted =
-            crate::agents::compaction::compact_transcript(&history, context_window, None);
+        let context_window = crate::agents::compaction::resolve_context_window_tokens(
+            None,
+            self.provider.model_id().as_deref(),
+        );
+        let compacted = crate::agents::compaction::compact_session_history(
+            &history,
+            context_window,
+            self.summarizer.as_deref(),
+            session,
+            None,
+        )
+        .await;
 
         let mut messages = compacted;
 
@@ -103,7 +226,7 @@ impl Agent {
         );
 
         // 4. RAG / Memory Context
-        if let Some(ref memory) = self.memory {
+        if self.memory.is_some() || self.memory_backend.is_some() {
             // Use the last user message as query for RAG
             let query = session
                 .transcript
@@ -114,7 +237,21 @@ impl Agent {
                 .unwrap_or("");
 
             if !query.is_empty() {
-                let results = memory.search_hybrid(query, Default::default()).await?;
+                let scope =
+                    crate::memory::ScopeContext::for_session(self.agent_id.as_deref(), session);
+                let opts = crate::memory::HybridSearchOptions {
+                    scope: Some(scope.clone()),
+                    ..Default::default()
+                };
+                // The configured backend serves recall; the builtin index otherwise
+                let (results, cite) = match (&self.memory_backend, &self.memory) {
+                    (Some(backend), _) => (
+                        backend.search(query, opts).await?,
+                        backend.citations().cites(Some(&scope)),
+                    ),
+                    (None, Some(memory)) => (memory.search_hybrid(query, opts).await?, false),
+                    (None, None) => (Vec::new(), false),
+                };
                 if !results.is_empty() {
                     let mut context =
                         "Use the following context to help answer the user's question:\n\n"
@@ -124,7 +261,7 @@ impl Agent {
                             "--- Document {} ({}) ---\n{}\n\n",
                             i + 1,
                             res.path,
-                            res.text
+                            crate::memory::backend::render_snippet(res, cite)
                         ));
                     }
                     messages.push(ChatMessage::System { content: context });
@@ -135,70 +272,504 @@ impl Agent {
         // 5. Interaction Loop
         let tool_definitions: Vec<ToolDefinition> =
             self.tools.iter().map(|t| t.definition()).collect();
+        let options = ChatOptions {
+            thinking_level: session.thinking_level,
+        };
 
-        loop {
-            let response = if let Some(ref handler) = stream_handler {
-                self.provider
-                    .stream(messages.clone(), Some(&tool_definitions), handler.clone())
-                    .await?
-            } else {
-                self.provider
-                    .complete(messages.clone(), Some(&tool_definitions))
-                    .await?
+        // Fallback notice is recorded (and the hook fired) at most once per turn.
+        let mut fallback_noted = false;
+        let mut turn_usage = TurnUsage::default();
+        let deadline = Instant::now() + self.loop_config.turn_timeout;
+
+        for _ in 0..self.loop_config.max_iterations {
+            let remaining = self.remaining_budget(deadline)?;
+            let request = async {
+                if let Some(ref handler) = stream_handler {
+                    // Providers read the reply back from the accumulator, so
+                    // the previous round's text and tool calls must go.
+                    handler.reset();
+                    self.provider
+                        .stream_with_options(
+                            messages.clone(),
+                            Some(&tool_definitions),
+                            handler.clone(),
+                            &options,
+                        )
+                        .await
+                } else {
+                    self.provider
+                        .complete_with_options(messages.clone(), Some(&tool_definitions), &options)
+                        .await
+                }
             };
+            let response = tokio::time::timeout(remaining, request)
+                .await
+                .map_err(|_| self.budget_exceeded())??;
+
+            match &response.fallback {
+                Some(notice) if !fallback_noted => {
+                    notice.apply_to_session(session);
+                    fallback_noted = true;
+                }
+                None if !fallback_noted => crate::agents::fallback::clear_fallback_notice(session),
+                _ => {}
+            }
+
+            self.account_usage(session, &response, &mut turn_usage);
 
             messages.push(response.message.clone());
+            session.append_transcript(
+                TranscriptEntry::from_message(&response.message).with_usage(response.usage),
+            );
 
-            match response.message {
-                ChatMessage::Assistant {
-                    ref tool_calls,
-                    ref content,
-                } => {
-                    if let Some(calls) = tool_calls {
-                        for call in calls {
-                            if let Some(ref handler) = stream_handler {
-                                handler.start_tool_call(&call.id, &call.name)?;
-                            }
-
-                            let tool = self
-                                .tools
-                                .iter()
-                                .find(|t| t.definition().name == call.name)
-                                .ok_or_else(|| anyhow::anyhow!("Tool not found: {}", call.name))?;
-
-                            let output = tool.call(&call.arguments).await?;
-
-                            if let Some(ref handler) = stream_handler {
-                                handler.tool_result(&call.id, &output, false)?;
-                            }
-
-                            messages.push(ChatMessage::Tool {
-                                tool_call_id: call.id.clone(),
-                                content: output,
-                            });
-                        }
-                    } else {
-                        // Final reply
-                        let final_text = content.clone().unwrap_or_default();
-                        session.append_transcript(crate::sessions::TranscriptEntry::assistant(
-                            &final_text,
-                        ));
-                        return Ok(final_text);
+            let ChatMessage::Assistant {
+                tool_calls,
+                content,
+            } = response.message
+            else {
+                return Err(anyhow::anyhow!("Unexpected response message type"));
+            };
+
+            match tool_calls {
+                Some(calls) if !calls.is_empty() => {
+                    let remaining = self.remaining_budget(deadline)?;
+                    let results = tokio::time::timeout(
+                        remaining,
+                        self.run_tool_calls(&calls, &stream_handler),
+                    )
+                    .await
+                    .map_err(|_| self.budget_exceeded())?;
+                    for result in results {
+                        session.append_transcript(TranscriptEntry::from_message(&result));
+                        messages.push(result);
+                    }
+                }
+                _ => {
+                    session.last_active = chrono::Utc::now();
+                    let mut reply = content.unwrap_or_default();
+                    let level = session.response_usage.unwrap_or_default();
+                    if let Some(footer) = format_usage_footer(level, &turn_usage, session) {
+                        reply.push_str("\n\n");
+                        reply.push_str(&footer);
                     }
+                    return Ok(reply);
                 }
-                _ => return Err(anyhow::anyhow!("Unexpected response message type")),
             }
+        }
 
-            if response.finish_reason == "stop" {
-                if let ChatMessage::Assistant { ref content, .. } = response.message {
-                    let final_text = content.clone().unwrap_or_default();
-                    session.append_transcript(crate::sessions::TranscriptEntry::assistant(
-                        &final_text,
-                    ));
-                    session.last_active = chrono::Utc::now();
-                    return Ok(final_text);
+        Err(anyhow::anyhow!(
+            "Agent stopped after {} iterations without a final reply",
+            self.loop_config.max_iterations
+        ))
+    }
+
+    /// Run one assistant turn's tool calls, at most `tool_concurrency` at a
+    /// time. Results come back in call order; failures become error content
+    /// for the model rather than aborting the turn.
+    async fn run_tool_calls(
+        &self,
+        calls: &[ToolCall],
+        stream_handler: &Option<crate::agents::streaming::StreamHandler>,
+    ) -> Vec<ChatMessage> {
+        let permits = Semaphore::new(self.loop_config.tool_concurrency.max(1));
+        join_all(
+            calls
+                .iter()
+                .map(|call| self.run_tool_call_limited(call, &permits, stream_handler)),
+        )
+        .await
+    }
+
+    async fn run_tool_call_limited(
+        &self,
+        call: &ToolCall,
+        permits: &Semaphore,
+        stream_handler: &Option<crate::agents::streaming::StreamHandler>,
+    ) -> ChatMessage {
+        let _permit = permits.acquire().await;
+        if let Some(ref handler) = stream_handler {
+            let _ = handler.start_tool_call(&call.id, &call.name);
+        }
+
+        let (output, is_error) = self.run_tool_call(call).await;
+
+        if let Some(ref handler) = stream_handler {
+            let _ = handler.tool_result(&call.id, &output, is_error);
+        }
+
+        ChatMessage::Tool {
+            tool_call_id: call.id.clone(),
+            content: output,
+        }
+    }
+
+    async fn run_tool_call(&self, call: &ToolCall) -> (String, bool) {
+        let Some(tool) = self.tools.iter().find(|t| t.definition().name == call.name) else {
+            let available: Vec<String> = self.tools.iter().map(|t| t.definition().name).collect();
+            return (
+                format!(
+                    "Error: unknown tool '{}'. Available tools: {}",
+                    call.name,
+                    available.join(", ")
+                ),
+                true,
+            );
+        };
+
+        match tool.call(&call.arguments).await {
+            Ok(output) => (output, false),
+            Err(e) => {
+                tracing::warn!("Tool {} failed: {}", call.name, e);
+                (format!("Error: {}", e), true)
+            }
+        }
+    }
+
+    /// Record a response's token usage on the session, in the turn total and,
+    /// when the agent has a memory store, in the persisted usage log.
+    fn account_usage(
+        &self,
+        session: &mut crate::sessions::Session,
+        response: &ChatCompletionResponse,
+        turn_usage: &mut TurnUsage,
+    ) {
+        let Some(usage) = response.usage else {
+            return;
+        };
+        session.record_usage(&usage);
+
+        // A fallback reply was produced by the fallback's model, not the primary.
+        let (provider, model) = match response
+            .fallback
+            .as_ref()
+            .and_then(|n| n.active_model.split_once('/'))
+        {
+            Some((provider, model)) => (provider.to_string(), model.to_string()),
+            None => (
+                self.provider
+                    .provider_name()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                self.provider
+                    .model_id()
+                    .unwrap_or_else(|| "unknown".to_string()),
+            ),
+        };
+        let record = UsageRecord::new(&session.id, &self.identity.name, provider, model, &usage);
+        if let Some(cost) = record.cost_usd {
+            add_session_cost(session, cost);
+        }
+        turn_usage.add(&record);
+
+        if let Some(ref memory) = self.memory {
+            if let Err(e) = memory.store.insert_usage_record(&record) {
+                tracing::warn!("Failed to persist usage record: {}", e);
+            }
+        }
+    }
+
+    fn remaining_budget(&self, deadline: Instant) -> Result<Duration> {
+        deadline
+            .checked_duration_since(Instant::now())
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| self.budget_exceeded())
+    }
+
+    fn budget_exceeded(&self) -> anyhow::Error {
+        anyhow::anyhow!(
+            "Agent turn exceeded its time budget of {}s",
+            self.loop_config.turn_timeout.as_secs()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Replays canned assistant messages and records what it was sent.
+    struct ScriptedProvider {
+        replies: Mutex<VecDeque<ChatMessage>>,
+        seen: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+    }
+
+    #[async_trait]
+    impl ChatProvider for ScriptedProvider {
+        async fn complete(
+            &self,
+            messages: Vec<ChatMessage>,
+            _tools: Option<&[ToolDefinition]>,
+        ) -> Result<ChatCompletionResponse> {
+            self.seen.lock().unwrap().push(messages);
+            let message = self
+                .replies
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| tool_turn(&[("again", "slow")]));
+            Ok(ChatCompletionResponse {
+                message,
+                finish_reason: "stop".to_string(),
+                fallback: None,
+                usage: Some(crate::agents::chat::TokenUsage::new(10, 2)),
+            })
+        }
+
+        async fn stream(
+            &self,
+            messages: Vec<ChatMessage>,
+            tools: Option<&[ToolDefinition]>,
+            handler: crate::agents::streaming::StreamHandler,
+        ) -> Result<ChatCompletionResponse> {
+            // Replay the reply through the handler and read it back, the way
+            // the OpenAI-compatible provider builds streamed replies.
+            let response = self.complete(messages, tools).await?;
+            if let ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } = &response.message
+            {
+                if let Some(text) = content {
+                    handler.push_text(text)?;
+                }
+                for call in tool_calls.iter().flatten() {
+                    handler.start_tool_call(&call.id, &call.name)?;
+                    handler.push_tool_arguments(&call.id, &call.arguments)?;
                 }
             }
+            let acc = handler.accumulator.lock().unwrap();
+            let tool_calls: Vec<ToolCall> = acc
+                .tool_calls
+                .iter()
+                .map(|tc| ToolCall {
+                    id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    arguments: tc.arguments.clone(),
+                })
+                .collect();
+            Ok(ChatCompletionResponse {
+                message: ChatMessage::Assistant {
+                    content: Some(acc.text().to_string()).filter(|t| !t.is_empty()),
+                    tool_calls: Some(tool_calls).filter(|calls| !calls.is_empty()),
+                },
+                ..response
+            })
+        }
+    }
+
+    struct SlowTool {
+        running: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "slow".to_string(),
+                description: "sleeps briefly".to_string(),
+                parameters: serde_json::json!({"type": "object"}),
+            }
+        }
+
+        async fn call(&self, _arguments: &str) -> Result<String> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok("done".to_string())
+        }
+    }
+
+    struct FailingTool;
+
+    #[async_trait]
+    impl Tool for FailingTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "broken".to_string(),
+                description: "always fails".to_string(),
+                parameters: serde_json::json!({"type": "object"}),
+            }
         }
+
+        async fn call(&self, _arguments: &str) -> Result<String> {
+            Err(anyhow::anyhow!("disk on fire"))
+        }
+    }
+
+    fn tool_turn(calls: &[(&str, &str)]) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: None,
+            tool_calls: Some(
+                calls
+                    .iter()
+                    .map(|(id, name)| ToolCall {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        arguments: "{}".to_string(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn final_turn(text: &str) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: Some(text.to_string()),
+            tool_calls: None,
+        }
+    }
+
+    struct Harness {
+        agent: Agent,
+        seen: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    fn harness(replies: Vec<ChatMessage>, loop_config: AgentLoopConfig) -> Harness {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let provider = ScriptedProvider {
+            replies: Mutex::new(replies.into()),
+            seen: seen.clone(),
+        };
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(SlowTool {
+                running: Arc::new(AtomicUsize::new(0)),
+                peak: peak.clone(),
+            }),
+            Box::new(FailingTool),
+        ];
+        let agent = Agent::new(AgentIdentity::default(), Box::new(provider), None, tools)
+            .with_loop_config(loop_config);
+        Harness { agent, seen, peak }
+    }
+
+    fn tool_messages(messages: &[ChatMessage]) -> Vec<(String, String)> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                ChatMessage::Tool {
+                    tool_call_id,
+                    content,
+                } => Some((tool_call_id.clone(), content.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn tool_calls_run_concurrently_up_to_limit() {
+        let h = harness(
+            vec![
+                tool_turn(&[("a", "slow"), ("b", "slow"), ("c", "slow"), ("d", "slow")]),
+                final_turn("all done"),
+            ],
+            AgentLoopConfig {
+                tool_concurrency: 2,
+                ..Default::default()
+            },
+        );
+
+        let reply = h.agent.answer("go").await.unwrap();
+
+        assert_eq!(reply, "all done");
+        assert_eq!(h.peak.load(Ordering::SeqCst), 2);
+        let seen = h.seen.lock().unwrap();
+        let ids: Vec<String> = tool_messages(&seen[1]).into_iter().map(|t| t.0).collect();
+        assert_eq!(ids, vec!["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn tool_failures_are_reported_to_the_model() {
+        let h = harness(
+            vec![
+                tool_turn(&[("x", "broken"), ("y", "missing")]),
+                final_turn("recovered"),
+            ],
+            AgentLoopConfig::default(),
+        );
+
+        let reply = h.agent.answer("go").await.unwrap();
+
+        assert_eq!(reply, "recovered");
+        let seen = h.seen.lock().unwrap();
+        let results = tool_messages(&seen[1]);
+        assert!(results[0].1.contains("disk on fire"));
+        assert!(results[1].1.contains("unknown tool 'missing'"));
+    }
+
+    #[tokio::test]
+    async fn usage_is_counted_and_shown_in_footer() {
+        let h = harness(
+            vec![tool_turn(&[("a", "slow")]), final_turn("done")],
+            AgentLoopConfig::default(),
+        );
+        let mut session = crate::sessions::Session::new("usage");
+        session.response_usage = Some(crate::sessions::UsageDisplayLevel::Tokens);
+        session.append_transcript(TranscriptEntry::user("go"));
+
+        let reply = h.agent.answer_session(&mut session, None).await.unwrap();
+
+        assert_eq!(reply, "done\n\nUsage: 20 in / 4 out tokens");
+        assert_eq!(session.input_tokens, 20);
+        assert_eq!(session.total_tokens, 24);
+        assert_eq!(session.context_tokens, 10);
+        // The footer is for the channel only; the transcript keeps the reply.
+        assert_eq!(session.transcript.last().unwrap().text, "done");
+    }
+
+    #[tokio::test]
+    async fn streamed_turns_start_each_model_call_afresh() {
+        let h = harness(
+            vec![tool_turn(&[("a", "slow")]), final_turn("done")],
+            AgentLoopConfig::default(),
+        );
+        let mut session = crate::sessions::Session::new("stream");
+        session.append_transcript(TranscriptEntry::user("go"));
+        let (handler, _rx) = crate::agents::streaming::create_stream_pair(2000);
+
+        let reply = h
+            .agent
+            .answer_session(&mut session, Some(handler))
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "done");
+        assert_eq!(h.seen.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_iterations() {
+        let h = harness(
+            Vec::new(),
+            AgentLoopConfig {
+                max_iterations: 3,
+                ..Default::default()
+            },
+        );
+
+        let err = h.agent.answer("go").await.unwrap_err();
+
+        assert!(err.to_string().contains("3 iterations"));
+        assert_eq!(h.seen.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_when_turn_budget_is_spent() {
+        let h = harness(
+            Vec::new(),
+            AgentLoopConfig {
+                turn_timeout: Duration::from_millis(120),
+                ..Default::default()
+            },
+        );
+
+        let err = h.agent.answer("go").await.unwrap_err();
+        assert!(err.to_string().contains("time budget"));
     }
 }
@@ -0,0 +1,553 @@
+//! fallback — Provider failover chain.
+//!
+//! `FallbackChatProvider` wraps an ordered list of chat providers and moves on
+//! to the next one when a request fails for a transient or capacity reason
+//! (rate limit, 5xx, timeout, context overflow). Auth failures are returned
+//! immediately: a bad key is a configuration problem, not something another
+//! model should paper over.
+
+use crate::agents::chat::{ChatCompletionResponse, ChatMessage, ChatOptions, ChatProvider};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::ToolDefinition;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// ─── Error classification ─────────────────────────────────────────────────────
+
+/// Why a provider request failed, as far as failover is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailoverReason {
+    RateLimit,
+    ServerError,
+    Timeout,
+    ContextOverflow,
+    Auth,
+    Other,
+}
+
+impl FailoverReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailoverReason::RateLimit => "rate_limit",
+            FailoverReason::ServerError => "server_error",
+            FailoverReason::Timeout => "timeout",
+            FailoverReason::ContextOverflow => "context_overflow",
+            FailoverReason::Auth => "auth",
+            FailoverReason::Other => "other",
+        }
+    }
+
+    /// Whether the next provider in the chain should be tried.
+    pub fn should_fallback(&self) -> bool {
+        matches!(
+            self,
+            FailoverReason::RateLimit
+                | FailoverReason::ServerError
+                | FailoverReason::Timeout
+                | FailoverReason::ContextOverflow
+        )
+    }
+}
+
+/// Matches the `StatusCode` display form ("429 Too Many Requests") that both
+/// provider error messages and `reqwest::Error` use.
+static STATUS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([45]\d\d) [A-Z]").unwrap());
+
+const CONTEXT_OVERFLOW_PATTERNS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "prompt is too long",
+    "too many tokens",
+    "input is too long",
+    "exceeds the context",
+];
+
+/// Classify a provider error for failover purposes.
+pub fn classify_provider_error(err: &anyhow::Error) -> FailoverReason {
+    if err.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+        return FailoverReason::Timeout;
+    }
+    if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+        if e.is_timeout() {
+            return FailoverReason::Timeout;
+        }
+        if let Some(status) = e.status() {
+            return classify_status(status.as_u16());
+        }
+    }
+
+    let text = format!("{:#}", err).to_lowercase();
+
+    if CONTEXT_OVERFLOW_PATTERNS.iter().any(|p| text.contains(p)) {
+        return FailoverReason::ContextOverflow;
+    }
+    if let Some(code) = STATUS_RE
+        .captures(&format!("{:#}", err))
+        .and_then(|c| c[1].parse::<u16>().ok())
+    {
+        let reason = classify_status(code);
+        if reason != FailoverReason::Other {
+            return reason;
+        }
+    }
+    if text.contains("invalid api key")
+        || text.contains("invalid x-api-key")
+        || text.contains("unauthorized")
+        || text.contains("authentication_error")
+    {
+        return FailoverReason::Auth;
+    }
+    if text.contains("rate limit") || text.contains("rate_limit") || text.contains("quota") {
+        return FailoverReason::RateLimit;
+    }
+    if text.contains("overloaded") || text.contains("service unavailable") {
+        return FailoverReason::ServerError;
+    }
+    if text.contains("timed out") || text.contains("timeout") {
+        return FailoverReason::Timeout;
+    }
+    FailoverReason::Other
+}
+
+fn classify_status(code: u16) -> FailoverReason {
+    match code {
+        401 | 403 => FailoverReason::Auth,
+        408 => FailoverReason::Timeout,
+        413 => FailoverReason::ContextOverflow,
+        429 => FailoverReason::RateLimit,
+        500..=599 => FailoverReason::ServerError,
+        _ => FailoverReason::Other,
+    }
+}
+
+// ─── Fallback notice ──────────────────────────────────────────────────────────
+
+/// Which model actually answered when the primary could not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackNotice {
+    pub selected_model: String,
+    pub active_model: String,
+    pub reason: FailoverReason,
+}
+
+impl FallbackNotice {
+    /// Short user-facing line, e.g. for channel footers.
+    pub fn describe(&self) -> String {
+        format!(
+            "answered by fallback model {} ({} unavailable: {})",
+            self.active_model,
+            self.selected_model,
+            self.reason.as_str()
+        )
+    }
+
+    /// Record the notice on the session and emit `model:fallback`.
+    pub fn apply_to_session(&self, session: &mut crate::sessions::Session) {
+        session.fallback_notice_selected_model = Some(self.selected_model.clone());
+        session.fallback_notice_active_model = Some(self.active_model.clone());
+        session.fallback_notice_reason = Some(self.reason.as_str().to_string());
+
+        let mut payload = crate::hooks::HookPayload::new();
+        payload.set("session_id", session.id.clone());
+        payload.set("selected_model", self.selected_model.clone());
+        payload.set("active_model", self.active_model.clone());
+        payload.set("reason", self.reason.as_str());
+        payload.set("notice", self.describe());
+        crate::hooks::emit(crate::hooks::events::MODEL_FALLBACK, &payload);
+    }
+}
+
+/// Clear a stale notice once the primary model answers again.
+pub fn clear_fallback_notice(session: &mut crate::sessions::Session) {
+    session.fallback_notice_selected_model = None;
+    session.fallback_notice_active_model = None;
+    session.fallback_notice_reason = None;
+}
+
+// ─── Fallback provider ────────────────────────────────────────────────────────
+
+struct FallbackEntry {
+    model: String,
+    provider: Box<dyn ChatProvider>,
+}
+
+/// Ordered chain of chat providers; the first entry is the primary.
+pub struct FallbackChatProvider {
+    entries: Vec<FallbackEntry>,
+}
+
+impl FallbackChatProvider {
+    pub fn new(primary_model: impl Into<String>, primary: Box<dyn ChatProvider>) -> Self {
+        Self {
+            entries: vec![FallbackEntry {
+                model: primary_model.into(),
+                provider: primary,
+            }],
+        }
+    }
+
+    /// Append a fallback provider, tried after all earlier entries.
+    pub fn with_fallback(
+        mut self,
+        model: impl Into<String>,
+        provider: Box<dyn ChatProvider>,
+    ) -> Self {
+        self.entries.push(FallbackEntry {
+            model: model.into(),
+            provider,
+        });
+        self
+    }
+
+    /// Model labels in failover order.
+    pub fn models(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.model.as_str()).collect()
+    }
+
+    fn primary_model(&self) -> &str {
+        &self.entries[0].model
+    }
+
+    /// Decide what to do with a failed attempt: return the error to the caller,
+    /// or remember the reason and continue with the next entry.
+    fn on_failure(
+        &self,
+        index: usize,
+        err: anyhow::Error,
+        first_reason: &mut Option<FailoverReason>,
+    ) -> Result<()> {
+        let reason = classify_provider_error(&err);
+        let entry = &self.entries[index];
+        if !reason.should_fallback() || index + 1 == self.entries.len() {
+            return Err(err);
+        }
+        tracing::warn!(
+            "Model {} failed ({}), falling back to {}: {}",
+            entry.model,
+            reason.as_str(),
+            self.entries[index + 1].model,
+            err
+        );
+        first_reason.get_or_insert(reason);
+        Ok(())
+    }
+
+    fn finish(
+        &self,
+        index: usize,
+        mut response: ChatCompletionResponse,
+        first_reason: Option<FailoverReason>,
+    ) -> ChatCompletionResponse {
+        if index > 0 {
+            response.fallback = Some(FallbackNotice {
+                selected_model: self.primary_model().to_string(),
+                active_model: self.entries[index].model.clone(),
+                reason: first_reason.unwrap_or(FailoverReason::Other),
+            });
+        }
+        response
+    }
+}
+
+#[async_trait]
+impl ChatProvider for FallbackChatProvider {
+    /// The primary's model: compaction budgets are sized for it.
+    fn model_id(&self) -> Option<String> {
+        self.entries[0].provider.model_id()
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        self.entries[0].provider.provider_name()
+    }
+
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<ChatCompletionResponse> {
+        self.complete_with_options(messages, tools, &ChatOptions::default())
+            .await
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> Result<ChatCompletionResponse> {
+        self.stream_with_options(messages, tools, handler, &ChatOptions::default())
+            .await
+    }
+
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let mut first_reason = None;
+        for (index, entry) in self.entries.iter().enumerate() {
+            match entry
+                .provider
+                .complete_with_options(messages.clone(), tools, options)
+                .await
+            {
+                Ok(response) => return Ok(self.finish(index, response, first_reason)),
+                Err(err) => self.on_failure(index, err, &mut first_reason)?,
+            }
+        }
+        Err(anyhow!("No chat providers configured"))
+    }
+
+    async fn stream_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let mut first_reason = None;
+        for (index, entry) in self.entries.iter().enumerate() {
+            // The handler may carry output from earlier rounds of the turn;
+            // only what this attempt emitted rules out failing over.
+            let mark = handler.output_mark();
+            match entry
+                .provider
+                .stream_with_options(messages.clone(), tools, handler.clone(), options)
+                .await
+            {
+                Ok(response) => return Ok(self.finish(index, response, first_reason)),
+                // Deltas already reached the client; another provider's
+                // answer would be appended to them rather than replace them.
+                Err(err) if handler.output_mark() != mark => return Err(err),
+                Err(err) => self.on_failure(index, err, &mut first_reason)?,
+            }
+        }
+        Err(anyhow!("No chat providers configured"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::chat::UserContent;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct ScriptedProvider {
+        reply: std::result::Result<&'static str, &'static str>,
+        /// Streamed before `reply` is returned.
+        partial: Option<&'static str>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ScriptedProvider {
+        fn boxed(
+            reply: std::result::Result<&'static str, &'static str>,
+        ) -> (Box<dyn ChatProvider>, Arc<AtomicUsize>) {
+            Self::streaming(None, reply)
+        }
+
+        fn streaming(
+            partial: Option<&'static str>,
+            reply: std::result::Result<&'static str, &'static str>,
+        ) -> (Box<dyn ChatProvider>, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            (
+                Box::new(Self {
+                    reply,
+                    partial,
+                    calls: calls.clone(),
+                }),
+                calls,
+            )
+        }
+    }
+
+    #[async_trait]
+    impl ChatProvider for ScriptedProvider {
+        async fn complete(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _tools: Option<&[ToolDefinition]>,
+        ) -> Result<ChatCompletionResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.reply {
+                Ok(text) => Ok(ChatCompletionResponse {
+                    message: ChatMessage::Assistant {
+                        content: Some(text.to_string()),
+                        tool_calls: None,
+                    },
+                    finish_reason: "stop".to_string(),
+                    fallback: None,
+                    usage: None,
+                }),
+                Err(e) => Err(anyhow!("{}", e)),
+            }
+        }
+
+        async fn stream(
+            &self,
+            messages: Vec<ChatMessage>,
+            tools: Option<&[ToolDefinition]>,
+            handler: StreamHandler,
+        ) -> Result<ChatCompletionResponse> {
+            if let Some(partial) = self.partial {
+                handler.push_text(partial)?;
+            }
+            self.complete(messages, tools).await
+        }
+    }
+
+    fn user(text: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage::User {
+            content: UserContent::Text(text.to_string()),
+        }]
+    }
+
+    #[test]
+    fn classifies_common_provider_errors() {
+        let cases = [
+            (
+                "OpenAI API error: 429 Too Many Requests - slow down",
+                FailoverReason::RateLimit,
+            ),
+            (
+                "Anthropic API error: 529 <unknown status code> - overloaded",
+                FailoverReason::ServerError,
+            ),
+            (
+                "Gemini API error: 503 Service Unavailable - try later",
+                FailoverReason::ServerError,
+            ),
+            (
+                "OpenAI API error: 401 Unauthorized - bad key",
+                FailoverReason::Auth,
+            ),
+            (
+                "OpenAI API error: 400 Bad Request - This model's maximum context length is 8192 tokens",
+                FailoverReason::ContextOverflow,
+            ),
+            (
+                "Anthropic API error: 400 Bad Request - prompt is too long",
+                FailoverReason::ContextOverflow,
+            ),
+            ("operation timed out", FailoverReason::Timeout),
+            ("Tool not found: x", FailoverReason::Other),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(classify_provider_error(&anyhow!(msg)), expected, "{}", msg);
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_rate_limit_and_reports_active_model() {
+        let (primary, primary_calls) =
+            ScriptedProvider::boxed(Err("OpenAI API error: 429 Too Many Requests - x"));
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("from backup"));
+        let chain = FallbackChatProvider::new("openai/gpt-4o", primary)
+            .with_fallback("anthropic/claude-sonnet-4-5", backup);
+
+        let response = chain.complete(user("hi"), None).await.unwrap();
+
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+        let notice = response.fallback.unwrap();
+        assert_eq!(notice.selected_model, "openai/gpt-4o");
+        assert_eq!(notice.active_model, "anthropic/claude-sonnet-4-5");
+        assert_eq!(notice.reason, FailoverReason::RateLimit);
+    }
+
+    #[tokio::test]
+    async fn streams_fail_over_only_before_any_output() {
+        use crate::agents::streaming::create_stream_pair;
+
+        let (primary, _) = ScriptedProvider::boxed(Err("API error: 503 Service Unavailable"));
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("from backup"));
+        let chain = FallbackChatProvider::new("a/b", primary).with_fallback("c/d", backup);
+        let (handler, _rx) = create_stream_pair(2000);
+        let response = chain.stream(user("hi"), None, handler).await.unwrap();
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+        assert!(response.fallback.is_some());
+
+        let (primary, _) = ScriptedProvider::streaming(
+            Some("partial answer"),
+            Err("API error: 503 Service Unavailable"),
+        );
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("from backup"));
+        let chain = FallbackChatProvider::new("a/b", primary).with_fallback("c/d", backup);
+        let (handler, _rx) = create_stream_pair(2000);
+        let err = chain.stream(user("hi"), None, handler).await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+
+        // Output from an earlier tool round does not count against a
+        // later attempt that failed before sending anything.
+        let (primary, _) = ScriptedProvider::boxed(Err("API error: 503 Service Unavailable"));
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("from backup"));
+        let chain = FallbackChatProvider::new("a/b", primary).with_fallback("c/d", backup);
+        let (handler, _rx) = create_stream_pair(2000);
+        handler.push_text("Let me check.").unwrap();
+        handler.start_tool_call("call_1", "read_file").unwrap();
+        let response = chain.stream(user("hi"), None, handler).await.unwrap();
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 1);
+        assert!(response.fallback.is_some());
+    }
+
+    #[tokio::test]
+    async fn auth_errors_do_not_fall_back() {
+        let (primary, _) = ScriptedProvider::boxed(Err("OpenAI API error: 401 Unauthorized - x"));
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("from backup"));
+        let chain = FallbackChatProvider::new("openai/gpt-4o", primary)
+            .with_fallback("ollama/llama3.1", backup);
+
+        let err = chain.complete(user("hi"), None).await.unwrap_err();
+        assert!(err.to_string().contains("401"));
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn primary_success_has_no_notice() {
+        let (primary, _) = ScriptedProvider::boxed(Ok("primary"));
+        let (backup, backup_calls) = ScriptedProvider::boxed(Ok("backup"));
+        let chain = FallbackChatProvider::new("a/b", primary).with_fallback("c/d", backup);
+
+        let response = chain.complete(user("hi"), None).await.unwrap();
+        assert!(response.fallback.is_none());
+        assert_eq!(backup_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn last_error_is_returned_when_chain_exhausted() {
+        let (primary, _) = ScriptedProvider::boxed(Err("API error: 500 Internal Server Error"));
+        let (backup, _) = ScriptedProvider::boxed(Err("API error: 502 Bad Gateway"));
+        let chain = FallbackChatProvider::new("a/b", primary).with_fallback("c/d", backup);
+
+        let err = chain.complete(user("hi"), None).await.unwrap_err();
+        assert!(err.to_string().contains("502"));
+    }
+
+    #[test]
+    fn notice_is_recorded_on_session() {
+        let mut session = crate::sessions::Session::new("s1");
+        let notice = FallbackNotice {
+            selected_model: "openai/gpt-4o".to_string(),
+            active_model: "ollama/llama3.1".to_string(),
+            reason: FailoverReason::Timeout,
+        };
+        notice.apply_to_session(&mut session);
+        assert_eq!(
+            session.fallback_notice_active_model.as_deref(),
+            Some("ollama/llama3.1")
+        );
+        assert_eq!(session.fallback_notice_reason.as_deref(), Some("timeout"));
+
+        clear_fallback_notice(&mut session);
+        assert!(session.fallback_notice_selected_model.is_none());
+    }
+}
@@ -1,17 +1,22 @@
 pub mod chat;
 pub mod compaction;
 pub mod core;
+pub mod fallback;
 pub mod identity;
 pub mod model_catalog;
+pub mod pool;
 pub mod provider_auth;
 pub mod session_repair;
 pub mod session_tools;
 pub mod streaming;
 pub mod tool;
+pub mod usage;
 
-pub use chat::{ChatMessage, ChatProvider, OpenAiChatProvider};
-pub use core::Agent;
+pub use chat::{ChatMessage, ChatOptions, ChatProvider, OpenAiChatProvider};
+pub use core::{Agent, AgentLoopConfig};
+pub use fallback::{FallbackChatProvider, FallbackNotice, FailoverReason};
 pub use identity::AgentIdentity;
+pub use pool::{AgentPool, RouteQuery, RoutedAgent};
 pub use session_repair::*;
 pub use model_catalog::{
     find_model_in_catalog, load_model_catalog, model_supports_vision, ModelCatalogEntry,
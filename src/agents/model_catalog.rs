@@ -76,7 +76,40 @@ static STATIC_MODEL_CATALOG: Lazy<Vec<ModelCatalogEntry>> = Lazy::new(|| {
             reasoning: Some(false),
             input_capabilities: vec![ModelInputCapability::Text],
         },
+        // Anthropic models
+        ModelCatalogEntry {
+            id: "claude-sonnet-4-5".to_string(),
+            name: "Claude Sonnet 4.5".to_string(),
+            provider: "anthropic".to_string(),
+            context_window: Some(200000),
+            reasoning: Some(true),
+            input_capabilities: vec![ModelInputCapability::Text, ModelInputCapability::Image],
+        },
+        ModelCatalogEntry {
+            id: "claude-opus-4-1".to_string(),
+            name: "Claude Opus 4.1".to_string(),
+            provider: "anthropic".to_string(),
+            context_window: Some(200000),
+            reasoning: Some(true),
+            input_capabilities: vec![ModelInputCapability::Text, ModelInputCapability::Image],
+        },
+        ModelCatalogEntry {
+            id: "claude-3-5-haiku-latest".to_string(),
+            name: "Claude 3.5 Haiku".to_string(),
+            provider: "anthropic".to_string(),
+            context_window: Some(200000),
+            reasoning: Some(false),
+            input_capabilities: vec![ModelInputCapability::Text, ModelInputCapability::Image],
+        },
         // Gemini models
+        ModelCatalogEntry {
+            id: "gemini-2.0-flash".to_string(),
+            name: "Gemini 2.0 Flash".to_string(),
+            provider: "gemini".to_string(),
+            context_window: Some(1048576),
+            reasoning: Some(false),
+            input_capabilities: vec![ModelInputCapability::Text, ModelInputCapability::Image],
+        },
         ModelCatalogEntry {
             id: "gemini-1.5-flash".to_string(),
             name: "Gemini 1.5 Flash".to_string(),
@@ -147,6 +180,17 @@ pub fn find_model_in_catalog(
         .cloned()
 }
 
+/// Find a model by `provider/model` reference or bare model ID.
+pub fn find_model_by_ref(
+    catalog: &[ModelCatalogEntry],
+    model_ref: &str,
+) -> Option<ModelCatalogEntry> {
+    match model_ref.split_once('/') {
+        Some((provider, model_id)) => find_model_in_catalog(catalog, provider, model_id),
+        None => catalog.iter().find(|entry| entry.id == model_ref).cloned(),
+    }
+}
+
 /// Check if a model supports vision (image input).
 pub fn model_supports_vision(entry: &ModelCatalogEntry) -> bool {
     entry
@@ -0,0 +1,306 @@
+//! Agent pool — the agents a gateway serves, keyed by agent id.
+//!
+//! Built from `agents.list` (or a single default agent when the list is
+//! empty). Inbound messages pick their agent through
+//! `routing::resolve_agent_route`, so `bindings` decide which agent answers
+//! and in which session.
+
+use crate::agents::Agent;
+use crate::routing::resolve_route::{
+    resolve_agent_route, resolve_default_agent_id, ResolveAgentRouteInput, ResolvedAgentRoute,
+    RoutePeer,
+};
+use crate::routing::session_key::{
+    normalize_agent_id, parse_agent_session_key, resolve_thread_session_keys,
+};
+use crate::OPENKRAB_CONFIG::OpenKrabConfig;
+use std::sync::Arc;
+
+/// Inbound message coordinates used for binding resolution.
+#[derive(Debug, Clone, Default)]
+pub struct RouteQuery<'a> {
+    pub channel: &'a str,
+    pub account_id: Option<&'a str>,
+    pub peer: Option<RoutePeer>,
+    pub parent_peer: Option<RoutePeer>,
+    pub guild_id: Option<&'a str>,
+    pub team_id: Option<&'a str>,
+    pub member_role_ids: Option<&'a [String]>,
+    /// Thread or topic within the peer; gets its own session.
+    pub thread_id: Option<&'a str>,
+}
+
+/// The agent chosen for a message and the route that chose it.
+#[derive(Debug, Clone)]
+pub struct RoutedAgent {
+    pub route: ResolvedAgentRoute,
+    pub agent: Arc<Agent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AgentPool {
+    agents: Vec<(String, Arc<Agent>)>,
+    default_id: String,
+    config: Arc<OpenKrabConfig>,
+}
+
+impl Default for AgentPool {
+    fn default() -> Self {
+        Self::new(OpenKrabConfig::default())
+    }
+}
+
+impl AgentPool {
+    /// An empty pool routing with `config`'s bindings and agent list.
+    pub fn new(config: OpenKrabConfig) -> Self {
+        Self {
+            agents: Vec::new(),
+            default_id: resolve_default_agent_id(&config),
+            config: Arc::new(config),
+        }
+    }
+
+    /// A pool with one agent serving as the default agent.
+    pub fn single(agent: Arc<Agent>) -> Self {
+        let mut pool = Self::default();
+        let id = pool.default_id.clone();
+        pool.insert(&id, agent);
+        pool
+    }
+
+    pub fn insert(&mut self, id: &str, agent: Arc<Agent>) {
+        let id = normalize_agent_id(Some(id));
+        self.agents.retain(|(existing, _)| *existing != id);
+        self.agents.push((id, agent));
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Agent>> {
+        let id = normalize_agent_id(Some(id));
+        self.agents
+            .iter()
+            .find(|(existing, _)| *existing == id)
+            .map(|(_, agent)| agent.clone())
+    }
+
+    pub fn default_id(&self) -> &str {
+        &self.default_id
+    }
+
+    /// The default agent, or the first one if the default isn't loaded.
+    pub fn default_agent(&self) -> Option<(String, Arc<Agent>)> {
+        self.get(&self.default_id)
+            .map(|agent| (self.default_id.clone(), agent))
+            .or_else(|| self.agents.first().cloned())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<Agent>)> {
+        self.agents.iter().map(|(id, agent)| (id.as_str(), agent))
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    pub fn config(&self) -> &OpenKrabConfig {
+        &self.config
+    }
+
+    /// Resolve the bound agent and session key for an inbound message.
+    /// Falls back to the default agent when the bound one isn't loaded.
+    pub fn route(&self, query: RouteQuery<'_>) -> Option<RoutedAgent> {
+        let mut route = resolve_agent_route(ResolveAgentRouteInput {
+            cfg: &self.config,
+            channel: query.channel,
+            account_id: query.account_id,
+            peer: query.peer,
+            parent_peer: query.parent_peer,
+            guild_id: query.guild_id,
+            team_id: query.team_id,
+            member_role_ids: query.member_role_ids,
+        });
+        if query.thread_id.is_some() {
+            route.session_key =
+                resolve_thread_session_keys(&route.session_key, query.thread_id, None, true).0;
+        }
+        if let Some(agent) = self.get(&route.agent_id) {
+            return Some(RoutedAgent { route, agent });
+        }
+        let (id, agent) = self.default_agent()?;
+        tracing::warn!(
+            "Agent '{}' is not loaded; '{}' answers instead",
+            route.agent_id,
+            id
+        );
+        route.agent_id = id;
+        Some(RoutedAgent { route, agent })
+    }
+
+    /// The agent named by an `agent:<id>:...` session key, if it is loaded.
+    pub fn for_session_key(&self, session_key: &str) -> Option<(String, Arc<Agent>)> {
+        let parsed = parse_agent_session_key(Some(session_key))?;
+        let id = normalize_agent_id(Some(&parsed.agent_id));
+        self.get(&id).map(|agent| (id, agent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::chat::{ChatCompletionResponse, ChatMessage, ChatProvider};
+    use crate::agents::streaming::StreamHandler;
+    use crate::agents::tool::ToolDefinition;
+    use crate::agents::AgentIdentity;
+    use crate::OPENKRAB_CONFIG::{
+        AgentBinding, AgentEntry, AgentsConfig, BindingMatch, BindingPeer,
+    };
+
+    struct NullProvider;
+
+    #[async_trait::async_trait]
+    impl ChatProvider for NullProvider {
+        async fn complete(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _tools: Option<&[ToolDefinition]>,
+        ) -> anyhow::Result<ChatCompletionResponse> {
+            anyhow::bail!("unused")
+        }
+
+        async fn stream(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _tools: Option<&[ToolDefinition]>,
+            _handler: StreamHandler,
+        ) -> anyhow::Result<ChatCompletionResponse> {
+            anyhow::bail!("unused")
+        }
+    }
+
+    fn agent(name: &str) -> Arc<Agent> {
+        let identity = AgentIdentity {
+            name: name.to_string(),
+            ..AgentIdentity::default()
+        };
+        Arc::new(Agent::new(identity, Box::new(NullProvider), None, vec![]))
+    }
+
+    fn config() -> OpenKrabConfig {
+        let entry = |id: &str, default: bool| AgentEntry {
+            id: id.to_string(),
+            default,
+            ..Default::default()
+        };
+        OpenKrabConfig {
+            agents: Some(AgentsConfig {
+                defaults: None,
+                list: vec![entry("home", false), entry("work", true)],
+            }),
+            bindings: vec![AgentBinding {
+                agent_id: "home".to_string(),
+                match_: BindingMatch {
+                    channel: "telegram".to_string(),
+                    peer: Some(BindingPeer {
+                        kind: "direct".to_string(),
+                        id: "42".to_string(),
+                    }),
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bindings_pick_the_agent_and_session() {
+        let mut pool = AgentPool::new(config());
+        pool.insert("home", agent("Home"));
+        pool.insert("work", agent("Work"));
+        assert_eq!(pool.default_id(), "work");
+
+        let peer = |id: &str| RoutePeer {
+            kind: "direct".to_string(),
+            id: id.to_string(),
+        };
+        let bound = pool
+            .route(RouteQuery {
+                channel: "telegram",
+                peer: Some(peer("42")),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(bound.agent.identity.name, "Home");
+        assert_eq!(bound.route.matched_by, "binding.peer");
+        assert!(bound.route.session_key.starts_with("agent:home:"));
+
+        let other = pool
+            .route(RouteQuery {
+                channel: "telegram",
+                peer: Some(peer("7")),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(other.agent.identity.name, "Work");
+        assert_eq!(other.route.matched_by, "default");
+    }
+
+    #[test]
+    fn dm_scope_and_threads_shape_session_keys() {
+        let mut cfg = config();
+        cfg.session = Some(crate::OPENKRAB_CONFIG::SessionConfig {
+            dm_scope: Some(crate::routing::session_key::DmScope::PerChannelPeer),
+            ..Default::default()
+        });
+        let mut pool = AgentPool::new(cfg);
+        pool.insert("work", agent("Work"));
+        let peer = |kind: &str, id: &str| RoutePeer {
+            kind: kind.to_string(),
+            id: id.to_string(),
+        };
+
+        let dm = pool
+            .route(RouteQuery {
+                channel: "discord",
+                peer: Some(peer("direct", "7")),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(dm.route.session_key, "agent:work:discord:direct:7");
+
+        let topic = pool
+            .route(RouteQuery {
+                channel: "telegram",
+                peer: Some(peer("group", "-100")),
+                thread_id: Some("12"),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            topic.route.session_key,
+            "agent:work:telegram:group:-100:thread:12"
+        );
+    }
+
+    #[test]
+    fn unloaded_agents_fall_back_to_the_default() {
+        let mut pool = AgentPool::new(config());
+        pool.insert("work", agent("Work"));
+        let routed = pool
+            .route(RouteQuery {
+                channel: "telegram",
+                peer: Some(RoutePeer {
+                    kind: "direct".to_string(),
+                    id: "42".to_string(),
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(routed.route.agent_id, "work");
+        assert!(pool.for_session_key("agent:work:main").is_some());
+        assert!(pool.for_session_key("agent:home:main").is_none());
+        assert!(AgentPool::default().route(RouteQuery::default()).is_none());
+    }
+}
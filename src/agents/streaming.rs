@@ -235,6 +235,16 @@ impl StreamAccumulator {
         events
     }
 
+    /// Forget the previous response's text and tool calls before the next
+    /// model call. Block numbering carries on so the turn reads as one stream.
+    pub fn reset(&mut self) {
+        self.text.clear();
+        self.reasoning.clear();
+        self.block_buffer.clear();
+        self.tool_calls.clear();
+        self.finished = false;
+    }
+
     /// Get the accumulated text so far.
     pub fn text(&self) -> &str {
         &self.text
@@ -366,6 +376,19 @@ impl StreamHandler {
         Ok(())
     }
 
+    /// Start accumulating a new model response; see `StreamAccumulator::reset`.
+    pub fn reset(&self) {
+        self.accumulator.lock().unwrap().reset();
+    }
+
+    /// How much text, reasoning and tool-call output has been sent so far.
+    /// Two readings differ when a call in between emitted something, which
+    /// consumers are then showing.
+    pub fn output_mark(&self) -> usize {
+        let acc = self.accumulator.lock().unwrap();
+        acc.text.len() + acc.reasoning.len() + acc.tool_calls.len()
+    }
+
     /// Send an error event.
     pub fn error(&self, message: &str) -> Result<()> {
         self.tx.send(StreamEvent::Error {
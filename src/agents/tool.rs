@@ -93,14 +93,46 @@ pub trait Tool: Send + Sync {
     async fn call(&self, arguments: &str) -> Result<String>;
 }
 
-#[derive(Debug)]
 pub struct SearchMemoryTool {
     manager: std::sync::Arc<crate::memory::MemoryManager>,
+    agent_id: Option<String>,
+    backend: Option<std::sync::Arc<dyn crate::memory::MemoryBackend>>,
+}
+
+impl std::fmt::Debug for SearchMemoryTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SearchMemoryTool")
+            .field("agent_id", &self.agent_id)
+            .field(
+                "backend",
+                &self.backend.as_ref().map(|b| b.id().to_string()),
+            )
+            .finish()
+    }
 }
 
 impl SearchMemoryTool {
     pub fn new(manager: std::sync::Arc<crate::memory::MemoryManager>) -> Self {
-        Self { manager }
+        Self {
+            manager,
+            agent_id: None,
+            backend: None,
+        }
+    }
+
+    /// Searches go to `backend` instead of the builtin index.
+    pub fn with_backend(
+        mut self,
+        backend: std::sync::Arc<dyn crate::memory::MemoryBackend>,
+    ) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Searches see `agent_id`'s memory besides the global scope.
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
     }
 }
 
@@ -130,10 +162,20 @@ impl Tool for SearchMemoryTool {
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing query argument"))?;
 
-        let results = self
-            .manager
-            .search_hybrid(query, Default::default())
-            .await?;
+        // No chat is attached to a tool call, so session memory stays out
+        let opts = crate::memory::HybridSearchOptions {
+            scope: Some(crate::memory::ScopeContext::for_agent(
+                self.agent_id.as_deref(),
+            )),
+            ..Default::default()
+        };
+        let (results, cite) = match &self.backend {
+            Some(backend) => (
+                backend.search(query, opts).await?,
+                backend.citations().cites(None),
+            ),
+            None => (self.manager.search_hybrid(query, opts).await?, false),
+        };
         if results.is_empty() {
             return Ok("No relevant information found in memory.".to_string());
         }
@@ -142,7 +184,9 @@ impl Tool for SearchMemoryTool {
         for res in results.iter().take(5) {
             out.push_str(&format!(
                 "--- Source: {} (Score: {:.2}) ---\n{}\n\n",
-                res.path, res.score, res.text
+                res.path,
+                res.score,
+                crate::memory::backend::render_snippet(res, cite)
             ));
         }
         Ok(out)
@@ -717,44 +761,63 @@ impl Tool for SpeakTool {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ScheduledTask {
-    id: String,
-    cron: String,
-    description: String,
-    enabled: bool,
-}
-
 #[derive(Debug)]
 pub struct ScheduleTool {
     workspace_root: std::path::PathBuf,
+    store_path: std::path::PathBuf,
+    agent_id: Option<String>,
+    timezone: Option<String>,
 }
 
 impl ScheduleTool {
     pub fn new(root: std::path::PathBuf) -> Self {
         Self {
             workspace_root: root,
+            store_path: crate::cron::default_store_path(),
+            agent_id: None,
+            timezone: None,
         }
     }
 
-    async fn load_schedule(&self) -> Result<Vec<ScheduledTask>> {
-        let file = self.workspace_root.join("memory/schedule.json");
-        if !file.exists() {
-            return Ok(Vec::new());
-        }
-        let content = tokio::fs::read_to_string(&file).await?;
-        let tasks: Vec<ScheduledTask> = serde_json::from_str(&content).unwrap_or_default();
-        Ok(tasks)
+    /// Use the job store at `path` (shared with `openkrab cron`).
+    pub fn with_store(mut self, path: std::path::PathBuf) -> Self {
+        self.store_path = path;
+        self
     }
 
-    async fn save_schedule(&self, tasks: &[ScheduledTask]) -> Result<()> {
-        let file = self.workspace_root.join("memory/schedule.json");
-        if let Some(parent) = file.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+    /// Jobs added by this tool run on `agent_id`.
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    /// Time zone for cron expressions that don't name one.
+    pub fn with_timezone(mut self, timezone: Option<String>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    fn service(&self) -> Result<crate::cron::CronService> {
+        let mut service = crate::cron::CronService::new(&self.store_path)?;
+        let imported = crate::cron::import_legacy_schedule(
+            &self.workspace_root,
+            &mut service,
+            self.agent_id.as_deref(),
+            self.timezone.as_deref(),
+        )?;
+        if imported > 0 {
+            tracing::info!(
+                "Imported {} task(s) from memory/schedule.json into {}",
+                imported,
+                self.store_path.display()
+            );
         }
-        let content = serde_json::to_string_pretty(tasks)?;
-        tokio::fs::write(file, content).await?;
-        Ok(())
+        Ok(service)
+    }
+
+    /// Agents only see and change their own jobs.
+    fn owns(&self, job: &crate::cron::CronJob) -> bool {
+        self.agent_id.is_none() || job.agent_id == self.agent_id
     }
 }
 
@@ -763,26 +826,38 @@ impl Tool for ScheduleTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "manage_schedule".to_string(),
-            description: "Manage scheduled cron jobs for the agent.".to_string(),
+            description: "Manage scheduled cron jobs for the agent. Each run sends the description to the agent as a new task.".to_string(),
             parameters: serde_json::json!({
                 "type": "object",
                 "properties": {
                     "action": {
                         "type": "string",
-                        "enum": ["add", "list", "remove"],
+                        "enum": ["add", "list", "remove", "enable", "disable"],
                         "description": "Action to perform."
                     },
                     "cron": {
                         "type": "string",
-                        "description": "Cron expression (e.g. '0 9 * * *' for daily at 9am)."
+                        "description": "Cron expression, 5 or 6 fields (e.g. '0 9 * * *' for daily at 9am), 'every 30m', or an RFC 3339 time for a one-off run."
+                    },
+                    "timezone": {
+                        "type": "string",
+                        "description": "IANA time zone for the cron expression (e.g. 'Asia/Bangkok')."
                     },
                     "description": {
                         "type": "string",
                         "description": "Description of the task to perform."
                     },
+                    "channel": {
+                        "type": "string",
+                        "description": "Channel to send each run's result to (e.g. 'telegram')."
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Chat or channel id on that channel."
+                    },
                     "id": {
                         "type": "string",
-                        "description": "Task ID (for removal)."
+                        "description": "Task ID (for remove, enable and disable)."
                     }
                 },
                 "required": ["action"]
@@ -795,6 +870,7 @@ impl Tool for ScheduleTool {
         let action = args["action"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing action argument"))?;
+        let mut service = self.service()?;
 
         match action {
             "add" => {
@@ -804,31 +880,44 @@ impl Tool for ScheduleTool {
                 let description = args["description"]
                     .as_str()
                     .ok_or_else(|| anyhow::anyhow!("Missing description argument"))?;
-                let id = uuid::Uuid::new_v4().to_string();
-
-                let mut tasks = self.load_schedule().await?;
-                tasks.push(ScheduledTask {
-                    id: id.clone(),
-                    cron: cron.to_string(),
-                    description: description.to_string(),
-                    enabled: true,
-                });
-                self.save_schedule(&tasks).await?;
-                Ok(format!("Scheduled task '{}' with ID: {}", description, id))
+                let timezone = args["timezone"].as_str().or(self.timezone.as_deref());
+                let frequency = crate::cron::parse_schedule(cron, timezone)?;
+                let id = crate::cron::new_job_id();
+
+                let mut job = crate::cron::CronJob::new(&id, description, description, frequency);
+                job.agent_id = self.agent_id.clone();
+                if let (Some(channel), Some(to)) = (args["channel"].as_str(), args["to"].as_str()) {
+                    job = job.with_delivery(crate::routing::DeliveryTarget::new(channel, to));
+                }
+                let next = job.next_run();
+                service.add_job(job)?;
+                Ok(match next {
+                    Some(next) => format!(
+                        "Scheduled task '{}' with ID: {} (next run {})",
+                        description,
+                        id,
+                        next.format("%Y-%m-%d %H:%M:%S UTC")
+                    ),
+                    None => format!("Scheduled task '{}' with ID: {}", description, id),
+                })
             }
             "list" => {
-                let tasks = self.load_schedule().await?;
-                if tasks.is_empty() {
+                let jobs: Vec<_> = service
+                    .list_jobs()
+                    .into_iter()
+                    .filter(|job| self.owns(job))
+                    .collect();
+                if jobs.is_empty() {
                     Ok("No scheduled tasks.".to_string())
                 } else {
-                    let list = tasks
+                    let list = jobs
                         .iter()
                         .map(|t| {
                             format!(
                                 "[{}] {} - {} ({})",
                                 if t.enabled { "ON" } else { "OFF" },
-                                t.cron,
-                                t.description,
+                                t.frequency.describe(),
+                                t.task,
                                 t.id
                             )
                         })
@@ -837,18 +926,19 @@ impl Tool for ScheduleTool {
                     Ok(list)
                 }
             }
-            "remove" => {
+            "remove" | "enable" | "disable" => {
                 let id = args["id"]
                     .as_str()
                     .ok_or_else(|| anyhow::anyhow!("Missing id argument"))?;
-                let mut tasks = self.load_schedule().await?;
-                let len_before = tasks.len();
-                tasks.retain(|t| t.id != id);
-                if tasks.len() < len_before {
-                    self.save_schedule(&tasks).await?;
+                if !service.get_job(id).is_some_and(|job| self.owns(job)) {
+                    return Ok(format!("Task not found with ID: {}", id));
+                }
+                if action == "remove" {
+                    service.remove_job(id)?;
                     Ok(format!("Removed task with ID: {}", id))
                 } else {
-                    Ok(format!("Task not found with ID: {}", id))
+                    service.set_enabled(id, action == "enable")?;
+                    Ok(format!("Task {} {}d", id, action))
                 }
             }
             _ => Err(anyhow::anyhow!("Unknown action: {}", action)),
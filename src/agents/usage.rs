@@ -0,0 +1,223 @@
+//! usage — Token and cost accounting for agent turns.
+//!
+//! Every model call that reports usage becomes a `UsageRecord`: it is added
+//! to the session counters, persisted (when the agent has a memory store),
+//! and rolled up into `shared::usage_aggregates` for `openkrab usage`.
+
+use crate::agents::chat::TokenUsage;
+use crate::providers::pricing::lookup_model_pricing;
+use crate::sessions::{Session, UsageDisplayLevel};
+use crate::shared::usage_aggregates::UsageAggregates;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Session metadata key holding the running cost in USD.
+pub const SESSION_COST_META_KEY: &str = "usage_cost_usd";
+
+/// One model call's usage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub session_id: String,
+    pub agent: String,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// `None` when the model has no known pricing.
+    pub cost_usd: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UsageRecord {
+    pub fn new(
+        session_id: impl Into<String>,
+        agent: impl Into<String>,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        usage: &TokenUsage,
+    ) -> Self {
+        let provider = provider.into();
+        let model = model.into();
+        let cost_usd = lookup_model_pricing(&provider, &model)
+            .map(|p| p.cost_usd(usage.input_tokens, usage.output_tokens));
+        Self {
+            session_id: session_id.into(),
+            agent: agent.into(),
+            provider,
+            model,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Usage summed over the model calls of one turn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurnUsage {
+    pub calls: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: Option<f64>,
+}
+
+impl TurnUsage {
+    pub fn add(&mut self, record: &UsageRecord) {
+        self.calls += 1;
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        if let Some(cost) = record.cost_usd {
+            self.cost_usd = Some(self.cost_usd.unwrap_or(0.0) + cost);
+        }
+    }
+}
+
+/// Add a call's cost to the session's running total.
+pub fn add_session_cost(session: &mut Session, cost_usd: f64) {
+    let total = session_cost(session) + cost_usd;
+    session.set_meta(SESSION_COST_META_KEY, format!("{:.6}", total));
+}
+
+pub fn session_cost(session: &Session) -> f64 {
+    session
+        .get_meta(SESSION_COST_META_KEY)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.0)
+}
+
+/// Footer appended to replies according to the session's `/usage` level.
+pub fn format_usage_footer(
+    level: UsageDisplayLevel,
+    turn: &TurnUsage,
+    session: &Session,
+) -> Option<String> {
+    if turn.calls == 0 {
+        return None;
+    }
+    let tokens = format!(
+        "Usage: {} in / {} out tokens",
+        turn.input_tokens, turn.output_tokens
+    );
+    match level {
+        UsageDisplayLevel::Off => None,
+        UsageDisplayLevel::Tokens => Some(tokens),
+        UsageDisplayLevel::Full => {
+            let mut footer = tokens;
+            if let Some(cost) = turn.cost_usd {
+                footer.push_str(&format!(" · ${:.4}", cost));
+            }
+            footer.push_str(&format!(
+                " · session {} tokens, ${:.4}",
+                session.total_tokens,
+                session_cost(session)
+            ));
+            Some(footer)
+        }
+    }
+}
+
+// ─── Aggregation ──────────────────────────────────────────────────────────────
+
+/// Dimension for `openkrab usage` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroupBy {
+    Session,
+    Agent,
+    Provider,
+    Model,
+}
+
+impl UsageGroupBy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "session" | "sessions" => Some(Self::Session),
+            "agent" | "agents" => Some(Self::Agent),
+            "provider" | "providers" => Some(Self::Provider),
+            "model" | "models" => Some(Self::Model),
+            _ => None,
+        }
+    }
+
+    fn key(&self, record: &UsageRecord) -> String {
+        match self {
+            Self::Session => record.session_id.clone(),
+            Self::Agent => record.agent.clone(),
+            Self::Provider => record.provider.clone(),
+            Self::Model => format!("{}/{}", record.provider, record.model),
+        }
+    }
+}
+
+/// Roll records up into `UsageAggregates`, one token entry per group key.
+pub fn aggregate_usage(records: &[UsageRecord], group_by: UsageGroupBy) -> UsageAggregates {
+    let mut aggregates = UsageAggregates::new();
+    if let Some(first) = records.iter().map(|r| r.created_at).min() {
+        aggregates.session_start = Some(first.to_rfc3339());
+    }
+    for record in records {
+        let key = group_by.key(record);
+        aggregates.record_priced_tokens(
+            key.clone(),
+            record.input_tokens,
+            record.output_tokens,
+            record.cost_usd.unwrap_or(0.0),
+        );
+        aggregates.record_api_call(key);
+    }
+    aggregates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(session: &str, provider: &str, model: &str, input: u64, output: u64) -> UsageRecord {
+        UsageRecord::new(
+            session,
+            "krab",
+            provider,
+            model,
+            &TokenUsage::new(input, output),
+        )
+    }
+
+    #[test]
+    fn records_are_priced_from_the_table() {
+        let r = record("s1", "openai", "gpt-4o", 1_000_000, 0);
+        assert_eq!(r.cost_usd, Some(2.5));
+        assert_eq!(record("s1", "custom", "x", 10, 10).cost_usd, None);
+    }
+
+    #[test]
+    fn aggregate_groups_by_provider() {
+        let records = vec![
+            record("s1", "openai", "gpt-4o", 100, 50),
+            record("s2", "openai", "gpt-4o-mini", 10, 5),
+            record("s1", "ollama", "llama3.1", 7, 3),
+        ];
+        let agg = aggregate_usage(&records, UsageGroupBy::Provider);
+        assert_eq!(agg.tokens["openai"].total, 165);
+        assert_eq!(agg.tokens["ollama"].estimated_cost, 0.0);
+        assert_eq!(agg.api_calls["openai"].total, 2);
+        assert_eq!(agg.total_tokens(), 175);
+    }
+
+    #[test]
+    fn footer_follows_display_level() {
+        let mut session = Session::new("s1");
+        session.total_tokens = 1200;
+        add_session_cost(&mut session, 0.01);
+        let mut turn = TurnUsage::default();
+        turn.add(&record("s1", "openai", "gpt-4o", 1000, 200));
+
+        assert!(format_usage_footer(UsageDisplayLevel::Off, &turn, &session).is_none());
+        assert_eq!(
+            format_usage_footer(UsageDisplayLevel::Tokens, &turn, &session).unwrap(),
+            "Usage: 1000 in / 200 out tokens"
+        );
+        let full = format_usage_footer(UsageDisplayLevel::Full, &turn, &session).unwrap();
+        assert!(full.contains("$0.0045"));
+        assert!(full.contains("session 1200 tokens, $0.0100"));
+    }
+}
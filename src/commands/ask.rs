@@ -1,6 +1,8 @@
-use crate::agents::{Agent, AgentIdentity, OpenAiChatProvider};
+use crate::agents::{Agent, AgentIdentity, AgentPool};
 use crate::memory::{MemoryConfig, MemoryManager, MemoryStore};
-use anyhow::{anyhow, Result};
+use crate::OPENKRAB_CONFIG::AgentEntry;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 pub async fn ask_command(query: &str, db_path: Option<&str>) -> Result<String> {
@@ -10,21 +12,108 @@ pub async fn ask_command(query: &str, db_path: Option<&str>) -> Result<String> {
     )
     .await?;
 
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .map_err(|_| anyhow!("Missing OPENAI_API_KEY environment variable"))?;
+    let cfg = cfg.unwrap_or_default();
+    let agent = build_agent_from_config(&cfg, db_path.unwrap_or("memory.db"))?;
 
-    let db_path = db_path.unwrap_or("memory.db");
-    let store = MemoryStore::open(db_path)?;
+    let response = agent.answer(query).await?;
+
+    Ok(response)
+}
+
+/// Build the default agent (provider chain, memory, built-in tools) from
+/// config. Shared by `ask` and the gateway.
+pub fn build_agent_from_config(
+    cfg: &crate::OPENKRAB_CONFIG::OpenKrabConfig,
+    db_path: &str,
+) -> Result<Agent> {
+    build_agent(cfg, None, open_memory(db_path)?)
+}
+
+/// Build every agent in `agents.list` (or the default agent when the list is
+/// empty), sharing one memory store.
+pub fn build_agent_pool_from_config(
+    cfg: &crate::OPENKRAB_CONFIG::OpenKrabConfig,
+    db_path: &str,
+) -> Result<AgentPool> {
+    let memory_manager = open_memory(db_path)?;
+    let mut pool = AgentPool::new(cfg.clone());
+    let entries = cfg
+        .agents
+        .as_ref()
+        .map(|a| a.list.as_slice())
+        .unwrap_or(&[]);
+    if entries.is_empty() {
+        let id = pool.default_id().to_string();
+        pool.insert(&id, Arc::new(build_agent(cfg, None, memory_manager)?));
+        return Ok(pool);
+    }
+    for entry in entries {
+        let agent = build_agent(cfg, Some(entry), memory_manager.clone())
+            .with_context(|| format!("failed to build agent '{}'", entry.id))?;
+        pool.insert(&entry.id, Arc::new(agent));
+    }
+    Ok(pool)
+}
 
+fn open_memory(db_path: &str) -> Result<Arc<MemoryManager>> {
+    let store = MemoryStore::open(db_path)?;
     let mem_config = MemoryConfig::default();
-    let memory_manager = Arc::new(MemoryManager::from_config(store, mem_config)?);
+    Ok(Arc::new(MemoryManager::from_config(store, mem_config)?))
+}
+
+fn build_agent(
+    cfg: &crate::OPENKRAB_CONFIG::OpenKrabConfig,
+    entry: Option<&AgentEntry>,
+    memory_manager: Arc<MemoryManager>,
+) -> Result<Agent> {
+    let provider = crate::providers::chat_factory::build_agent_chat_provider(
+        cfg,
+        entry.and_then(|e| e.model.as_ref()),
+    )?;
+    let loop_config = crate::agents::AgentLoopConfig::from_agent_defaults(
+        cfg.agents.as_ref().and_then(|a| a.defaults.as_ref()),
+    );
 
-    let identity = AgentIdentity::default();
-    let provider = Box::new(OpenAiChatProvider::new(api_key, None, None));
+    let mut identity = AgentIdentity::default();
+    if let Some(entry) = entry {
+        identity.name = entry.name.clone().unwrap_or_else(|| entry.id.clone());
+        if let Some(emoji) = &entry.emoji {
+            identity.emoji = emoji.clone();
+        }
+        if let Some(personality) = &entry.personality {
+            identity.personality = personality.clone();
+        }
+        identity.system_prompt = entry.system_prompt.clone();
+    }
 
-    let workspace_root = std::env::current_dir()?;
-    let tools: Vec<Box<dyn crate::agents::Tool>> = vec![
-        Box::new(crate::agents::SearchMemoryTool::new(memory_manager.clone())),
+    let agent_id = match entry {
+        Some(entry) => crate::routing::session_key::normalize_agent_id(Some(&entry.id)),
+        None => crate::routing::resolve_route::resolve_default_agent_id(cfg),
+    };
+    let workspace_root = match entry.and_then(|e| e.workspace.as_deref()) {
+        Some(dir) => PathBuf::from(crate::utils::resolve_user_path(dir)),
+        None => std::env::current_dir()?,
+    };
+    // `memory.backend` picks where recall is served from; unset keeps the builtin index
+    let memory_backend = cfg
+        .memory
+        .as_ref()
+        .and_then(|m| m.backend_config())
+        .map(|backend| {
+            crate::memory::create_backend(
+                Some(backend),
+                memory_manager.clone(),
+                &agent_id,
+                workspace_root.clone(),
+            )
+        });
+    let mut search_tool =
+        crate::agents::SearchMemoryTool::new(memory_manager.clone()).with_agent(&agent_id);
+    if let Some(backend) = &memory_backend {
+        search_tool = search_tool.with_backend(backend.clone());
+    }
+    let mut tools: Vec<Box<dyn crate::agents::Tool>> = vec![
+        Box::new(search_tool),
         Box::new(crate::agents::ReadFileTool::new(workspace_root.clone())),
         Box::new(crate::agents::ListFilesTool::new(workspace_root.clone())),
         Box::new(crate::agents::WriteFileTool::new(workspace_root.clone())),
@@ -38,16 +127,29 @@ pub async fn ask_command(query: &str, db_path: Option<&str>) -> Result<String> {
         )),
         Box::new(crate::agents::TaskTool::new(workspace_root.clone())),
         Box::new(crate::agents::SpeakTool::new()),
-        Box::new(crate::agents::ScheduleTool::new(workspace_root.clone())),
+        Box::new(
+            crate::agents::ScheduleTool::new(workspace_root.clone())
+                .with_store(crate::cron::store_path(cfg))
+                .with_agent(agent_id.clone())
+                .with_timezone(cfg.cron.as_ref().and_then(|c| c.timezone.clone())),
+        ),
         Box::new(crate::agents::BrowserTool::new()),
         Box::new(crate::agents::CodeInterpreterTool::new(
             workspace_root.clone(),
         )),
     ];
+    if let Some(policy) = entry.and_then(|e| e.tools.as_ref()) {
+        tools.retain(|tool| policy.permits(&tool.definition().name));
+    }
 
-    let agent = Agent::new(identity, provider, Some(memory_manager), tools);
-
-    let response = agent.answer(query).await?;
-
-    Ok(response)
+    let mut agent = Agent::new(identity, provider, Some(memory_manager), tools)
+        .with_loop_config(loop_config)
+        .with_agent_id(agent_id);
+    if let Some(backend) = memory_backend {
+        agent = agent.with_memory_backend(backend);
+    }
+    if let Some(summarizer) = crate::providers::chat_factory::build_compaction_provider(cfg) {
+        agent = agent.with_summarizer(summarizer);
+    }
+    Ok(agent)
 }
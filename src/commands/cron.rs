@@ -1,143 +1,190 @@
 //! Cron command - Manage scheduled tasks
+//!
+//! Reads and writes the same job store as the `manage_schedule` agent tool;
+//! a running gateway picks up changes on its next tick.
 
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
+use crate::cron::{parse_schedule, CatchUp, CronJob, CronService};
+use crate::routing::DeliveryTarget;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CronJob {
-    pub id: String,
+/// Options for `openkrab cron add`.
+#[derive(Debug, Clone, Default)]
+pub struct CronAddOptions {
     pub schedule: String,
     pub command: String,
-    pub enabled: bool,
-    pub last_run: Option<String>,
-    pub next_run: Option<String>,
+    pub timezone: Option<String>,
+    pub agent: Option<String>,
+    /// Delivery target as `channel:to`.
+    pub deliver: Option<String>,
+    pub catch_up: Option<String>,
 }
 
-fn get_cron_path() -> PathBuf {
-    let home = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-    home.join("openkrab").join("cron.toml")
+fn open_service() -> Result<(CronService, Option<String>), String> {
+    let cfg = crate::config_io::load_config().unwrap_or_default();
+    let timezone = cfg.cron.as_ref().and_then(|c| c.timezone.clone());
+    CronService::new(crate::cron::store_path(&cfg))
+        .map(|service| (service, timezone))
+        .map_err(|e| format!("Failed to read cron jobs: {}", e))
 }
 
-pub fn cron_list_command() -> String {
-    let cron_path = get_cron_path();
+fn format_job(job: &CronJob) -> String {
+    let mut line = format!(
+        "[{}] {} {} -> {}",
+        if job.enabled { "ON " } else { "OFF" },
+        job.id,
+        job.frequency.describe(),
+        job.task
+    );
+    if let Some(agent) = &job.agent_id {
+        line.push_str(&format!("\n      agent: {}", agent));
+    }
+    if let Some(target) = &job.delivery {
+        line.push_str(&format!(
+            "\n      deliver: {}:{}",
+            target.connector, target.to
+        ));
+    }
+    if let Some(next) = job.next_run() {
+        line.push_str(&format!(
+            "\n      next: {}",
+            next.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+    }
+    if let Some(run) = job.history.last() {
+        line.push_str(&format!(
+            "\n      last: {} {}",
+            run.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            run.status.as_str()
+        ));
+    }
+    line
+}
 
-    if !cron_path.exists() {
+pub fn cron_list_command() -> String {
+    let (service, _) = match open_service() {
+        Ok(opened) => opened,
+        Err(e) => return e,
+    };
+    let jobs = service.list_jobs();
+    if jobs.is_empty() {
         return r#"No cron jobs configured.
 Run 'openkrab cron add <schedule> <command>' to add one.
 
 Example:
-  openkrab cron add "0 9 * * *" "Send daily summary"
+  openkrab cron add "0 9 * * *" "Send daily summary" --tz Asia/Bangkok
 "#
         .to_string();
     }
 
-    match fs::read_to_string(&cron_path) {
-        Ok(content) => {
-            let mut output = String::from("Cron Jobs:\n");
-            output.push_str("==========\n\n");
-            output.push_str(&content);
-            output
-        }
-        Err(e) => format!("Failed to read cron jobs: {}", e),
+    let mut output = String::from("Cron Jobs:\n");
+    output.push_str("==========\n\n");
+    for job in jobs {
+        output.push_str(&format_job(job));
+        output.push('\n');
     }
+    output
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CronConfig {
-    #[serde(default)]
-    pub jobs: Vec<CronJob>,
-}
-
-pub fn cron_add_command(schedule: &str, command: &str) -> String {
-    let cron_path = get_cron_path();
-    if let Some(parent) = cron_path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-
-    let mut config: CronConfig = if cron_path.exists() {
-        fs::read_to_string(&cron_path)
-            .ok()
-            .and_then(|s| toml::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        CronConfig::default()
+pub fn cron_add_command(opts: CronAddOptions) -> String {
+    let (mut service, default_tz) = match open_service() {
+        Ok(opened) => opened,
+        Err(e) => return e,
+    };
+    let timezone = opts.timezone.or(default_tz);
+    let frequency = match parse_schedule(&opts.schedule, timezone.as_deref()) {
+        Ok(frequency) => frequency,
+        Err(e) => return e.to_string(),
     };
 
-    let id = uuid::Uuid::new_v4().to_string();
-    config.jobs.push(CronJob {
-        id: id.clone(),
-        schedule: schedule.to_string(),
-        command: command.to_string(),
-        enabled: true,
-        last_run: None,
-        next_run: None,
-    });
+    let id = crate::cron::new_job_id();
+    let mut job = CronJob::new(&id, &opts.command, &opts.command, frequency);
+    if let Some(agent) = opts.agent {
+        job = job.with_agent(crate::routing::session_key::normalize_agent_id(Some(
+            &agent,
+        )));
+    }
+    if let Some(deliver) = opts.deliver {
+        let Some((channel, to)) = deliver.split_once(':') else {
+            return format!(
+                "Invalid delivery target '{}' (expected channel:to)",
+                deliver
+            );
+        };
+        job = job.with_delivery(DeliveryTarget::new(channel, to));
+    }
+    if let Some(catch_up) = opts.catch_up {
+        let Some(catch_up) = CatchUp::parse(&catch_up) else {
+            return format!(
+                "Invalid catch-up policy '{}' (expected skip, latest or all)",
+                catch_up
+            );
+        };
+        job = job.with_catch_up(catch_up);
+    }
 
-    if let Ok(content) = toml::to_string(&config) {
-        if fs::write(&cron_path, &content).is_ok() {
-            return format!("Added cron job [{}]: {} -> {}", id, schedule, command);
-        }
+    let summary = format_job(&job);
+    match service.add_job(job) {
+        Ok(()) => format!("Added cron job:\n{}", summary),
+        Err(e) => format!("Failed to add cron job: {}", e),
     }
-    "Failed to add cron job".to_string()
 }
 
 pub fn cron_remove_command(id: &str) -> String {
-    let cron_path = get_cron_path();
-    let mut config: CronConfig = fs::read_to_string(&cron_path)
-        .ok()
-        .and_then(|s| toml::from_str(&s).ok())
-        .unwrap_or_default();
-
-    let initial_len = config.jobs.len();
-    config.jobs.retain(|j| j.id != id);
+    let (mut service, _) = match open_service() {
+        Ok(opened) => opened,
+        Err(e) => return e,
+    };
+    match service.remove_job(id) {
+        Ok(Some(job)) => format!("Removed cron job: {}", job.id),
+        Ok(None) => format!("Cron job not found: {}", id),
+        Err(e) => format!("Failed to remove cron job: {}", e),
+    }
+}
 
-    if config.jobs.len() < initial_len {
-        if let Ok(content) = toml::to_string(&config) {
-            let _ = fs::write(&cron_path, &content);
-            return format!("Removed cron job: {}", id);
-        }
+fn set_enabled(id: &str, enabled: bool) -> String {
+    let (mut service, _) = match open_service() {
+        Ok(opened) => opened,
+        Err(e) => return e,
+    };
+    let verb = if enabled { "Enabled" } else { "Disabled" };
+    match service.set_enabled(id, enabled) {
+        Ok(Some(id)) => format!("{} cron job: {}", verb, id),
+        Ok(None) => format!("Cron job not found: {}", id),
+        Err(e) => format!("Failed to save cron configuration: {}", e),
     }
-    format!("Cron job not found: {}", id)
 }
 
 pub fn cron_enable_command(id: &str) -> String {
-    let cron_path = get_cron_path();
-    let mut config: CronConfig = fs::read_to_string(&cron_path)
-        .ok()
-        .and_then(|s| toml::from_str(&s).ok())
-        .unwrap_or_default();
-
-    for job in &mut config.jobs {
-        if job.id == id {
-            job.enabled = true;
-            if let Ok(content) = toml::to_string(&config) {
-                let _ = fs::write(&cron_path, &content);
-                return format!("Enabled cron job: {}", id);
-            }
-            return "Failed to save cron configuration".to_string();
-        }
-    }
-    format!("Cron job not found: {}", id)
+    set_enabled(id, true)
 }
 
 pub fn cron_disable_command(id: &str) -> String {
-    let cron_path = get_cron_path();
-    let mut config: CronConfig = fs::read_to_string(&cron_path)
-        .ok()
-        .and_then(|s| toml::from_str(&s).ok())
-        .unwrap_or_default();
+    set_enabled(id, false)
+}
 
-    for job in &mut config.jobs {
-        if job.id == id {
-            job.enabled = false;
-            if let Ok(content) = toml::to_string(&config) {
-                let _ = fs::write(&cron_path, &content);
-                return format!("Disabled cron job: {}", id);
-            }
-            return "Failed to save cron configuration".to_string();
+/// Run history for one job, newest first.
+pub fn cron_runs_command(id: &str) -> String {
+    let (service, _) = match open_service() {
+        Ok(opened) => opened,
+        Err(e) => return e,
+    };
+    let Some(job) = service.get_job(id) else {
+        return format!("Cron job not found: {}", id);
+    };
+    if job.history.is_empty() {
+        return format!("Cron job {} has not run yet.", job.id);
+    }
+    let mut output = format!("Runs of {} ({}):\n", job.id, job.task);
+    for run in job.history.iter().rev() {
+        output.push_str(&format!(
+            "- {} {}{} (slot {})\n",
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.status.as_str(),
+            if run.catch_up { ", catch-up" } else { "" },
+            run.scheduled_for.format("%Y-%m-%d %H:%M:%S")
+        ));
+        if let Some(detail) = run.error.as_ref().or(run.output.as_ref()) {
+            output.push_str(&format!("    {}\n", detail));
         }
     }
-    format!("Cron job not found: {}", id)
+    output
 }
-
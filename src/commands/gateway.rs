@@ -1,7 +1,100 @@
-use crate::gateway::{start_gateway, GatewayServerOptions};
-use anyhow::Result;
+use crate::gateway::{serve_gateway, start_gateway, GatewayServerOptions, TokenScope, TokenStore};
+use anyhow::{bail, Result};
 
-pub async fn gateway_start_command(_db_path: Option<&str>) -> Result<()> {
-    let _server = start_gateway(GatewayServerOptions::default()).await?;
-    Ok(())
+pub async fn gateway_start_command(db_path: Option<&str>) -> Result<()> {
+    let cfg = crate::config_io::load_config().unwrap_or_default();
+    let gateway_cfg = cfg.gateway.as_ref();
+    let defaults = GatewayServerOptions::default();
+    let opts = GatewayServerOptions {
+        port: gateway_cfg.and_then(|g| g.port).or(defaults.port),
+        bind_host: gateway_cfg
+            .and_then(|g| g.bind_address.clone())
+            .or(defaults.bind_host),
+        enable_cors: defaults.enable_cors,
+    };
+    let enable_cors = opts.enable_cors;
+
+    let mut server = start_gateway(opts).await?;
+    let agents =
+        crate::commands::build_agent_pool_from_config(&cfg, db_path.unwrap_or("memory.db"))?;
+    server.memory = agents.default_agent().and_then(|(_, a)| a.memory.clone());
+    for (id, agent) in agents.iter() {
+        if let Some(backend) = &agent.memory_backend {
+            if let Err(e) = backend.start().await {
+                tracing::warn!(
+                    "Memory backend '{}' for agent '{}' failed to start: {}",
+                    backend.id(),
+                    id,
+                    e
+                );
+            }
+        }
+    }
+    tracing::info!(
+        "Serving {} agent(s); default '{}'",
+        agents.len(),
+        agents.default_id()
+    );
+    server.agents = agents;
+
+    if let Some(settings) = crate::gateway::scheduler::CronSettings::from_config(&cfg) {
+        crate::gateway::scheduler::spawn_cron_scheduler(
+            std::sync::Arc::new(server.clone()),
+            settings,
+        );
+    }
+    crate::gateway::spawn_connector_monitors(
+        std::sync::Arc::new(server.clone()),
+        &cfg.channels.clone().unwrap_or_default(),
+    );
+
+    serve_gateway(server, enable_cors).await
+}
+
+pub fn gateway_token_issue_command(name: &str, scope: &str) -> Result<String> {
+    let Some(scope) = TokenScope::parse(scope) else {
+        bail!("unknown scope '{}' (expected status, chat or admin)", scope);
+    };
+    let store = TokenStore::open_default();
+    let (token, secret) = store.issue(name, scope)?;
+    Ok(format!(
+        "issued token {} ({}, scope={})\n{}\nstore it now; it will not be shown again",
+        token.id,
+        token.name,
+        token.scope.as_str(),
+        secret
+    ))
+}
+
+pub fn gateway_token_list_command() -> Result<String> {
+    let tokens = TokenStore::open_default().list()?;
+    if tokens.is_empty() {
+        return Ok("gateway tokens: none".to_string());
+    }
+    let lines: Vec<String> = tokens
+        .iter()
+        .map(|t| {
+            let state = t
+                .revoked_at
+                .map(|at| format!(" revoked={}", at.format("%Y-%m-%d %H:%M")))
+                .unwrap_or_default();
+            format!(
+                "- {} ({}): scope={} created={}{}",
+                t.id,
+                t.name,
+                t.scope.as_str(),
+                t.created_at.format("%Y-%m-%d %H:%M"),
+                state
+            )
+        })
+        .collect();
+    Ok(format!("gateway tokens:\n{}", lines.join("\n")))
+}
+
+pub fn gateway_token_revoke_command(id_or_name: &str) -> Result<String> {
+    if TokenStore::open_default().revoke(id_or_name)? {
+        Ok(format!("revoked token {}", id_or_name))
+    } else {
+        bail!("no active token '{}'", id_or_name)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::memory::{MemoryConfig, MemoryManager, MemoryStore};
+use crate::memory::{HybridSearchOptions, MemoryConfig, MemoryManager, MemoryStats, MemoryStore};
 use anyhow::Result;
 use std::path::Path;
 
@@ -28,14 +28,23 @@ pub async fn memory_sync_command(
     }
 }
 
-pub async fn memory_search_command(query: &str, db_path: Option<&str>) -> Result<String> {
+/// Searches the memory index; `collections` limits results to those sources.
+pub async fn memory_search_command(
+    query: &str,
+    db_path: Option<&str>,
+    collections: &[String],
+) -> Result<String> {
     let db_path = db_path.unwrap_or("memory.db");
     let store = MemoryStore::open(db_path)?;
 
     let config = MemoryConfig::default();
     let manager = MemoryManager::from_config(store, config)?;
 
-    let results = manager.search_hybrid(query, Default::default()).await?;
+    let opts = HybridSearchOptions {
+        sources: collections.to_vec(),
+        ..Default::default()
+    };
+    let results = manager.search_hybrid(query, opts).await?;
 
     if results.is_empty() {
         return Ok("No results found.".to_string());
@@ -44,10 +53,11 @@ pub async fn memory_search_command(query: &str, db_path: Option<&str>) -> Result
     let mut out = format!("Search results for '{}':\n", query);
     for (i, res) in results.iter().enumerate() {
         out.push_str(&format!(
-            "{}. [{:.4}] {} (L{}-L{})\n   {}\n\n",
+            "{}. [{:.4}] {} [{}] (L{}-L{})\n   {}\n\n",
             i + 1,
             res.score,
             res.path,
+            res.source,
             res.start_line,
             res.end_line,
             res.text.lines().next().unwrap_or("")
@@ -56,3 +66,93 @@ pub async fn memory_search_command(query: &str, db_path: Option<&str>) -> Result
 
     Ok(out)
 }
+
+/// Index size and embedding cache effectiveness for the memory database.
+pub fn memory_stats_command(db_path: Option<&str>, json: bool) -> Result<String> {
+    let store = MemoryStore::open(db_path.unwrap_or("memory.db"))?;
+    let stats = store.memory_stats()?;
+    if json {
+        return Ok(serde_json::to_string_pretty(&stats)?);
+    }
+    Ok(format_memory_stats(&stats))
+}
+
+fn format_memory_stats(stats: &MemoryStats) -> String {
+    let mut lines = vec![
+        "Memory index:".to_string(),
+        format!("  files:      {}", stats.files),
+        format!("  db size:    {:.1} KiB", stats.db_bytes as f64 / 1024.0),
+    ];
+
+    if stats.chunks.is_empty() {
+        lines.push("  chunks:     none".to_string());
+    } else {
+        let width = stats
+            .chunks
+            .iter()
+            .map(|c| c.source.len() + c.model.len() + 1)
+            .max()
+            .unwrap_or(0);
+        lines.push("  chunks by source/model:".to_string());
+        for c in &stats.chunks {
+            lines.push(format!(
+                "    {:<width$}  {:>8}",
+                format!("{}/{}", c.source, c.model),
+                c.chunks,
+                width = width
+            ));
+        }
+    }
+
+    let hit_rate = match stats.cache_hit_rate() {
+        Some(rate) => format!("{:.1}%", rate * 100.0),
+        None => "n/a".to_string(),
+    };
+    lines.push(format!(
+        "Embedding cache: {} entries, {} hits / {} misses (hit rate {})",
+        stats.cache_entries, stats.cache_hits, stats.cache_misses, hit_rate
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::ChunkCount;
+
+    #[test]
+    fn stats_report_lists_chunks_and_hit_rate() {
+        let stats = MemoryStats {
+            files: 2,
+            chunks: vec![
+                ChunkCount {
+                    source: "memory".into(),
+                    model: "text-embedding-3-small".into(),
+                    chunks: 12,
+                },
+                ChunkCount {
+                    source: "session".into(),
+                    model: "text-embedding-3-small".into(),
+                    chunks: 3,
+                },
+            ],
+            cache_entries: 14,
+            cache_hits: 9,
+            cache_misses: 3,
+            db_bytes: 4096,
+        };
+        let out = format_memory_stats(&stats);
+        assert!(out.contains("files:      2"));
+        assert!(out.contains("db size:    4.0 KiB"));
+        let line = out
+            .lines()
+            .find(|l| l.contains("memory/text-embedding-3-small"))
+            .unwrap();
+        assert!(line.trim_end().ends_with("12"));
+        assert!(out.contains("14 entries, 9 hits / 3 misses (hit rate 75.0%)"));
+
+        let empty = format_memory_stats(&MemoryStats::default());
+        assert!(empty.contains("chunks:     none"));
+        assert!(empty.contains("hit rate n/a"));
+    }
+}
@@ -39,6 +39,7 @@ pub mod status_summary;
 pub mod status_update;
 pub mod telegram;
 pub mod uninstall;
+pub mod usage;
 pub mod whatsapp_send;
 
 pub use crate::shell::run_interactive_shell;
@@ -47,7 +48,7 @@ pub use admin::{
     exec_approvals_command, hooks_command, nodes_command, sandbox_command, skills_command,
     system_command, update_command, webhooks_command,
 };
-pub use ask::ask_command;
+pub use ask::{ask_command, build_agent_from_config, build_agent_pool_from_config};
 pub use bridge::bridge_command;
 pub use channels::{
     channels_add_command, channels_list_command, channels_logs_command, channels_remove_command,
@@ -60,7 +61,7 @@ pub use config::{
 pub use configure::{configure_command, configure_command_interactive, ConfigureInput};
 pub use cron::{
     cron_add_command, cron_disable_command, cron_enable_command, cron_list_command,
-    cron_remove_command,
+    cron_remove_command, cron_runs_command, CronAddOptions,
 };
 pub use discord::{discord_send_command, discord_send_dry_run_command};
 pub use doctor::{doctor_command, doctor_simple, format_doctor_result, DoctorResult};
@@ -80,13 +81,16 @@ pub use doctor_sandbox::{
 pub use doctor_security::{
     check_security, format_security_check, note_security_warnings, SecurityCheckResult,
 };
-pub use gateway::gateway_start_command;
+pub use gateway::{
+    gateway_start_command, gateway_token_issue_command, gateway_token_list_command,
+    gateway_token_revoke_command,
+};
 pub use health::{
     format_health_check_failure, format_health_result, health_command, CheckStatus, HealthCheck,
     HealthResult,
 };
 pub use logs::logs_tail_command;
-pub use memory::{memory_search_command, memory_sync_command};
+pub use memory::{memory_search_command, memory_stats_command, memory_sync_command};
 pub use message::{format_message, message_send_command, MessageSendOptions};
 pub use mission_control::mission_control_command;
 pub use models::models_list_command;
@@ -134,5 +138,6 @@ pub use status_update::{
 };
 pub use telegram::{telegram_send_command, telegram_send_dry_run_command};
 pub use uninstall::{uninstall_command, UninstallOptions};
+pub use usage::usage_report_command;
 pub use whatsapp_send::{send_whatsapp_media, send_whatsapp_message, send_whatsapp_template};
 
@@ -0,0 +1,120 @@
+//! usage — Token usage and cost report (`openkrab usage`).
+
+use crate::agents::usage::{aggregate_usage, UsageGroupBy};
+use crate::memory::MemoryStore;
+use crate::shared::usage_aggregates::UsageAggregates;
+use anyhow::{anyhow, Result};
+
+/// Report usage recorded in the memory database, grouped by
+/// `session`/`agent`/`provider`/`model`, optionally for the last `days`.
+pub fn usage_report_command(
+    db_path: Option<&str>,
+    group_by: &str,
+    days: Option<u32>,
+    json: bool,
+) -> Result<String> {
+    let group = UsageGroupBy::parse(group_by).ok_or_else(|| {
+        anyhow!(
+            "Unknown grouping '{}' (expected session, agent, provider or model)",
+            group_by
+        )
+    })?;
+    let store = MemoryStore::open(db_path.unwrap_or("memory.db"))?;
+    let since = days.map(|d| chrono::Utc::now() - chrono::Duration::days(d as i64));
+    let records = store.list_usage_records(since)?;
+    let aggregates = aggregate_usage(&records, group);
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&aggregates)?);
+    }
+    Ok(format_usage_report(&aggregates, group_by, days))
+}
+
+fn format_usage_report(aggregates: &UsageAggregates, group_by: &str, days: Option<u32>) -> String {
+    let period = match days {
+        Some(d) => format!("last {} day(s)", d),
+        None => "all time".to_string(),
+    };
+    if aggregates.tokens.is_empty() {
+        return format!("No usage recorded ({}).", period);
+    }
+
+    let mut rows: Vec<_> = aggregates.tokens.iter().collect();
+    rows.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(b.0)));
+
+    let width = rows
+        .iter()
+        .map(|(k, _)| k.len())
+        .max()
+        .unwrap_or(0)
+        .max(group_by.len());
+    let mut lines = vec![
+        format!("Token usage by {} ({}):", group_by, period),
+        format!(
+            "{:<width$}  {:>6}  {:>12}  {:>12}  {:>12}  {:>10}",
+            group_by,
+            "calls",
+            "input",
+            "output",
+            "total",
+            "cost",
+            width = width
+        ),
+    ];
+    for (key, usage) in rows {
+        let calls = aggregates.api_calls.get(key).map(|m| m.total).unwrap_or(0);
+        lines.push(format!(
+            "{:<width$}  {:>6}  {:>12}  {:>12}  {:>12}  {:>10}",
+            key,
+            calls,
+            usage.input,
+            usage.output,
+            usage.total,
+            format!("${:.4}", usage.estimated_cost),
+            width = width
+        ));
+    }
+    lines.push(format!(
+        "Total: {} tokens across {} calls, ${:.4}",
+        aggregates.total_tokens(),
+        aggregates.total_api_calls(),
+        aggregates.total_estimated_cost()
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::chat::TokenUsage;
+    use crate::agents::usage::UsageRecord;
+
+    #[test]
+    fn report_lists_groups_and_totals() {
+        let records = vec![
+            UsageRecord::new(
+                "s1",
+                "krab",
+                "openai",
+                "gpt-4o",
+                &TokenUsage::new(1000, 100),
+            ),
+            UsageRecord::new("s2", "krab", "ollama", "llama3.1", &TokenUsage::new(50, 5)),
+        ];
+        let agg = aggregate_usage(&records, UsageGroupBy::Provider);
+        let out = format_usage_report(&agg, "provider", Some(7));
+
+        assert!(out.starts_with("Token usage by provider (last 7 day(s)):"));
+        let openai_line = out.lines().find(|l| l.starts_with("openai")).unwrap();
+        assert!(openai_line.contains("1100"));
+        assert!(openai_line.contains("$0.0035"));
+        assert!(out.contains("Total: 1155 tokens across 2 calls"));
+    }
+
+    #[test]
+    fn empty_report_and_bad_grouping() {
+        let out = format_usage_report(&UsageAggregates::new(), "model", None);
+        assert_eq!(out, "No usage recorded (all time).");
+        assert!(usage_report_command(None, "planet", None, false).is_err());
+    }
+}
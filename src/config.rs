@@ -108,10 +108,17 @@ fn map_OPENKRAB_AGENTS(agents: Option<&crate::OPENKRAB_CONFIG::AgentsConfig>) ->
             tools: std::collections::HashMap::new(),
         });
 
-    AgentsConfig {
-        defaults,
-        list: Vec::new(),
-    }
+    let list = agents
+        .list
+        .iter()
+        .map(|a| AgentInstance {
+            id: a.id.clone(),
+            name: a.name.clone(),
+            model: a.model.as_ref().map(|m| m.primary.clone()),
+        })
+        .collect();
+
+    AgentsConfig { defaults, list }
 }
 
 fn map_OPENKRAB_AUTH(auth: Option<&crate::OPENKRAB_CONFIG::AuthConfig>) -> AuthConfig {
@@ -455,6 +455,7 @@ pub fn get_default_config() -> OpenKrabConfig {
             enabled: true,
             port: Some(18789),
             bind_address: Some("127.0.0.1".to_string()),
+            auth: None,
         }),
         logging: Some(crate::OPENKRAB_CONFIG::LoggingConfig {
             level: "info".to_string(),
@@ -537,6 +538,7 @@ mod tests {
             enabled: true,
             port: Some(8080),
             bind_address: Some("localhost".to_string()),
+            auth: None,
         });
 
         save_config_to_path(&config, config_path).unwrap();
@@ -559,6 +561,7 @@ mod tests {
             enabled: true,
             port: Some(9000),
             bind_address: None,
+            auth: None,
         });
         save_config_to_path(&config2, config_path).unwrap();
         let hash2 = compute_file_hash(config_path).unwrap();
@@ -135,6 +135,36 @@ fn validate_channels_config(
         }
     }
 
+    // Validate Signal accounts
+    for (name, acct) in &channels.signal {
+        if acct.enabled && acct.phone_number.is_none() {
+            errors.push(ValidationError {
+                field: format!("channels.signal.{}.phone_number", name),
+                message: "phone_number is required when account is enabled".to_string(),
+            });
+        }
+    }
+
+    // Validate Matrix accounts
+    for (name, acct) in &channels.matrix {
+        if !acct.enabled {
+            continue;
+        }
+        if !acct.user_id.as_deref().is_some_and(|id| id.starts_with('@')) {
+            errors.push(ValidationError {
+                field: format!("channels.matrix.{}.user_id", name),
+                message: "user_id (@user:server) is required when account is enabled"
+                    .to_string(),
+            });
+        }
+        if acct.access_token.is_none() {
+            errors.push(ValidationError {
+                field: format!("channels.matrix.{}.access_token", name),
+                message: "access_token is required when account is enabled".to_string(),
+            });
+        }
+    }
+
     // Validate HashMap-based channels
     let channel_types: &[(
         &str,
@@ -142,7 +172,6 @@ fn validate_channels_config(
     )] = &[
         ("slack", &channels.slack),
         ("whatsapp", &channels.whatsapp),
-        ("signal", &channels.signal),
         ("imessage", &channels.imessage),
         ("irc", &channels.irc),
         ("web", &channels.web),
@@ -271,6 +300,7 @@ mod tests {
                 enabled: true,
                 port: Some(8080),
                 bind_address: None,
+                auth: None,
             }),
             ..Default::default()
         };
@@ -301,6 +331,7 @@ mod tests {
                 enabled: true,
                 port: Some(0),
                 bind_address: None,
+                auth: None,
             }),
             ..Default::default()
         };
@@ -6,11 +6,15 @@
 //! and the thread-safe runtime singleton with gateway lifecycle.
 
 use crate::common::{Message, UserId};
+use crate::routing::resolve_route::RoutePeer;
 pub use crate::connectors::discord_client::*;
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
-use serenity::all::{Context, EventHandler, GatewayIntents, Message as SerenityMessage, Ready};
-use std::sync::{Arc, OnceLock, RwLock};
+use serenity::all::{
+    Channel, ChannelId, Context, EventHandler, GatewayIntents, Message as SerenityMessage, Ready,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::time::sleep;
 
@@ -796,8 +800,74 @@ fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
     chunks
 }
 
+/// Binding peer for a Discord message: the author in DMs, the channel in
+/// guilds.
+fn route_peer(in_guild: bool, channel_id: u64, author_id: u64) -> RoutePeer {
+    if in_guild {
+        RoutePeer {
+            kind: "channel".to_string(),
+            id: channel_id.to_string(),
+        }
+    } else {
+        RoutePeer {
+            kind: "direct".to_string(),
+            id: author_id.to_string(),
+        }
+    }
+}
+
+/// Binding peer and thread for a Discord message. A thread routes through
+/// its parent channel's bindings and gets its own session under it.
+fn route_target(
+    in_guild: bool,
+    channel_id: u64,
+    thread_parent: Option<u64>,
+    author_id: u64,
+) -> (RoutePeer, Option<String>) {
+    match thread_parent {
+        Some(parent) if in_guild => (
+            route_peer(true, parent, author_id),
+            Some(channel_id.to_string()),
+        ),
+        _ => (route_peer(in_guild, channel_id, author_id), None),
+    }
+}
+
 struct DiscordEventHandler {
     state: Arc<crate::gateway::GatewayState>,
+    /// Parent channel of each thread seen so far (`None` for plain channels).
+    /// There is no serenity cache, so channels are looked up once over HTTP.
+    thread_parents: Mutex<HashMap<u64, Option<u64>>>,
+}
+
+impl DiscordEventHandler {
+    async fn thread_parent(&self, ctx: &Context, channel_id: ChannelId) -> Option<u64> {
+        let key = channel_id.get();
+        if let Some(parent) = self
+            .thread_parents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+        {
+            return *parent;
+        }
+        let parent = match channel_id.to_channel(ctx).await {
+            Ok(Channel::Guild(channel)) if channel.thread_metadata.is_some() => {
+                channel.parent_id.map(|p| p.get())
+            }
+            Ok(_) => None,
+            Err(e) => {
+                // Not cached, so the next message retries.
+                tracing::debug!("[discord] channel lookup failed: {}", e);
+                return None;
+            }
+        };
+        self.thread_parents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, parent);
+        parent
+    }
 }
 
 #[serenity::async_trait]
@@ -822,23 +892,54 @@ impl EventHandler for DiscordEventHandler {
 
         mark_inbound();
 
-        let agent = match &self.state.agent {
-            Some(agent) => agent,
+        let guild_id = msg.guild_id.map(|g| g.get().to_string());
+        let role_ids: Vec<String> = msg
+            .member
+            .as_ref()
+            .map(|m| m.roles.iter().map(|r| r.get().to_string()).collect())
+            .unwrap_or_default();
+        let thread_parent = match msg.guild_id {
+            Some(_) => self.thread_parent(&ctx, msg.channel_id).await,
+            None => None,
+        };
+        let (peer, thread_id) = route_target(
+            guild_id.is_some(),
+            msg.channel_id.get(),
+            thread_parent,
+            msg.author.id.get(),
+        );
+        let routed = match self.state.agents.route(crate::agents::RouteQuery {
+            channel: "discord",
+            peer: Some(peer),
+            guild_id: guild_id.as_deref(),
+            member_role_ids: Some(&role_ids),
+            thread_id: thread_id.as_deref(),
+            ..Default::default()
+        }) {
+            Some(routed) => routed,
             None => {
                 let _ = msg.channel_id.say(&ctx.http, "Agent not available").await;
                 return;
             }
         };
 
-        let answer = agent.answer(&inbound.text).await;
+        let answer = self.state.answer_in_session(&routed, &inbound.text).await;
         match answer {
             Ok(text) => {
                 let chunks = chunk_text(&text, TEXT_CHUNK_LIMIT);
+                // In guilds the first chunk replies to the message so the
+                // answer stays attached to it; the rest follow in the channel.
+                let mut reply = guild_id.is_some();
                 for part in chunks {
                     if part.trim().is_empty() {
                         continue;
                     }
-                    if let Err(e) = msg.channel_id.say(&ctx.http, &part).await {
+                    let sent = if std::mem::take(&mut reply) {
+                        msg.reply(&ctx.http, &part).await
+                    } else {
+                        msg.channel_id.say(&ctx.http, &part).await
+                    };
+                    if let Err(e) = sent {
                         set_error_status(format!("send failed: {}", e));
                         break;
                     }
@@ -861,7 +962,10 @@ async fn run_gateway_session(state: Arc<crate::gateway::GatewayState>, token: &s
         | GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
-    let handler = DiscordEventHandler { state };
+    let handler = DiscordEventHandler {
+        state,
+        thread_parents: Mutex::new(HashMap::new()),
+    };
     let mut client = serenity::Client::builder(token, intents)
         .event_handler(handler)
         .await
@@ -1295,5 +1399,21 @@ mod tests {
         let parsed: MessageAction = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed, MessageAction::ThreadCreate);
     }
-}
 
+    #[test]
+    fn threads_route_through_their_parent_channel() {
+        let (peer, thread) = route_target(true, 30, Some(10), 7);
+        assert_eq!(peer.kind, "channel");
+        assert_eq!(peer.id, "10");
+        assert_eq!(thread.as_deref(), Some("30"));
+
+        let (peer, thread) = route_target(true, 10, None, 7);
+        assert_eq!(peer.id, "10");
+        assert_eq!(thread, None);
+
+        let (peer, thread) = route_target(false, 99, Some(10), 7);
+        assert_eq!(peer.kind, "direct");
+        assert_eq!(peer.id, "7");
+        assert_eq!(thread, None);
+    }
+}
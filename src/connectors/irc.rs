@@ -20,6 +20,19 @@ pub struct IrcConfig {
     pub password: Option<String>,
     /// Whether to use TLS.
     pub tls: bool,
+    /// SASL mechanism: "PLAIN" (account and `password`) or "EXTERNAL"
+    /// (TLS client certificate). Without it `password` is sent as PASS.
+    #[serde(default)]
+    pub sasl: Option<String>,
+    /// SASL account name (default: `nick`).
+    #[serde(default)]
+    pub sasl_username: Option<String>,
+    /// PEM client certificate path, for SASL EXTERNAL.
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM (PKCS#8) client key path, for SASL EXTERNAL.
+    #[serde(default)]
+    pub client_key: Option<String>,
 }
 
 impl Default for IrcConfig {
@@ -41,6 +54,10 @@ impl Default for IrcConfig {
             tls: std::env::var("IRC_TLS")
                 .map(|v| v == "1" || v == "true")
                 .unwrap_or(false),
+            sasl: std::env::var("IRC_SASL").ok(),
+            sasl_username: std::env::var("IRC_SASL_USERNAME").ok(),
+            client_cert: std::env::var("IRC_CLIENT_CERT").ok(),
+            client_key: std::env::var("IRC_CLIENT_KEY").ok(),
         }
     }
 }
@@ -56,8 +73,126 @@ impl IrcConfig {
         if self.nick.is_empty() {
             anyhow::bail!("IRC_NICK is required");
         }
+        match self.sasl_mechanism().as_deref() {
+            None => {}
+            Some("PLAIN") if self.password.is_none() => {
+                anyhow::bail!("IRC_PASSWORD is required for SASL PLAIN")
+            }
+            Some("PLAIN") => {}
+            Some("EXTERNAL") if !self.tls => anyhow::bail!("SASL EXTERNAL requires IRC_TLS"),
+            Some("EXTERNAL") if self.client_cert.is_none() || self.client_key.is_none() => {
+                anyhow::bail!("IRC_CLIENT_CERT and IRC_CLIENT_KEY are required for SASL EXTERNAL")
+            }
+            Some("EXTERNAL") => {}
+            Some(other) => anyhow::bail!("unsupported SASL mechanism: {}", other),
+        }
         Ok(())
     }
+
+    /// Upper-cased SASL mechanism, if one is configured.
+    pub fn sasl_mechanism(&self) -> Option<String> {
+        self.sasl
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_ascii_uppercase)
+    }
+}
+
+// ─── Protocol lines ───────────────────────────────────────────────────────────
+
+/// One parsed protocol line: `[@tags] [:prefix] COMMAND params [:trailing]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrcLine {
+    pub prefix: Option<String>,
+    pub command: String,
+    /// Middle params followed by the trailing one, if any.
+    pub params: Vec<String>,
+}
+
+impl IrcLine {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut rest = raw.trim_end_matches(['\r', '\n']);
+        if rest.starts_with('@') {
+            rest = rest.split_once(' ')?.1;
+        }
+        let prefix = match rest.strip_prefix(':') {
+            Some(stripped) => {
+                let (prefix, after) = stripped.split_once(' ')?;
+                rest = after;
+                Some(prefix.to_string())
+            }
+            None => None,
+        };
+        let (middle, trailing) = match rest.split_once(" :") {
+            Some((middle, trailing)) => (middle, Some(trailing)),
+            None => (rest, None),
+        };
+        let mut words = middle.split(' ').filter(|w| !w.is_empty());
+        let command = words.next()?.to_ascii_uppercase();
+        let mut params: Vec<String> = words.map(str::to_string).collect();
+        if let Some(trailing) = trailing {
+            params.push(trailing.to_string());
+        }
+        Some(Self {
+            prefix,
+            command,
+            params,
+        })
+    }
+
+    /// Nick of the sender (`nick` in `nick!user@host`).
+    pub fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        Some(prefix.split('!').next().unwrap_or(prefix))
+    }
+
+    pub fn param(&self, index: usize) -> Option<&str> {
+        self.params.get(index).map(String::as_str)
+    }
+}
+
+/// Text of a channel message addressed to `nick`: `nick: question` yields
+/// the question, a message naming `nick` elsewhere yields the whole text.
+pub fn addressed_text(text: &str, nick: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let nick_lower = nick.to_lowercase();
+    if let Some(after) = lower.strip_prefix(&nick_lower) {
+        if after.is_empty() || after.starts_with([':', ',', ' ']) {
+            let rest = text
+                .get(nick.len()..)
+                .unwrap_or_default()
+                .trim_start_matches([':', ','])
+                .trim();
+            return (!rest.is_empty()).then(|| rest.to_string());
+        }
+    }
+    let is_nick_char = |c: char| c.is_alphanumeric() || "-_[]\\`^{}|".contains(c);
+    lower
+        .match_indices(&nick_lower)
+        .any(|(at, _)| {
+            let before = lower[..at].chars().next_back();
+            let after = lower[at + nick_lower.len()..].chars().next();
+            !before.is_some_and(is_nick_char) && !after.is_some_and(is_nick_char)
+        })
+        .then(|| text.trim().to_string())
+}
+
+/// `AUTHENTICATE` lines carrying SASL PLAIN credentials (base64, 400-byte
+/// pieces, `+` closing a payload that ends on a piece boundary).
+pub fn build_sasl_plain(username: &str, password: &str) -> Vec<String> {
+    use base64::Engine;
+    let payload = base64::engine::general_purpose::STANDARD
+        .encode(format!("{}\0{}\0{}", username, username, password));
+    let mut lines: Vec<String> = payload
+        .as_bytes()
+        .chunks(400)
+        .map(|piece| format!("AUTHENTICATE {}\r\n", String::from_utf8_lossy(piece)))
+        .collect();
+    if payload.len() % 400 == 0 {
+        lines.push("AUTHENTICATE +\r\n".to_string());
+    }
+    lines
 }
 
 // ─── IRC message ──────────────────────────────────────────────────────────────
@@ -168,6 +303,40 @@ mod tests {
         assert!(build_nick("mybot").contains("NICK mybot"));
     }
 
+    #[test]
+    fn parse_protocol_lines() {
+        let line = IrcLine::parse("@time=x :alice!a@h PRIVMSG #ch :hi: there\r\n").unwrap();
+        assert_eq!(line.nick(), Some("alice"));
+        assert_eq!(line.command, "PRIVMSG");
+        assert_eq!(line.params, vec!["#ch", "hi: there"]);
+
+        let ping = IrcLine::parse("PING :tungsten.libera.chat").unwrap();
+        assert_eq!(ping.prefix, None);
+        assert_eq!(ping.param(0), Some("tungsten.libera.chat"));
+    }
+
+    #[test]
+    fn mentions_of_the_nick() {
+        assert_eq!(
+            addressed_text("KrabBot: what's up?", "krabbot").as_deref(),
+            Some("what's up?")
+        );
+        assert_eq!(
+            addressed_text("ask krabbot later", "krabbot").as_deref(),
+            Some("ask krabbot later")
+        );
+        assert_eq!(addressed_text("krabbots unite", "krabbot"), None);
+        assert_eq!(addressed_text("hello all", "krabbot"), None);
+    }
+
+    #[test]
+    fn sasl_plain_payload() {
+        assert_eq!(
+            build_sasl_plain("krab", "pw"),
+            vec!["AUTHENTICATE a3JhYgBrcmFiAHB3\r\n".to_string()]
+        );
+    }
+
     #[test]
     fn config_validate_missing_server() {
         let cfg = IrcConfig {
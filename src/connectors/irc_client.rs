@@ -0,0 +1,457 @@
+//! irc_client — live IRC connection answering through the gateway's agents.
+//!
+//! Connects (optionally over TLS), registers with SASL PLAIN/EXTERNAL or a
+//! server password, joins the configured channels and answers PING. Private
+//! messages and channel messages addressed to the bot nick are routed to an
+//! agent; replies are split to fit the 512-byte line limit and paced to stay
+//! under server flood limits. A dropped connection is retried with
+//! `polls::next_interval` back-off.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+use crate::agents::RouteQuery;
+use crate::connectors::irc::{
+    addressed_text, build_join, build_nick, build_privmsg, build_sasl_plain, build_user, IrcConfig,
+    IrcLine,
+};
+use crate::gateway::GatewayState;
+use crate::polls::{next_interval, PollConfig};
+use crate::routing::resolve_route::RoutePeer;
+use crate::shared::text_chunking::chunk_by_words;
+
+/// Longest line a server accepts, CRLF included.
+pub const MAX_LINE_BYTES: usize = 512;
+/// Room kept for the `:nick!user@host ` prefix a server adds when relaying.
+const RELAY_PREFIX_BYTES: usize = 100;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Outgoing message pacing: `burst` lines at once, then one per `interval`.
+#[derive(Debug, Clone, Copy)]
+pub struct FloodControl {
+    pub burst: u32,
+    pub interval: Duration,
+}
+
+impl Default for FloodControl {
+    fn default() -> Self {
+        Self {
+            burst: 4,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Binding peer for a message sent to `target` by `from`.
+pub fn route_peer(target: &str, from: &str, private: bool) -> RoutePeer {
+    if private {
+        RoutePeer {
+            kind: "direct".to_string(),
+            id: from.to_string(),
+        }
+    } else {
+        RoutePeer {
+            kind: "group".to_string(),
+            id: target.to_string(),
+        }
+    }
+}
+
+/// PRIVMSG lines carrying `text` to `target`, each within `MAX_LINE_BYTES`
+/// once relayed. IRC has no multi-line messages, so every line of `text` is
+/// sent on its own.
+pub fn reply_lines(target: &str, text: &str) -> Vec<String> {
+    let overhead = build_privmsg(target, "").len() + RELAY_PREFIX_BYTES;
+    let budget = MAX_LINE_BYTES.saturating_sub(overhead);
+    text.lines()
+        .flat_map(|line| chunk_by_words(line, budget))
+        .map(|chunk| build_privmsg(target, &chunk))
+        .collect()
+}
+
+trait IrcStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> IrcStream for T {}
+
+async fn connect(config: &IrcConfig) -> Result<Box<dyn IrcStream>> {
+    let tcp = tokio::time::timeout(
+        CONNECT_TIMEOUT,
+        TcpStream::connect((config.server.as_str(), config.port)),
+    )
+    .await
+    .map_err(|_| anyhow!("timed out connecting to {}", config.server))??;
+    if !config.tls {
+        return Ok(Box::new(tcp));
+    }
+    let mut builder = tokio_native_tls::native_tls::TlsConnector::builder();
+    if let (Some(cert), Some(key)) = (&config.client_cert, &config.client_key) {
+        let cert = std::fs::read(cert).with_context(|| format!("reading {}", cert))?;
+        let key = std::fs::read(key).with_context(|| format!("reading {}", key))?;
+        builder.identity(tokio_native_tls::native_tls::Identity::from_pkcs8(
+            &cert, &key,
+        )?);
+    }
+    let connector = tokio_native_tls::TlsConnector::from(builder.build()?);
+    Ok(Box::new(connector.connect(&config.server, tcp).await?))
+}
+
+/// Write queued lines: `urgent` ones (registration, PONG) at once, `paced`
+/// ones (replies) under `flood`.
+async fn write_lines<W: AsyncWrite + Unpin>(
+    mut writer: W,
+    mut urgent: UnboundedReceiver<String>,
+    mut paced: UnboundedReceiver<String>,
+    flood: FloodControl,
+) -> Result<()> {
+    let window = flood.interval * flood.burst.max(1);
+    let mut clock = Instant::now();
+    loop {
+        let line = tokio::select! {
+            biased;
+            line = urgent.recv() => line,
+            line = paced.recv() => {
+                let now = Instant::now();
+                clock = clock.max(now) + flood.interval;
+                if clock > now + window {
+                    tokio::time::sleep_until(clock - window).await;
+                }
+                line
+            }
+        };
+        let Some(line) = line else {
+            return Ok(());
+        };
+        writer.write_all(line.as_bytes()).await?;
+        writer.flush().await?;
+    }
+}
+
+/// A bot connection that answers through the gateway's agents.
+pub struct IrcClient {
+    state: Arc<GatewayState>,
+    config: IrcConfig,
+    flood: FloodControl,
+}
+
+impl IrcClient {
+    pub fn new(state: Arc<GatewayState>, config: IrcConfig) -> Self {
+        Self {
+            state,
+            config,
+            flood: FloodControl::default(),
+        }
+    }
+
+    pub fn with_flood_control(mut self, flood: FloodControl) -> Self {
+        self.flood = flood;
+        self
+    }
+
+    /// Connect, and reconnect with back-off, until the runtime shuts down.
+    pub async fn run(self: Arc<Self>) {
+        let backoff = PollConfig::default();
+        let mut interval = backoff.initial_interval;
+        loop {
+            let mut registered = false;
+            let error = match self.clone().run_session(&mut registered).await {
+                Ok(()) => anyhow!("connection closed"),
+                Err(e) => e,
+            };
+            if registered {
+                interval = backoff.initial_interval;
+            }
+            interval = next_interval(interval, &backoff, true);
+            tracing::warn!(
+                "[irc] {}: {}; reconnecting in {:?}",
+                self.config.server,
+                error,
+                interval
+            );
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Serve one connection until it drops. `registered` is set once the
+    /// server welcomes the bot.
+    async fn run_session(self: Arc<Self>, registered: &mut bool) -> Result<()> {
+        let stream = connect(&self.config).await?;
+        let (reader, writer) = tokio::io::split(stream);
+        let (urgent, urgent_rx) = unbounded_channel();
+        let (paced, paced_rx) = unbounded_channel();
+        let writer = tokio::spawn(write_lines(writer, urgent_rx, paced_rx, self.flood));
+        let result = self
+            .serve(BufReader::new(reader), &urgent, &paced, registered)
+            .await;
+        writer.abort();
+        result
+    }
+
+    async fn serve<R: AsyncRead + Unpin>(
+        self: &Arc<Self>,
+        mut reader: BufReader<R>,
+        urgent: &UnboundedSender<String>,
+        paced: &UnboundedSender<String>,
+        registered: &mut bool,
+    ) -> Result<()> {
+        let send = |line: String| urgent.send(line).map_err(|_| anyhow!("writer stopped"));
+        let config = &self.config;
+        let mut nick = config.nick.clone();
+        let mechanism = config.sasl_mechanism();
+        if mechanism.is_some() {
+            send("CAP REQ :sasl\r\n".to_string())?;
+        } else if let Some(password) = &config.password {
+            send(format!("PASS {}\r\n", password))?;
+        }
+        send(build_nick(&nick))?;
+        send(build_user(&nick))?;
+
+        let mut raw = Vec::new();
+        loop {
+            raw.clear();
+            if reader.read_until(b'\n', &mut raw).await? == 0 {
+                return Ok(());
+            }
+            let Some(line) = IrcLine::parse(&String::from_utf8_lossy(&raw)) else {
+                continue;
+            };
+            match line.command.as_str() {
+                "PING" => send(format!("PONG :{}\r\n", line.param(0).unwrap_or_default()))?,
+                "CAP" => match (line.param(1), mechanism.as_deref()) {
+                    (Some("ACK"), Some(mechanism)) => {
+                        send(format!("AUTHENTICATE {}\r\n", mechanism))?
+                    }
+                    (Some("NAK"), Some(_)) => bail!("server refused SASL"),
+                    _ => {}
+                },
+                "AUTHENTICATE" if line.param(0) == Some("+") => {
+                    if mechanism.as_deref() == Some("PLAIN") {
+                        let username = config.sasl_username.as_deref().unwrap_or(&config.nick);
+                        let password = config.password.as_deref().unwrap_or_default();
+                        for piece in build_sasl_plain(username, password) {
+                            send(piece)?;
+                        }
+                    } else {
+                        send("AUTHENTICATE +\r\n".to_string())?;
+                    }
+                }
+                // RPL_SASLSUCCESS
+                "903" => send("CAP END\r\n".to_string())?,
+                // ERR_NICKLOCKED, ERR_SASLFAIL, ERR_SASLTOOLONG, ERR_SASLABORTED
+                "902" | "904" | "905" | "906" => bail!(
+                    "SASL authentication failed: {}",
+                    line.params.last().map(String::as_str).unwrap_or_default()
+                ),
+                // RPL_WELCOME
+                "001" => {
+                    *registered = true;
+                    if let Some(confirmed) = line.param(0) {
+                        nick = confirmed.to_string();
+                    }
+                    tracing::info!("[irc] registered on {} as {}", config.server, nick);
+                    for channel in &config.channels {
+                        send(build_join(channel))?;
+                    }
+                }
+                // ERR_NICKNAMEINUSE
+                "433" if !*registered => {
+                    nick.push('_');
+                    send(build_nick(&nick))?;
+                }
+                "PRIVMSG" => self.on_privmsg(&line, &nick, paced),
+                "ERROR" => bail!(
+                    "server closed the link: {}",
+                    line.param(0).unwrap_or_default()
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    fn on_privmsg(self: &Arc<Self>, line: &IrcLine, nick: &str, paced: &UnboundedSender<String>) {
+        let (Some(from), Some(target), Some(text)) = (line.nick(), line.param(0), line.param(1))
+        else {
+            return;
+        };
+        // CTCP requests (VERSION, ACTION, ...) are not conversation.
+        if text.starts_with('\u{1}') || from.eq_ignore_ascii_case(nick) {
+            return;
+        }
+        let private = target.eq_ignore_ascii_case(nick);
+        let question = if private {
+            Some(text.trim().to_string()).filter(|t| !t.is_empty())
+        } else {
+            addressed_text(text, nick)
+        };
+        let Some(question) = question else {
+            return;
+        };
+        let reply_to = if private { from } else { target }.to_string();
+        let peer = route_peer(target, from, private);
+        let from = from.to_string();
+        let client = self.clone();
+        let paced = paced.clone();
+        tokio::spawn(async move {
+            let answer = client.answer(peer, &question).await;
+            let answer = if private {
+                answer
+            } else {
+                format!("{}: {}", from, answer)
+            };
+            for line in reply_lines(&reply_to, &answer) {
+                if paced.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    async fn answer(&self, peer: RoutePeer, question: &str) -> String {
+        let Some(routed) = self.state.agents.route(RouteQuery {
+            channel: "irc",
+            peer: Some(peer),
+            ..Default::default()
+        }) else {
+            return "Agent not available".to_string();
+        };
+        tracing::debug!("[irc] answering in {}", routed.route.session_key);
+        match self.state.answer_in_session(&routed, question).await {
+            Ok(answer) => answer,
+            Err(e) => {
+                tracing::warn!("[irc] agent failed: {}", e);
+                format!("unavailable: {}", e)
+            }
+        }
+    }
+}
+
+/// Run the IRC connection described by `config` until shutdown.
+pub async fn monitor(state: Arc<GatewayState>, config: IrcConfig) {
+    Arc::new(IrcClient::new(state, config)).run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::GatewayServer;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use tokio::io::{AsyncBufReadExt, Lines};
+    use tokio::net::tcp::OwnedReadHalf;
+    use tokio::net::TcpListener;
+
+    fn long_answer() -> String {
+        (0..150)
+            .map(|i| format!("word{}", i))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    async fn expect(lines: &mut Lines<BufReader<OwnedReadHalf>>, want: &str) {
+        let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+            .await
+            .expect("timed out waiting for the client")
+            .unwrap()
+            .unwrap();
+        assert_eq!(line, want);
+    }
+
+    #[test]
+    fn replies_fit_the_line_limit() {
+        let lines = reply_lines("#openkrab", &format!("{}\nsecond line", long_answer()));
+        assert!(lines.len() > 2);
+        assert!(lines
+            .iter()
+            .all(|l| l.len() + RELAY_PREFIX_BYTES <= MAX_LINE_BYTES));
+        assert_eq!(lines.last().unwrap(), "PRIVMSG #openkrab :second line\r\n");
+    }
+
+    #[tokio::test]
+    async fn registers_with_sasl_answers_mentions_and_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::fixed(long_answer()));
+        let config = IrcConfig {
+            server: "127.0.0.1".to_string(),
+            port,
+            nick: "krabbot".to_string(),
+            channels: vec!["#openkrab".to_string()],
+            password: Some("hunter2".to_string()),
+            tls: false,
+            sasl: Some("plain".to_string()),
+            sasl_username: Some("krab".to_string()),
+            client_cert: None,
+            client_key: None,
+        };
+        let client = IrcClient::new(Arc::new(server), config).with_flood_control(FloodControl {
+            burst: 2,
+            interval: Duration::from_millis(10),
+        });
+        let running = tokio::spawn(Arc::new(client).run());
+
+        let (socket, _) = listener.accept().await.unwrap();
+        let (read, mut write) = socket.into_split();
+        let mut lines = BufReader::new(read).lines();
+        expect(&mut lines, "CAP REQ :sasl").await;
+        expect(&mut lines, "NICK krabbot").await;
+        expect(&mut lines, "USER krabbot 0 * :krabbot").await;
+        write
+            .write_all(b":irc.test CAP * ACK :sasl\r\n")
+            .await
+            .unwrap();
+        expect(&mut lines, "AUTHENTICATE PLAIN").await;
+        write.write_all(b"AUTHENTICATE +\r\n").await.unwrap();
+        expect(&mut lines, "AUTHENTICATE a3JhYgBrcmFiAGh1bnRlcjI=").await;
+        write
+            .write_all(b":irc.test 903 krabbot :SASL authentication successful\r\n")
+            .await
+            .unwrap();
+        expect(&mut lines, "CAP END").await;
+        write
+            .write_all(b":irc.test 001 krabbot :Welcome\r\nPING :irc.test\r\n")
+            .await
+            .unwrap();
+        expect(&mut lines, "JOIN #openkrab").await;
+        expect(&mut lines, "PONG :irc.test").await;
+
+        write
+            .write_all(b":alice!a@host PRIVMSG #openkrab :nice weather\r\n")
+            .await
+            .unwrap();
+        write
+            .write_all(b":alice!a@host PRIVMSG #openkrab :krabbot: tell me a lot\r\n")
+            .await
+            .unwrap();
+        let mut said = Vec::new();
+        while said.join(" ").len() < "alice: ".len() + long_answer().len() {
+            let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert!(line.len() + 2 + RELAY_PREFIX_BYTES <= MAX_LINE_BYTES);
+            said.push(
+                line.strip_prefix("PRIVMSG #openkrab :")
+                    .expect("only the mention is answered")
+                    .to_string(),
+            );
+        }
+        assert!(said.len() > 1);
+        assert_eq!(said.join(" "), format!("alice: {}", long_answer()));
+
+        // The server drops the link; the client comes back.
+        drop(write);
+        drop(lines);
+        let (socket, _) = tokio::time::timeout(Duration::from_secs(30), listener.accept())
+            .await
+            .expect("client did not reconnect")
+            .unwrap();
+        let mut lines = BufReader::new(socket.into_split().0).lines();
+        expect(&mut lines, "CAP REQ :sasl").await;
+        running.abort();
+    }
+}
@@ -287,14 +287,21 @@ pub async fn handle_events(state: Arc<GatewayState>, payload: serde_json::Value)
         tracing::info!("[line] Received: {:?}", normalized);
 
         tokio::spawn(async move {
-            let agent = match state_clone.agent.as_ref() {
-                Some(a) => a,
+            let routed = match state_clone.agents.route(crate::agents::RouteQuery {
+                channel: "line",
+                peer: Some(crate::routing::resolve_route::RoutePeer {
+                    kind: "direct".to_string(),
+                    id: user_id.clone(),
+                }),
+                ..Default::default()
+            }) {
+                Some(routed) => routed,
                 None => {
                     tracing::error!("[line] Agent not available");
                     return;
                 }
             };
-            match agent.answer(&text).await {
+            match state_clone.answer_in_session(&routed, &text).await {
                 Ok(answer) => {
                     if let Ok(token) = std::env::var("LINE_CHANNEL_ACCESS_TOKEN") {
                         if let Some(reply_token) = reply_token.as_ref() {
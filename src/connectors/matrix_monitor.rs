@@ -0,0 +1,270 @@
+//! matrix_monitor — answers Matrix room messages through the gateway's agents.
+//!
+//! Long-polls `/sync` for one account, skipping whatever the first sync
+//! returns so old messages aren't answered on startup. Each new text message
+//! is routed as a `group` peer (a DM is a two-member room) and answered with
+//! a reply to it. Failed syncs are retried with `polls::next_interval`
+//! back-off and reported in the monitor's `ChannelHealth`.
+
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use crate::agents::RouteQuery;
+use crate::gateway::monitor_manager::HealthReporter;
+use crate::gateway::GatewayServer;
+use crate::matrix::{self, MatrixConfig, ParsedMatrixMessage};
+use crate::polls::{next_interval, PollConfig};
+use crate::routing::resolve_route::RoutePeer;
+use crate::OPENKRAB_CONFIG::MatrixAccountConfig;
+
+/// A configured Matrix account.
+#[derive(Debug, Clone)]
+pub struct MatrixAccount {
+    pub account_id: String,
+    pub config: MatrixConfig,
+    /// Senders that may talk to the agent; empty allows everyone.
+    pub allowlist: Vec<String>,
+}
+
+impl MatrixAccount {
+    pub fn from_config(account_id: &str, cfg: &MatrixAccountConfig) -> Result<Self> {
+        let config = MatrixConfig {
+            homeserver: cfg
+                .homeserver
+                .as_deref()
+                .unwrap_or("https://matrix.org")
+                .trim_end_matches('/')
+                .to_string(),
+            access_token: cfg.access_token.clone().unwrap_or_default(),
+            user_id: cfg.user_id.clone().unwrap_or_default(),
+            encryption: None,
+            sync_timeout_ms: Some(30_000),
+        };
+        config.validate()?;
+        Ok(Self {
+            account_id: account_id.to_string(),
+            config,
+            allowlist: cfg.allowlist.clone(),
+        })
+    }
+
+    fn accepts(&self, msg: &ParsedMatrixMessage) -> bool {
+        msg.sender != self.config.user_id
+            && !msg.body.trim().is_empty()
+            && (self.allowlist.is_empty() || self.allowlist.contains(&msg.sender))
+    }
+}
+
+/// Binding peer for a Matrix room.
+pub fn route_peer(room_id: &str) -> RoutePeer {
+    RoutePeer {
+        kind: "group".to_string(),
+        id: room_id.to_string(),
+    }
+}
+
+/// Run the sync loop until `stop_rx` flips.
+pub fn spawn_matrix_monitor(
+    state: Arc<GatewayServer>,
+    account: MatrixAccount,
+    health: HealthReporter,
+    mut stop_rx: watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let monitor = Arc::new(MatrixMonitor {
+            state,
+            client: reqwest::Client::new(),
+            account,
+        });
+        let backoff = PollConfig::default();
+        let mut interval = backoff.initial_interval;
+        let mut since: Option<String> = None;
+        loop {
+            let sync = tokio::select! {
+                sync = matrix::sync_once(&monitor.client, &monitor.account.config, since.as_deref()) => sync,
+                _ = stop_rx.changed() => break,
+            };
+            match sync {
+                Ok(sync) => {
+                    health.healthy(None);
+                    interval = backoff.initial_interval;
+                    if since.is_some() {
+                        for msg in matrix::extract_text_messages(&sync) {
+                            if monitor.account.accepts(&msg) {
+                                tokio::spawn(monitor.clone().answer(msg));
+                            }
+                        }
+                    }
+                    since = Some(sync.next_batch);
+                }
+                Err(e) => {
+                    health.failed(&e.to_string());
+                    interval = next_interval(interval, &backoff, true);
+                    tracing::warn!(
+                        "[matrix] {} sync failed: {}; retrying in {:?}",
+                        monitor.account.account_id,
+                        e,
+                        interval
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(interval) => {}
+                        _ = stop_rx.changed() => break,
+                    }
+                }
+            }
+        }
+        tracing::info!("[matrix] {} monitor stopped", monitor.account.account_id);
+    })
+}
+
+struct MatrixMonitor {
+    state: Arc<GatewayServer>,
+    client: reqwest::Client,
+    account: MatrixAccount,
+}
+
+impl MatrixMonitor {
+    async fn answer(self: Arc<Self>, msg: ParsedMatrixMessage) {
+        let routed = self.state.agents.route(RouteQuery {
+            channel: "matrix",
+            account_id: Some(&self.account.account_id),
+            peer: Some(route_peer(&msg.room_id)),
+            ..Default::default()
+        });
+        let reply = match routed {
+            Some(routed) => {
+                tracing::debug!("[matrix] {} in {}", msg.event_id, routed.route.session_key);
+                match self.state.answer_in_session(&routed, &msg.body).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        tracing::warn!("[matrix] agent failed: {}", e);
+                        format!("unavailable: {}", e)
+                    }
+                }
+            }
+            None => "Agent not available".to_string(),
+        };
+        if let Err(e) = self.reply(&msg, &reply).await {
+            tracing::warn!("[matrix] failed to send reply: {}", e);
+        }
+    }
+
+    async fn reply(&self, msg: &ParsedMatrixMessage, text: &str) -> Result<String> {
+        matrix::send_reply(
+            &self.client,
+            &self.account.config,
+            &msg.room_id,
+            text,
+            &msg.event_id,
+        )
+        .await
+        .map_err(|e| anyhow!("{} in {}: {}", msg.event_id, msg.room_id, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::types::ChannelStatus;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use axum::extract::{Path, Query, State};
+    use axum::routing::{get, put};
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn text_event(id: &str, sender: &str, body: &str) -> Value {
+        json!({
+            "event_id": id,
+            "room_id": "!room:example.org",
+            "sender": sender,
+            "type": "m.room.message",
+            "content": { "msgtype": "m.text", "body": body },
+            "origin_server_ts": 1_760_000_000_000i64
+        })
+    }
+
+    type Sent = Arc<Mutex<Vec<(String, Value)>>>;
+
+    async fn sync(Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+        let room = |events: Vec<Value>| json!({ "join": { "!room:example.org": { "timeline": { "events": events } } } });
+        match query.get("since").map(String::as_str) {
+            None => Json(json!({
+                "next_batch": "s1",
+                "rooms": room(vec![text_event("$old", "@alice:example.org", "from before")])
+            })),
+            Some("s1") => Json(json!({
+                "next_batch": "s2",
+                "rooms": room(vec![
+                    text_event("$own", "@bot:example.org", "my own reply"),
+                    text_event("$new", "@alice:example.org", "status?"),
+                ])
+            })),
+            Some(_) => {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Json(json!({ "next_batch": "s2" }))
+            }
+        }
+    }
+
+    async fn send(
+        Path((room, _txn)): Path<(String, String)>,
+        State(sent): State<Sent>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        sent.lock().unwrap().push((room, body));
+        Json(json!({ "event_id": "$reply" }))
+    }
+
+    #[tokio::test]
+    async fn new_messages_are_answered_as_replies() {
+        let sent: Sent = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/_matrix/client/v3/sync", get(sync))
+            .route(
+                "/_matrix/client/v3/rooms/:room/send/m.room.message/:txn",
+                put(send),
+            )
+            .with_state(sent.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::default());
+        let account = MatrixAccount::from_config(
+            "default",
+            &MatrixAccountConfig {
+                enabled: true,
+                homeserver: Some(format!("http://{}/", addr)),
+                user_id: Some("@bot:example.org".to_string()),
+                access_token: Some("syt_token".to_string()),
+                allowlist: vec![],
+            },
+        )
+        .unwrap();
+        let health = HealthReporter::new("matrix:default");
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let handle = spawn_matrix_monitor(Arc::new(server), account, health.clone(), stop_rx);
+
+        for _ in 0..100 {
+            if !sent.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        stop_tx.send(true).unwrap();
+        handle.await.unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1, "only the new message is answered");
+        let (room, body) = &sent[0];
+        assert_eq!(room, "!room:example.org");
+        assert_eq!(body["body"], "re: status?");
+        assert_eq!(body["m.relates_to"]["m.in_reply_to"]["event_id"], "$new");
+        assert_eq!(health.snapshot().status, ChannelStatus::Healthy);
+    }
+}
@@ -1,7 +1,8 @@
 //! mattermost — Mattermost connector.
 //! Ported from `openkrab/extensions/mattermost/` (Phase 11).
 //!
-//! Uses the Mattermost REST API v4 and Incoming Webhooks.
+//! Uses the Mattermost REST API v4 and Incoming Webhooks. The live
+//! WebSocket event stream is handled by `mattermost_monitor`.
 
 use crate::common::{Message, UserId};
 use anyhow::{bail, Result};
@@ -112,6 +113,44 @@ pub fn build_post_payload(channel_id: &str, message: &str) -> serde_json::Value
     })
 }
 
+/// Build a REST API post payload replying in the thread rooted at `root_id`.
+pub fn build_reply_payload(channel_id: &str, message: &str, root_id: &str) -> serde_json::Value {
+    let mut v = build_post_payload(channel_id, message);
+    v["root_id"] = serde_json::json!(root_id);
+    v
+}
+
+/// WebSocket event stream URL for `server_url`.
+pub fn websocket_url(server_url: &str) -> String {
+    let base = server_url.trim_end_matches('/');
+    let base = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        base.to_string()
+    };
+    format!("{}/api/v4/websocket", base)
+}
+
+/// The account behind a token (`GET /api/v4/users/me`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MattermostUser {
+    pub id: String,
+    pub username: String,
+}
+
+pub async fn get_me(client: &reqwest::Client, cfg: &MattermostConfig) -> Result<MattermostUser> {
+    let url = format!("{}/api/v4/users/me", cfg.server_url.trim_end_matches('/'));
+    let resp = client
+        .get(&url)
+        .bearer_auth(&cfg.access_token)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(resp.json().await?)
+}
+
 /// Send a message via incoming webhook.
 pub async fn send_webhook(
     client: &reqwest::Client,
@@ -136,12 +175,32 @@ pub async fn send_post(
     channel_id: &str,
     text: &str,
 ) -> Result<serde_json::Value> {
-    let url = format!("{}/api/v4/posts", cfg.server_url);
     let payload = build_post_payload(channel_id, text);
+    create_post(client, cfg, &payload).await
+}
+
+/// Reply in the thread rooted at `root_id`.
+pub async fn send_reply(
+    client: &reqwest::Client,
+    cfg: &MattermostConfig,
+    channel_id: &str,
+    root_id: &str,
+    text: &str,
+) -> Result<serde_json::Value> {
+    let payload = build_reply_payload(channel_id, text, root_id);
+    create_post(client, cfg, &payload).await
+}
+
+async fn create_post(
+    client: &reqwest::Client,
+    cfg: &MattermostConfig,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let url = format!("{}/api/v4/posts", cfg.server_url.trim_end_matches('/'));
     let resp = client
         .post(&url)
         .bearer_auth(&cfg.access_token)
-        .json(&payload)
+        .json(payload)
         .send()
         .await?
         .error_for_status()?;
@@ -209,6 +268,21 @@ mod tests {
         assert_eq!(v["channel"].as_str(), Some("#general"));
     }
 
+    #[test]
+    fn reply_payload_and_websocket_url() {
+        let v = build_reply_payload("ch1", "hi", "root1");
+        assert_eq!(v["channel_id"], "ch1");
+        assert_eq!(v["root_id"], "root1");
+        assert_eq!(
+            websocket_url("https://mm.example.com/"),
+            "wss://mm.example.com/api/v4/websocket"
+        );
+        assert_eq!(
+            websocket_url("http://127.0.0.1:8065"),
+            "ws://127.0.0.1:8065/api/v4/websocket"
+        );
+    }
+
     #[test]
     fn normalize_inbound_test() {
         let msg = ParsedMattermostMessage {
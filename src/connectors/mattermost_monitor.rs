@@ -0,0 +1,380 @@
+//! mattermost_monitor — answers Mattermost posts over the WebSocket event stream.
+//!
+//! Connects to `/api/v4/websocket` with the bot token and watches `posted`
+//! events. Direct messages and posts that mention the bot go to the agent;
+//! the reply is threaded under the post (or the thread it is already in) via
+//! `root_id`, which also keys the session. The stream is reopened with
+//! `polls::next_interval` back-off when it drops.
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::agents::RouteQuery;
+use crate::connectors::mattermost::{self, MattermostConfig, MattermostUser};
+use crate::gateway::GatewayState;
+use crate::polls::{next_interval, PollConfig};
+use crate::routing::resolve_route::RoutePeer;
+
+/// A post as carried in a `posted` event.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MattermostPost {
+    pub id: String,
+    pub channel_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub root_id: String,
+    #[serde(default)]
+    pub message: String,
+    /// Set on system messages (joins, header changes, ...).
+    #[serde(default, rename = "type")]
+    pub kind: String,
+}
+
+/// A post addressed to the bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MattermostInbound {
+    pub post: MattermostPost,
+    /// `D` (direct), `G` (group DM), `O` (public) or `P` (private).
+    pub channel_type: String,
+    /// Message text with the bot's @-mention removed.
+    pub text: String,
+}
+
+impl MattermostInbound {
+    /// Direct messages and mentions; the bot's own and system posts are
+    /// ignored.
+    pub fn from_event(event: &Value, bot: &MattermostUser) -> Option<Self> {
+        if event.get("event").and_then(|e| e.as_str()) != Some("posted") {
+            return None;
+        }
+        let data = event.get("data")?;
+        let post: MattermostPost = serde_json::from_str(data.get("post")?.as_str()?).ok()?;
+        if post.user_id == bot.id || !post.kind.is_empty() {
+            return None;
+        }
+        let channel_type = data
+            .get("channel_type")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .to_string();
+        // `mentions` is a JSON-encoded array of user ids.
+        let mentioned = data
+            .get("mentions")
+            .and_then(|m| m.as_str())
+            .and_then(|m| serde_json::from_str::<Vec<String>>(m).ok())
+            .is_some_and(|ids| ids.contains(&bot.id));
+        if channel_type != "D" && !mentioned {
+            return None;
+        }
+        let text = strip_mention(&post.message, &bot.username);
+        if text.is_empty() {
+            return None;
+        }
+        Some(Self {
+            post,
+            channel_type,
+            text,
+        })
+    }
+
+    /// Post the reply threads under: the existing thread, or the post itself.
+    pub fn root_id(&self) -> &str {
+        if self.post.root_id.is_empty() {
+            &self.post.id
+        } else {
+            &self.post.root_id
+        }
+    }
+
+    pub fn route_peer(&self) -> RoutePeer {
+        if self.channel_type == "D" {
+            RoutePeer {
+                kind: "direct".to_string(),
+                id: self.post.user_id.clone(),
+            }
+        } else {
+            RoutePeer {
+                kind: "group".to_string(),
+                id: self.post.channel_id.clone(),
+            }
+        }
+    }
+}
+
+/// Remove `@username` mentions and tidy the surrounding whitespace.
+pub fn strip_mention(text: &str, username: &str) -> String {
+    let mention = format!("@{}", username);
+    text.split_whitespace()
+        .filter(|word| {
+            !word
+                .trim_end_matches([',', ':', '.', '!', '?'])
+                .eq_ignore_ascii_case(&mention)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A Mattermost bot answering through the gateway's agents.
+pub struct MattermostMonitor {
+    state: Arc<GatewayState>,
+    client: reqwest::Client,
+    config: MattermostConfig,
+}
+
+impl MattermostMonitor {
+    pub fn new(state: Arc<GatewayState>, config: MattermostConfig) -> Self {
+        Self {
+            state,
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Follow the event stream, reconnecting with back-off, until shutdown.
+    pub async fn run(self: Arc<Self>) {
+        let backoff = PollConfig::default();
+        let mut interval = backoff.initial_interval;
+        loop {
+            let mut connected = false;
+            let error = match self.clone().stream_events(&mut connected).await {
+                Ok(()) => anyhow!("event stream closed"),
+                Err(e) => e,
+            };
+            if connected {
+                interval = backoff.initial_interval;
+            }
+            interval = next_interval(interval, &backoff, true);
+            tracing::warn!("[mattermost] {}; reconnecting in {:?}", error, interval);
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn stream_events(self: Arc<Self>, connected: &mut bool) -> Result<()> {
+        let bot = mattermost::get_me(&self.client, &self.config).await?;
+        let mut request =
+            mattermost::websocket_url(&self.config.server_url).into_client_request()?;
+        request.headers_mut().insert(
+            "Authorization",
+            format!("Bearer {}", self.config.access_token).parse()?,
+        );
+        let (ws, _) = connect_async(request).await?;
+        *connected = true;
+        tracing::info!("[mattermost] connected as @{}", bot.username);
+        let (mut write, mut read) = ws.split();
+        while let Some(msg) = read.next().await {
+            let text = match msg? {
+                WsMessage::Text(text) => text,
+                WsMessage::Ping(data) => {
+                    write.send(WsMessage::Pong(data)).await?;
+                    continue;
+                }
+                WsMessage::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(event) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            if let Some(inbound) = MattermostInbound::from_event(&event, &bot) {
+                tokio::spawn(self.clone().answer(inbound));
+            }
+        }
+        Ok(())
+    }
+
+    async fn answer(self: Arc<Self>, inbound: MattermostInbound) {
+        let routed = self.state.agents.route(RouteQuery {
+            channel: "mattermost",
+            peer: Some(inbound.route_peer()),
+            thread_id: Some(inbound.root_id()),
+            ..Default::default()
+        });
+        let reply = match routed {
+            Some(routed) => {
+                tracing::debug!(
+                    "[mattermost] {} in {}",
+                    inbound.post.id,
+                    routed.route.session_key
+                );
+                match self.state.answer_in_session(&routed, &inbound.text).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        tracing::warn!("[mattermost] agent failed: {}", e);
+                        format!("unavailable: {}", e)
+                    }
+                }
+            }
+            None => "Agent not available".to_string(),
+        };
+        if let Err(e) = mattermost::send_reply(
+            &self.client,
+            &self.config,
+            &inbound.post.channel_id,
+            inbound.root_id(),
+            &reply,
+        )
+        .await
+        {
+            tracing::warn!("[mattermost] failed to reply to {}: {}", inbound.post.id, e);
+        }
+    }
+}
+
+/// Run the Mattermost bot described by `config` until shutdown.
+pub async fn monitor(state: Arc<GatewayState>, config: MattermostConfig) {
+    Arc::new(MattermostMonitor::new(state, config)).run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::GatewayServer;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use axum::extract::ws::{Message as AxumWsMessage, WebSocketUpgrade};
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn bot() -> MattermostUser {
+        MattermostUser {
+            id: "bot1".to_string(),
+            username: "krab".to_string(),
+        }
+    }
+
+    fn posted(channel_type: &str, post: Value, mentions: &[&str]) -> Value {
+        json!({
+            "event": "posted",
+            "data": {
+                "channel_type": channel_type,
+                "post": post.to_string(),
+                "mentions": serde_json::to_string(mentions).unwrap(),
+            },
+            "broadcast": { "channel_id": post["channel_id"] },
+            "seq": 1
+        })
+    }
+
+    #[test]
+    fn only_dms_and_mentions_are_inbound() {
+        let post = |id: &str, user: &str, message: &str| json!({ "id": id, "channel_id": "ch1", "user_id": user, "root_id": "", "message": message });
+        let dm = MattermostInbound::from_event(&posted("D", post("p1", "u1", "hi"), &[]), &bot())
+            .unwrap();
+        assert_eq!(dm.route_peer().kind, "direct");
+        assert_eq!(dm.root_id(), "p1");
+
+        let mention = MattermostInbound::from_event(
+            &posted("O", post("p2", "u1", "@krab, status?"), &["bot1"]),
+            &bot(),
+        )
+        .unwrap();
+        assert_eq!(mention.text, "status?");
+        assert_eq!(mention.route_peer().id, "ch1");
+
+        assert!(MattermostInbound::from_event(
+            &posted("O", post("p3", "u1", "chatter"), &[]),
+            &bot()
+        )
+        .is_none());
+        assert!(MattermostInbound::from_event(
+            &posted("D", post("p4", "bot1", "mine"), &[]),
+            &bot()
+        )
+        .is_none());
+    }
+
+    type Posts = Arc<Mutex<Vec<Value>>>;
+
+    async fn me(headers: HeaderMap) -> Response {
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer tok") {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        Json(json!({ "id": "bot1", "username": "krab" })).into_response()
+    }
+
+    async fn websocket(ws: WebSocketUpgrade, headers: HeaderMap) -> Response {
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer tok") {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        ws.on_upgrade(|mut socket| async move {
+            let events = [
+                json!({ "event": "hello", "data": { "server_version": "9.0" } }),
+                posted(
+                    "D",
+                    json!({ "id": "p1", "channel_id": "dm1", "user_id": "u1", "message": "ping" }),
+                    &[],
+                ),
+                posted(
+                    "O",
+                    json!({ "id": "p2", "channel_id": "town", "user_id": "u2", "message": "not for the bot" }),
+                    &[],
+                ),
+                posted(
+                    "O",
+                    json!({ "id": "p3", "channel_id": "town", "user_id": "u2", "root_id": "p0", "message": "@krab summarize" }),
+                    &["bot1"],
+                ),
+            ];
+            for event in events {
+                let _ = socket.send(AxumWsMessage::Text(event.to_string())).await;
+            }
+            while socket.recv().await.is_some() {}
+        })
+    }
+
+    async fn create_post(State(posts): State<Posts>, Json(body): Json<Value>) -> Json<Value> {
+        posts.lock().unwrap().push(body);
+        Json(json!({ "id": "reply" }))
+    }
+
+    #[tokio::test]
+    async fn answers_dms_and_mentions_in_threads() {
+        let posts: Posts = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/api/v4/users/me", get(me))
+            .route("/api/v4/websocket", get(websocket))
+            .route("/api/v4/posts", post(create_post))
+            .with_state(posts.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::default());
+        let config = MattermostConfig {
+            server_url: format!("http://{}", addr),
+            access_token: "tok".to_string(),
+            default_channel: None,
+            webhook_url: None,
+            team_name: None,
+        };
+        let running = tokio::spawn(monitor(Arc::new(server), config));
+
+        for _ in 0..250 {
+            if posts.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        running.abort();
+
+        let mut posts = posts.lock().unwrap().clone();
+        posts.sort_by_key(|p| p["channel_id"].as_str().unwrap_or_default().to_string());
+        assert_eq!(
+            posts,
+            vec![
+                json!({ "channel_id": "dm1", "message": "re: ping", "root_id": "p1" }),
+                json!({ "channel_id": "town", "message": "re: summarize", "root_id": "p0" }),
+            ]
+        );
+    }
+}
@@ -3,8 +3,10 @@
 // ── Core connectors ───────────────────────────────────────────────────────────
 pub mod slack;
 pub mod slack_client;
+pub mod slack_monitor;
 pub mod telegram;
 pub mod telegram_client;
+pub mod telegram_media;
 
 // ── Extended connectors (Phase 4–10) ─────────────────────────────────────────
 pub mod bluebubbles;
@@ -18,8 +20,11 @@ pub mod whatsapp_monitor;
 
 // ── Phase 11 connectors ────────────────────────────────────────────────────────
 pub mod irc;
+pub mod irc_client;
 pub mod mattermost;
+pub mod mattermost_monitor;
 pub mod msteams;
+pub mod msteams_bot;
 pub mod twitch;
 pub mod zalo;
 
@@ -28,6 +33,9 @@ pub mod feishu;
 pub mod googlechat;
 pub mod nextcloud_talk;
 pub mod nostr;
+pub mod nostr_crypto;
+pub mod nostr_monitor;
+pub mod nostr_relay;
 
 // ── Phase 13 connectors ────────────────────────────────────────────────────────
 pub mod tlon;
@@ -37,7 +45,9 @@ pub mod zalouser;
 
 // ── Phase 5-6 connectors (additional) ───────────────────────────────────────────
 pub mod matrix;
+pub mod matrix_monitor;
 pub mod signal;
+pub mod signal_monitor;
 
 // ── Public re-exports ─────────────────────────────────────────────────────────
 pub use bluebubbles::{
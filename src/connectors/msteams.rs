@@ -1,7 +1,8 @@
 //! msteams — Microsoft Teams connector.
 //! Ported from `openkrab/extensions/msteams/` (Phase 11).
 //!
-//! Uses the Bot Framework / Incoming Webhook API.
+//! Uses the Bot Framework / Incoming Webhook API. The inbound Bot Framework
+//! runtime (token validation, proactive replies) lives in `msteams_bot`.
 
 use crate::common::{Message, UserId};
 use anyhow::{bail, Result};
@@ -19,6 +20,14 @@ pub struct MsTeamsConfig {
     pub app_password: Option<String>,
     /// Tenant ID.
     pub tenant_id: Option<String>,
+    /// JWKS document with the keys inbound activity tokens are signed with
+    /// (default: the Bot Framework's).
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+    /// OAuth token endpoint for the bot's own token (default: derived from
+    /// `tenant_id`).
+    #[serde(default)]
+    pub token_url: Option<String>,
 }
 
 impl Default for MsTeamsConfig {
@@ -28,6 +37,8 @@ impl Default for MsTeamsConfig {
             app_id: std::env::var("MSTEAMS_APP_ID").ok(),
             app_password: std::env::var("MSTEAMS_APP_PASSWORD").ok(),
             tenant_id: std::env::var("MSTEAMS_TENANT_ID").ok(),
+            jwks_url: std::env::var("MSTEAMS_JWKS_URL").ok(),
+            token_url: std::env::var("MSTEAMS_TOKEN_URL").ok(),
         }
     }
 }
@@ -42,6 +53,18 @@ impl MsTeamsConfig {
         }
         Ok(())
     }
+
+    /// Checks for the Bot Framework runtime, which needs app credentials
+    /// instead of a webhook.
+    pub fn validate_bot(&self) -> Result<()> {
+        if self.app_id.as_deref().unwrap_or_default().is_empty() {
+            bail!("MSTEAMS_APP_ID is required");
+        }
+        if self.app_password.as_deref().unwrap_or_default().is_empty() {
+            bail!("MSTEAMS_APP_PASSWORD is required");
+        }
+        Ok(())
+    }
 }
 
 // ─── Activity types ───────────────────────────────────────────────────────────
@@ -166,6 +189,21 @@ mod tests {
         assert!(cfg.validate().is_err());
     }
 
+    #[test]
+    fn bot_config_needs_app_credentials() {
+        let cfg = MsTeamsConfig {
+            app_id: Some("app".into()),
+            app_password: None,
+            ..Default::default()
+        };
+        assert!(cfg.validate_bot().is_err());
+        let cfg = MsTeamsConfig {
+            app_password: Some("secret".into()),
+            ..cfg
+        };
+        assert!(cfg.validate_bot().is_ok());
+    }
+
     #[test]
     fn parse_message_activity_ok() {
         let a = TeamsActivity {
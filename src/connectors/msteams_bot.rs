@@ -0,0 +1,581 @@
+//! msteams_bot — Bot Framework runtime for Microsoft Teams.
+//!
+//! Inbound activities carry a JWT from the Bot Framework connector. It is
+//! checked as RS256 against the keys of the configured JWKS source (cached
+//! for a day, refetched at once for an unknown key id), then for issuer,
+//! audience (the app id), lifetime, the `serviceurl` claim and the key's
+//! channel endorsements. Replies are proactive: once the activity has been
+//! acked, the bot takes a client-credentials token and posts to the
+//! conversation reference's service URL. Messages go through the agent pool
+//! like every other channel.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::agents::RouteQuery;
+use crate::connectors::msteams::MsTeamsConfig;
+use crate::gateway::GatewayState;
+use crate::routing::resolve_route::RoutePeer;
+
+pub const BOT_FRAMEWORK_ISSUER: &str = "https://api.botframework.com";
+pub const DEFAULT_JWKS_URL: &str = "https://login.botframework.com/v1/.well-known/keys";
+const BOT_FRAMEWORK_SCOPE: &str = "https://api.botframework.com/.default";
+const CLOCK_SKEW_SECS: i64 = 300;
+const JWKS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Unknown key ids trigger a refetch at most this often.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+fn b64url() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+}
+
+// ─── Token validation ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kid: String,
+    kty: String,
+    #[serde(default)]
+    n: String,
+    #[serde(default)]
+    e: String,
+    /// Channels the key may sign for (e.g. `msteams`).
+    #[serde(default)]
+    endorsements: Vec<String>,
+}
+
+struct SigningKey {
+    key: RsaPublicKey,
+    endorsements: Vec<String>,
+}
+
+/// RSA keys from a JWKS document, by key id.
+pub struct SigningKeys {
+    keys: HashMap<String, SigningKey>,
+}
+
+impl SigningKeys {
+    pub fn from_jwks(jwks: &Value) -> Result<Self> {
+        let jwks: Vec<Jwk> = serde_json::from_value(
+            jwks.get("keys")
+                .cloned()
+                .ok_or_else(|| anyhow!("JWKS has no keys"))?,
+        )?;
+        let mut keys = HashMap::new();
+        for jwk in jwks.into_iter().filter(|k| k.kty == "RSA") {
+            let n = BigUint::from_bytes_be(&b64url().decode(&jwk.n)?);
+            let e = BigUint::from_bytes_be(&b64url().decode(&jwk.e)?);
+            let key = RsaPublicKey::new(n, e).with_context(|| format!("JWK {}", jwk.kid))?;
+            keys.insert(
+                jwk.kid,
+                SigningKey {
+                    key,
+                    endorsements: jwk.endorsements,
+                },
+            );
+        }
+        Ok(Self { keys })
+    }
+
+    fn contains(&self, kid: &str) -> bool {
+        self.keys.contains_key(kid)
+    }
+}
+
+/// Claims the Bot Framework puts in connector tokens.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BotClaims {
+    pub iss: String,
+    pub aud: String,
+    pub exp: i64,
+    #[serde(default)]
+    pub nbf: Option<i64>,
+    #[serde(default)]
+    pub serviceurl: Option<String>,
+}
+
+/// What an inbound token has to match.
+pub struct Expected<'a> {
+    pub app_id: &'a str,
+    pub service_url: &'a str,
+    /// The activity's `channelId`, checked against key endorsements.
+    pub channel_id: Option<&'a str>,
+    pub now: i64,
+}
+
+/// Key id of a JWT, without verifying anything.
+pub fn token_key_id(token: &str) -> Result<String> {
+    let header = token.split('.').next().unwrap_or_default();
+    let header: Value = serde_json::from_slice(&b64url().decode(header)?)?;
+    if header.get("alg").and_then(|a| a.as_str()) != Some("RS256") {
+        bail!("token is not RS256");
+    }
+    header
+        .get("kid")
+        .and_then(|k| k.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("token has no key id"))
+}
+
+pub fn verify_token(token: &str, keys: &SigningKeys, expected: &Expected) -> Result<BotClaims> {
+    let kid = token_key_id(token)?;
+    let signing_key = keys
+        .keys
+        .get(&kid)
+        .ok_or_else(|| anyhow!("unknown signing key {}", kid))?;
+    let (signed, signature) = token
+        .rsplit_once('.')
+        .ok_or_else(|| anyhow!("malformed token"))?;
+    let signature = Signature::try_from(b64url().decode(signature)?.as_slice())?;
+    VerifyingKey::<Sha256>::new(signing_key.key.clone())
+        .verify(signed.as_bytes(), &signature)
+        .map_err(|_| anyhow!("bad token signature"))?;
+
+    let payload = signed.split('.').nth(1).unwrap_or_default();
+    let claims: BotClaims = serde_json::from_slice(&b64url().decode(payload)?)?;
+    if claims.iss != BOT_FRAMEWORK_ISSUER {
+        bail!("unexpected issuer {}", claims.iss);
+    }
+    if claims.aud != expected.app_id {
+        bail!("token is for another app");
+    }
+    if claims.exp + CLOCK_SKEW_SECS < expected.now {
+        bail!("token expired");
+    }
+    if claims
+        .nbf
+        .is_some_and(|nbf| nbf - CLOCK_SKEW_SECS > expected.now)
+    {
+        bail!("token not yet valid");
+    }
+    let same_service =
+        |url: &str| url.trim_end_matches('/') == expected.service_url.trim_end_matches('/');
+    if !claims.serviceurl.as_deref().is_some_and(same_service) {
+        bail!("token is for another service URL");
+    }
+    if let Some(channel) = expected.channel_id {
+        if !signing_key.endorsements.is_empty()
+            && !signing_key.endorsements.iter().any(|e| e == channel)
+        {
+            bail!("signing key is not endorsed for {}", channel);
+        }
+    }
+    Ok(claims)
+}
+
+/// Signing keys fetched from a JWKS URL.
+pub struct JwksCache {
+    client: reqwest::Client,
+    url: String,
+    cached: Mutex<Option<(Arc<SigningKeys>, Instant)>>,
+}
+
+impl JwksCache {
+    pub fn new(client: reqwest::Client, url: &str) -> Self {
+        Self {
+            client,
+            url: url.to_string(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Keys that should include `kid`, refetching when the cache is stale
+    /// or lacks it.
+    pub async fn keys_for(&self, kid: &str) -> Result<Arc<SigningKeys>> {
+        let mut cached = self.cached.lock().await;
+        if let Some((keys, fetched)) = cached.as_ref() {
+            let fresh = fetched.elapsed() < JWKS_TTL;
+            if fresh && (keys.contains(kid) || fetched.elapsed() < JWKS_MIN_REFRESH) {
+                return Ok(keys.clone());
+            }
+        }
+        let jwks: Value = self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let keys = Arc::new(SigningKeys::from_jwks(&jwks)?);
+        *cached = Some((keys.clone(), Instant::now()));
+        Ok(keys)
+    }
+}
+
+// ─── Conversation references ──────────────────────────────────────────────────
+
+/// Where a reply to an activity goes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationReference {
+    pub service_url: String,
+    pub conversation_id: String,
+    pub is_group: bool,
+    pub activity_id: String,
+    pub user: Value,
+    pub bot: Value,
+}
+
+impl ConversationReference {
+    pub fn from_activity(activity: &Value) -> Option<Self> {
+        let text = |v: &Value| v.as_str().map(str::to_string);
+        let conversation = activity.get("conversation")?;
+        Some(Self {
+            service_url: text(activity.get("serviceUrl")?)?,
+            conversation_id: text(conversation.get("id")?)?,
+            is_group: conversation
+                .get("isGroup")
+                .and_then(|g| g.as_bool())
+                .unwrap_or(false),
+            activity_id: activity.get("id").and_then(text).unwrap_or_default(),
+            user: activity.get("from")?.clone(),
+            bot: activity.get("recipient").cloned().unwrap_or(Value::Null),
+        })
+    }
+
+    /// Channel conversations carry the thread as `;messageid=...`.
+    pub fn thread(&self) -> (&str, Option<&str>) {
+        match self.conversation_id.split_once(";messageid=") {
+            Some((conversation, thread)) => (conversation, Some(thread)),
+            None => (&self.conversation_id, None),
+        }
+    }
+
+    pub fn route_peer(&self) -> RoutePeer {
+        if self.is_group {
+            RoutePeer {
+                kind: "group".to_string(),
+                id: self.thread().0.to_string(),
+            }
+        } else {
+            RoutePeer {
+                kind: "direct".to_string(),
+                id: self.user["id"].as_str().unwrap_or_default().to_string(),
+            }
+        }
+    }
+
+    pub fn reply_activity(&self, text: &str) -> Value {
+        json!({
+            "type": "message",
+            "from": self.bot,
+            "recipient": self.user,
+            "conversation": { "id": self.conversation_id },
+            "replyToId": self.activity_id,
+            "text": text,
+        })
+    }
+
+    fn reply_url(&self) -> String {
+        let base = format!(
+            "{}/v3/conversations/{}/activities",
+            self.service_url.trim_end_matches('/'),
+            urlencoding::encode(&self.conversation_id)
+        );
+        if self.activity_id.is_empty() {
+            base
+        } else {
+            format!("{}/{}", base, urlencoding::encode(&self.activity_id))
+        }
+    }
+}
+
+/// Drop `<at>Bot</at>` mention markup from a message.
+pub fn strip_mentions(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<at>") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start..].find("</at>") {
+            Some(end) => &rest[start + end + "</at>".len()..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// ─── Bot ──────────────────────────────────────────────────────────────────────
+
+/// A Teams bot: validates inbound activities and answers them.
+pub struct MsTeamsBot {
+    client: reqwest::Client,
+    app_id: String,
+    app_password: String,
+    token_url: String,
+    jwks: JwksCache,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl MsTeamsBot {
+    pub fn new(config: &MsTeamsConfig) -> Result<Self> {
+        config.validate_bot()?;
+        let client = reqwest::Client::new();
+        let token_url = config.token_url.clone().unwrap_or_else(|| {
+            format!(
+                "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                config.tenant_id.as_deref().unwrap_or("botframework.com")
+            )
+        });
+        Ok(Self {
+            jwks: JwksCache::new(
+                client.clone(),
+                config.jwks_url.as_deref().unwrap_or(DEFAULT_JWKS_URL),
+            ),
+            client,
+            app_id: config.app_id.clone().unwrap_or_default(),
+            app_password: config.app_password.clone().unwrap_or_default(),
+            token_url,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Check the `Authorization` header of an inbound activity.
+    pub async fn authenticate(&self, authorization: Option<&str>, activity: &Value) -> Result<()> {
+        let token = authorization
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .ok_or_else(|| anyhow!("missing bearer token"))?;
+        let keys = self.jwks.keys_for(&token_key_id(token)?).await?;
+        let service_url = activity
+            .get("serviceUrl")
+            .and_then(|s| s.as_str())
+            .ok_or_else(|| anyhow!("activity has no serviceUrl"))?;
+        verify_token(
+            token,
+            &keys,
+            &Expected {
+                app_id: &self.app_id,
+                service_url,
+                channel_id: activity.get("channelId").and_then(|c| c.as_str()),
+                now: chrono::Utc::now().timestamp(),
+            },
+        )?;
+        Ok(())
+    }
+
+    async fn access_token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some((value, expires)) = token.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(value.clone());
+            }
+        }
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            expires_in: u64,
+        }
+        let response: TokenResponse = self
+            .client
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.app_id.as_str()),
+                ("client_secret", self.app_password.as_str()),
+                ("scope", BOT_FRAMEWORK_SCOPE),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // Renew a minute early.
+        let lifetime = Duration::from_secs(response.expires_in.saturating_sub(60));
+        *token = Some((response.access_token.clone(), Instant::now() + lifetime));
+        Ok(response.access_token)
+    }
+
+    /// Post `text` to the conversation `reference` points at.
+    pub async fn reply(&self, reference: &ConversationReference, text: &str) -> Result<()> {
+        let token = self.access_token().await?;
+        self.client
+            .post(reference.reply_url())
+            .bearer_auth(token)
+            .json(&reference.reply_activity(text))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Answer a message activity through the agent pool.
+    pub async fn answer(self: Arc<Self>, state: Arc<GatewayState>, activity: Value) {
+        let Some(reference) = ConversationReference::from_activity(&activity) else {
+            return;
+        };
+        let text = strip_mentions(activity["text"].as_str().unwrap_or_default());
+        if text.is_empty() {
+            return;
+        }
+        let (_, thread) = reference.thread();
+        let routed = state.agents.route(RouteQuery {
+            channel: "msteams",
+            peer: Some(reference.route_peer()),
+            thread_id: thread,
+            ..Default::default()
+        });
+        let reply = match routed {
+            Some(routed) => {
+                tracing::debug!(
+                    "[msteams] {} in {}",
+                    reference.activity_id,
+                    routed.route.session_key
+                );
+                match state.answer_in_session(&routed, &text).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        tracing::warn!("[msteams] agent failed: {}", e);
+                        format!("unavailable: {}", e)
+                    }
+                }
+            }
+            None => "Agent not available".to_string(),
+        };
+        if let Err(e) = self.reply(&reference, &reply).await {
+            tracing::warn!(
+                "[msteams] failed to reply in {}: {}",
+                reference.conversation_id,
+                e
+            );
+        }
+    }
+}
+
+/// A local signing key and tokens for tests that stand in for the Bot
+/// Framework.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::traits::PublicKeyParts;
+    use rsa::RsaPrivateKey;
+    use std::sync::OnceLock;
+
+    pub const KID: &str = "test-key";
+
+    fn private_key() -> &'static RsaPrivateKey {
+        static KEY: OnceLock<RsaPrivateKey> = OnceLock::new();
+        KEY.get_or_init(|| RsaPrivateKey::new(&mut rand::rngs::OsRng, 1024).unwrap())
+    }
+
+    pub fn jwks() -> Value {
+        let public = private_key().to_public_key();
+        json!({ "keys": [{
+            "kty": "RSA",
+            "use": "sig",
+            "kid": KID,
+            "n": b64url().encode(public.n().to_bytes_be()),
+            "e": b64url().encode(public.e().to_bytes_be()),
+            "endorsements": ["msteams"],
+        }]})
+    }
+
+    pub fn token(claims: Value) -> String {
+        let header = json!({ "alg": "RS256", "typ": "JWT", "kid": KID });
+        let signed = format!(
+            "{}.{}",
+            b64url().encode(header.to_string()),
+            b64url().encode(claims.to_string())
+        );
+        let signature = SigningKey::<Sha256>::new(private_key().clone()).sign(signed.as_bytes());
+        format!("{}.{}", signed, b64url().encode(signature.to_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{jwks, token};
+    use super::*;
+
+    const SERVICE_URL: &str = "https://smba.trafficmanager.net/amer/";
+
+    fn claims(now: i64) -> Value {
+        json!({
+            "iss": BOT_FRAMEWORK_ISSUER,
+            "aud": "app-id",
+            "exp": now + 3600,
+            "nbf": now - 60,
+            "serviceurl": SERVICE_URL,
+        })
+    }
+
+    #[test]
+    fn tokens_are_checked_against_keys_and_claims() {
+        let keys = SigningKeys::from_jwks(&jwks()).unwrap();
+        let now = 1_760_000_000;
+        let expected = Expected {
+            app_id: "app-id",
+            service_url: "https://smba.trafficmanager.net/amer",
+            channel_id: Some("msteams"),
+            now,
+        };
+        verify_token(&token(claims(now)), &keys, &expected).unwrap();
+
+        let with = |key: &str, value: Value| {
+            let mut c = claims(now);
+            c[key] = value;
+            token(c)
+        };
+        for (bad, why) in [
+            (with("aud", json!("other-app")), "audience"),
+            (with("iss", json!("https://evil.example")), "issuer"),
+            (with("exp", json!(now - 3600)), "expiry"),
+            (
+                with("serviceurl", json!("https://evil.example")),
+                "service url",
+            ),
+        ] {
+            assert!(verify_token(&bad, &keys, &expected).is_err(), "{}", why);
+        }
+
+        let good = token(claims(now));
+        let (signed, _) = good.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", signed, b64url().encode([0u8; 128]));
+        assert!(verify_token(&forged, &keys, &expected).is_err());
+
+        let slack = Expected {
+            channel_id: Some("slack"),
+            ..expected
+        };
+        assert!(verify_token(&good, &keys, &slack).is_err());
+    }
+
+    #[test]
+    fn references_route_and_reply_in_threads() {
+        let activity = json!({
+            "type": "message",
+            "id": "1700000000001",
+            "serviceUrl": SERVICE_URL,
+            "channelId": "msteams",
+            "from": { "id": "29:user", "name": "Alice" },
+            "recipient": { "id": "28:bot", "name": "Krab" },
+            "conversation": { "id": "19:chan@thread.tacv2;messageid=1699", "isGroup": true },
+            "text": "<at>Krab</at> what's new?"
+        });
+        let reference = ConversationReference::from_activity(&activity).unwrap();
+        assert_eq!(reference.thread(), ("19:chan@thread.tacv2", Some("1699")));
+        assert_eq!(reference.route_peer().id, "19:chan@thread.tacv2");
+        assert_eq!(
+            reference.reply_url(),
+            "https://smba.trafficmanager.net/amer/v3/conversations/19%3Achan%40thread.tacv2%3Bmessageid%3D1699/activities/1700000000001"
+        );
+        let reply = reference.reply_activity("hi");
+        assert_eq!(reply["recipient"]["id"], "29:user");
+        assert_eq!(reply["replyToId"], "1700000000001");
+        assert_eq!(
+            strip_mentions(activity["text"].as_str().unwrap()),
+            "what's new?"
+        );
+    }
+}
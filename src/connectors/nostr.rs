@@ -1,7 +1,9 @@
 //! nostr — Nostr decentralized messaging connector.
 //! Ported from `openkrab/extensions/nostr/` (Phase 12).
 //!
-//! Uses NIP-01 (basic protocol) and NIP-04 (encrypted DMs).
+//! Uses NIP-01 (basic protocol), NIP-04 (encrypted DMs) and NIP-17
+//! (gift-wrapped DMs). Signing and encryption live in `nostr_crypto`, relay
+//! connections in `nostr_relay`.
 //! Reference: https://github.com/nostr-protocol/nostr
 
 use crate::common::{Message, UserId};
@@ -65,7 +67,10 @@ pub mod kind {
     pub const DELETE: u64 = 5;
     pub const REPOST: u64 = 6;
     pub const REACTION: u64 = 7;
+    pub const SEAL: u64 = 13;
+    pub const PRIVATE_DM: u64 = 14;
     pub const CHANNEL_MSG: u64 = 42;
+    pub const GIFT_WRAP: u64 = 1059;
 }
 
 /// A Nostr event (NIP-01 format).
@@ -95,6 +100,11 @@ pub enum RelayMessage {
     },
     Notice(String),
     Eose(String), // End of stored events
+    /// The relay ended a subscription.
+    Closed {
+        subscription_id: String,
+        message: String,
+    },
 }
 
 /// Parse a JSON relay message (from WebSocket frame).
@@ -128,6 +138,18 @@ pub fn parse_relay_message(raw: &str) -> Option<RelayMessage> {
             let sub_id = arr.get(1)?.as_str()?.to_string();
             Some(RelayMessage::Eose(sub_id))
         }
+        "CLOSED" => {
+            let subscription_id = arr.get(1)?.as_str()?.to_string();
+            let message = arr
+                .get(2)
+                .and_then(|m| m.as_str())
+                .unwrap_or("")
+                .to_string();
+            Some(RelayMessage::Closed {
+                subscription_id,
+                message,
+            })
+        }
         _ => None,
     }
 }
@@ -185,7 +207,12 @@ pub fn build_close(subscription_id: &str) -> String {
     serde_json::json!(["CLOSE", subscription_id]).to_string()
 }
 
-/// Build an EVENT publish message (unsigned — caller must fill id/sig).
+/// Build an EVENT publish message for a signed event.
+pub fn build_publish(event: &NostrEvent) -> String {
+    serde_json::json!(["EVENT", event]).to_string()
+}
+
+/// Build an unsigned event body (sign it with `nostr_crypto::sign_event`).
 pub fn build_event_payload(
     pubkey: &str,
     kind: u64,
@@ -205,7 +232,7 @@ pub fn build_event_payload(
     })
 }
 
-/// Build a DM event shell (content must be NIP-04 encrypted by caller).
+/// Build a DM event shell (content from `nostr_crypto::nip04_encrypt`).
 pub fn build_dm_event(
     from_pubkey: &str,
     to_pubkey: &str,
@@ -282,6 +309,21 @@ mod tests {
         assert!(matches!(msg, RelayMessage::Notice(_)));
     }
 
+    #[test]
+    fn parse_relay_closed() {
+        let raw = r#"["CLOSED","sub1","auth-required: sign in first"]"#;
+        match parse_relay_message(raw).unwrap() {
+            RelayMessage::Closed {
+                subscription_id,
+                message,
+            } => {
+                assert_eq!(subscription_id, "sub1");
+                assert!(message.starts_with("auth-required"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
     #[test]
     fn config_validate_invalid_key_length() {
         let cfg = NostrConfig {
@@ -0,0 +1,517 @@
+//! nostr_crypto — keys, event signing and DM encryption for Nostr.
+//!
+//! Event ids are the SHA-256 of the NIP-01 serialization, signed with
+//! BIP-340 Schnorr signatures. Direct messages are either NIP-04 (AES-256-CBC
+//! keyed by the ECDH secret) or NIP-17: the kind-14 message is left unsigned
+//! (a "rumor"), sealed to the recipient with NIP-44 v2 encryption and then
+//! gift-wrapped by a throwaway key, so relays see neither sender nor time.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use k256::schnorr::{Signature, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::connectors::nostr::{kind, NostrEvent};
+
+/// Gift wraps and seals are backdated by up to this much (NIP-59).
+const TIMESTAMP_JITTER_SECS: i64 = 2 * 24 * 60 * 60;
+const NIP44_VERSION: u8 = 2;
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+// ─── Keys ─────────────────────────────────────────────────────────────────────
+
+/// A secp256k1 key pair; the public key is the x-only form used on Nostr.
+#[derive(Clone)]
+pub struct NostrKeys {
+    signing: SigningKey,
+}
+
+impl NostrKeys {
+    pub fn from_hex(secret_hex: &str) -> Result<Self> {
+        let bytes = hex::decode(secret_hex.trim()).context("secret key is not hex")?;
+        let signing = SigningKey::from_bytes(&bytes).map_err(|_| anyhow!("invalid secret key"))?;
+        Ok(Self { signing })
+    }
+
+    pub fn generate() -> Self {
+        Self {
+            signing: SigningKey::random(&mut OsRng),
+        }
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing.verifying_key().to_bytes())
+    }
+
+    fn sign_id(&self, id: &[u8; 32], aux_rand: &[u8; 32]) -> Result<String> {
+        let sig = self
+            .signing
+            .sign_raw(id, aux_rand)
+            .map_err(|_| anyhow!("signing failed"))?;
+        Ok(hex::encode(sig.to_bytes()))
+    }
+
+    /// ECDH shared x-coordinate with `peer` (x-only hex public key).
+    fn shared_secret(&self, peer: &str) -> Result<[u8; 32]> {
+        let peer = verifying_key(peer)?;
+        let shared = k256::ecdh::diffie_hellman(self.signing.as_nonzero_scalar(), peer.as_affine());
+        Ok((*shared.raw_secret_bytes()).into())
+    }
+}
+
+impl std::fmt::Debug for NostrKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NostrKeys")
+            .field("public_key", &self.public_key_hex())
+            .finish_non_exhaustive()
+    }
+}
+
+fn verifying_key(public_hex: &str) -> Result<VerifyingKey> {
+    let bytes = hex::decode(public_hex).context("public key is not hex")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| anyhow!("invalid public key {}", public_hex))
+}
+
+// ─── Events ───────────────────────────────────────────────────────────────────
+
+/// An event before signing. Unsigned kind-14 events are NIP-17 rumors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedEvent {
+    pub pubkey: String,
+    pub created_at: i64,
+    pub kind: u64,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+}
+
+impl UnsignedEvent {
+    pub fn new(pubkey: &str, kind: u64, content: &str, tags: Vec<Vec<String>>) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            created_at: now_secs(),
+            kind,
+            tags,
+            content: content.to_string(),
+        }
+    }
+
+    /// NIP-01 event id: SHA-256 of `[0, pubkey, created_at, kind, tags, content]`.
+    pub fn id(&self) -> String {
+        hex::encode(self.id_bytes())
+    }
+
+    fn id_bytes(&self) -> [u8; 32] {
+        let canonical = serde_json::json!([
+            0,
+            self.pubkey,
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content
+        ]);
+        Sha256::digest(canonical.to_string().as_bytes()).into()
+    }
+
+    /// The event as sent inside a seal: with its id, without a signature.
+    fn to_rumor_json(&self) -> String {
+        serde_json::json!({
+            "id": self.id(),
+            "pubkey": self.pubkey,
+            "created_at": self.created_at,
+            "kind": self.kind,
+            "tags": self.tags,
+            "content": self.content,
+        })
+        .to_string()
+    }
+
+    /// First value of the first tag named `name`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|t| t.first().map(String::as_str) == Some(name))
+            .and_then(|t| t.get(1))
+            .map(String::as_str)
+    }
+}
+
+/// Sign `event` with `keys`, which must own `event.pubkey`.
+pub fn sign_event(keys: &NostrKeys, event: UnsignedEvent) -> Result<NostrEvent> {
+    if event.pubkey != keys.public_key_hex() {
+        bail!("event pubkey does not match the signing key");
+    }
+    let mut aux_rand = [0u8; 32];
+    OsRng.fill_bytes(&mut aux_rand);
+    let id = event.id_bytes();
+    let sig = keys.sign_id(&id, &aux_rand)?;
+    Ok(NostrEvent {
+        id: hex::encode(id),
+        pubkey: event.pubkey,
+        created_at: event.created_at,
+        kind: event.kind,
+        tags: event.tags,
+        content: event.content,
+        sig,
+    })
+}
+
+/// Check that `event.id` matches its content and `event.sig` is the author's.
+pub fn verify_event(event: &NostrEvent) -> Result<()> {
+    let unsigned = UnsignedEvent {
+        pubkey: event.pubkey.clone(),
+        created_at: event.created_at,
+        kind: event.kind,
+        tags: event.tags.clone(),
+        content: event.content.clone(),
+    };
+    let id = unsigned.id_bytes();
+    if hex::encode(id) != event.id.to_ascii_lowercase() {
+        bail!("event id does not match its content");
+    }
+    let sig = hex::decode(&event.sig).context("signature is not hex")?;
+    let sig = Signature::try_from(sig.as_slice()).map_err(|_| anyhow!("malformed signature"))?;
+    verifying_key(&event.pubkey)?
+        .verify_raw(&id, &sig)
+        .map_err(|_| anyhow!("bad signature on {}", event.id))
+}
+
+// ─── NIP-04 ───────────────────────────────────────────────────────────────────
+
+type Aes256CbcEnc = cbc::Encryptor<aes_gcm::aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes_gcm::aes::Aes256>;
+
+/// NIP-04 content: `base64(ciphertext)?iv=base64(iv)`.
+pub fn nip04_encrypt(keys: &NostrKeys, peer: &str, plaintext: &str) -> Result<String> {
+    use cbc::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+    let key = keys.shared_secret(peer)?;
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut iv);
+    let ciphertext = Aes256CbcEnc::new(&key.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(plaintext.as_bytes());
+    Ok(format!(
+        "{}?iv={}",
+        b64().encode(ciphertext),
+        b64().encode(iv)
+    ))
+}
+
+pub fn nip04_decrypt(keys: &NostrKeys, peer: &str, content: &str) -> Result<String> {
+    use cbc::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+    let (ciphertext, iv) = content
+        .split_once("?iv=")
+        .ok_or_else(|| anyhow!("NIP-04 content has no iv"))?;
+    let ciphertext = b64().decode(ciphertext)?;
+    let iv: [u8; 16] = b64()
+        .decode(iv)?
+        .try_into()
+        .map_err(|_| anyhow!("NIP-04 iv must be 16 bytes"))?;
+    let key = keys.shared_secret(peer)?;
+    let plaintext = Aes256CbcDec::new(&key.into(), &iv.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(&ciphertext)
+        .map_err(|_| anyhow!("NIP-04 decryption failed"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+// ─── NIP-44 v2 ────────────────────────────────────────────────────────────────
+
+/// Long-lived key shared by `keys` and `peer`: HKDF-extract of the ECDH
+/// secret with salt `nip44-v2`.
+pub fn nip44_conversation_key(keys: &NostrKeys, peer: &str) -> Result<[u8; 32]> {
+    let shared = keys.shared_secret(peer)?;
+    let (prk, _) = hkdf::Hkdf::<Sha256>::extract(Some(b"nip44-v2"), &shared);
+    Ok(prk.into())
+}
+
+/// ChaCha20 key, ChaCha20 nonce and HMAC key for one message.
+fn nip44_message_keys(
+    conversation_key: &[u8; 32],
+    nonce: &[u8; 32],
+) -> ([u8; 32], [u8; 12], [u8; 32]) {
+    let mut okm = [0u8; 76];
+    hkdf::Hkdf::<Sha256>::from_prk(conversation_key)
+        .expect("conversation key is a full PRK")
+        .expand(nonce, &mut okm)
+        .expect("76 bytes is a valid HKDF length");
+    let mut chacha_key = [0u8; 32];
+    let mut chacha_nonce = [0u8; 12];
+    let mut hmac_key = [0u8; 32];
+    chacha_key.copy_from_slice(&okm[..32]);
+    chacha_nonce.copy_from_slice(&okm[32..44]);
+    hmac_key.copy_from_slice(&okm[44..]);
+    (chacha_key, chacha_nonce, hmac_key)
+}
+
+/// Padded plaintext length: 32 bytes minimum, then power-of-two buckets.
+fn nip44_padded_len(len: usize) -> usize {
+    if len <= 32 {
+        return 32;
+    }
+    let next_power = 1usize << (usize::BITS - (len - 1).leading_zeros());
+    let chunk = if next_power <= 256 {
+        32
+    } else {
+        next_power / 8
+    };
+    chunk * ((len - 1) / chunk + 1)
+}
+
+fn nip44_mac(hmac_key: &[u8; 32], nonce: &[u8; 32], ciphertext: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(hmac_key).expect("any key length");
+    mac.update(nonce);
+    mac.update(ciphertext);
+    mac
+}
+
+pub fn nip44_encrypt(conversation_key: &[u8; 32], plaintext: &str) -> Result<String> {
+    let mut nonce = [0u8; 32];
+    OsRng.fill_bytes(&mut nonce);
+    nip44_encrypt_with_nonce(conversation_key, plaintext, &nonce)
+}
+
+fn nip44_encrypt_with_nonce(
+    conversation_key: &[u8; 32],
+    plaintext: &str,
+    nonce: &[u8; 32],
+) -> Result<String> {
+    use chacha20::cipher::{KeyIvInit, StreamCipher};
+    let len = plaintext.len();
+    if !(1..=65_535).contains(&len) {
+        bail!("NIP-44 plaintext must be 1 to 65535 bytes");
+    }
+    let (chacha_key, chacha_nonce, hmac_key) = nip44_message_keys(conversation_key, nonce);
+    let mut padded = vec![0u8; 2 + nip44_padded_len(len)];
+    padded[..2].copy_from_slice(&(len as u16).to_be_bytes());
+    padded[2..2 + len].copy_from_slice(plaintext.as_bytes());
+    chacha20::ChaCha20::new(&chacha_key.into(), &chacha_nonce.into()).apply_keystream(&mut padded);
+    let mac = nip44_mac(&hmac_key, nonce, &padded).finalize().into_bytes();
+
+    let mut payload = Vec::with_capacity(1 + 32 + padded.len() + 32);
+    payload.push(NIP44_VERSION);
+    payload.extend_from_slice(nonce);
+    payload.extend_from_slice(&padded);
+    payload.extend_from_slice(&mac);
+    Ok(b64().encode(payload))
+}
+
+pub fn nip44_decrypt(conversation_key: &[u8; 32], payload: &str) -> Result<String> {
+    use chacha20::cipher::{KeyIvInit, StreamCipher};
+    if payload.starts_with('#') {
+        bail!("unsupported NIP-44 version");
+    }
+    let data = b64()
+        .decode(payload)
+        .context("NIP-44 payload is not base64")?;
+    if data.len() < 99 || data.len() > 65_603 {
+        bail!("NIP-44 payload has an invalid length");
+    }
+    if data[0] != NIP44_VERSION {
+        bail!("unsupported NIP-44 version {}", data[0]);
+    }
+    let nonce: [u8; 32] = data[1..33].try_into().expect("32 bytes");
+    let (ciphertext, mac) = data[33..].split_at(data.len() - 33 - 32);
+    let (chacha_key, chacha_nonce, hmac_key) = nip44_message_keys(conversation_key, &nonce);
+    nip44_mac(&hmac_key, &nonce, ciphertext)
+        .verify_slice(mac)
+        .map_err(|_| anyhow!("NIP-44 MAC mismatch"))?;
+
+    let mut padded = ciphertext.to_vec();
+    chacha20::ChaCha20::new(&chacha_key.into(), &chacha_nonce.into()).apply_keystream(&mut padded);
+    let len = u16::from_be_bytes([padded[0], padded[1]]) as usize;
+    if len == 0 || padded.len() != 2 + nip44_padded_len(len) {
+        bail!("NIP-44 padding is invalid");
+    }
+    Ok(String::from_utf8(padded[2..2 + len].to_vec())?)
+}
+
+// ─── NIP-17 ───────────────────────────────────────────────────────────────────
+
+fn jittered_now() -> i64 {
+    now_secs() - rand::thread_rng().gen_range(0..TIMESTAMP_JITTER_SECS)
+}
+
+/// Kind-14 chat message from `keys` to `recipient`, optionally replying to a
+/// previous rumor id.
+pub fn private_message(
+    keys: &NostrKeys,
+    recipient: &str,
+    text: &str,
+    reply_to: Option<&str>,
+) -> UnsignedEvent {
+    let mut tags = vec![vec!["p".to_string(), recipient.to_string()]];
+    if let Some(id) = reply_to {
+        tags.push(vec!["e".to_string(), id.to_string()]);
+    }
+    UnsignedEvent::new(&keys.public_key_hex(), kind::PRIVATE_DM, text, tags)
+}
+
+/// Seal `rumor` (authored by `keys`) to `recipient` and gift-wrap it with a
+/// fresh key. The result is the kind-1059 event to publish.
+pub fn gift_wrap(keys: &NostrKeys, recipient: &str, rumor: &UnsignedEvent) -> Result<NostrEvent> {
+    if rumor.pubkey != keys.public_key_hex() {
+        bail!("rumor pubkey does not match the sender key");
+    }
+    let seal = UnsignedEvent {
+        pubkey: rumor.pubkey.clone(),
+        created_at: jittered_now(),
+        kind: kind::SEAL,
+        tags: vec![],
+        content: nip44_encrypt(
+            &nip44_conversation_key(keys, recipient)?,
+            &rumor.to_rumor_json(),
+        )?,
+    };
+    let seal = sign_event(keys, seal)?;
+
+    let wrapper = NostrKeys::generate();
+    let wrap = UnsignedEvent {
+        pubkey: wrapper.public_key_hex(),
+        created_at: jittered_now(),
+        kind: kind::GIFT_WRAP,
+        tags: vec![vec!["p".to_string(), recipient.to_string()]],
+        content: nip44_encrypt(
+            &nip44_conversation_key(&wrapper, recipient)?,
+            &serde_json::to_string(&seal)?,
+        )?,
+    };
+    sign_event(&wrapper, wrap)
+}
+
+/// Open a gift wrap addressed to `keys` and return the rumor inside, after
+/// checking the seal's signature and that the seal and rumor authors match.
+pub fn unwrap_gift(keys: &NostrKeys, wrap: &NostrEvent) -> Result<UnsignedEvent> {
+    if wrap.kind != kind::GIFT_WRAP {
+        bail!("not a gift wrap (kind {})", wrap.kind);
+    }
+    let seal_json = nip44_decrypt(&nip44_conversation_key(keys, &wrap.pubkey)?, &wrap.content)?;
+    let seal: NostrEvent = serde_json::from_str(&seal_json).context("malformed seal")?;
+    if seal.kind != kind::SEAL {
+        bail!("gift wrap does not hold a seal (kind {})", seal.kind);
+    }
+    verify_event(&seal)?;
+    let rumor_json = nip44_decrypt(&nip44_conversation_key(keys, &seal.pubkey)?, &seal.content)?;
+    let rumor: UnsignedEvent = serde_json::from_str(&rumor_json).context("malformed rumor")?;
+    if rumor.pubkey != seal.pubkey {
+        bail!("rumor author does not match the seal");
+    }
+    Ok(rumor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> NostrKeys {
+        let mut secret = [0u8; 32];
+        secret[31] = n;
+        NostrKeys::from_hex(&hex::encode(secret)).unwrap()
+    }
+
+    #[test]
+    fn bip340_test_vector() {
+        // BIP-340 test vector 0.
+        let keys = key(3);
+        assert_eq!(
+            keys.public_key_hex(),
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"
+        );
+        assert_eq!(
+            keys.sign_id(&[0u8; 32], &[0u8; 32]).unwrap(),
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca8215\
+             25f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0"
+        );
+    }
+
+    #[test]
+    fn signed_events_verify_and_tampering_is_caught() {
+        let keys = NostrKeys::generate();
+        let event = UnsignedEvent::new(
+            &keys.public_key_hex(),
+            kind::TEXT_NOTE,
+            "gm \"nostr\"\n",
+            vec![],
+        );
+        let signed = sign_event(&keys, event.clone()).unwrap();
+        assert_eq!(signed.id, event.id());
+        verify_event(&signed).unwrap();
+
+        let mut tampered = signed.clone();
+        tampered.content = "gn".to_string();
+        assert!(verify_event(&tampered).is_err());
+
+        let other = NostrKeys::generate();
+        assert!(sign_event(&other, event).is_err());
+    }
+
+    #[test]
+    fn nip04_round_trip() {
+        let (alice, bob) = (NostrKeys::generate(), NostrKeys::generate());
+        let content = nip04_encrypt(&alice, &bob.public_key_hex(), "hello bob").unwrap();
+        assert!(content.contains("?iv="));
+        assert_eq!(
+            nip04_decrypt(&bob, &alice.public_key_hex(), &content).unwrap(),
+            "hello bob"
+        );
+        let eve = NostrKeys::generate();
+        assert_ne!(
+            nip04_decrypt(&eve, &alice.public_key_hex(), &content).ok(),
+            Some("hello bob".to_string())
+        );
+    }
+
+    #[test]
+    fn nip44_test_vector() {
+        // First encrypt_decrypt vector from the NIP-44 spec.
+        let conversation_key = nip44_conversation_key(&key(1), &key(2).public_key_hex()).unwrap();
+        assert_eq!(
+            hex::encode(conversation_key),
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d"
+        );
+        let mut nonce = [0u8; 32];
+        nonce[31] = 1;
+        let payload = nip44_encrypt_with_nonce(&conversation_key, "a", &nonce).unwrap();
+        assert_eq!(
+            payload,
+            "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb"
+        );
+        assert_eq!(nip44_decrypt(&conversation_key, &payload).unwrap(), "a");
+
+        let mut tampered = b64().decode(&payload).unwrap();
+        tampered[40] ^= 1;
+        assert!(nip44_decrypt(&conversation_key, &b64().encode(tampered)).is_err());
+    }
+
+    #[test]
+    fn nip44_padding_buckets() {
+        assert_eq!(nip44_padded_len(1), 32);
+        assert_eq!(nip44_padded_len(32), 32);
+        assert_eq!(nip44_padded_len(33), 64);
+        assert_eq!(nip44_padded_len(257), 320);
+        assert_eq!(nip44_padded_len(1_000), 1_024);
+    }
+
+    #[test]
+    fn gift_wrapped_messages_reach_only_the_recipient() {
+        let (alice, bob) = (NostrKeys::generate(), NostrKeys::generate());
+        let rumor = private_message(&alice, &bob.public_key_hex(), "meet at noon", Some("abc"));
+        let wrap = gift_wrap(&alice, &bob.public_key_hex(), &rumor).unwrap();
+        assert_eq!(wrap.kind, kind::GIFT_WRAP);
+        assert_ne!(wrap.pubkey, alice.public_key_hex());
+        verify_event(&wrap).unwrap();
+
+        let opened = unwrap_gift(&bob, &wrap).unwrap();
+        assert_eq!(opened, rumor);
+        assert_eq!(opened.tag("e"), Some("abc"));
+        assert!(unwrap_gift(&NostrKeys::generate(), &wrap).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! nostr_monitor — answers Nostr direct messages through the gateway's agents.
+//!
+//! Subscribes on every configured relay to NIP-04 DMs (kind 4) and NIP-17
+//! gift wraps (kind 1059) tagged with the bot's pubkey. Each message is
+//! decrypted, routed as a `direct` peer keyed by the sender's pubkey, and
+//! answered in the same scheme it arrived in. Gift wraps are backdated, so
+//! they are requested from two days back and filtered on the real time of
+//! the message inside.
+
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::agents::RouteQuery;
+use crate::connectors::nostr::{kind, NostrConfig, NostrEvent};
+use crate::connectors::nostr_crypto::{
+    gift_wrap, nip04_decrypt, nip04_encrypt, private_message, sign_event, unwrap_gift, NostrKeys,
+    UnsignedEvent,
+};
+use crate::connectors::nostr_relay::RelayPool;
+use crate::gateway::GatewayState;
+use crate::routing::resolve_route::RoutePeer;
+
+const DM_SUBSCRIPTION: &str = "openkrab-dms";
+/// How far back gift wraps are requested (their timestamps are randomized).
+const GIFT_WRAP_LOOKBACK_SECS: i64 = 2 * 24 * 60 * 60;
+
+/// How a DM reached the bot, and so how the reply is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmScheme {
+    Nip04,
+    Nip17,
+}
+
+/// A decrypted direct message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundDm {
+    pub sender: String,
+    pub text: String,
+    /// Event (NIP-04) or rumor (NIP-17) id the reply points at.
+    pub event_id: String,
+    pub scheme: DmScheme,
+}
+
+/// Binding peer for a Nostr sender.
+pub fn route_peer(sender: &str) -> RoutePeer {
+    RoutePeer {
+        kind: "direct".to_string(),
+        id: sender.to_string(),
+    }
+}
+
+/// Subscription filters for DMs to `pubkey` sent from `since` on.
+pub fn dm_filters(pubkey: &str, since: i64) -> Vec<serde_json::Value> {
+    vec![
+        json!({ "kinds": [kind::ENCRYPTED_DM], "#p": [pubkey], "since": since }),
+        json!({
+            "kinds": [kind::GIFT_WRAP],
+            "#p": [pubkey],
+            "since": since - GIFT_WRAP_LOOKBACK_SECS
+        }),
+    ]
+}
+
+/// Decrypt `event` if it is a DM to `keys` sent at or after `since`.
+pub fn open_dm(keys: &NostrKeys, event: &NostrEvent, since: i64) -> Result<Option<InboundDm>> {
+    let me = keys.public_key_hex();
+    let to_me = |tags: &[Vec<String>]| {
+        tags.iter()
+            .any(|t| t.first().map(String::as_str) == Some("p") && t.get(1) == Some(&me))
+    };
+    let (sender, text, event_id, scheme) = match event.kind {
+        kind::ENCRYPTED_DM => {
+            if event.pubkey == me || !to_me(&event.tags) {
+                return Ok(None);
+            }
+            let text = nip04_decrypt(keys, &event.pubkey, &event.content)?;
+            (
+                event.pubkey.clone(),
+                text,
+                event.id.clone(),
+                DmScheme::Nip04,
+            )
+        }
+        kind::GIFT_WRAP => {
+            let rumor = unwrap_gift(keys, event)?;
+            if rumor.kind != kind::PRIVATE_DM || rumor.pubkey == me || rumor.created_at < since {
+                return Ok(None);
+            }
+            let id = rumor.id();
+            (rumor.pubkey, rumor.content, id, DmScheme::Nip17)
+        }
+        _ => return Ok(None),
+    };
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(InboundDm {
+        sender,
+        text,
+        event_id,
+        scheme,
+    }))
+}
+
+/// The signed event carrying `text` back to the sender of `dm`.
+pub fn build_reply(keys: &NostrKeys, dm: &InboundDm, text: &str) -> Result<NostrEvent> {
+    match dm.scheme {
+        DmScheme::Nip04 => {
+            let content = nip04_encrypt(keys, &dm.sender, text)?;
+            let tags = vec![
+                vec!["p".to_string(), dm.sender.clone()],
+                vec!["e".to_string(), dm.event_id.clone()],
+            ];
+            sign_event(
+                keys,
+                UnsignedEvent::new(&keys.public_key_hex(), kind::ENCRYPTED_DM, &content, tags),
+            )
+        }
+        DmScheme::Nip17 => {
+            let rumor = private_message(keys, &dm.sender, text, Some(&dm.event_id));
+            gift_wrap(keys, &dm.sender, &rumor)
+        }
+    }
+}
+
+struct NostrMonitor {
+    state: Arc<GatewayState>,
+    keys: NostrKeys,
+    pool: RelayPool,
+}
+
+impl NostrMonitor {
+    async fn answer(self: Arc<Self>, dm: InboundDm) {
+        let routed = self.state.agents.route(RouteQuery {
+            channel: "nostr",
+            peer: Some(route_peer(&dm.sender)),
+            ..Default::default()
+        });
+        let reply = match routed {
+            Some(routed) => {
+                tracing::debug!("[nostr] {} in {}", dm.event_id, routed.route.session_key);
+                match self.state.answer_in_session(&routed, &dm.text).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        tracing::warn!("[nostr] agent failed: {}", e);
+                        format!("unavailable: {}", e)
+                    }
+                }
+            }
+            None => "Agent not available".to_string(),
+        };
+        let sent = build_reply(&self.keys, &dm, &reply).and_then(|event| self.pool.publish(&event));
+        if let Err(e) = sent {
+            tracing::warn!("[nostr] failed to send reply to {}: {}", dm.sender, e);
+        }
+    }
+}
+
+/// Answer DMs to `config`'s key on its relays until shutdown.
+pub async fn monitor(state: Arc<GatewayState>, config: NostrConfig) {
+    let keys = match NostrKeys::from_hex(&config.private_key_hex) {
+        Ok(keys) => keys,
+        Err(e) => {
+            tracing::warn!("[nostr] not started: {}", e);
+            return;
+        }
+    };
+    run(state, keys, config.relays).await
+}
+
+async fn run(state: Arc<GatewayState>, keys: NostrKeys, relays: Vec<String>) {
+    let since = chrono::Utc::now().timestamp();
+    let (pool, mut events) = RelayPool::connect(&relays);
+    pool.subscribe(DM_SUBSCRIPTION, dm_filters(&keys.public_key_hex(), since));
+    tracing::info!(
+        "[nostr] listening for DMs to {} on {} relays",
+        keys.public_key_hex(),
+        relays.len()
+    );
+    let monitor = Arc::new(NostrMonitor { state, keys, pool });
+    while let Some(delivered) = events.recv().await {
+        match open_dm(&monitor.keys, &delivered.event, since) {
+            Ok(Some(dm)) => {
+                tokio::spawn(monitor.clone().answer(dm));
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("[nostr] could not open {}: {}", delivered.event.id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::GatewayServer;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::Value;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    #[tokio::test]
+    async fn answers_nip04_and_gift_wrapped_dms() {
+        let bot = NostrKeys::generate();
+        let alice = NostrKeys::generate();
+        let bot_pk = bot.public_key_hex();
+        let nip04 = sign_event(
+            &alice,
+            UnsignedEvent::new(
+                &alice.public_key_hex(),
+                kind::ENCRYPTED_DM,
+                &nip04_encrypt(&alice, &bot_pk, "ping over nip-04").unwrap(),
+                vec![vec!["p".to_string(), bot_pk.clone()]],
+            ),
+        )
+        .unwrap();
+        let mut fresh_rumor = private_message(&alice, &bot_pk, "ping over nip-17", None);
+        fresh_rumor.created_at += 5;
+        let wrapped = gift_wrap(&alice, &bot_pk, &fresh_rumor).unwrap();
+        let mut stale_rumor = private_message(&alice, &bot_pk, "from last week", None);
+        stale_rumor.created_at -= 7 * 24 * 60 * 60;
+        let stale = gift_wrap(&alice, &bot_pk, &stale_rumor).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (published_tx, mut published) = mpsc::unbounded_channel::<NostrEvent>();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                let frame: Value = serde_json::from_str(&text).unwrap();
+                match frame[0].as_str() {
+                    Some("REQ") => {
+                        assert_eq!(frame[1], DM_SUBSCRIPTION);
+                        for event in [&nip04, &stale, &wrapped] {
+                            let msg = json!(["EVENT", frame[1], event]).to_string();
+                            ws.send(WsMessage::Text(msg)).await.unwrap();
+                        }
+                    }
+                    Some("EVENT") => published_tx
+                        .send(serde_json::from_value(frame[1].clone()).unwrap())
+                        .unwrap(),
+                    _ => {}
+                }
+            }
+        });
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::default());
+        let running = tokio::spawn(run(Arc::new(server), bot.clone(), vec![url]));
+
+        let mut replies = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(10), published.recv())
+                .await
+                .expect("no reply published")
+                .unwrap();
+            let text = match event.kind {
+                kind::ENCRYPTED_DM => nip04_decrypt(&alice, &bot_pk, &event.content).unwrap(),
+                kind::GIFT_WRAP => {
+                    let rumor = unwrap_gift(&alice, &event).unwrap();
+                    assert_eq!(rumor.pubkey, bot_pk);
+                    rumor.content
+                }
+                other => panic!("unexpected kind {}", other),
+            };
+            replies.push(text);
+        }
+        replies.sort();
+        assert_eq!(
+            replies,
+            vec!["re: ping over nip-04", "re: ping over nip-17"]
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(published.try_recv().is_err(), "stale DM was answered");
+        running.abort();
+    }
+}
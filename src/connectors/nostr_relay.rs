@@ -0,0 +1,338 @@
+//! nostr_relay — a pool of Nostr relay WebSocket connections.
+//!
+//! Each relay runs in its own task: it connects, sends the pool's open
+//! subscriptions, forwards publishes and reconnects with `polls::next_interval`
+//! back-off when the socket drops. Events from any relay have their id and
+//! signature checked and are delivered once, however many relays carry them.
+
+use anyhow::{bail, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::connectors::nostr::{
+    build_close, build_publish, parse_relay_message, NostrEvent, RelayMessage,
+};
+use crate::connectors::nostr_crypto::verify_event;
+use crate::polls::{next_interval, PollConfig};
+
+/// Event ids remembered to drop copies from other relays.
+const SEEN_EVENTS: usize = 4_096;
+/// Outbound frames buffered per relay before a slow one starts losing them.
+const OUTBOUND_BUFFER: usize = 256;
+
+/// An event delivered by the pool.
+#[derive(Debug, Clone)]
+pub struct PoolEvent {
+    /// Relay that delivered it first.
+    pub relay: String,
+    pub subscription_id: String,
+    pub event: NostrEvent,
+}
+
+#[derive(Default)]
+struct SeenEvents {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenEvents {
+    /// Whether `id` is new; remembers it either way.
+    fn insert(&mut self, id: &str) -> bool {
+        if !self.ids.insert(id.to_string()) {
+            return false;
+        }
+        self.order.push_back(id.to_string());
+        if self.order.len() > SEEN_EVENTS {
+            if let Some(old) = self.order.pop_front() {
+                self.ids.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+struct PoolShared {
+    subscriptions: Mutex<HashMap<String, Vec<Value>>>,
+    outbound: broadcast::Sender<String>,
+    seen: Mutex<SeenEvents>,
+    events: mpsc::UnboundedSender<PoolEvent>,
+}
+
+/// `["REQ", id, filter...]`.
+fn req_message(subscription_id: &str, filters: &[Value]) -> String {
+    let mut frame = vec![Value::from("REQ"), Value::from(subscription_id)];
+    frame.extend(filters.iter().cloned());
+    Value::Array(frame).to_string()
+}
+
+impl PoolShared {
+    fn handle(&self, relay: &str, raw: &str) {
+        match parse_relay_message(raw) {
+            Some(RelayMessage::Event {
+                subscription_id,
+                event,
+            }) => {
+                if let Err(e) = verify_event(&event) {
+                    tracing::debug!("[nostr] {} sent an invalid event: {}", relay, e);
+                    return;
+                }
+                if !self.seen.lock().unwrap().insert(&event.id) {
+                    return;
+                }
+                let _ = self.events.send(PoolEvent {
+                    relay: relay.to_string(),
+                    subscription_id,
+                    event,
+                });
+            }
+            Some(RelayMessage::Ok {
+                event_id,
+                success: false,
+                message,
+            }) => tracing::warn!("[nostr] {} rejected {}: {}", relay, event_id, message),
+            Some(RelayMessage::Notice(notice)) => {
+                tracing::info!("[nostr] notice from {}: {}", relay, notice)
+            }
+            Some(RelayMessage::Closed {
+                subscription_id,
+                message,
+            }) => tracing::warn!(
+                "[nostr] {} closed subscription {}: {}",
+                relay,
+                subscription_id,
+                message
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Connections to a set of relays sharing subscriptions and a de-duplicated
+/// event stream. Dropping the pool closes every connection.
+pub struct RelayPool {
+    shared: Arc<PoolShared>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl RelayPool {
+    /// Start connecting to `relays`; events arrive on the returned receiver.
+    pub fn connect(relays: &[String]) -> (Self, mpsc::UnboundedReceiver<PoolEvent>) {
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let shared = Arc::new(PoolShared {
+            subscriptions: Mutex::new(HashMap::new()),
+            outbound: broadcast::channel(OUTBOUND_BUFFER).0,
+            seen: Mutex::new(SeenEvents::default()),
+            events,
+        });
+        let tasks = relays
+            .iter()
+            .map(|url| tokio::spawn(run_relay(url.clone(), shared.clone())))
+            .collect();
+        (Self { shared, tasks }, events_rx)
+    }
+
+    /// Open (or replace) subscription `id` on every relay, now and after
+    /// each reconnect.
+    pub fn subscribe(&self, id: &str, filters: Vec<Value>) {
+        let req = req_message(id, &filters);
+        self.shared
+            .subscriptions
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), filters);
+        let _ = self.shared.outbound.send(req);
+    }
+
+    pub fn unsubscribe(&self, id: &str) {
+        if self
+            .shared
+            .subscriptions
+            .lock()
+            .unwrap()
+            .remove(id)
+            .is_some()
+        {
+            let _ = self.shared.outbound.send(build_close(id));
+        }
+    }
+
+    /// Send a signed event to every connected relay; returns how many.
+    pub fn publish(&self, event: &NostrEvent) -> Result<usize> {
+        match self.shared.outbound.send(build_publish(event)) {
+            Ok(relays) => Ok(relays),
+            Err(_) => bail!("no relay is connected"),
+        }
+    }
+
+    /// Relays currently connected.
+    pub fn connected(&self) -> usize {
+        self.shared.outbound.receiver_count()
+    }
+}
+
+impl Drop for RelayPool {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+async fn run_relay(url: String, shared: Arc<PoolShared>) {
+    let backoff = PollConfig::default();
+    let mut interval = backoff.initial_interval;
+    loop {
+        let mut connected = false;
+        let result = serve_relay(&url, &shared, &mut connected).await;
+        if connected {
+            interval = backoff.initial_interval;
+        }
+        interval = next_interval(interval, &backoff, true);
+        match result {
+            Ok(()) => tracing::info!(
+                "[nostr] {} disconnected; reconnecting in {:?}",
+                url,
+                interval
+            ),
+            Err(e) => tracing::warn!("[nostr] {}: {}; reconnecting in {:?}", url, e, interval),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn serve_relay(url: &str, shared: &PoolShared, connected: &mut bool) -> Result<()> {
+    let (ws, _) = connect_async(url).await?;
+    *connected = true;
+    tracing::info!("[nostr] connected to {}", url);
+    let (mut write, mut read) = ws.split();
+    // Subscribe to live frames before replaying, so nothing sent in between
+    // is missed; at worst a REQ goes out twice, which relays treat as a
+    // replacement.
+    let mut outbound = shared.outbound.subscribe();
+    let replay: Vec<String> = shared
+        .subscriptions
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, filters)| req_message(id, filters))
+        .collect();
+    for req in replay {
+        write.send(WsMessage::Text(req)).await?;
+    }
+    loop {
+        tokio::select! {
+            msg = read.next() => match msg {
+                Some(Ok(WsMessage::Text(text))) => shared.handle(url, &text),
+                Some(Ok(WsMessage::Ping(data))) => write.send(WsMessage::Pong(data)).await?,
+                Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+            frame = outbound.recv() => match frame {
+                Ok(frame) => write.send(WsMessage::Text(frame)).await?,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("[nostr] {} fell behind; {} frames dropped", url, missed)
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::nostr::kind;
+    use crate::connectors::nostr_crypto::{sign_event, NostrKeys, UnsignedEvent};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    /// A relay that answers every REQ with `events`, reports what it
+    /// receives and hangs up after the first publish on its first connection.
+    async fn fake_relay(events: Vec<NostrEvent>) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (frames_tx, frames) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut first = true;
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                    let frame: Value = serde_json::from_str(&text).unwrap();
+                    frames_tx.send(frame.clone()).unwrap();
+                    match frame[0].as_str() {
+                        Some("REQ") => {
+                            for event in &events {
+                                let msg = json!(["EVENT", frame[1], event]).to_string();
+                                ws.send(WsMessage::Text(msg)).await.unwrap();
+                            }
+                        }
+                        Some("EVENT") if first => break,
+                        _ => {}
+                    }
+                }
+                first = false;
+            }
+        });
+        (url, frames)
+    }
+
+    async fn next_frame(frames: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+        tokio::time::timeout(Duration::from_secs(10), frames.recv())
+            .await
+            .expect("timed out waiting for the pool")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn pool_dedups_verifies_and_resubscribes() {
+        let keys = NostrKeys::generate();
+        let note = |text: &str| {
+            sign_event(
+                &keys,
+                UnsignedEvent::new(&keys.public_key_hex(), kind::TEXT_NOTE, text, vec![]),
+            )
+            .unwrap()
+        };
+        let shared_note = note("carried by both relays");
+        let mut forged = note("original");
+        forged.content = "forged".to_string();
+
+        let (url_a, mut frames_a) = fake_relay(vec![shared_note.clone(), forged]).await;
+        let (url_b, mut frames_b) = fake_relay(vec![shared_note.clone()]).await;
+        let (pool, mut events) = RelayPool::connect(&[url_a, url_b]);
+        pool.subscribe("notes", vec![json!({ "kinds": [1] })]);
+
+        assert_eq!(next_frame(&mut frames_a).await[0], "REQ");
+        assert_eq!(next_frame(&mut frames_b).await[0], "REQ");
+        let delivered = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivered.event.id, shared_note.id);
+        assert_eq!(delivered.subscription_id, "notes");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(
+            events.try_recv().is_err(),
+            "duplicate or forged event delivered"
+        );
+
+        let reply = note("published");
+        assert_eq!(pool.publish(&reply).unwrap(), 2);
+        assert_eq!(next_frame(&mut frames_a).await[1]["id"], reply.id);
+        assert_eq!(next_frame(&mut frames_b).await[1]["id"], reply.id);
+
+        // Both relays hung up after the publish; the pool reconnects and
+        // sends the subscription again.
+        let again = next_frame(&mut frames_a).await;
+        assert_eq!(again, json!(["REQ", "notes", { "kinds": [1] }]));
+        pool.unsubscribe("notes");
+        assert_eq!(next_frame(&mut frames_a).await, json!(["CLOSE", "notes"]));
+    }
+}
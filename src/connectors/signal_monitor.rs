@@ -0,0 +1,332 @@
+//! signal_monitor — answers Signal messages through the gateway's agents.
+//!
+//! Follows the signal-cli HTTP daemon's `/api/v1/events` stream for one
+//! account (spawning the daemon first when it is local) and answers each
+//! incoming text message with the JSON-RPC `send` method, to the group it came
+//! from or back to the sender. A dropped stream is reopened with
+//! `polls::next_interval` back-off and reported in the monitor's
+//! `ChannelHealth`.
+
+use anyhow::{anyhow, bail, Result};
+use futures_util::StreamExt;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use crate::agents::RouteQuery;
+use crate::gateway::monitor_manager::HealthReporter;
+use crate::gateway::types::ChannelStatus;
+use crate::gateway::GatewayServer;
+use crate::polls::{next_interval, PollConfig};
+use crate::routing::resolve_route::RoutePeer;
+use crate::signal::client::SseDecoder;
+use crate::signal::daemon::{
+    daemon_opts_from_config, should_auto_start, spawn_daemon, wait_for_daemon_ready, DaemonHandle,
+};
+use crate::signal::identity::{looks_like_uuid, SignalSender};
+use crate::signal::send::{send_message, SignalTarget};
+use crate::signal::{parse_inbound, ParsedSignalMessage, SignalConfig, SignalInbound};
+use crate::OPENKRAB_CONFIG::SignalAccountConfig;
+
+/// A configured Signal account.
+#[derive(Debug, Clone)]
+pub struct SignalAccount {
+    pub account_id: String,
+    pub config: SignalConfig,
+    /// Senders that may talk to the agent (numbers, UUIDs or `*`); empty
+    /// allows everyone.
+    pub allowlist: Vec<String>,
+}
+
+impl SignalAccount {
+    pub fn from_config(account_id: &str, cfg: &SignalAccountConfig) -> Result<Self> {
+        let phone_number = cfg
+            .phone_number
+            .clone()
+            .ok_or_else(|| anyhow!("phone_number is not set"))?;
+        let config = SignalConfig {
+            account: Some(phone_number.clone()),
+            phone_number,
+            api_base: cfg
+                .api_base
+                .as_deref()
+                .unwrap_or("http://localhost:8080")
+                .trim_end_matches('/')
+                .to_string(),
+            cli_path: cfg.cli_path.clone(),
+            auto_start: cfg.auto_start,
+            startup_timeout_ms: Some(30_000),
+            send_read_receipts: None,
+        };
+        config.validate()?;
+        Ok(Self {
+            account_id: account_id.to_string(),
+            config,
+            allowlist: cfg.allowlist.clone(),
+        })
+    }
+
+    fn accepts(&self, msg: &ParsedSignalMessage) -> bool {
+        !msg.is_sync
+            && msg.reaction.is_none()
+            && msg.from != self.config.phone_number
+            && !msg.text.trim().is_empty()
+            && (self.allowlist.is_empty() || self.sender_allowed(&msg.from))
+    }
+
+    fn sender_allowed(&self, from: &str) -> bool {
+        let sender = if looks_like_uuid(from) {
+            SignalSender::resolve(None, Some(from))
+        } else {
+            SignalSender::resolve(Some(from), None)
+        };
+        sender.is_some_and(|s| s.is_allowed(&self.allowlist))
+    }
+}
+
+/// Binding peer for a Signal message: its group, or the sender.
+pub fn route_peer(msg: &ParsedSignalMessage) -> RoutePeer {
+    match &msg.group_id {
+        Some(group_id) => RoutePeer {
+            kind: "group".to_string(),
+            id: group_id.clone(),
+        },
+        None => RoutePeer {
+            kind: "direct".to_string(),
+            id: msg.from.clone(),
+        },
+    }
+}
+
+/// Where the answer to `msg` goes.
+pub fn reply_target(msg: &ParsedSignalMessage) -> SignalTarget {
+    match &msg.group_id {
+        Some(group_id) => SignalTarget::Group(group_id.clone()),
+        None => SignalTarget::Recipient(msg.from.clone()),
+    }
+}
+
+/// Follow the event stream until `stop_rx` flips.
+pub fn spawn_signal_monitor(
+    state: Arc<GatewayServer>,
+    account: SignalAccount,
+    health: HealthReporter,
+    mut stop_rx: watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let monitor = Arc::new(SignalMonitor {
+            state,
+            client: reqwest::Client::new(),
+            account,
+        });
+        let daemon = monitor.start_daemon().await;
+        let backoff = PollConfig::default();
+        let mut interval = backoff.initial_interval;
+        loop {
+            let error = tokio::select! {
+                result = monitor.clone().follow_events(&health) => match result {
+                    Ok(()) => anyhow!("event stream closed"),
+                    Err(e) => e,
+                },
+                _ = stop_rx.changed() => break,
+            };
+            if health.snapshot().status == ChannelStatus::Healthy {
+                interval = backoff.initial_interval;
+            }
+            health.failed(&error.to_string());
+            interval = next_interval(interval, &backoff, true);
+            tracing::warn!(
+                "[signal] {}: {}; reconnecting in {:?}",
+                monitor.account.account_id,
+                error,
+                interval
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = stop_rx.changed() => break,
+            }
+        }
+        if let Some(daemon) = daemon {
+            daemon.stop().await;
+        }
+        tracing::info!("[signal] {} monitor stopped", monitor.account.account_id);
+    })
+}
+
+struct SignalMonitor {
+    state: Arc<GatewayServer>,
+    client: reqwest::Client,
+    account: SignalAccount,
+}
+
+impl SignalMonitor {
+    async fn start_daemon(&self) -> Option<DaemonHandle> {
+        let config = &self.account.config;
+        if !should_auto_start(config) {
+            return None;
+        }
+        let mut opts = daemon_opts_from_config(config);
+        // Receive as soon as the daemon is up rather than on request.
+        opts.receive_mode = Some("on-start".to_string());
+        let daemon = match spawn_daemon(opts).await {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                tracing::warn!("[signal] failed to start signal-cli: {}", e);
+                return None;
+            }
+        };
+        let timeout_ms = config.startup_timeout_ms.unwrap_or(30_000);
+        if let Err(e) = wait_for_daemon_ready(&self.client, &config.api_base, timeout_ms).await {
+            tracing::warn!("[signal] signal-cli not ready: {}", e);
+        }
+        Some(daemon)
+    }
+
+    /// Read events until the stream ends or fails.
+    async fn follow_events(self: Arc<Self>, health: &HealthReporter) -> Result<()> {
+        let config = &self.account.config;
+        let url = format!(
+            "{}/api/v1/events?account={}",
+            config.api_base,
+            urlencoding::encode(config.resolve_account())
+        );
+        let started = std::time::Instant::now();
+        let resp = self.client.get(&url).send().await?;
+        if !resp.status().is_success() {
+            bail!("event stream returned {}", resp.status());
+        }
+        health.healthy(Some(started.elapsed()));
+
+        let mut decoder = SseDecoder::default();
+        let mut stream = resp.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            for event in decoder.push(&chunk?) {
+                if event.event != "receive" {
+                    continue;
+                }
+                let Ok(inbound) = serde_json::from_str::<SignalInbound>(&event.data) else {
+                    continue;
+                };
+                if let Some(msg) = parse_inbound(&inbound).filter(|m| self.account.accepts(m)) {
+                    tokio::spawn(self.clone().answer(msg));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn answer(self: Arc<Self>, msg: ParsedSignalMessage) {
+        let routed = self.state.agents.route(RouteQuery {
+            channel: "signal",
+            account_id: Some(&self.account.account_id),
+            peer: Some(route_peer(&msg)),
+            ..Default::default()
+        });
+        let reply = match routed {
+            Some(routed) => {
+                tracing::debug!("[signal] {} in {}", msg.timestamp, routed.route.session_key);
+                match self.state.answer_in_session(&routed, &msg.text).await {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        tracing::warn!("[signal] agent failed: {}", e);
+                        format!("unavailable: {}", e)
+                    }
+                }
+            }
+            None => "Agent not available".to_string(),
+        };
+        let target = reply_target(&msg);
+        if let Err(e) = send_message(&self.client, &self.account.config, &target, &reply).await {
+            tracing::warn!("[signal] failed to send reply: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use axum::extract::State;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct FakeDaemon {
+        delivered: Arc<AtomicBool>,
+        rpc: Arc<Mutex<Vec<Value>>>,
+    }
+
+    /// Streams one group message on the first connection only.
+    async fn events(State(daemon): State<FakeDaemon>) -> String {
+        if daemon.delivered.swap(true, Ordering::SeqCst) {
+            return String::new();
+        }
+        let receive = json!({
+            "envelope": {
+                "source": "+15550001111",
+                "sourceDevice": 1,
+                "timestamp": 1_760_000_000_000i64,
+                "dataMessage": {
+                    "message": "where are you?",
+                    "timestamp": 1_760_000_000_000i64,
+                    "groupInfo": { "groupId": "Z3JvdXA=", "type": "DELIVER" }
+                }
+            },
+            "account": "+15550009999"
+        });
+        format!(":\n\nevent:receive\ndata:{}\n\n", receive)
+    }
+
+    async fn rpc(State(daemon): State<FakeDaemon>, Json(body): Json<Value>) -> Json<Value> {
+        daemon.rpc.lock().unwrap().push(body.clone());
+        Json(json!({ "jsonrpc": "2.0", "result": { "timestamp": 1 }, "id": body["id"] }))
+    }
+
+    #[tokio::test]
+    async fn group_messages_are_answered_in_the_group() {
+        let daemon = FakeDaemon::default();
+        let app = Router::new()
+            .route("/api/v1/events", get(events))
+            .route("/api/v1/rpc", post(rpc))
+            .with_state(daemon.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::fixed("on my way"));
+        let account = SignalAccount::from_config(
+            "default",
+            &SignalAccountConfig {
+                enabled: true,
+                phone_number: Some("+15550009999".to_string()),
+                api_base: Some(format!("http://{}", addr)),
+                auto_start: Some(false),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let health = HealthReporter::new("signal:default");
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let handle = spawn_signal_monitor(Arc::new(server), account, health, stop_rx);
+
+        for _ in 0..100 {
+            if !daemon.rpc.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        stop_tx.send(true).unwrap();
+        handle.await.unwrap();
+
+        let rpc = daemon.rpc.lock().unwrap();
+        assert_eq!(rpc.len(), 1);
+        assert_eq!(rpc[0]["method"], "send");
+        assert_eq!(rpc[0]["params"]["account"], "+15550009999");
+        assert_eq!(rpc[0]["params"]["groupId"], "Z3JvdXA=");
+        assert_eq!(rpc[0]["params"]["message"], "on my way");
+    }
+}
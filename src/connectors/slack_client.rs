@@ -38,6 +38,82 @@ pub async fn send_message(
     Ok(v)
 }
 
+pub const SLACK_API_BASE: &str = "https://slack.com/api";
+
+/// Slack Web API calls made with one token (bot `xoxb-` or app `xapp-`).
+#[derive(Clone)]
+pub struct SlackApi {
+    client: Client,
+    token: String,
+    base_url: String,
+}
+
+impl SlackApi {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            client: crate::infra::retry_http::build_retrying_client(),
+            token: token.into(),
+            base_url: SLACK_API_BASE.to_string(),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Call a Web API method; Slack reports failures as `ok: false`.
+    pub async fn call(
+        &self,
+        method: &str,
+        payload: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let url = format!("{}/{}", self.base_url, method);
+        let resp = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.token)
+            .json(payload)
+            .send()
+            .await?;
+        let v: serde_json::Value = resp.json().await?;
+        if v.get("ok").and_then(|ok| ok.as_bool()) != Some(true) {
+            let error = v.get("error").and_then(|e| e.as_str()).unwrap_or("unknown");
+            anyhow::bail!("slack {} failed: {}", method, error);
+        }
+        Ok(v)
+    }
+
+    /// Post a message and return its `ts`.
+    pub async fn post_message(
+        &self,
+        channel: &str,
+        text: &str,
+        thread_ts: Option<&str>,
+    ) -> Result<String> {
+        let payload = build_slack_http_payload(channel, text, thread_ts);
+        let v = self.call("chat.postMessage", &payload).await?;
+        Ok(v.get("ts")
+            .and_then(|ts| ts.as_str())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    pub async fn update_message(&self, channel: &str, ts: &str, text: &str) -> Result<()> {
+        let payload = json!({ "channel": channel, "ts": ts, "text": text });
+        self.call("chat.update", &payload).await.map(|_| ())
+    }
+
+    /// Socket Mode WebSocket URL (`apps.connections.open`, app token).
+    pub async fn open_socket(&self) -> Result<String> {
+        let v = self.call("apps.connections.open", &json!({})).await?;
+        v.get("url")
+            .and_then(|url| url.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("slack apps.connections.open returned no url"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,649 @@
+//! slack_monitor — inbound Slack messages over Socket Mode or the Events API.
+//!
+//! Socket Mode opens a WebSocket from `apps.connections.open` (app-level
+//! `xapp-` token) and acks every envelope as soon as it arrives. The Events
+//! API posts to the gateway's `/slack/events`, which checks the request
+//! signature and answers before the agent runs, well inside Slack's three
+//! second window. Either way `app_mention` events and direct messages go to
+//! the agent. Replies land in the event's thread (`thread_ts`, or the
+//! mention itself for a new thread), which is also what keys the session,
+//! and stream in by editing one message through a `DraftStreamLoop`.
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::agents::streaming::{create_stream_pair, StreamEvent};
+use crate::agents::RouteQuery;
+use crate::channels::draft_stream_loop::{create_draft_stream_loop, DraftStreamLoop};
+use crate::connectors::slack_client::SlackApi;
+use crate::routing::resolve_route::RoutePeer;
+use crate::sessions::TranscriptEntry;
+
+/// Requests signed longer ago than this are replays.
+pub const SIGNATURE_MAX_AGE_SECS: i64 = 300;
+/// Minimum gap between draft edits (`chat.update` is rate limited).
+const DRAFT_THROTTLE_MS: u64 = 1_000;
+/// Longest text put in one message.
+const TEXT_CHUNK_LIMIT: usize = 4_000;
+/// Event ids remembered to drop redeliveries.
+const SEEN_EVENTS: usize = 256;
+
+// ─── Inbound events ──────────────────────────────────────────────────────────
+
+/// A Slack message addressed to the bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlackInbound {
+    pub channel: String,
+    /// `im`, `mpim`, `channel` or `group`.
+    pub channel_type: String,
+    pub user: String,
+    pub team: Option<String>,
+    pub text: String,
+    pub ts: String,
+    pub thread_ts: Option<String>,
+}
+
+impl SlackInbound {
+    /// Mentions in channels and messages in DMs; edits, bot messages and
+    /// the bot's own messages are ignored. Plain channel messages also
+    /// arrive as `app_mention` when they mention the bot, so they are
+    /// skipped here.
+    pub fn from_event(event: &Value, bot_user_id: Option<&str>) -> Option<Self> {
+        let text_field = |key: &str| event.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        if event.get("bot_id").is_some() || event.get("subtype").is_some() {
+            return None;
+        }
+        let user = text_field("user")?;
+        if Some(user.as_str()) == bot_user_id {
+            return None;
+        }
+        let channel_type = match event.get("type").and_then(|t| t.as_str())? {
+            "app_mention" => text_field("channel_type").unwrap_or_else(|| "channel".to_string()),
+            "message" => text_field("channel_type").filter(|t| t == "im")?,
+            _ => return None,
+        };
+        let text = strip_mention(&text_field("text")?, bot_user_id);
+        if text.is_empty() {
+            return None;
+        }
+        Some(Self {
+            channel: text_field("channel")?,
+            channel_type,
+            user,
+            team: text_field("team"),
+            text,
+            ts: text_field("ts")?,
+            thread_ts: text_field("thread_ts"),
+        })
+    }
+
+    /// Thread the reply goes to. Mentions in channels start one; DMs stay
+    /// top level unless already threaded.
+    pub fn reply_thread_ts(&self) -> Option<&str> {
+        match &self.thread_ts {
+            Some(ts) => Some(ts),
+            None if self.channel_type != "im" => Some(&self.ts),
+            None => None,
+        }
+    }
+
+    pub fn route_peer(&self) -> RoutePeer {
+        let (kind, id) = match self.channel_type.as_str() {
+            "im" => ("direct", &self.user),
+            "mpim" => ("group", &self.channel),
+            _ => ("channel", &self.channel),
+        };
+        RoutePeer {
+            kind: kind.to_string(),
+            id: id.clone(),
+        }
+    }
+}
+
+/// Drop the bot's mention, or a leading mention when the bot id is unknown.
+fn strip_mention(text: &str, bot_user_id: Option<&str>) -> String {
+    let text = text.trim();
+    let stripped = match bot_user_id {
+        Some(id) => text.replace(&format!("<@{}>", id), ""),
+        None => match text
+            .strip_prefix("<@")
+            .and_then(|rest| rest.split_once('>'))
+        {
+            Some((_, rest)) => rest.to_string(),
+            None => text.to_string(),
+        },
+    };
+    stripped.trim().to_string()
+}
+
+/// Bot user id from an event payload's `authorizations`.
+fn bot_user_id(payload: &Value) -> Option<&str> {
+    payload
+        .get("authorizations")
+        .and_then(|a| a.get(0))
+        .and_then(|a| a.get("user_id"))
+        .and_then(|id| id.as_str())
+}
+
+// ─── Request signatures ──────────────────────────────────────────────────────
+
+/// Check `X-Slack-Signature` (`v0=` HMAC-SHA256 of `v0:{timestamp}:{body}`)
+/// and reject timestamps more than five minutes from `now`.
+pub fn verify_signature(
+    signing_secret: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    now: i64,
+) -> bool {
+    use hmac::{Hmac, Mac};
+    type HmacSha256 = Hmac<sha2::Sha256>;
+
+    let Ok(sent_at) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    // `abs_diff` because the timestamp is attacker-controlled and a
+    // subtraction could overflow.
+    if signing_secret.is_empty() || now.abs_diff(sent_at) > SIGNATURE_MAX_AGE_SECS as u64 {
+        return false;
+    }
+    let Some(Ok(expected)) = signature.strip_prefix("v0=").map(hex::decode) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(signing_secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(format!("v0:{}:", timestamp).as_bytes());
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+// ─── Monitor ─────────────────────────────────────────────────────────────────
+
+/// Answers Slack events with the gateway's agents.
+#[derive(Clone)]
+pub struct SlackMonitor {
+    state: Arc<crate::gateway::GatewayState>,
+    api: SlackApi,
+    seen: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl SlackMonitor {
+    /// `api` carries the bot token replies are posted with.
+    pub fn new(state: Arc<crate::gateway::GatewayState>, api: SlackApi) -> Self {
+        Self {
+            state,
+            api,
+            seen: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Whether `event_id` was already handled; redeliveries of it are
+    /// dropped.
+    pub fn has_seen(&self, event_id: &str) -> bool {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.iter().any(|s| s == event_id)
+    }
+
+    /// Handle an `event_callback` payload.
+    pub async fn handle_payload(&self, payload: Value) {
+        if let Some(id) = payload.get("event_id").and_then(|id| id.as_str()) {
+            let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+            if seen.iter().any(|s| s == id) {
+                return;
+            }
+            if seen.len() >= SEEN_EVENTS {
+                seen.pop_front();
+            }
+            seen.push_back(id.to_string());
+        }
+        let Some(event) = payload.get("event") else {
+            return;
+        };
+        if let Some(inbound) = SlackInbound::from_event(event, bot_user_id(&payload)) {
+            self.answer(inbound).await;
+        }
+    }
+
+    async fn answer(&self, inbound: SlackInbound) {
+        let thread_ts = inbound.reply_thread_ts().map(str::to_string);
+        let reply = SlackReply::new(self.api.clone(), &inbound.channel, thread_ts.clone());
+        let routed = match self.state.agents.route(RouteQuery {
+            channel: "slack",
+            peer: Some(inbound.route_peer()),
+            team_id: inbound.team.as_deref(),
+            thread_id: thread_ts.as_deref(),
+            ..Default::default()
+        }) {
+            Some(routed) => routed,
+            None => {
+                reply.finish("Agent not available").await;
+                return;
+            }
+        };
+        tracing::debug!("[slack] {} in {}", inbound.ts, routed.route.session_key);
+
+        let draft = reply.draft_loop();
+        let (handler, mut events) = create_stream_pair(usize::MAX);
+        let forwarder = {
+            let draft = draft.clone();
+            tokio::spawn(async move {
+                while let Some(event) = events.rx.recv().await {
+                    if let StreamEvent::TextDelta { accumulated, .. } = event {
+                        draft.update(accumulated);
+                    }
+                }
+            })
+        };
+        let answer = self
+            .state
+            .answer_entry_streaming(&routed, TranscriptEntry::user(&inbound.text), Some(handler))
+            .await;
+        let _ = forwarder.await;
+        draft.stop();
+
+        match answer {
+            Ok(text) => reply.finish(&text).await,
+            Err(e) => {
+                tracing::warn!("[slack] agent failed: {}", e);
+                reply.finish(&format!("unavailable: {}", e)).await;
+            }
+        }
+    }
+
+    /// Serve one Socket Mode connection until Slack closes it or asks for
+    /// a reconnect.
+    pub async fn run_socket_session(&self, url: &str) -> Result<()> {
+        let (ws, _) = connect_async(url).await?;
+        let (mut write, mut read) = ws.split();
+        while let Some(frame) = read.next().await {
+            let text = match frame? {
+                WsMessage::Text(text) => text,
+                WsMessage::Ping(data) => {
+                    write.send(WsMessage::Pong(data)).await?;
+                    continue;
+                }
+                WsMessage::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(envelope) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            // Ack first; Slack redelivers anything not acked within 3s.
+            if let Some(id) = envelope.get("envelope_id").and_then(|id| id.as_str()) {
+                let ack = json!({ "envelope_id": id }).to_string();
+                write.send(WsMessage::Text(ack)).await?;
+            }
+            match envelope.get("type").and_then(|t| t.as_str()) {
+                Some("hello") => tracing::info!("[slack] socket mode connected"),
+                Some("disconnect") => break,
+                Some("events_api") => {
+                    let monitor = self.clone();
+                    let payload = envelope.get("payload").cloned().unwrap_or_default();
+                    tokio::spawn(async move { monitor.handle_payload(payload).await });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run Socket Mode forever, reconnecting with backoff.
+pub async fn monitor_socket_mode(
+    state: Arc<crate::gateway::GatewayState>,
+    bot_token: String,
+    app_token: String,
+) {
+    let monitor = SlackMonitor::new(state, SlackApi::new(bot_token));
+    let app = SlackApi::new(app_token);
+    let mut attempt = 0u32;
+    loop {
+        let session = match app.open_socket().await {
+            Ok(url) => monitor.run_socket_session(&url).await,
+            Err(e) => Err(e),
+        };
+        match session {
+            Ok(()) => attempt = 0,
+            Err(e) => {
+                attempt = attempt.saturating_add(1);
+                tracing::warn!("[slack] socket mode session failed: {}", e);
+            }
+        }
+        // 1s, 2s, 4s, ... up to 30s after failures; a moment after a
+        // requested reconnect.
+        let delay_ms = match attempt {
+            0 => 250,
+            n => (1_000u64 << (n - 1).min(5)).min(30_000),
+        };
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+    }
+}
+
+// ─── Replies ─────────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct DraftState {
+    /// The message being edited, once posted.
+    ts: Option<String>,
+    finished: bool,
+}
+
+/// One reply message, drafted while the agent streams and finished with
+/// the full text. Writes are serialized so a late draft edit can never
+/// overwrite the final text.
+#[derive(Clone)]
+struct SlackReply {
+    api: SlackApi,
+    channel: String,
+    thread_ts: Option<String>,
+    state: Arc<tokio::sync::Mutex<DraftState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl SlackReply {
+    fn new(api: SlackApi, channel: &str, thread_ts: Option<String>) -> Self {
+        Self {
+            api,
+            channel: channel.to_string(),
+            thread_ts,
+            state: Arc::new(tokio::sync::Mutex::new(DraftState::default())),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    async fn write_draft(&self, text: &str) -> bool {
+        let mut state = self.state.lock().await;
+        if state.finished {
+            return true;
+        }
+        let text: String = text.chars().take(TEXT_CHUNK_LIMIT).collect();
+        let written = match &state.ts {
+            Some(ts) => self.api.update_message(&self.channel, ts, &text).await,
+            None => self
+                .api
+                .post_message(&self.channel, &text, self.thread_ts.as_deref())
+                .await
+                .map(|ts| state.ts = Some(ts)),
+        };
+        if let Err(e) = &written {
+            tracing::debug!("[slack] draft edit failed: {}", e);
+        }
+        written.is_ok()
+    }
+
+    /// The draft loop runs its sends off the runtime, so they are spawned
+    /// back onto it.
+    fn draft_loop(&self) -> DraftStreamLoop {
+        let handle = tokio::runtime::Handle::current();
+        let stopped = self.stopped.clone();
+        let reply = self.clone();
+        create_draft_stream_loop(
+            DRAFT_THROTTLE_MS,
+            Arc::new(move || stopped.load(Ordering::SeqCst)),
+            Arc::new(move |text: String| {
+                let reply = reply.clone();
+                let task = handle.spawn(async move { reply.write_draft(&text).await });
+                Box::pin(async move { task.await.ok() })
+            }),
+        )
+    }
+
+    async fn finish(&self, text: &str) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let mut state = self.state.lock().await;
+        state.finished = true;
+        let chunks = chunk_text(text, TEXT_CHUNK_LIMIT);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let sent = match (i, &state.ts) {
+                (0, Some(ts)) => self.api.update_message(&self.channel, ts, chunk).await,
+                _ => self
+                    .api
+                    .post_message(&self.channel, chunk, self.thread_ts.as_deref())
+                    .await
+                    .map(|_| ()),
+            };
+            if let Err(e) = sent {
+                tracing::warn!("[slack] failed to send reply: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(max_chars)
+        .map(|c| c.iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::GatewayServer;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use axum::{extract::State, routing::post, Json, Router};
+
+    type Calls = Arc<Mutex<Vec<(String, Value)>>>;
+
+    async fn record(
+        axum::extract::Path(method): axum::extract::Path<String>,
+        State(calls): State<Calls>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        calls.lock().unwrap().push((method, body));
+        Json(json!({ "ok": true, "ts": "1760000000.000900" }))
+    }
+
+    /// A fake Slack Web API recording every call.
+    async fn fake_web_api() -> (String, Calls) {
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/:method", post(record))
+            .with_state(calls.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", addr), calls)
+    }
+
+    fn mention_payload() -> Value {
+        json!({
+            "type": "event_callback",
+            "team_id": "T1",
+            "event_id": "Ev1",
+            "authorizations": [{"user_id": "UBOT"}],
+            "event": {
+                "type": "app_mention",
+                "user": "U1",
+                "team": "T1",
+                "channel": "C1",
+                "text": "<@UBOT> how is the deploy?",
+                "ts": "1760000000.000100"
+            }
+        })
+    }
+
+    #[test]
+    fn inbound_events_are_filtered_and_threaded() {
+        let payload = mention_payload();
+        let mention = SlackInbound::from_event(&payload["event"], Some("UBOT")).unwrap();
+        assert_eq!(mention.text, "how is the deploy?");
+        assert_eq!(mention.reply_thread_ts(), Some("1760000000.000100"));
+        assert_eq!(mention.route_peer().kind, "channel");
+
+        let dm = json!({"type": "message", "channel_type": "im", "user": "U1",
+            "channel": "D1", "text": "hi", "ts": "1.2"});
+        let dm = SlackInbound::from_event(&dm, Some("UBOT")).unwrap();
+        assert_eq!(dm.reply_thread_ts(), None);
+        assert_eq!(dm.route_peer().id, "U1");
+
+        let threaded = json!({"type": "message", "channel_type": "im", "user": "U1",
+            "channel": "D1", "text": "more", "ts": "1.3", "thread_ts": "1.2"});
+        let threaded = SlackInbound::from_event(&threaded, Some("UBOT")).unwrap();
+        assert_eq!(threaded.reply_thread_ts(), Some("1.2"));
+
+        for ignored in [
+            json!({"type": "message", "channel_type": "channel", "user": "U1",
+                "channel": "C1", "text": "chatter", "ts": "1.4"}),
+            json!({"type": "message", "channel_type": "im", "bot_id": "B1", "user": "U2",
+                "channel": "D1", "text": "bot", "ts": "1.5"}),
+            json!({"type": "message", "channel_type": "im", "subtype": "message_changed",
+                "channel": "D1", "ts": "1.6"}),
+            json!({"type": "message", "channel_type": "im", "user": "UBOT",
+                "channel": "D1", "text": "me", "ts": "1.7"}),
+        ] {
+            assert!(SlackInbound::from_event(&ignored, Some("UBOT")).is_none());
+        }
+    }
+
+    #[test]
+    fn signatures_are_checked() {
+        use hmac::{Hmac, Mac};
+        let body = br#"{"type":"url_verification","challenge":"abc"}"#;
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(b"v0:1760000000:");
+        mac.update(body);
+        let signature = format!("v0={}", hex::encode(mac.finalize().into_bytes()));
+
+        assert!(verify_signature(
+            "secret",
+            "1760000000",
+            body,
+            &signature,
+            1760000100
+        ));
+        assert!(!verify_signature(
+            "other",
+            "1760000000",
+            body,
+            &signature,
+            1760000100
+        ));
+        assert!(!verify_signature(
+            "secret",
+            "1760000000",
+            b"{}",
+            &signature,
+            1760000100
+        ));
+        // Replayed after five minutes.
+        assert!(!verify_signature(
+            "secret",
+            "1760000000",
+            body,
+            &signature,
+            1760000400
+        ));
+        assert!(!verify_signature(
+            "secret",
+            "1760000000",
+            body,
+            "v0=zz",
+            1760000100
+        ));
+        // Extreme timestamps are rejected rather than overflowing.
+        for timestamp in [i64::MIN, i64::MAX] {
+            assert!(!verify_signature(
+                "secret",
+                &timestamp.to_string(),
+                body,
+                &signature,
+                1760000100
+            ));
+        }
+        assert!(!verify_signature(
+            "secret",
+            "1760000000",
+            body,
+            &signature,
+            i64::MIN
+        ));
+    }
+
+    #[tokio::test]
+    async fn socket_mode_acks_and_answers_in_thread() {
+        let (api_base, calls) = fake_web_api().await;
+
+        // A fake Socket Mode endpoint: hello, one mention, then a
+        // disconnect once the mention is acked.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+        let slack = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            ws.send(WsMessage::Text(json!({"type": "hello"}).to_string()))
+                .await
+                .unwrap();
+            let envelope = json!({
+                "type": "events_api",
+                "envelope_id": "env-1",
+                "payload": mention_payload(),
+            });
+            ws.send(WsMessage::Text(envelope.to_string()))
+                .await
+                .unwrap();
+            let ack = loop {
+                if let Some(Ok(WsMessage::Text(text))) = ws.next().await {
+                    break serde_json::from_str::<Value>(&text).unwrap();
+                }
+            };
+            ws.send(WsMessage::Text(json!({"type": "disconnect"}).to_string()))
+                .await
+                .unwrap();
+            ack
+        });
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents =
+            single_agent_pool(EchoProvider::fixed("deploy is green").streaming("deploy is"));
+        let monitor = SlackMonitor::new(
+            Arc::new(server),
+            SlackApi::new("xoxb-test").with_base_url(&api_base),
+        );
+        monitor.run_socket_session(&ws_url).await.unwrap();
+        assert_eq!(slack.await.unwrap()["envelope_id"], "env-1");
+
+        // The reply runs after the ack; wait for the final text.
+        let mut final_text = None;
+        for _ in 0..100 {
+            final_text = calls
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .map(|(_, body)| body["text"].as_str().unwrap_or_default().to_string())
+                .find(|text| text == "deploy is green");
+            if final_text.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(
+            final_text.is_some(),
+            "no final reply: {:?}",
+            calls.lock().unwrap()
+        );
+
+        let calls = calls.lock().unwrap();
+        let (method, first) = &calls[0];
+        assert_eq!(method, "chat.postMessage");
+        assert_eq!(first["channel"], "C1");
+        assert_eq!(first["thread_ts"], "1760000000.000100");
+        // Any later write edits that same message.
+        assert!(calls[1..]
+            .iter()
+            .all(|(method, body)| method == "chat.update" && body["ts"] == "1760000000.000900"));
+    }
+}
@@ -1,6 +1,14 @@
+use crate::agents::chat::ChatMessage;
+use crate::agents::RouteQuery;
 use crate::common::Message;
 use crate::common::UserId;
 use crate::connectors::telegram_client;
+use crate::connectors::telegram_media::{self, MediaInputContext, TelegramMedia};
+use crate::media_understanding::MediaUnderstandingProvider;
+use crate::routing::resolve_route::RoutePeer;
+use crate::sessions::TranscriptEntry;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
@@ -16,11 +24,84 @@ pub fn format_outbound(text: &str) -> String {
     format!("[telegram] {text}")
 }
 
+/// Binding peer for a Telegram chat (`private` chats are direct).
+pub fn route_peer(chat_type: &str, chat_id: i64) -> RoutePeer {
+    let kind = match chat_type {
+        "group" | "supergroup" => "group",
+        "channel" => "channel",
+        _ => "direct",
+    };
+    RoutePeer {
+        kind: kind.to_string(),
+        id: chat_id.to_string(),
+    }
+}
+
+/// A message pulled out of a Telegram update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelegramInbound {
+    pub chat_id: i64,
+    pub chat_type: String,
+    pub user_id: i64,
+    pub message_id: i64,
+    /// Forum topic, for messages posted in one.
+    pub thread_id: Option<i64>,
+    /// Message text, or the caption of a media message.
+    pub text: String,
+    pub media: Option<TelegramMedia>,
+}
+
+impl TelegramInbound {
+    pub fn from_update(update: &serde_json::Value) -> Option<Self> {
+        let msg = update.get("message")?;
+        let chat = msg.get("chat")?;
+        let is_topic = msg
+            .get("is_topic_message")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        Some(Self {
+            chat_id: chat.get("id")?.as_i64()?,
+            chat_type: chat
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or("private")
+                .to_string(),
+            user_id: msg
+                .get("from")
+                .and_then(|f| f.get("id"))
+                .and_then(|id| id.as_i64())
+                .unwrap_or(0),
+            message_id: msg.get("message_id").and_then(|v| v.as_i64()).unwrap_or(0),
+            thread_id: msg
+                .get("message_thread_id")
+                .and_then(|v| v.as_i64())
+                .filter(|_| is_topic),
+            text: msg
+                .get("text")
+                .or_else(|| msg.get("caption"))
+                .and_then(|t| t.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            media: TelegramMedia::from_message(msg),
+        })
+        .filter(|inbound| !inbound.text.trim().is_empty() || inbound.media.is_some())
+    }
+
+    /// Message to reply to: the inbound one outside private chats, so the
+    /// answer lands in the same thread or topic.
+    pub fn reply_to(&self) -> Option<i64> {
+        (self.chat_type != "private" && self.message_id != 0).then_some(self.message_id)
+    }
+}
+
 /// Simple long-polling loop for Telegram updates.
 /// This runs indefinitely until an error occurs or the process stops.
 pub async fn monitor(state: std::sync::Arc<crate::gateway::GatewayState>, token: String) {
     let client = crate::infra::retry_http::build_retrying_client();
 
+    let transcribers: Arc<HashMap<String, Box<dyn MediaUnderstandingProvider>>> =
+        Arc::new(crate::media_understanding::providers::build_provider_registry());
+
     let mut offset: Option<i64> = None;
     println!("[telegram] Starting monitor loop...");
 
@@ -34,78 +115,96 @@ pub async fn monitor(state: std::sync::Arc<crate::gateway::GatewayState>, token:
                             offset = Some(upd_id + 1);
                         }
 
-                        // Extract message text if present
-                        if let Some(msg) = update.get("message") {
-                            if let Some(text) = msg.get("text").and_then(|t| t.as_str()) {
-                                let chat_id = msg
-                                    .get("chat")
-                                    .and_then(|c| c.get("id"))
-                                    .and_then(|id| id.as_i64())
-                                    .unwrap_or(0);
-                                let user_id = msg
-                                    .get("from")
-                                    .and_then(|f| f.get("id"))
-                                    .and_then(|id| id.as_i64())
-                                    .unwrap_or(0);
-
-                                let normalized = normalize_inbound(text, chat_id, user_id);
-                                println!("Received telegram msg: {:?}", normalized);
-
-                                // Simple echo for testing (optional, maybe too dangerous for auto-loop)
-                                // let _ = telegram_client::send_message(&client, &token, &chat_id.to_string(), &format!("Echo: {}", text), None).await;
-
-                                // Dispatch to Agent
-                                let state_clone = state.clone();
-                                let token_clone = token.clone();
-                                let client_clone = client.clone();
-                                let text_owned = text.to_string();
-                                let chat_id_str = chat_id.to_string();
-
-                                tokio::spawn(async move {
-                                    println!("[telegram] Processing with agent: {}", text_owned);
-                                    let agent = match state_clone.agent.as_ref() {
-                                        Some(a) => a,
-                                        None => {
-                                            let _ = telegram_client::send_message(
-                                                &client_clone,
-                                                &token_clone,
-                                                &chat_id_str,
-                                                "Agent not available",
-                                                None,
-                                            )
-                                            .await;
-                                            return;
-                                        }
+                        let Some(inbound) = TelegramInbound::from_update(update) else {
+                            continue;
+                        };
+                        let normalized =
+                            normalize_inbound(&inbound.text, inbound.chat_id, inbound.user_id);
+                        println!("Received telegram msg: {:?}", normalized);
+
+                        // Dispatch to Agent
+                        let state_clone = state.clone();
+                        let token_clone = token.clone();
+                        let client_clone = client.clone();
+                        let transcribers = transcribers.clone();
+
+                        tokio::spawn(async move {
+                            let chat_id_str = inbound.chat_id.to_string();
+                            let thread_id = inbound.thread_id.map(|t| t.to_string());
+                            let reply_to = inbound.reply_to();
+                            let routed = match state_clone.agents.route(RouteQuery {
+                                channel: "telegram",
+                                peer: Some(route_peer(&inbound.chat_type, inbound.chat_id)),
+                                thread_id: thread_id.as_deref(),
+                                ..Default::default()
+                            }) {
+                                Some(routed) => routed,
+                                None => {
+                                    let _ = telegram_client::send_message(
+                                        &client_clone,
+                                        &token_clone,
+                                        &chat_id_str,
+                                        "Agent not available",
+                                        reply_to,
+                                    )
+                                    .await;
+                                    return;
+                                }
+                            };
+                            println!(
+                                "[telegram] Processing in {}: {}",
+                                routed.route.session_key, inbound.text
+                            );
+                            let entry = match &inbound.media {
+                                Some(media) => {
+                                    let ctx = MediaInputContext {
+                                        vision: routed.agent.supports_vision(),
+                                        transcribers: &transcribers,
+                                        media: crate::media_understanding::audio_preflight::MediaContext {
+                                            session_key: Some(routed.route.session_key.clone()),
+                                            channel: Some("telegram".to_string()),
+                                            chat_type: Some(inbound.chat_type.clone()),
+                                        },
                                     };
-                                    match agent.answer(&text_owned).await {
-                                        Ok(answer) => {
-                                            if let Err(e) = telegram_client::send_message(
-                                                &client_clone,
-                                                &token_clone,
-                                                &chat_id_str,
-                                                &answer,
-                                                None,
-                                            )
-                                            .await
-                                            {
-                                                eprintln!("[telegram] Failed to send reply: {}", e);
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!("[telegram] Agent error: {}", e);
-                                            let _ = telegram_client::send_message(
-                                                &client_clone,
-                                                &token_clone,
-                                                &chat_id_str,
-                                                &format!("unavailable: {}", e),
-                                                None,
-                                            )
-                                            .await;
-                                        }
+                                    let content = telegram_media::inbound_content(
+                                        &client_clone,
+                                        &token_clone,
+                                        media,
+                                        &inbound.text,
+                                        &ctx,
+                                    )
+                                    .await;
+                                    TranscriptEntry::from_message(&ChatMessage::User { content })
+                                }
+                                None => TranscriptEntry::user(&inbound.text),
+                            };
+                            match state_clone.answer_entry_in_session(&routed, entry).await {
+                                Ok(answer) => {
+                                    if let Err(e) = telegram_client::send_message(
+                                        &client_clone,
+                                        &token_clone,
+                                        &chat_id_str,
+                                        &answer,
+                                        reply_to,
+                                    )
+                                    .await
+                                    {
+                                        eprintln!("[telegram] Failed to send reply: {}", e);
                                     }
-                                });
+                                }
+                                Err(e) => {
+                                    eprintln!("[telegram] Agent error: {}", e);
+                                    let _ = telegram_client::send_message(
+                                        &client_clone,
+                                        &token_clone,
+                                        &chat_id_str,
+                                        &format!("unavailable: {}", e),
+                                        reply_to,
+                                    )
+                                    .await;
+                                }
                             }
-                        }
+                        });
                     }
                 }
             }
@@ -125,4 +224,51 @@ mod tests {
     fn test_format_outbound() {
         assert_eq!(format_outbound("hi"), "[telegram] hi".to_string());
     }
+
+    #[test]
+    fn test_route_peer_kinds() {
+        assert_eq!(route_peer("private", 5).kind, "direct");
+        assert_eq!(route_peer("supergroup", -100).kind, "group");
+        assert_eq!(route_peer("supergroup", -100).id, "-100");
+    }
+
+    #[test]
+    fn test_inbound_from_topic_update() {
+        let update = serde_json::json!({
+            "update_id": 1,
+            "message": {
+                "message_id": 55,
+                "message_thread_id": 12,
+                "is_topic_message": true,
+                "from": {"id": 7},
+                "chat": {"id": -100, "type": "supergroup"},
+                "text": "hello"
+            }
+        });
+        let inbound = TelegramInbound::from_update(&update).unwrap();
+        assert_eq!(inbound.thread_id, Some(12));
+        assert_eq!(inbound.reply_to(), Some(55));
+
+        let private = serde_json::json!({
+            "message": {
+                "message_id": 3,
+                "from": {"id": 7},
+                "chat": {"id": 7, "type": "private"},
+                "text": "hi"
+            }
+        });
+        let inbound = TelegramInbound::from_update(&private).unwrap();
+        assert_eq!(inbound.thread_id, None);
+        assert_eq!(inbound.reply_to(), None);
+        assert!(TelegramInbound::from_update(&serde_json::json!({"edited_message": {}})).is_none());
+
+        let photo: serde_json::Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/telegram/photo_update.json"
+        ))
+        .unwrap();
+        let inbound = TelegramInbound::from_update(&photo).unwrap();
+        assert_eq!(inbound.text, "what does this chart say?");
+        assert_eq!(inbound.thread_id, Some(7));
+        assert!(inbound.media.is_some());
+    }
 }
@@ -55,6 +55,44 @@ pub async fn get_updates(
     Ok(v)
 }
 
+/// Async `getFile` shim for Telegram Bot API.
+pub async fn get_file(client: &Client, token: &str, file_id: &str) -> Result<serde_json::Value> {
+    let url = format!("https://api.telegram.org/bot{}/getFile", token);
+    let resp = client
+        .post(&url)
+        .json(&json!({ "file_id": file_id }))
+        .send()
+        .await?;
+    let v: serde_json::Value = resp.json().await?;
+    Ok(v)
+}
+
+/// `file_path` from a `getFile` response, or the API's error description.
+pub fn file_path_from_response(response: &serde_json::Value) -> Result<String> {
+    if let Some(path) = response
+        .get("result")
+        .and_then(|r| r.get("file_path"))
+        .and_then(|p| p.as_str())
+    {
+        return Ok(path.to_string());
+    }
+    let description = response
+        .get("description")
+        .and_then(|d| d.as_str())
+        .unwrap_or("no file_path in getFile response");
+    anyhow::bail!("telegram getFile failed: {}", description)
+}
+
+/// Download a file by the `file_path` returned from `getFile`.
+pub async fn download_file(client: &Client, token: &str, file_path: &str) -> Result<Vec<u8>> {
+    let url = format!("https://api.telegram.org/file/bot{}/{}", token, file_path);
+    let resp = client.get(&url).send().await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("telegram file download failed: HTTP {}", resp.status());
+    }
+    Ok(resp.bytes().await?.to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,4 +111,17 @@ mod tests {
         let p = build_telegram_http_payload("-12345", "reply", Some(42));
         assert_eq!(p["reply_to_message_id"], 42);
     }
+
+    #[test]
+    fn get_file_response_yields_path_or_error() {
+        let ok: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/telegram/get_file.json"))
+                .unwrap();
+        assert_eq!(file_path_from_response(&ok).unwrap(), "voice/file_12.oga");
+
+        let err =
+            json!({"ok": false, "error_code": 400, "description": "Bad Request: file is too big"});
+        let msg = file_path_from_response(&err).unwrap_err().to_string();
+        assert!(msg.contains("file is too big"));
+    }
 }
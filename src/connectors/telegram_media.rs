@@ -0,0 +1,397 @@
+//! telegram_media — photos, voice notes, audio and documents as agent input.
+//!
+//! Media on an inbound message is fetched with `getFile`, saved to the media
+//! store and turned into user content: voice notes and audio are
+//! transcribed, photos become image parts when the agent's model has vision,
+//! and PDFs and text files are extracted. Anything else is mentioned by name
+//! so the agent knows it arrived.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use reqwest_middleware::ClientWithMiddleware as Client;
+use serde_json::Value;
+
+use crate::agents::chat::{ContentPart, ImageUrl, UserContent};
+use crate::connectors::telegram_client;
+use crate::media::input_files::{
+    extract_file_content_from_source, resolve_input_file_limits, InputFileSource,
+};
+use crate::media_understanding::audio_preflight::{transcribe_first_audio, MediaContext};
+use crate::media_understanding::resolve::{MediaUnderstandingScope, ScopePolicy};
+use crate::media_understanding::{
+    MediaAttachment, MediaAttachmentCache, MediaUnderstandingConfig, MediaUnderstandingProvider,
+};
+
+/// Largest file the Bot API lets bots download.
+pub const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelegramMediaKind {
+    Photo,
+    Voice,
+    Audio,
+    Document,
+}
+
+impl TelegramMediaKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Photo => "photo",
+            Self::Voice => "voice note",
+            Self::Audio => "audio",
+            Self::Document => "document",
+        }
+    }
+}
+
+/// A file attached to a Telegram message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelegramMedia {
+    pub kind: TelegramMediaKind,
+    pub file_id: String,
+    pub mime_type: Option<String>,
+    pub file_name: Option<String>,
+    pub file_size: Option<u64>,
+}
+
+impl TelegramMedia {
+    /// The media on a message, if any. Photos come in several sizes; the
+    /// largest is taken.
+    pub fn from_message(msg: &Value) -> Option<Self> {
+        let (kind, file) = if let Some(sizes) = msg.get("photo").and_then(|p| p.as_array()) {
+            (TelegramMediaKind::Photo, sizes.last()?)
+        } else if let Some(voice) = msg.get("voice") {
+            (TelegramMediaKind::Voice, voice)
+        } else if let Some(audio) = msg.get("audio") {
+            (TelegramMediaKind::Audio, audio)
+        } else if let Some(document) = msg.get("document") {
+            (TelegramMediaKind::Document, document)
+        } else {
+            return None;
+        };
+        let text = |key: &str| file.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Some(Self {
+            kind,
+            file_id: text("file_id")?,
+            mime_type: text("mime_type"),
+            file_name: text("file_name"),
+            file_size: file.get("file_size").and_then(|v| v.as_u64()),
+        })
+    }
+
+    fn display_name(&self) -> String {
+        self.file_name
+            .clone()
+            .unwrap_or_else(|| self.kind.label().to_string())
+    }
+}
+
+/// What the agent can take in and who transcribes audio.
+pub struct MediaInputContext<'a> {
+    /// The agent's model accepts images.
+    pub vision: bool,
+    pub transcribers: &'a HashMap<String, Box<dyn MediaUnderstandingProvider>>,
+    pub media: MediaContext,
+}
+
+/// Download a message's media through `getFile`.
+pub async fn fetch_media(client: &Client, token: &str, media: &TelegramMedia) -> Result<Vec<u8>> {
+    if media.file_size.unwrap_or(0) > MAX_DOWNLOAD_BYTES {
+        bail!(
+            "{} is larger than the {}MB bots may download",
+            media.display_name(),
+            MAX_DOWNLOAD_BYTES / (1024 * 1024)
+        );
+    }
+    let response = telegram_client::get_file(client, token, &media.file_id).await?;
+    let file_path = telegram_client::file_path_from_response(&response)?;
+    telegram_client::download_file(client, token, &file_path).await
+}
+
+/// User content for a message carrying `media`; `caption` is the message's
+/// own text. Download failures are reported to the agent instead of
+/// dropping the message.
+pub async fn inbound_content(
+    client: &Client,
+    token: &str,
+    media: &TelegramMedia,
+    caption: &str,
+    ctx: &MediaInputContext<'_>,
+) -> UserContent {
+    match fetch_media(client, token, media).await {
+        Ok(bytes) => media_to_content(media, &bytes, caption, ctx).await,
+        Err(e) => {
+            tracing::warn!("[telegram] media download failed: {}", e);
+            let note = format!("[{} could not be downloaded: {}]", media.kind.label(), e);
+            UserContent::Text(join_text(caption, &[note]))
+        }
+    }
+}
+
+/// Turn downloaded media into user content.
+pub async fn media_to_content(
+    media: &TelegramMedia,
+    bytes: &[u8],
+    caption: &str,
+    ctx: &MediaInputContext<'_>,
+) -> UserContent {
+    let saved = match crate::media::store::save_media_buffer(
+        bytes,
+        media.mime_type.as_deref(),
+        Some("telegram"),
+        Some(MAX_DOWNLOAD_BYTES as usize),
+        media.file_name.as_deref(),
+    )
+    .await
+    {
+        Ok(saved) => saved,
+        Err(e) => {
+            let note = format!("[{} could not be saved: {}]", media.kind.label(), e);
+            return UserContent::Text(join_text(caption, &[note]));
+        }
+    };
+    // Telegram's declared type wins over sniffing, which cannot tell text
+    // formats apart.
+    let mime = media
+        .mime_type
+        .clone()
+        .or(saved.content_type)
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let name = media.display_name();
+
+    let mut images = Vec::new();
+    let note = match media.kind {
+        TelegramMediaKind::Voice | TelegramMediaKind::Audio => {
+            match transcribe(&saved.path.to_string_lossy(), &mime, ctx).await {
+                Some(transcript) => format!("[{} transcript]\n{}", media.kind.label(), transcript),
+                None => format!(
+                    "[{} received; no transcription available]",
+                    media.kind.label()
+                ),
+            }
+        }
+        _ if mime.starts_with("image/") => {
+            if ctx.vision {
+                images.push(image_part(&mime, bytes));
+                format!("[{} attached]", media.kind.label())
+            } else {
+                format!(
+                    "[{} received: {}; this model cannot view images]",
+                    media.kind.label(),
+                    name
+                )
+            }
+        }
+        _ => match extract_text(&name, &mime, bytes).await {
+            Ok(text) => format!("[file: {}]\n{}", name, text),
+            Err(e) => format!("[file received: {} ({}); {}]", name, mime, e),
+        },
+    };
+
+    let text = join_text(caption, &[note]);
+    if images.is_empty() {
+        return UserContent::Text(text);
+    }
+    let mut parts = vec![ContentPart::Text { text }];
+    parts.extend(images);
+    UserContent::Parts(parts)
+}
+
+async fn transcribe(path: &str, mime: &str, ctx: &MediaInputContext<'_>) -> Option<String> {
+    let attachments = vec![MediaAttachment {
+        index: 0,
+        path: Some(path.to_string()),
+        mime: Some(mime.to_string()),
+        ..Default::default()
+    }];
+    let cache = MediaAttachmentCache::new(attachments.clone());
+    // The request already passed the channel's policies; only the
+    // transcriber's own limits apply here.
+    let cfg = MediaUnderstandingConfig {
+        enabled: Some(true),
+        scope: Some(MediaUnderstandingScope {
+            default_policy: ScopePolicy::Allow,
+            rules: Vec::new(),
+        }),
+        ..Default::default()
+    };
+    match transcribe_first_audio(&ctx.media, &attachments, &cache, &cfg, ctx.transcribers).await {
+        Ok(transcript) => transcript.filter(|t| !t.trim().is_empty()),
+        Err(e) => {
+            tracing::warn!("[telegram] transcription failed: {:?}", e);
+            None
+        }
+    }
+}
+
+async fn extract_text(name: &str, mime: &str, bytes: &[u8]) -> Result<String> {
+    use base64::{engine::general_purpose, Engine as _};
+    let limits = resolve_input_file_limits(
+        Some(false),
+        None,
+        None,
+        Some(MAX_DOWNLOAD_BYTES as usize),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+    let source = InputFileSource {
+        source_type: "base64".to_string(),
+        data: Some(general_purpose::STANDARD.encode(bytes)),
+        url: None,
+        media_type: Some(mime.to_string()),
+        filename: Some(name.to_string()),
+    };
+    let extracted = extract_file_content_from_source(&source, &limits).await?;
+    Ok(extracted.text.unwrap_or_default())
+}
+
+fn image_part(mime: &str, bytes: &[u8]) -> ContentPart {
+    use base64::{engine::general_purpose, Engine as _};
+    ContentPart::ImageUrl {
+        image_url: ImageUrl {
+            url: format!(
+                "data:{};base64,{}",
+                mime,
+                general_purpose::STANDARD.encode(bytes)
+            ),
+        },
+    }
+}
+
+fn join_text(caption: &str, notes: &[String]) -> String {
+    let caption = caption.trim();
+    let mut blocks: Vec<&str> = Vec::new();
+    if !caption.is_empty() {
+        blocks.push(caption);
+    }
+    blocks.extend(notes.iter().map(String::as_str));
+    blocks.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_understanding::MockMediaProvider;
+
+    fn fixture(json: &str) -> Value {
+        let update: Value = serde_json::from_str(json).unwrap();
+        update["message"].clone()
+    }
+
+    fn context(
+        vision: bool,
+        transcribers: &HashMap<String, Box<dyn MediaUnderstandingProvider>>,
+    ) -> MediaInputContext<'_> {
+        MediaInputContext {
+            vision,
+            transcribers,
+            media: MediaContext::default(),
+        }
+    }
+
+    #[test]
+    fn media_is_read_from_recorded_updates() {
+        let photo = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/photo_update.json"
+        )))
+        .unwrap();
+        assert_eq!(photo.kind, TelegramMediaKind::Photo);
+        assert_eq!(photo.file_id, "AgACAgUAAxkBAAIBnGcM-large");
+
+        let voice = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/voice_update.json"
+        )))
+        .unwrap();
+        assert_eq!(voice.kind, TelegramMediaKind::Voice);
+        assert_eq!(voice.mime_type.as_deref(), Some("audio/ogg"));
+
+        let document = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/document_update.json"
+        )))
+        .unwrap();
+        assert_eq!(document.kind, TelegramMediaKind::Document);
+        assert_eq!(document.file_name.as_deref(), Some("notes.txt"));
+
+        assert!(TelegramMedia::from_message(&serde_json::json!({"text": "hi"})).is_none());
+    }
+
+    #[tokio::test]
+    async fn voice_notes_are_transcribed() {
+        let voice = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/voice_update.json"
+        )))
+        .unwrap();
+        let mut transcribers: HashMap<String, Box<dyn MediaUnderstandingProvider>> = HashMap::new();
+        transcribers.insert("mock".to_string(), Box::new(MockMediaProvider));
+        let content = media_to_content(&voice, b"OggS", "", &context(false, &transcribers)).await;
+        let UserContent::Text(text) = content else {
+            panic!("expected text content");
+        };
+        assert!(text.starts_with("[voice note transcript]"));
+        assert!(text.contains("Hello, this is a test."));
+
+        let none = HashMap::new();
+        let content = media_to_content(&voice, b"OggS", "", &context(false, &none)).await;
+        assert!(matches!(content, UserContent::Text(t) if t.contains("no transcription")));
+    }
+
+    #[tokio::test]
+    async fn photos_become_image_parts_only_with_vision() {
+        let photo = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/photo_update.json"
+        )))
+        .unwrap();
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let none = HashMap::new();
+
+        let content = media_to_content(&photo, png, "what is this?", &context(true, &none)).await;
+        let UserContent::Parts(parts) = content else {
+            panic!("expected image parts");
+        };
+        assert!(
+            matches!(&parts[0], ContentPart::Text { text } if text.starts_with("what is this?"))
+        );
+        assert!(
+            matches!(&parts[1], ContentPart::ImageUrl { image_url } if image_url.url.starts_with("data:image/png;base64,"))
+        );
+
+        let content = media_to_content(&photo, png, "what is this?", &context(false, &none)).await;
+        assert!(matches!(content, UserContent::Text(t) if t.contains("cannot view images")));
+    }
+
+    #[tokio::test]
+    async fn text_documents_are_extracted() {
+        let document = TelegramMedia::from_message(&fixture(include_str!(
+            "../../tests/fixtures/telegram/document_update.json"
+        )))
+        .unwrap();
+        let none = HashMap::new();
+        let content = media_to_content(
+            &document,
+            b"deploy on friday\nrollback plan ready",
+            "summarize please",
+            &context(false, &none),
+        )
+        .await;
+        let UserContent::Text(text) = content else {
+            panic!("expected text content");
+        };
+        assert!(text.starts_with("summarize please\n\n[file: notes.txt]"));
+        assert!(text.contains("rollback plan ready"));
+
+        let zip = TelegramMedia {
+            mime_type: Some("application/zip".to_string()),
+            file_name: Some("logs.zip".to_string()),
+            ..document
+        };
+        let content = media_to_content(&zip, b"PK\x03\x04", "", &context(false, &none)).await;
+        assert!(
+            matches!(content, UserContent::Text(t) if t.starts_with("[file received: logs.zip"))
+        );
+    }
+}
@@ -147,8 +147,15 @@ pub async fn handle_web_bridge_event(
         }
     };
 
-    let agent = match state.agent.as_ref() {
-        Some(a) => a,
+    let routed = match state.agents.route(crate::agents::RouteQuery {
+        channel: "whatsapp",
+        peer: Some(crate::routing::resolve_route::RoutePeer {
+            kind: "direct".to_string(),
+            id: inbound.from.clone(),
+        }),
+        ..Default::default()
+    }) {
+        Some(routed) => routed,
         None => {
             return json!({
                 "type": "error",
@@ -156,7 +163,7 @@ pub async fn handle_web_bridge_event(
             })
         }
     };
-    match agent.answer(&inbound.text).await {
+    match state.answer_in_session(&routed, &inbound.text).await {
         Ok(answer) => {
             let outbound = WhatsAppWebOutbound {
                 event_type: "send".to_string(),
@@ -466,14 +473,21 @@ pub async fn handle_events(state: Arc<GatewayState>, payload: serde_json::Value)
                 .await;
             }
 
-            let agent = match state_clone.agent.as_ref() {
-                Some(a) => a,
+            let routed = match state_clone.agents.route(crate::agents::RouteQuery {
+                channel: "whatsapp",
+                peer: Some(crate::routing::resolve_route::RoutePeer {
+                    kind: "direct".to_string(),
+                    id: from.clone(),
+                }),
+                ..Default::default()
+            }) {
+                Some(routed) => routed,
                 None => {
                     tracing::error!("[whatsapp] Agent not available");
                     return;
                 }
             };
-            match agent.answer(&text).await {
+            match state_clone.answer_in_session(&routed, &text).await {
                 Ok(answer) => {
                     for chunk in split_outbound_chunks(&answer, WHATSAPP_MAX_TEXT_CHARS) {
                         if let Err(e) = crate::connectors::whatsapp_client::send_message(
@@ -0,0 +1,257 @@
+//! expr — cron expressions.
+//!
+//! Standard 5-field (`min hour dom month dow`) and 6-field (leading
+//! seconds) expressions with lists, ranges, steps, month/weekday names and
+//! the `@hourly`/`@daily`/`@weekly`/`@monthly`/`@yearly` shorthands. When
+//! both day-of-month and day-of-week are restricted a day matching either
+//! fires, as in Vixie cron.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+
+use super::tz::CronTz;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A parsed cron expression. Each field is a bit set of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let expr = expr.trim();
+        let expanded = match expr.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => bail!("unknown cron shorthand '{}'", expr),
+            _ => expr,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let (seconds, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => bail!("cron expression needs 5 or 6 fields, got {}", n),
+        };
+        let weekdays = parse_field(rest[4], 0, 7, &WEEKDAYS)?;
+        // 7 is Sunday too.
+        let weekdays = (weekdays | (weekdays >> 7)) & 0x7f;
+        let days = parse_field(rest[2], 1, 31, &[])?;
+        let months = parse_field(rest[3], 1, 12, &MONTHS)?;
+        let restricted = |field: &str| !(field.starts_with('*') || field.starts_with('?'));
+        Ok(Self {
+            seconds: parse_field(seconds, 0, 59, &[])?,
+            minutes: parse_field(rest[0], 0, 59, &[])?,
+            hours: parse_field(rest[1], 0, 23, &[])?,
+            days,
+            months,
+            weekdays,
+            days_restricted: restricted(rest[2]),
+            weekdays_restricted: restricted(rest[4]),
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = bit(self.days, date.day());
+        let dow = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// The first wall-clock time strictly after `after` that matches.
+    pub fn next_after_naive(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = after.with_nanosecond(0)? + Duration::seconds(1);
+        // Feb 29 on a given weekday can be up to 28 years out.
+        let limit = after.year() + 28;
+        while t.year() <= limit {
+            let date = t.date();
+            if !bit(self.months, t.month()) {
+                let (y, m) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                t = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, t.hour()) {
+                t = date.and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t = date.and_hms_opt(t.hour(), t.minute(), 0)? + Duration::minutes(1);
+            } else if !bit(self.seconds, t.second()) {
+                t += Duration::seconds(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    /// The first instant strictly after `after` whose wall-clock time in
+    /// `tz` matches.
+    pub fn next_after(&self, after: DateTime<Utc>, tz: &CronTz) -> Option<DateTime<Utc>> {
+        let mut local = tz.to_local(after);
+        // Wall times repeated when clocks go back map to instants at or
+        // before `after`; step past them.
+        for _ in 0..10_000 {
+            let next = self.next_after_naive(local)?;
+            let utc = tz.from_local(next);
+            if utc > after {
+                return Some(utc);
+            }
+            local = next;
+        }
+        None
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_value(s: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+    let upper = s.to_ascii_uppercase();
+    let value = match names.iter().position(|n| *n == upper) {
+        Some(i) => i as u32 + min,
+        None => s
+            .parse::<u32>()
+            .map_err(|_| anyhow!("invalid cron value '{}'", s))?,
+    };
+    if value < min || value > max {
+        bail!("cron value {} out of range {}-{}", value, min, max);
+    }
+    Ok(value)
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| anyhow!("invalid cron step '{}'", step))?;
+                if step == 0 {
+                    bail!("cron step must be positive");
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (lo, hi) = if range == "*" || range == "?" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (
+                parse_value(lo, min, max, names)?,
+                parse_value(hi, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/15` means 5, 20, 35, 50.
+            (value, if step.is_some() { max } else { value })
+        };
+        if lo > hi {
+            bail!("cron range {}-{} is reversed", lo, hi);
+        }
+        for value in (lo..=hi).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, s)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_fields_names_and_shorthands() {
+        assert!(CronExpr::parse("*/15 9-17 * * MON-FRI").is_ok());
+        assert!(CronExpr::parse("30 0 9 1,15 JAN,jul ?").is_ok());
+        assert_eq!(
+            CronExpr::parse("@daily").unwrap(),
+            CronExpr::parse("0 0 * * *").unwrap()
+        );
+        assert_eq!(
+            CronExpr::parse("0 0 * * 7").unwrap(),
+            CronExpr::parse("0 0 * * SUN").unwrap()
+        );
+        assert!(CronExpr::parse("61 * * * *").is_err());
+        assert!(CronExpr::parse("* * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn next_fire_times() {
+        let weekdays = CronExpr::parse("0 9 * * 1-5").unwrap();
+        // 2026-10-16 is a Friday.
+        let fri = local(2026, 10, 16, 9, 0, 0);
+        assert_eq!(
+            weekdays.next_after_naive(fri),
+            Some(local(2026, 10, 19, 9, 0, 0))
+        );
+
+        let secs = CronExpr::parse("*/20 * * * * *").unwrap();
+        assert_eq!(
+            secs.next_after_naive(local(2026, 1, 1, 0, 0, 45)),
+            Some(local(2026, 1, 1, 0, 1, 0))
+        );
+
+        // Either the 13th or a Friday.
+        let either = CronExpr::parse("0 0 13 * FRI").unwrap();
+        assert_eq!(
+            either.next_after_naive(local(2026, 10, 14, 0, 0, 0)),
+            Some(local(2026, 10, 16, 0, 0, 0))
+        );
+
+        let leap = CronExpr::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap.next_after_naive(local(2026, 3, 1, 0, 0, 0)),
+            Some(local(2028, 2, 29, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn next_fire_in_a_time_zone() {
+        let tz = CronTz::Fixed(7 * 3600);
+        let expr = CronExpr::parse("0 9 * * *").unwrap();
+        let after = Utc.with_ymd_and_hms(2026, 10, 18, 3, 0, 0).unwrap();
+        assert_eq!(
+            expr.next_after(after, &tz),
+            Some(Utc.with_ymd_and_hms(2026, 10, 19, 2, 0, 0).unwrap())
+        );
+
+        // Clocks go back at 02:00 EDT on 2031-11-02; 01:30 only fires once.
+        let tz = CronTz::parse("America/New_York").unwrap();
+        let expr = CronExpr::parse("30 1 * * *").unwrap();
+        let first = Utc.with_ymd_and_hms(2031, 11, 2, 5, 30, 0).unwrap();
+        let next = expr.next_after(first, &tz).unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2031, 11, 3, 6, 30, 0).unwrap());
+    }
+}
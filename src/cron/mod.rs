@@ -2,13 +2,33 @@
 //! Ported from `openkrab/src/cron/` (Phase 6).
 //!
 //! Provides a persistent cron-like scheduler for recurring agent tasks.
-//! Jobs are stored in `memory/schedule.json` and evaluated against the
-//! system clock on each tick.
+//! Jobs live in one shared store (`<state dir>/cron/jobs.json`) written by
+//! the `manage_schedule` tool and the `openkrab cron` CLI. The gateway
+//! scheduler (`gateway::scheduler`) takes due jobs from it on each tick and
+//! records every run back into the job's history.
 
-use anyhow::Result;
-use chrono::{DateTime, Timelike, Utc};
+pub mod expr;
+pub mod tz;
+
+pub use expr::CronExpr;
+pub use tz::CronTz;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::routing::DeliveryTarget;
+
+/// Runs kept in each job's history.
+pub const MAX_HISTORY: usize = 20;
+/// Most missed runs `CatchUp::All` replays.
+pub const MAX_CATCH_UP: usize = 10;
+/// A run starting later than this after its slot counts as a catch-up.
+pub const MISSED_GRACE_SECS: i64 = 60;
+/// Most slots examined per job and tick.
+const MAX_SCAN: usize = 10_000;
 
 // ─── Job definition ───────────────────────────────────────────────────────────
 
@@ -24,6 +44,181 @@ pub enum JobFrequency {
     Daily { hour: u8, minute: u8 },
     /// Hourly at a specific minute.
     Hourly { minute: u8 },
+    /// 5- or 6-field cron expression in an IANA time zone (UTC if unset).
+    Cron {
+        expr: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tz: Option<String>,
+    },
+}
+
+impl JobFrequency {
+    /// A validated cron frequency.
+    pub fn cron(expr: &str, tz: Option<&str>) -> Result<Self> {
+        CronExpr::parse(expr)?;
+        let tz = tz.map(str::trim).filter(|tz| !tz.is_empty());
+        if let Some(tz) = tz {
+            CronTz::parse(tz)?;
+        }
+        Ok(Self::Cron {
+            expr: expr.trim().to_string(),
+            tz: tz.map(str::to_string),
+        })
+    }
+
+    /// The first fire time strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let cron = |expr: String, tz: &CronTz| CronExpr::parse(&expr).ok()?.next_after(after, tz);
+        match self {
+            Self::Interval { secs } => {
+                let secs = i64::try_from((*secs).max(1)).ok()?;
+                after.checked_add_signed(chrono::Duration::try_seconds(secs)?)
+            }
+            Self::Once { at } => (*at > after).then_some(*at),
+            Self::Daily { hour, minute } => {
+                cron(format!("{} {} * * *", minute, hour), &CronTz::Utc)
+            }
+            Self::Hourly { minute } => cron(format!("{} * * * *", minute), &CronTz::Utc),
+            Self::Cron { expr, tz } => {
+                let tz = match tz {
+                    Some(tz) => CronTz::parse(tz).ok()?,
+                    None => CronTz::Utc,
+                };
+                cron(expr.clone(), &tz)
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Interval { secs } => format!("every {}s", secs),
+            Self::Once { at } => format!("once at {}", at.format("%Y-%m-%d %H:%M:%S UTC")),
+            Self::Daily { hour, minute } => format!("daily at {:02}:{:02} UTC", hour, minute),
+            Self::Hourly { minute } => format!("hourly at :{:02}", minute),
+            Self::Cron { expr, tz } => format!("{} ({})", expr, tz.as_deref().unwrap_or("UTC")),
+        }
+    }
+}
+
+/// What to do with runs that came due while no scheduler was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUp {
+    /// Drop missed runs; only on-time runs fire.
+    Skip,
+    /// Run once, however many runs were missed.
+    #[default]
+    Latest,
+    /// Replay every missed run, up to `MAX_CATCH_UP`.
+    All,
+}
+
+impl CatchUp {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" | "none" => Some(Self::Skip),
+            "latest" | "once" | "last" => Some(Self::Latest),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Latest => "latest",
+            Self::All => "all",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Ok,
+    Error,
+    /// Missed runs dropped by the catch-up policy.
+    Skipped,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// One run of a job, or a batch of skipped runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: String,
+    /// The slot this run was scheduled for.
+    pub scheduled_for: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: RunStatus,
+    /// Whether this run made up for a missed slot.
+    #[serde(default)]
+    pub catch_up: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+    /// The agent's reply, truncated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Failure or skip reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JobRun {
+    /// A run starting now for `slot`.
+    pub fn start(slot: DateTime<Utc>, catch_up: bool) -> Self {
+        Self {
+            id: new_job_id(),
+            scheduled_for: slot,
+            started_at: Utc::now(),
+            finished_at: None,
+            status: RunStatus::Ok,
+            catch_up,
+            session_key: None,
+            output: None,
+            error: None,
+        }
+    }
+
+    fn skipped(last_slot: DateTime<Utc>, now: DateTime<Utc>, count: usize) -> Self {
+        Self {
+            status: RunStatus::Skipped,
+            started_at: now,
+            finished_at: Some(now),
+            error: Some(format!("{} missed run(s) skipped", count)),
+            ..Self::start(last_slot, true)
+        }
+    }
+
+    /// Mark finished with the agent's reply or error.
+    pub fn finish(mut self, result: std::result::Result<String, String>) -> Self {
+        self.finished_at = Some(Utc::now());
+        match result {
+            Ok(output) => self.output = Some(truncate(&output, 500)),
+            Err(error) => {
+                self.status = RunStatus::Error;
+                self.error = Some(truncate(&error, 500));
+            }
+        }
+        self
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((at, _)) => format!("{}…", &s[..at]),
+        None => s.to_string(),
+    }
 }
 
 /// A single scheduled job.
@@ -43,6 +238,28 @@ pub struct CronJob {
     pub last_run: Option<DateTime<Utc>>,
     /// Created at.
     pub created_at: DateTime<Utc>,
+    /// Agent that runs the task (the default agent when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Where the agent's reply is sent (`cron.delivery` when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<DeliveryTarget>,
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    /// Latest slot handled, whether run or skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_slot: Option<DateTime<Utc>>,
+    /// Most recent runs, oldest first.
+    #[serde(default)]
+    pub history: Vec<JobRun>,
+}
+
+/// Slots due at some instant, after the catch-up policy is applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunPlan {
+    pub runs: Vec<DateTime<Utc>>,
+    pub skipped: usize,
+    pub last_slot: DateTime<Utc>,
 }
 
 impl CronJob {
@@ -60,41 +277,110 @@ impl CronJob {
             enabled: true,
             last_run: None,
             created_at: Utc::now(),
+            agent_id: None,
+            delivery: None,
+            catch_up: CatchUp::default(),
+            last_slot: None,
+            history: Vec::new(),
         }
     }
 
-    /// Check if this job should fire now, given the current time.
-    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    pub fn with_delivery(mut self, target: DeliveryTarget) -> Self {
+        self.delivery = Some(target);
+        self
+    }
+
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Slots that came due since the last one handled, filtered by the
+    /// catch-up policy. `None` when nothing came due.
+    pub fn plan(&self, now: DateTime<Utc>) -> Option<RunPlan> {
         if !self.enabled {
-            return false;
+            return None;
         }
-        match &self.frequency {
-            JobFrequency::Interval { secs } => match self.last_run {
-                None => true,
-                Some(last) => (now - last).num_seconds() >= *secs as i64,
-            },
-            JobFrequency::Once { at } => self.last_run.is_none() && now >= *at,
-            JobFrequency::Daily { hour, minute } => {
-                let same_slot = now.hour() == *hour as u32 && now.minute() == *minute as u32;
-                let not_run_today = match self.last_run {
-                    None => true,
-                    Some(last) => now.date_naive() > last.date_naive(),
-                };
-                same_slot && not_run_today
-            }
-            JobFrequency::Hourly { minute } => {
-                let same_slot = now.minute() == *minute as u32;
-                let not_run_this_hour = match self.last_run {
-                    None => true,
-                    Some(last) => {
-                        let hour_secs = 3600i64;
-                        (now - last).num_seconds() >= hour_secs
+        let slots = match (&self.frequency, self.last_slot.or(self.last_run)) {
+            // A new interval job runs right away.
+            (JobFrequency::Interval { .. }, None) => vec![now],
+            // A one-shot fires even if `at` had passed when it was created.
+            (JobFrequency::Once { at }, None) if *at <= now => vec![*at],
+            (JobFrequency::Once { .. }, _) => Vec::new(),
+            (frequency, anchor) => {
+                let mut slots = Vec::new();
+                let mut cursor = anchor.unwrap_or(self.created_at);
+                while slots.len() < MAX_SCAN {
+                    match frequency.next_after(cursor) {
+                        Some(next) if next <= now => {
+                            slots.push(next);
+                            cursor = next;
+                        }
+                        _ => break,
                     }
-                };
-                same_slot && not_run_this_hour
+                }
+                slots
             }
+        };
+        // Past the scan limit, the rest of the backlog is dropped.
+        let last_slot = if slots.len() == MAX_SCAN {
+            now
+        } else {
+            *slots.last()?
+        };
+        let runs = match self.catch_up {
+            CatchUp::Skip => slots
+                .last()
+                .filter(|slot| (now - **slot).num_seconds() <= MISSED_GRACE_SECS)
+                .into_iter()
+                .copied()
+                .collect(),
+            CatchUp::Latest => slots.last().into_iter().copied().collect(),
+            CatchUp::All => slots[slots.len().saturating_sub(MAX_CATCH_UP)..].to_vec(),
+        };
+        Some(RunPlan {
+            skipped: slots.len() - runs.len(),
+            runs,
+            last_slot,
+        })
+    }
+
+    /// Check if this job should fire now, given the current time.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.plan(now).is_some_and(|plan| !plan.runs.is_empty())
+    }
+
+    /// The next slot after the last one handled.
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        if !self.enabled {
+            return None;
+        }
+        match (&self.frequency, self.last_slot.or(self.last_run)) {
+            (JobFrequency::Once { at }, None) => Some(*at),
+            (JobFrequency::Interval { .. }, None) => Some(self.created_at),
+            (frequency, anchor) => frequency.next_after(anchor.unwrap_or(self.created_at)),
         }
     }
+
+    pub fn push_run(&mut self, run: JobRun) {
+        self.history.push(run);
+        let excess = self.history.len().saturating_sub(MAX_HISTORY);
+        self.history.drain(..excess);
+    }
+}
+
+/// A job run handed to the scheduler.
+#[derive(Debug, Clone)]
+pub struct DueRun {
+    /// The job as it was when the run was taken.
+    pub job: CronJob,
+    pub scheduled_for: DateTime<Utc>,
+    pub catch_up: bool,
 }
 
 // ─── Cron store ───────────────────────────────────────────────────────────────
@@ -122,13 +408,20 @@ impl CronStore {
         Ok(store)
     }
 
-    /// Persist to a JSON file.
+    /// Persist to a JSON file. The gateway and the CLI share the file, so
+    /// it is replaced by a rename and a concurrent `load` never sees it
+    /// half-written.
     pub fn save(&self, path: &std::path::Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
+        let dir = path.parent().unwrap_or(Path::new(""));
+        std::fs::create_dir_all(dir)?;
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, content)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = dir.join(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+        std::fs::write(&tmp, content)?;
+        if let Err(e) = std::fs::rename(&tmp, path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
         Ok(())
     }
 
@@ -144,6 +437,21 @@ impl CronStore {
         self.jobs.get_mut(id)
     }
 
+    /// Resolve a full id or an unambiguous id prefix.
+    pub fn resolve_id(&self, id: &str) -> Option<String> {
+        if self.jobs.contains_key(id) {
+            return Some(id.to_string());
+        }
+        let mut matches = self
+            .jobs
+            .keys()
+            .filter(|k| !id.is_empty() && k.starts_with(id));
+        match (matches.next(), matches.next()) {
+            (Some(only), None) => Some(only.clone()),
+            _ => None,
+        }
+    }
+
     /// Returns a sorted list of all jobs.
     pub fn list(&self) -> Vec<&CronJob> {
         let mut jobs: Vec<&CronJob> = self.jobs.values().collect();
@@ -160,6 +468,9 @@ impl CronStore {
 // ─── Cron service ─────────────────────────────────────────────────────────────
 
 /// High-level cron service that can be ticked from an async runtime loop.
+///
+/// The store file is shared with other processes (the CLI, agents in other
+/// gateways), so every mutation re-reads it first.
 pub struct CronService {
     store: CronStore,
     store_path: std::path::PathBuf,
@@ -175,52 +486,174 @@ impl CronService {
         })
     }
 
+    /// Service over the store at the default location.
+    pub fn open_default() -> Result<Self> {
+        Self::new(default_store_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.store_path
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        self.store = CronStore::load(&self.store_path)?;
+        Ok(())
+    }
+
     pub fn add_job(&mut self, job: CronJob) -> Result<()> {
+        self.reload()?;
         self.store.add(job);
         self.store.save(&self.store_path)
     }
 
+    /// Remove by id or id prefix.
     pub fn remove_job(&mut self, id: &str) -> Result<Option<CronJob>> {
-        let removed = self.store.remove(id);
+        self.reload()?;
+        let Some(id) = self.store.resolve_id(id) else {
+            return Ok(None);
+        };
+        let removed = self.store.remove(&id);
         self.store.save(&self.store_path)?;
         Ok(removed)
     }
 
+    /// Enable or disable by id or id prefix. Returns the job's full id.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> Result<Option<String>> {
+        self.reload()?;
+        let Some(id) = self.store.resolve_id(id) else {
+            return Ok(None);
+        };
+        if let Some(job) = self.store.get_mut(&id) {
+            job.enabled = enabled;
+            // Re-enabling starts from now rather than replaying the pause.
+            if enabled && !matches!(job.frequency, JobFrequency::Once { .. }) {
+                job.last_slot = Some(Utc::now());
+            }
+        }
+        self.store.save(&self.store_path)?;
+        Ok(Some(id))
+    }
+
     pub fn list_jobs(&self) -> Vec<&CronJob> {
         self.store.list()
     }
 
-    /// Tick: collect all due jobs, mark them as run, persist, and return their tasks.
-    pub fn tick(&mut self) -> Result<Vec<(String, String)>> {
-        let now = Utc::now();
-        let due_ids: Vec<String> = self
-            .store
-            .due_jobs(now)
-            .iter()
-            .map(|j| j.id.clone())
-            .collect();
+    pub fn get_job(&self, id: &str) -> Option<&CronJob> {
+        self.store.jobs.get(&self.store.resolve_id(id)?)
+    }
 
-        let mut tasks = Vec::new();
-        for id in &due_ids {
-            if let Some(job) = self.store.get_mut(id) {
-                tasks.push((job.id.clone(), job.task.clone()));
+    /// Take every run due at `now`, skipping jobs in `busy` (still running
+    /// from an earlier tick). Marks the slots handled, records skipped runs,
+    /// disables fired one-shots and persists.
+    pub fn take_due(&mut self, now: DateTime<Utc>, busy: &HashSet<String>) -> Result<Vec<DueRun>> {
+        self.reload()?;
+        let mut due = Vec::new();
+        let mut changed = false;
+        for job in self.store.jobs.values_mut() {
+            if busy.contains(&job.id) {
+                continue;
+            }
+            let Some(plan) = job.plan(now) else {
+                continue;
+            };
+            changed = true;
+            job.last_slot = Some(plan.last_slot);
+            if plan.skipped > 0 {
+                job.push_run(JobRun::skipped(plan.last_slot, now, plan.skipped));
+            }
+            if !plan.runs.is_empty() {
                 job.last_run = Some(now);
-                // Disable one-shot jobs after firing
-                if matches!(job.frequency, JobFrequency::Once { .. }) {
-                    job.enabled = false;
-                }
             }
+            // Disable one-shot jobs after firing
+            if matches!(job.frequency, JobFrequency::Once { .. }) {
+                job.enabled = false;
+            }
+            for slot in plan.runs {
+                due.push(DueRun {
+                    job: job.clone(),
+                    scheduled_for: slot,
+                    catch_up: (now - slot).num_seconds() > MISSED_GRACE_SECS,
+                });
+            }
+        }
+        if changed {
+            self.store.save(&self.store_path)?;
         }
+        due.sort_by_key(|run| run.scheduled_for);
+        Ok(due)
+    }
+
+    /// Tick: collect all due jobs, mark them as run, persist, and return their tasks.
+    pub fn tick(&mut self) -> Result<Vec<(String, String)>> {
+        let due = self.take_due(Utc::now(), &HashSet::new())?;
+        Ok(due
+            .into_iter()
+            .map(|run| (run.job.id, run.job.task))
+            .collect())
+    }
 
-        if !tasks.is_empty() {
+    /// Append a finished run to its job's history. Runs of jobs removed in
+    /// the meantime are dropped.
+    pub fn record_run(&mut self, id: &str, run: JobRun) -> Result<()> {
+        self.reload()?;
+        if let Some(job) = self.store.get_mut(id) {
+            job.push_run(run);
             self.store.save(&self.store_path)?;
         }
-        Ok(tasks)
+        Ok(())
     }
 }
 
 // ─── Helpers ──────────────────────────────────────────────────────────────────
 
+/// Shared job store: `<state dir>/cron/jobs.json`.
+pub fn default_store_path() -> PathBuf {
+    PathBuf::from(crate::utils::resolve_config_dir())
+        .join("cron")
+        .join("jobs.json")
+}
+
+/// Job store for a config: `cron.store`, else the default.
+pub fn store_path(cfg: &crate::OPENKRAB_CONFIG::OpenKrabConfig) -> PathBuf {
+    cfg.cron
+        .as_ref()
+        .and_then(|c| c.store.as_deref())
+        .map(|p| PathBuf::from(crate::utils::resolve_user_path(p)))
+        .unwrap_or_else(default_store_path)
+}
+
+pub fn new_job_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// Session key for one run: `agent:<agent>:cron:<job>:run:<run>`.
+pub fn run_session_key(agent_id: &str, job_id: &str, run_id: &str) -> String {
+    let part = |s: &str| s.replace(':', "-");
+    format!(
+        "agent:{}:cron:{}:run:{}",
+        part(agent_id),
+        part(job_id),
+        part(run_id)
+    )
+}
+
+/// Parse a schedule: `every 5m`, an RFC 3339 timestamp (one-shot), or a
+/// cron expression evaluated in `tz`.
+pub fn parse_schedule(s: &str, tz: Option<&str>) -> Result<JobFrequency> {
+    if let Some(interval) = parse_interval(s) {
+        return Ok(interval);
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(s.trim()) {
+        return Ok(JobFrequency::Once {
+            at: at.with_timezone(&Utc),
+        });
+    }
+    match JobFrequency::cron(s, tz) {
+        Ok(frequency) => Ok(frequency),
+        Err(e) => bail!("invalid schedule '{}': {}", s, e),
+    }
+}
+
 /// Parse a simple "every Xm", "every Xh", "every Xs" duration string.
 pub fn parse_interval(s: &str) -> Option<JobFrequency> {
     let s = s.trim().to_lowercase();
@@ -237,6 +670,60 @@ pub fn parse_interval(s: &str) -> Option<JobFrequency> {
     None
 }
 
+/// Entry in the per-workspace `memory/schedule.json` older versions of
+/// `manage_schedule` wrote.
+#[derive(Debug, Deserialize)]
+struct LegacyTask {
+    id: String,
+    cron: String,
+    description: String,
+    #[serde(default = "legacy_enabled")]
+    enabled: bool,
+}
+
+fn legacy_enabled() -> bool {
+    true
+}
+
+/// Move a workspace's legacy `memory/schedule.json` into the shared store
+/// and rename it to `schedule.json.imported`. Entries whose cron
+/// expression doesn't parse are left behind in the renamed file.
+pub fn import_legacy_schedule(
+    workspace_root: &Path,
+    service: &mut CronService,
+    agent_id: Option<&str>,
+    tz: Option<&str>,
+) -> Result<usize> {
+    let file = workspace_root.join("memory").join("schedule.json");
+    if !file.exists() {
+        return Ok(0);
+    }
+    let tasks: Vec<LegacyTask> = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+    let mut imported = 0;
+    for task in tasks {
+        let Ok(frequency) = JobFrequency::cron(&task.cron, tz) else {
+            tracing::warn!(
+                "Not importing schedule '{}': bad cron '{}'",
+                task.id,
+                task.cron
+            );
+            continue;
+        };
+        let mut job = CronJob::new(
+            task.id,
+            task.description.clone(),
+            task.description,
+            frequency,
+        );
+        job.enabled = task.enabled;
+        job.agent_id = agent_id.map(str::to_string);
+        service.add_job(job)?;
+        imported += 1;
+    }
+    std::fs::rename(&file, file.with_extension("json.imported"))?;
+    Ok(imported)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,4 +803,145 @@ mod tests {
         store.remove("a");
         assert_eq!(store.jobs.len(), 1);
     }
+
+    #[test]
+    fn cron_store_saves_by_replacing_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cron").join("jobs.json");
+        let mut store = CronStore::new();
+        store.add(CronJob::new(
+            "a",
+            "A",
+            "task a",
+            JobFrequency::Interval { secs: 60 },
+        ));
+        store.save(&path).unwrap();
+        store.remove("a");
+        store.save(&path).unwrap();
+
+        assert!(CronStore::load(&path).unwrap().jobs.is_empty());
+        let leftovers: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers, vec![std::ffi::OsString::from("jobs.json")]);
+    }
+
+    fn daily_at_nine(created: DateTime<Utc>, catch_up: CatchUp) -> CronJob {
+        let mut job = CronJob::new(
+            "d",
+            "Daily",
+            "report",
+            JobFrequency::cron("0 9 * * *", Some("+07:00")).unwrap(),
+        )
+        .with_catch_up(catch_up);
+        job.created_at = created;
+        job
+    }
+
+    #[test]
+    fn cron_jobs_apply_the_catch_up_policy() {
+        // 09:00 at +07:00 is 02:00 UTC; three slots were missed by 10:00 UTC.
+        let created = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 10, 0, 0).unwrap();
+        let slot = |d: u32| Utc.with_ymd_and_hms(2026, 10, d, 2, 0, 0).unwrap();
+
+        let latest = daily_at_nine(created, CatchUp::Latest).plan(now).unwrap();
+        assert_eq!(latest.runs, vec![slot(17)]);
+        assert_eq!(latest.skipped, 2);
+        assert_eq!(latest.last_slot, slot(17));
+
+        let all = daily_at_nine(created, CatchUp::All).plan(now).unwrap();
+        assert_eq!(all.runs, vec![slot(15), slot(16), slot(17)]);
+
+        let skip = daily_at_nine(created, CatchUp::Skip);
+        assert!(skip.plan(now).unwrap().runs.is_empty());
+        assert!(!skip.is_due(now));
+        let on_time = slot(17) + chrono::Duration::seconds(5);
+        assert_eq!(skip.plan(on_time).unwrap().runs, vec![slot(17)]);
+
+        let mut handled = daily_at_nine(created, CatchUp::Latest);
+        handled.last_slot = Some(slot(17));
+        assert!(handled.plan(now).is_none());
+        assert_eq!(handled.next_run(), Some(slot(18)));
+    }
+
+    #[test]
+    fn service_takes_due_runs_and_records_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let mut service = CronService::new(&path).unwrap();
+        let created = Utc.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap();
+        service
+            .add_job(daily_at_nine(created, CatchUp::Skip).with_agent("work"))
+            .unwrap();
+        let mut once = CronJob::new("o", "Once", "ping", JobFrequency::Once { at: created });
+        once.created_at = created;
+        service.add_job(once).unwrap();
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 10, 0, 0).unwrap();
+        let busy = HashSet::from(["o".to_string()]);
+        assert!(service.take_due(now, &busy).unwrap().is_empty());
+        // The skipped slots were recorded and won't come back.
+        let job = service.get_job("d").unwrap();
+        assert_eq!(job.history.len(), 1);
+        assert_eq!(job.history[0].status, RunStatus::Skipped);
+        assert!(service.take_due(now, &busy).unwrap().is_empty());
+
+        let due = service.take_due(now, &HashSet::new()).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].job.id, "o");
+        assert!(due[0].catch_up);
+        let run = JobRun::start(due[0].scheduled_for, true).finish(Ok("pong".to_string()));
+        service.record_run("o", run).unwrap();
+
+        // Another process sees the disabled one-shot and its history.
+        let fresh = CronService::new(&path).unwrap();
+        let job = fresh.get_job("o").unwrap();
+        assert!(!job.enabled);
+        assert_eq!(job.history[0].output.as_deref(), Some("pong"));
+        assert_eq!(
+            fresh.get_job("d").unwrap().agent_id.as_deref(),
+            Some("work")
+        );
+    }
+
+    #[test]
+    fn schedules_and_legacy_imports() {
+        assert!(matches!(
+            parse_schedule("every 5m", None).unwrap(),
+            JobFrequency::Interval { secs: 300 }
+        ));
+        assert!(matches!(
+            parse_schedule("2026-12-24T18:00:00+07:00", None).unwrap(),
+            JobFrequency::Once { .. }
+        ));
+        assert!(parse_schedule("0 9 * * *", Some("Mars/Olympus")).is_err());
+        assert_eq!(
+            run_session_key("main", "abc", "r1"),
+            "agent:main:cron:abc:run:r1"
+        );
+        assert!(crate::sessions::session_key_utils::is_cron_run_session_key(
+            Some(&run_session_key("main", "abc", "r1"))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("memory")).unwrap();
+        std::fs::write(
+            dir.path().join("memory/schedule.json"),
+            r#"[{"id":"t1","cron":"0 9 * * *","description":"standup","enabled":true},
+                {"id":"t2","cron":"not cron","description":"bad","enabled":true}]"#,
+        )
+        .unwrap();
+        let mut service = CronService::new(dir.path().join("jobs.json")).unwrap();
+        let imported =
+            import_legacy_schedule(dir.path(), &mut service, Some("main"), None).unwrap();
+        assert_eq!(imported, 1);
+        assert_eq!(service.get_job("t1").unwrap().task, "standup");
+        assert!(dir.path().join("memory/schedule.json.imported").exists());
+        assert_eq!(
+            import_legacy_schedule(dir.path(), &mut service, None, None).unwrap(),
+            0
+        );
+    }
 }
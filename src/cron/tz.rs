@@ -0,0 +1,156 @@
+//! tz — time zones for cron schedules.
+//!
+//! IANA zone names resolve against the tz database embedded by `chrono-tz`,
+//! so schedules behave the same on every platform whether or not the host
+//! ships `/usr/share/zoneinfo`. Fixed offsets (`+07:00`, `UTC-5`) are
+//! accepted as well.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDateTime, Offset, TimeZone, Utc};
+
+/// A resolved time zone. Offsets are seconds east of UTC.
+#[derive(Debug, Clone)]
+pub enum CronTz {
+    Utc,
+    Fixed(i32),
+    Zone(chrono_tz::Tz),
+}
+
+impl CronTz {
+    /// Resolve an IANA zone name or fixed offset.
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        match name.to_ascii_uppercase().as_str() {
+            "" | "UTC" | "Z" | "GMT" | "ETC/UTC" | "ETC/GMT" | "UNIVERSAL" => return Ok(Self::Utc),
+            _ => {}
+        }
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(Self::Fixed(offset));
+        }
+        match name.parse::<chrono_tz::Tz>() {
+            Ok(zone) => Ok(Self::Zone(zone)),
+            Err(_) => bail!("unknown time zone '{}'", name),
+        }
+    }
+
+    /// UTC offset in effect at `utc` (unix seconds).
+    pub fn offset_at(&self, utc: i64) -> i32 {
+        match self {
+            Self::Utc => 0,
+            Self::Fixed(offset) => *offset,
+            Self::Zone(zone) => DateTime::from_timestamp(utc, 0)
+                .map(|t| {
+                    zone.offset_from_utc_datetime(&t.naive_utc())
+                        .fix()
+                        .local_minus_utc()
+                })
+                .unwrap_or(0),
+        }
+    }
+
+    /// Wall-clock time at `utc`.
+    pub fn to_local(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        let offset = self.offset_at(utc.timestamp());
+        utc.naive_utc() + chrono::Duration::seconds(offset as i64)
+    }
+
+    /// The instant a wall-clock time refers to. Ambiguous times (clocks
+    /// going back) resolve to the earlier instant; times skipped by a
+    /// forward jump resolve to the same distance past the jump.
+    pub fn from_local(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let wall = local.and_utc().timestamp();
+        let before = self.offset_at(wall - 86_400);
+        let after = self.offset_at(wall + 86_400);
+        let valid = [before, after]
+            .into_iter()
+            .map(|offset| wall - offset as i64)
+            .filter(|utc| {
+                let offset = self.offset_at(*utc);
+                wall - offset as i64 == *utc
+            })
+            .min();
+        let utc = valid.unwrap_or(wall - before as i64);
+        DateTime::from_timestamp(utc, 0).unwrap_or_default()
+    }
+}
+
+/// `+07:00`, `-0530`, `UTC+7`, `GMT-03:30`.
+fn parse_fixed_offset(s: &str) -> Option<i32> {
+    if !s.is_ascii() {
+        return None;
+    }
+    let upper = s.to_ascii_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    let (sign, digits) = match rest.as_bytes().first()? {
+        b'+' => (1, &rest[1..]),
+        b'-' => (-1, &rest[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+        None if digits.len() == 4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+        None => (digits.parse().ok()?, 0),
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn fixed_offsets() {
+        assert_eq!(parse_fixed_offset("+07:00"), Some(7 * 3600));
+        assert_eq!(parse_fixed_offset("UTC-0530"), Some(-(5 * 3600 + 1800)));
+        assert_eq!(parse_fixed_offset("Asia/Bangkok"), None);
+        assert_eq!(parse_fixed_offset("+1ä1"), None);
+        assert!(CronTz::parse("+1ä1").is_err());
+        assert_eq!(CronTz::parse("GMT+7").unwrap().offset_at(0), 7 * 3600);
+    }
+
+    #[test]
+    fn local_times_across_dst_transitions() {
+        let tz = CronTz::parse("America/New_York").unwrap();
+        let local = |mo: u32, d: u32, h: u32, mi: u32| {
+            NaiveDate::from_ymd_opt(2031, mo, d)
+                .unwrap()
+                .and_hms_opt(h, mi, 0)
+                .unwrap()
+        };
+        // 2031-03-09 02:30 does not exist; it lands 30 minutes past the jump.
+        assert_eq!(tz.from_local(local(3, 9, 2, 30)), at(2031, 3, 9, 7, 30));
+        // 2031-11-02 01:30 happens twice; the first (EDT) wins.
+        assert_eq!(tz.from_local(local(11, 2, 1, 30)), at(2031, 11, 2, 5, 30));
+        assert_eq!(tz.to_local(at(2031, 11, 2, 6, 30)), local(11, 2, 1, 30));
+    }
+
+    #[test]
+    fn iana_zones_from_the_embedded_database() {
+        let berlin = CronTz::parse("Europe/Berlin").unwrap();
+        assert_eq!(berlin.offset_at(at(2024, 1, 15, 12, 0).timestamp()), 3600);
+        assert_eq!(berlin.offset_at(at(2024, 7, 15, 12, 0).timestamp()), 7200);
+        assert_eq!(berlin.offset_at(at(2080, 7, 15, 12, 0).timestamp()), 7200);
+        // 2030-03-31 is the last Sunday of March; DST starts at 01:00 UTC.
+        assert_eq!(berlin.offset_at(at(2030, 3, 31, 0, 59).timestamp()), 3600);
+        assert_eq!(berlin.offset_at(at(2030, 3, 31, 1, 0).timestamp()), 7200);
+
+        let kiritimati = CronTz::parse("Pacific/Kiritimati").unwrap();
+        assert_eq!(
+            kiritimati.offset_at(at(2030, 1, 10, 0, 0).timestamp()),
+            14 * 3600
+        );
+        assert!(CronTz::parse("Not/AZone").is_err());
+        assert!(CronTz::parse("../etc/passwd").is_err());
+    }
+}
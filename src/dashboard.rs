@@ -654,8 +654,11 @@ pub async fn api_chat_handler(
             .into_response();
     }
 
-    let agent = match state.agent.as_ref() {
-        Some(a) => a,
+    let routed = match state.agents.route(crate::agents::RouteQuery {
+        channel: "webchat",
+        ..Default::default()
+    }) {
+        Some(routed) => routed,
         None => {
             return (
                 StatusCode::SERVICE_UNAVAILABLE,
@@ -665,7 +668,7 @@ pub async fn api_chat_handler(
         }
     };
 
-    match agent.answer(&req.message).await {
+    match state.answer_in_session(&routed, &req.message).await {
         Ok(reply) => (StatusCode::OK, Json(json!({ "ok": true, "reply": reply }))).into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -6,6 +6,8 @@ use std::time::{Duration, Instant};
 use subtle::ConstantTimeEq;
 use tokio::sync::RwLock;
 
+use crate::gateway::tokens::{TokenScope, TokenStore};
+
 struct RateLimitEntry {
     attempts: u32,
     first_attempt: Instant,
@@ -85,6 +87,35 @@ pub struct ResolvedAuth {
     pub rate_limit_per_user: Option<u32>,
 }
 
+impl ResolvedAuth {
+    /// Resolve `gateway.auth`. `OPENKRAB_GATEWAY_TOKEN` overrides the
+    /// configured token; a token alone implies token mode.
+    pub fn from_config(cfg: Option<&crate::OPENKRAB_CONFIG::GatewayAuthConfig>) -> Self {
+        let cfg = cfg.cloned().unwrap_or_default();
+        let token = std::env::var("OPENKRAB_GATEWAY_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty())
+            .or(cfg.token);
+        let mode = match cfg.mode.as_deref().map(|m| m.trim().to_lowercase()) {
+            Some(m) if m == "token" => AuthMode::Token,
+            Some(m) if m == "password" => AuthMode::Password,
+            Some(m) if m == "trusted-proxy" || m == "trusted_proxy" => AuthMode::TrustedProxy,
+            Some(m) if m == "none" => AuthMode::None,
+            _ if token.is_some() => AuthMode::Token,
+            _ => AuthMode::None,
+        };
+        Self {
+            mode,
+            token,
+            username: cfg.username,
+            password: cfg.password,
+            allow_tailscale: false,
+            trusted_proxy: None,
+            rate_limit_per_user: cfg.rate_limit_per_user,
+        }
+    }
+}
+
 /// Trusted proxy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrustedProxyConfig {
@@ -100,6 +131,9 @@ pub struct AuthResult {
     pub reason: Option<String>,
     pub rate_limited: Option<bool>,
     pub retry_after_ms: Option<u64>,
+    /// What the caller may do; set by `AuthManager` on success.
+    #[serde(default)]
+    pub scope: Option<TokenScope>,
 }
 
 /// Authentication context for a request
@@ -152,6 +186,7 @@ impl Authenticator for NoAuth {
             reason: None,
             rate_limited: None,
             retry_after_ms: None,
+            scope: None,
         }
     }
 }
@@ -184,6 +219,7 @@ impl Authenticator for TokenAuth {
                 reason: None,
                 rate_limited: None,
                 retry_after_ms: None,
+                scope: None,
             },
             _ => AuthResult {
                 ok: false,
@@ -192,6 +228,7 @@ impl Authenticator for TokenAuth {
                 reason: Some("Invalid token".to_string()),
                 rate_limited: None,
                 retry_after_ms: None,
+                scope: None,
             },
         }
     }
@@ -237,6 +274,7 @@ impl Authenticator for PasswordAuth {
                                     reason: None,
                                     rate_limited: None,
                                     retry_after_ms: None,
+                                    scope: None,
                                 };
                             }
                         }
@@ -252,14 +290,17 @@ impl Authenticator for PasswordAuth {
             reason: Some("Invalid credentials".to_string()),
             rate_limited: None,
             retry_after_ms: None,
+            scope: None,
         }
     }
 }
 
 /// Authentication manager that handles different auth methods
 pub struct AuthManager {
+    mode: AuthMode,
     authenticator: Box<dyn Authenticator>,
     rate_limiter: Option<Arc<RateLimiter>>,
+    tokens: Option<Arc<TokenStore>>,
 }
 
 impl AuthManager {
@@ -268,6 +309,7 @@ impl AuthManager {
             .rate_limit_per_user
             .map(|max_attempts| Arc::new(RateLimiter::new(max_attempts, 60)));
 
+        let mode = auth_config.mode;
         let authenticator: Box<dyn Authenticator> = match auth_config.mode {
             AuthMode::None => Box::new(NoAuth),
             AuthMode::Token => {
@@ -283,31 +325,83 @@ impl AuthManager {
         };
 
         Self {
+            mode,
             authenticator,
             rate_limiter,
+            tokens: None,
         }
     }
 
+    /// Also accept scoped API tokens from `store`.
+    pub fn with_token_store(mut self, store: TokenStore) -> Self {
+        self.tokens = Some(Arc::new(store));
+        self
+    }
+
+    pub fn mode(&self) -> AuthMode {
+        self.mode
+    }
+
+    /// Authenticate a request. Scoped API tokens are checked first; any
+    /// other successful login is admin. The rate limit is keyed by the
+    /// authenticated user (or token) and by client IP otherwise.
     pub async fn authenticate(&self, ctx: &AuthContext) -> AuthResult {
+        let result = self.resolve(ctx);
+
         if let Some(limiter) = &self.rate_limiter {
-            let client_ip = ctx.client_ip.as_deref().unwrap_or("unknown");
-            let (allowed, retry_after) = limiter.check(client_ip).await;
+            let key = match (&result.user, result.ok) {
+                (Some(user), true) => format!("user:{}", user),
+                _ => format!("ip:{}", ctx.client_ip.as_deref().unwrap_or("unknown")),
+            };
+            let (allowed, retry_after) = limiter.check(&key).await;
             if !allowed {
                 return AuthResult {
                     ok: false,
-                    method: None,
-                    user: None,
+                    method: result.method,
+                    user: result.user,
                     reason: Some("Rate limit exceeded".to_string()),
                     rate_limited: Some(true),
                     retry_after_ms: retry_after,
+                    scope: None,
+                };
+            }
+        }
+
+        result
+    }
+
+    fn resolve(&self, ctx: &AuthContext) -> AuthResult {
+        if let (Some(store), Some(secret)) = (&self.tokens, presented_token(ctx)) {
+            if let Some(token) = store.verify(secret) {
+                return AuthResult {
+                    ok: true,
+                    method: Some("api-token".to_string()),
+                    user: Some(format!("token:{}", token.id)),
+                    reason: None,
+                    rate_limited: None,
+                    retry_after_ms: None,
+                    scope: Some(token.scope),
                 };
             }
         }
 
-        self.authenticator.authenticate(ctx)
+        let mut result = self.authenticator.authenticate(ctx);
+        if result.ok {
+            result.scope = Some(TokenScope::Admin);
+        }
+        result
     }
 }
 
+/// Bearer header or `?token=` query parameter.
+fn presented_token(ctx: &AuthContext) -> Option<&str> {
+    ctx.headers
+        .get("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or_else(|| ctx.query_params.get("token").map(|s| s.as_str()))
+        .map(str::trim)
+}
+
 impl Default for AuthManager {
     fn default() -> Self {
         Self::new(ResolvedAuth {
@@ -372,6 +466,59 @@ mod tests {
         assert_eq!(result.user, Some("admin".to_string()));
     }
 
+    #[test]
+    fn test_resolved_auth_from_config() {
+        let cfg = crate::OPENKRAB_CONFIG::GatewayAuthConfig {
+            token: Some("abc".to_string()),
+            ..Default::default()
+        };
+        if std::env::var("OPENKRAB_GATEWAY_TOKEN").is_err() {
+            let resolved = ResolvedAuth::from_config(Some(&cfg));
+            assert_eq!(resolved.mode, AuthMode::Token);
+            assert_eq!(resolved.token.as_deref(), Some("abc"));
+            assert_eq!(ResolvedAuth::from_config(None).mode, AuthMode::None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scoped_token_and_per_user_rate_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TokenStore::new(dir.path().join("tokens.json"));
+        let (token, secret) = store.issue("bot", TokenScope::Chat).unwrap();
+        let manager = AuthManager::new(ResolvedAuth {
+            mode: AuthMode::Token,
+            token: Some("root".to_string()),
+            username: None,
+            password: None,
+            allow_tailscale: false,
+            trusted_proxy: None,
+            rate_limit_per_user: Some(1),
+        })
+        .with_token_store(store);
+
+        let ctx = AuthContext::new()
+            .with_client_ip("10.0.0.1")
+            .with_query_param("token", secret.clone());
+        let result = manager.authenticate(&ctx).await;
+        assert!(result.ok);
+        assert_eq!(result.scope, Some(TokenScope::Chat));
+        assert_eq!(result.user, Some(format!("token:{}", token.id)));
+
+        // Same token from another address shares the user's budget.
+        let ctx = AuthContext::new()
+            .with_client_ip("10.0.0.2")
+            .with_header("authorization", format!("Bearer {}", secret));
+        assert_eq!(manager.authenticate(&ctx).await.rate_limited, Some(true));
+
+        let root = AuthContext::new()
+            .with_client_ip("10.0.0.1")
+            .with_header("authorization", "Bearer root");
+        assert_eq!(
+            manager.authenticate(&root).await.scope,
+            Some(TokenScope::Admin)
+        );
+    }
+
     #[test]
     fn test_is_loopback_address() {
         assert!(is_loopback_address("127.0.0.1"));
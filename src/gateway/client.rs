@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use url::Url;
 
+use crate::agents::streaming::StreamEvent;
 use crate::gateway::types::*;
 
 /// Gateway WebSocket client for connecting to the gateway server
@@ -85,25 +86,80 @@ impl GatewayConnection {
         message: String,
         attachments: Option<Vec<crate::gateway::types::Attachment>>,
     ) -> Result<GatewayMessage, Box<dyn std::error::Error + Send + Sync>> {
-        let chat_msg = GatewayMessage::Chat {
-            session_key,
+        let turn = self
+            .send_chat_streaming(
+                session_key,
+                message,
+                attachments,
+                std::future::pending(),
+                |_| {},
+            )
+            .await?;
+        Ok(GatewayMessage::Chat {
+            session_key: turn.session_key,
+            message: turn.message,
+            attachments: None,
+        })
+    }
+
+    /// Send a chat message, calling `on_event` for each streamed event until
+    /// the reply arrives. When `cancel` resolves first, a `chat.cancel` is
+    /// sent and the turn ends with a `cancelled` error.
+    pub async fn send_chat_streaming<C, F>(
+        &mut self,
+        session_key: String,
+        message: String,
+        attachments: Option<Vec<crate::gateway::types::Attachment>>,
+        cancel: C,
+        mut on_event: F,
+    ) -> Result<ChatTurn, Box<dyn std::error::Error + Send + Sync>>
+    where
+        C: std::future::Future<Output = ()>,
+        F: FnMut(&StreamEvent),
+    {
+        self.send(GatewayMessage::Chat {
+            session_key: session_key.clone(),
             message,
             attachments,
-        };
-
-        self.send(chat_msg).await?;
+        })
+        .await?;
 
+        let mut cancel = std::pin::pin!(cancel);
+        let mut cancel_sent = false;
+        let mut usage = (0, 0);
         loop {
-            match self.receive().await? {
+            let next = if cancel_sent {
+                self.receive().await?
+            } else {
+                tokio::select! {
+                    next = self.receive() => next?,
+                    _ = &mut cancel => {
+                        self.cancel_chat(session_key.clone()).await?;
+                        cancel_sent = true;
+                        continue;
+                    }
+                }
+            };
+            match next {
+                Some(GatewayMessage::ChatEvent {
+                    session_key: key,
+                    event,
+                }) if key == session_key => on_event(&event),
+                Some(GatewayMessage::ChatFinish {
+                    session_key: key,
+                    input_tokens,
+                    output_tokens,
+                }) if key == session_key => usage = (input_tokens, output_tokens),
                 Some(GatewayMessage::Chat {
-                    session_key,
+                    session_key: key,
                     message,
-                    attachments,
-                }) => {
-                    return Ok(GatewayMessage::Chat {
-                        session_key,
+                    ..
+                }) if key == session_key => {
+                    return Ok(ChatTurn {
+                        session_key: key,
                         message,
-                        attachments,
+                        input_tokens: usage.0,
+                        output_tokens: usage.1,
                     });
                 }
                 Some(GatewayMessage::Error { code, message }) => {
@@ -115,6 +171,14 @@ impl GatewayConnection {
         }
     }
 
+    /// Ask the gateway to abort the in-flight turn of a session.
+    pub async fn cancel_chat(
+        &mut self,
+        session_key: String,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.send(GatewayMessage::ChatCancel { session_key }).await
+    }
+
     /// Get server status
     pub async fn get_status(
         &mut self,
@@ -127,6 +191,15 @@ impl GatewayConnection {
     }
 }
 
+/// A completed chat turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatTurn {
+    pub session_key: String,
+    pub message: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
 /// Builder for creating gateway client connections
 pub struct GatewayClientBuilder {
     host: String,
@@ -189,4 +262,66 @@ mod tests {
         assert_eq!(client.url.host_str(), Some("localhost"));
         assert_eq!(client.url.port(), Some(8080));
     }
+
+    #[tokio::test]
+    async fn streaming_chat_collects_events_and_usage() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _chat = ws.next().await;
+            let replies = [
+                GatewayMessage::ChatEvent {
+                    session_key: "main".to_string(),
+                    event: StreamEvent::TextDelta {
+                        content: "Hel".to_string(),
+                        accumulated: "Hel".to_string(),
+                    },
+                },
+                // Other sessions' traffic is ignored.
+                GatewayMessage::Chat {
+                    session_key: "other".to_string(),
+                    message: "nope".to_string(),
+                    attachments: None,
+                },
+                GatewayMessage::ChatFinish {
+                    session_key: "main".to_string(),
+                    input_tokens: 7,
+                    output_tokens: 2,
+                },
+                GatewayMessage::Chat {
+                    session_key: "main".to_string(),
+                    message: "Hello".to_string(),
+                    attachments: None,
+                },
+            ];
+            for reply in replies {
+                let json = serde_json::to_string(&reply).unwrap();
+                ws.send(Message::Text(json)).await.unwrap();
+            }
+        });
+
+        let client = GatewayClientBuilder::new().port(port).build().unwrap();
+        let mut conn = client.connect().await.unwrap();
+        let mut deltas = Vec::new();
+        let turn = conn
+            .send_chat_streaming(
+                "main".to_string(),
+                "hi".to_string(),
+                None,
+                std::future::pending(),
+                |event| {
+                    if let StreamEvent::TextDelta { content, .. } = event {
+                        deltas.push(content.clone());
+                    }
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(deltas, vec!["Hel".to_string()]);
+        assert_eq!(turn.message, "Hello");
+        assert_eq!((turn.input_tokens, turn.output_tokens), (7, 2));
+    }
 }
@@ -0,0 +1,249 @@
+//! middleware — authentication for every gateway route.
+//!
+//! Each request is authenticated with the server's `AuthManager` (bearer or
+//! basic header, or `?token=` for WebSocket upgrades from browsers), rate
+//! limited, and checked against the scope its route needs. Failures get an
+//! OpenAI-shaped JSON error and a `security_audit` event. The authenticated
+//! principal is left in the request extensions for handlers that check
+//! scope per message (`/ws`).
+
+use axum::{
+    extract::{connect_info::ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::gateway::auth::AuthContext;
+use crate::gateway::openai_compat::ApiError;
+use crate::gateway::server::GatewayServer;
+use crate::gateway::tokens::TokenScope;
+use crate::security_audit::{audit, SecurityEvent, SecurityEventType, SecuritySeverity};
+
+/// Who made a request, as established by the auth middleware.
+#[derive(Debug, Clone)]
+pub struct AuthPrincipal {
+    pub method: Option<String>,
+    pub user: Option<String>,
+    pub scope: TokenScope,
+}
+
+/// What a route requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteAccess {
+    Public,
+    /// Any authenticated principal; the handler checks scope itself.
+    Authenticated,
+    Scope(TokenScope),
+}
+
+pub fn route_access(path: &str) -> RouteAccess {
+    match path {
+        "/health" => RouteAccess::Public,
+        // Checked against the Slack signing secret by the handler.
+        p if p == crate::gateway::slack_events::SLACK_EVENTS_PATH => RouteAccess::Public,
+        // Checked against the Bot Framework's signed token by the handler.
+        p if p == crate::gateway::msteams_events::MSTEAMS_MESSAGES_PATH => RouteAccess::Public,
+        "/ws" => RouteAccess::Authenticated,
+        "/v1/models" => RouteAccess::Scope(TokenScope::Status),
+        "/v1/chat/completions" => RouteAccess::Scope(TokenScope::Chat),
+        p if p == crate::acp::ACP_DEFAULT_PATH => RouteAccess::Scope(TokenScope::Chat),
+        p if p == "/webrtc" || p.starts_with("/webrtc/") => RouteAccess::Scope(TokenScope::Chat),
+        _ => RouteAccess::Scope(TokenScope::Admin),
+    }
+}
+
+fn auth_context(request: &Request) -> AuthContext {
+    let mut ctx = AuthContext::new();
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        ctx = ctx.with_client_ip(addr.ip().to_string());
+    }
+    for (name, value) in request.headers() {
+        if let Ok(value) = value.to_str() {
+            ctx = ctx.with_header(name.as_str(), value);
+        }
+    }
+    if let Some(query) = request.uri().query() {
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            ctx = ctx.with_query_param(key, value);
+        }
+    }
+    ctx
+}
+
+async fn audit_failure(ctx: &AuthContext, path: &str, reason: &str) {
+    let mut event = SecurityEvent::new(
+        SecurityEventType::AuthFailure,
+        SecuritySeverity::Warning,
+        "gateway",
+        reason,
+    )
+    .with_context("path", path);
+    if let Some(ip) = &ctx.client_ip {
+        event = event.with_subject(ip.clone());
+    }
+    audit().log(event).await;
+}
+
+/// Axum middleware enforcing `AuthManager` and token scopes.
+pub async fn require_auth(
+    State(server): State<Arc<GatewayServer>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let access = route_access(&path);
+    if access == RouteAccess::Public {
+        return next.run(request).await;
+    }
+
+    let ctx = auth_context(&request);
+    let result = server.auth.authenticate(&ctx).await;
+    if !result.ok {
+        let reason = result
+            .reason
+            .unwrap_or_else(|| "Authentication failed".to_string());
+        audit_failure(&ctx, &path, &reason).await;
+        let err = if result.rate_limited == Some(true) {
+            let mut err = ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limit_error", reason)
+                .with_code("rate_limit_exceeded");
+            err.retry_after_ms = result.retry_after_ms;
+            err
+        } else {
+            ApiError::new(StatusCode::UNAUTHORIZED, "authentication_error", reason)
+                .with_code("invalid_api_key")
+        };
+        return err.into_response();
+    }
+
+    let scope = result.scope.unwrap_or(TokenScope::Admin);
+    if let RouteAccess::Scope(required) = access {
+        if !scope.allows(required) {
+            let reason = format!(
+                "Token scope '{}' does not allow '{}' access",
+                scope.as_str(),
+                required.as_str()
+            );
+            audit_failure(&ctx, &path, &reason).await;
+            return ApiError::new(StatusCode::FORBIDDEN, "permission_error", reason)
+                .with_code("insufficient_scope")
+                .into_response();
+        }
+    }
+
+    request.extensions_mut().insert(AuthPrincipal {
+        method: result.method,
+        user: result.user,
+        scope,
+    });
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::auth::{AuthManager, AuthMode, ResolvedAuth};
+    use crate::gateway::tokens::TokenStore;
+
+    #[test]
+    fn routes_map_to_scopes() {
+        assert_eq!(route_access("/health"), RouteAccess::Public);
+        assert_eq!(route_access("/slack/events"), RouteAccess::Public);
+        assert_eq!(route_access("/api/messages"), RouteAccess::Public);
+        assert_eq!(route_access("/ws"), RouteAccess::Authenticated);
+        assert_eq!(
+            route_access("/v1/models"),
+            RouteAccess::Scope(TokenScope::Status)
+        );
+        assert_eq!(
+            route_access("/webrtc/offer"),
+            RouteAccess::Scope(TokenScope::Chat)
+        );
+        assert_eq!(
+            route_access("/admin/anything"),
+            RouteAccess::Scope(TokenScope::Admin)
+        );
+    }
+
+    #[tokio::test]
+    async fn every_route_requires_a_token_with_the_right_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TokenStore::new(dir.path().join("tokens.json"));
+        let (_, status_token) = store.issue("dash", TokenScope::Status).unwrap();
+        let (_, chat_token) = store.issue("ide", TokenScope::Chat).unwrap();
+        let auth = AuthManager::new(ResolvedAuth {
+            mode: AuthMode::Token,
+            token: Some("root-secret".to_string()),
+            username: None,
+            password: None,
+            allow_tailscale: false,
+            trusted_proxy: None,
+            rate_limit_per_user: None,
+        })
+        .with_token_store(store);
+
+        let server = Arc::new(GatewayServer::new(0, "127.0.0.1".to_string()).with_auth(auth));
+        let app = crate::gateway::server::build_gateway_router(server, false);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+
+        let client = reqwest::Client::new();
+        let get = |path: &str, token: Option<&str>| {
+            let mut request = client.get(format!("{}{}", base, path));
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+
+        assert_eq!(get("/health", None).await.unwrap().status(), 200);
+        assert_eq!(get("/ws", None).await.unwrap().status(), 401);
+        assert_eq!(get("/v1/models", None).await.unwrap().status(), 401);
+        assert_eq!(
+            get("/v1/models", Some(&status_token))
+                .await
+                .unwrap()
+                .status(),
+            200
+        );
+        // Chat includes status: SDKs list models before chatting.
+        assert_eq!(
+            get("/v1/models", Some(&chat_token)).await.unwrap().status(),
+            200
+        );
+        assert_eq!(
+            get("/v1/models", Some("root-secret"))
+                .await
+                .unwrap()
+                .status(),
+            200
+        );
+
+        let denied = client
+            .post(format!("{}/v1/chat/completions", base))
+            .bearer_auth(&status_token)
+            .json(&serde_json::json!({"messages": []}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(denied.status(), 403);
+        let body: serde_json::Value = denied.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "insufficient_scope");
+
+        // Browsers can't set headers on WS upgrades; the query token passes
+        // auth and the request reaches the upgrade handler.
+        let upgrade = get(&format!("/ws?token={}", status_token), None)
+            .await
+            .unwrap();
+        assert_ne!(upgrade.status(), 401);
+    }
+}
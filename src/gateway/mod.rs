@@ -8,19 +8,95 @@ pub mod client;
 pub mod config_reload;
 pub mod constants;
 pub mod heartbeat;
+pub mod middleware;
 pub mod monitor_manager;
+pub mod msteams_events;
+pub mod openai_compat;
+pub mod scheduler;
 pub mod server;
+pub mod session_queue;
+pub mod slack_events;
+pub mod tokens;
 pub mod types;
 
 // Re-exports for convenience
 pub use client::*;
 pub use constants::*;
-pub use server::{ClientConnection, GatewayServer, GatewayServerOptions};
+pub use server::{serve_gateway, ClientConnection, GatewayServer, GatewayServerOptions};
+pub use session_queue::{QueueMode, QueueSettings, SessionQueue};
+pub use tokens::{ApiToken, TokenScope, TokenStore};
 pub use types::*;
 
 /// Gateway state wrapper (for compatibility with old code)
 pub type GatewayState = GatewayServer;
 
+/// Start the inbound monitors of connectors whose tokens are set
+/// (`TELEGRAM_BOT_TOKEN`, `DISCORD_BOT_TOKEN`, and `SLACK_BOT_TOKEN` with
+/// `SLACK_APP_TOKEN` for Socket Mode, `IRC_SERVER` with the other `IRC_*`
+/// settings, `NOSTR_PRIVATE_KEY` with `NOSTR_RELAYS`, and `MATTERMOST_URL`
+/// with `MATTERMOST_TOKEN`), plus the Matrix and Signal accounts enabled in `channels`,
+/// which run under the gateway's `MonitorManager`.
+/// Each runs until shutdown and answers through the agent pool.
+pub fn spawn_connector_monitors(
+    state: std::sync::Arc<GatewayState>,
+    channels: &crate::OPENKRAB_CONFIG::ChannelsConfig,
+) {
+    match state.monitors.try_write() {
+        Ok(mut monitors) => monitors.start_channels(state.clone(), channels),
+        Err(_) => tracing::warn!("Monitor manager busy; Matrix and Signal monitors not started"),
+    }
+    let token = |name: &str| std::env::var(name).ok().filter(|t| !t.trim().is_empty());
+    if let Some(token) = token("TELEGRAM_BOT_TOKEN") {
+        tracing::info!("Starting Telegram monitor");
+        tokio::spawn(crate::connectors::telegram::monitor(state.clone(), token));
+    }
+    if let Some(token) = token("DISCORD_BOT_TOKEN") {
+        tracing::info!("Starting Discord monitor");
+        tokio::spawn(crate::connectors::discord::monitor(state.clone(), token));
+    }
+    if let (Some(bot), Some(app)) = (token("SLACK_BOT_TOKEN"), token("SLACK_APP_TOKEN")) {
+        tracing::info!("Starting Slack Socket Mode monitor");
+        tokio::spawn(crate::connectors::slack_monitor::monitor_socket_mode(
+            state.clone(),
+            bot,
+            app,
+        ));
+    }
+    if token("IRC_SERVER").is_some() {
+        let config = crate::connectors::irc::IrcConfig::from_env();
+        match config.validate() {
+            Ok(()) => {
+                tracing::info!("Starting IRC client for {}", config.server);
+                tokio::spawn(crate::connectors::irc_client::monitor(
+                    state.clone(),
+                    config,
+                ));
+            }
+            Err(e) => tracing::warn!("IRC not started: {}", e),
+        }
+    }
+    if token("NOSTR_PRIVATE_KEY").is_some() {
+        let config = crate::connectors::nostr::NostrConfig::from_env();
+        match config.validate() {
+            Ok(()) => {
+                tracing::info!("Starting Nostr DM monitor");
+                tokio::spawn(crate::connectors::nostr_monitor::monitor(
+                    state.clone(),
+                    config,
+                ));
+            }
+            Err(e) => tracing::warn!("Nostr not started: {}", e),
+        }
+    }
+    if token("MATTERMOST_URL").is_some() && token("MATTERMOST_TOKEN").is_some() {
+        let config = crate::connectors::mattermost::MattermostConfig::from_env();
+        tracing::info!("Starting Mattermost monitor for {}", config.server_url);
+        tokio::spawn(crate::connectors::mattermost_monitor::monitor(
+            state, config,
+        ));
+    }
+}
+
 /// Start the gateway server
 pub async fn start_gateway(opts: GatewayServerOptions) -> anyhow::Result<GatewayServer> {
     let cfg = crate::config_io::load_config().ok();
@@ -40,7 +116,29 @@ pub async fn start_gateway(opts: GatewayServerOptions) -> anyhow::Result<Gateway
 
     let port = opts.port.unwrap_or(18789);
     let bind_host = opts.bind_host.unwrap_or_else(|| "127.0.0.1".to_string());
-    let server = GatewayServer::new(port, bind_host);
+    let queue_settings = crate::gateway::session_queue::QueueSettings::from_config(
+        cfg.as_ref()
+            .and_then(|c| c.messages.as_ref())
+            .and_then(|m| m.queue.as_ref()),
+    );
+    let auth =
+        crate::gateway::auth::AuthManager::new(crate::gateway::auth::ResolvedAuth::from_config(
+            cfg.as_ref()
+                .and_then(|c| c.gateway.as_ref())
+                .and_then(|g| g.auth.as_ref()),
+        ))
+        .with_token_store(TokenStore::open_default());
+    if auth.mode() == crate::gateway::auth::AuthMode::None
+        && !crate::gateway::auth::is_loopback_address(&bind_host)
+    {
+        tracing::warn!(
+            "Gateway bound to {} without authentication; set gateway.auth.token",
+            bind_host
+        );
+    }
+    let server = GatewayServer::new(port, bind_host)
+        .with_queue_settings(queue_settings)
+        .with_auth(auth);
 
     // Start heartbeat runner
     let heartbeat = crate::gateway::heartbeat::start_heartbeat_runner(
@@ -1,24 +1,39 @@
 //! Monitor manager — manages connector monitors for inbound messages
+//!
+//! WhatsApp monitors hand messages to a `MessageHandler`; Matrix and Signal
+//! monitors answer through the gateway's agents and report a `ChannelHealth`.
 
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 
 use crate::channels::{MessageHandler, MonitorOptions};
 use crate::connectors;
+use crate::connectors::matrix_monitor::MatrixAccount;
+use crate::connectors::signal_monitor::SignalAccount;
+use crate::gateway::server::GatewayServer;
+use crate::gateway::types::{ChannelHealth, ChannelStatus};
+use crate::OPENKRAB_CONFIG::ChannelsConfig;
+
+/// Consecutive failures after which a degraded monitor counts as unhealthy.
+const UNHEALTHY_AFTER_ERRORS: u32 = 3;
 
 /// Monitor manager for all connector monitors
+#[derive(Default)]
 pub struct MonitorManager {
     monitors: HashMap<String, Box<dyn MonitorHandle>>,
-    message_handler: Arc<dyn MessageHandler>,
+    /// Receives WhatsApp messages; monitors that answer through the gateway
+    /// don't need one.
+    message_handler: Option<Arc<dyn MessageHandler>>,
 }
 
 impl MonitorManager {
     pub fn new(message_handler: Arc<dyn MessageHandler>) -> Self {
         Self {
             monitors: HashMap::new(),
-            message_handler,
+            message_handler: Some(message_handler),
         }
     }
 
@@ -36,9 +51,13 @@ impl MonitorManager {
 
         match connector {
             "whatsapp" => {
+                let message_handler = self
+                    .message_handler
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("WhatsApp monitor needs a message handler"))?;
                 let monitor_result = connectors::whatsapp_monitor::monitor_whatsapp_provider(
                     options,
-                    self.message_handler.clone(),
+                    message_handler,
                 )
                 .await?;
 
@@ -59,6 +78,78 @@ impl MonitorManager {
         Ok(())
     }
 
+    /// Start a monitor for every enabled Matrix and Signal account in
+    /// `channels`. Accounts that fail to start are logged and skipped.
+    pub fn start_channels(&mut self, gateway: Arc<GatewayServer>, channels: &ChannelsConfig) {
+        for (account_id, account) in &channels.matrix {
+            if !account.enabled {
+                continue;
+            }
+            let started = MatrixAccount::from_config(account_id, account)
+                .and_then(|account| self.start_matrix(gateway.clone(), account));
+            if let Err(e) = started {
+                tracing::warn!("[matrix] account {} not started: {}", account_id, e);
+            }
+        }
+        for (account_id, account) in &channels.signal {
+            if !account.enabled {
+                continue;
+            }
+            let started = SignalAccount::from_config(account_id, account)
+                .and_then(|account| self.start_signal(gateway.clone(), account));
+            if let Err(e) = started {
+                tracing::warn!("[signal] account {} not started: {}", account_id, e);
+            }
+        }
+    }
+
+    /// Start a Matrix monitor that answers through `gateway`.
+    pub fn start_matrix(
+        &mut self,
+        gateway: Arc<GatewayServer>,
+        account: MatrixAccount,
+    ) -> Result<()> {
+        let key = format!("matrix:{}", account.account_id);
+        self.start_channel(key, |health, stop_rx| {
+            connectors::matrix_monitor::spawn_matrix_monitor(gateway, account, health, stop_rx)
+        })
+    }
+
+    /// Start a Signal monitor that answers through `gateway`.
+    pub fn start_signal(
+        &mut self,
+        gateway: Arc<GatewayServer>,
+        account: SignalAccount,
+    ) -> Result<()> {
+        let key = format!("signal:{}", account.account_id);
+        self.start_channel(key, |health, stop_rx| {
+            connectors::signal_monitor::spawn_signal_monitor(gateway, account, health, stop_rx)
+        })
+    }
+
+    fn start_channel(
+        &mut self,
+        key: String,
+        spawn: impl FnOnce(HealthReporter, watch::Receiver<bool>) -> tokio::task::JoinHandle<()>,
+    ) -> Result<()> {
+        if self.monitors.contains_key(&key) {
+            anyhow::bail!("{} is already running", key);
+        }
+        let health = HealthReporter::new(&key);
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let handle = spawn(health.clone(), stop_rx);
+        tracing::info!("Started {} monitor", key);
+        self.monitors.insert(
+            key,
+            Box::new(ChannelMonitorHandle {
+                stop_tx,
+                handle: Mutex::new(Some(handle)),
+                health,
+            }),
+        );
+        Ok(())
+    }
+
     /// Stop monitoring for a connector
     pub async fn stop_monitor(
         &mut self,
@@ -82,6 +173,70 @@ impl MonitorManager {
 
         status
     }
+
+    /// Health of the monitors that report one, by channel key.
+    pub fn health(&self) -> Vec<ChannelHealth> {
+        let mut health: Vec<ChannelHealth> =
+            self.monitors.values().filter_map(|m| m.health()).collect();
+        health.sort_by(|a, b| a.channel.cmp(&b.channel));
+        health
+    }
+}
+
+// ─── Channel health ──────────────────────────────────────────────────────────
+
+/// Shared `ChannelHealth` of one monitor, updated by its loop.
+#[derive(Clone)]
+pub struct HealthReporter {
+    inner: Arc<Mutex<(ChannelHealth, u32)>>,
+}
+
+impl HealthReporter {
+    pub fn new(channel: &str) -> Self {
+        let health = ChannelHealth {
+            channel: channel.to_string(),
+            status: ChannelStatus::Unknown,
+            last_check: chrono::Utc::now().timestamp(),
+            error_message: None,
+            latency_ms: None,
+        };
+        Self {
+            inner: Arc::new(Mutex::new((health, 0))),
+        }
+    }
+
+    /// The connection works; `latency` is the last round trip, if measured.
+    pub fn healthy(&self, latency: Option<Duration>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (health, errors) = &mut *inner;
+        *errors = 0;
+        health.status = ChannelStatus::Healthy;
+        health.last_check = chrono::Utc::now().timestamp();
+        health.error_message = None;
+        health.latency_ms = latency.map(|l| l.as_millis() as u64);
+    }
+
+    /// The connection failed and will be retried.
+    pub fn failed(&self, error: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let (health, errors) = &mut *inner;
+        *errors += 1;
+        health.status = if *errors >= UNHEALTHY_AFTER_ERRORS {
+            ChannelStatus::Unhealthy
+        } else {
+            ChannelStatus::Degraded
+        };
+        health.last_check = chrono::Utc::now().timestamp();
+        health.error_message = Some(error.to_string());
+    }
+
+    pub fn snapshot(&self) -> ChannelHealth {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .0
+            .clone()
+    }
 }
 
 /// Trait for monitor handles
@@ -89,6 +244,36 @@ impl MonitorManager {
 pub trait MonitorHandle: Send + Sync {
     async fn stop(&self) -> Result<()>;
     fn status(&self) -> serde_json::Value;
+    fn health(&self) -> Option<ChannelHealth> {
+        None
+    }
+}
+
+/// Handle of a monitor that answers through the gateway (Matrix, Signal)
+pub struct ChannelMonitorHandle {
+    stop_tx: watch::Sender<bool>,
+    handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    health: HealthReporter,
+}
+
+#[async_trait::async_trait]
+impl MonitorHandle for ChannelMonitorHandle {
+    async fn stop(&self) -> Result<()> {
+        let _ = self.stop_tx.send(true);
+        let handle = self.handle.lock().unwrap().take();
+        if let Some(handle) = handle {
+            let _ = handle.await;
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> serde_json::Value {
+        serde_json::to_value(self.health.snapshot()).unwrap_or_default()
+    }
+
+    fn health(&self) -> Option<ChannelHealth> {
+        Some(self.health.snapshot())
+    }
 }
 
 /// WhatsApp monitor handle
@@ -162,4 +347,25 @@ mod tests {
         let result = manager.start_monitor("unsupported", None).await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn health_degrades_then_recovers() {
+        let health = HealthReporter::new("matrix:default");
+        assert_eq!(health.snapshot().status, ChannelStatus::Unknown);
+
+        health.failed("sync timed out");
+        assert_eq!(health.snapshot().status, ChannelStatus::Degraded);
+        for _ in 1..UNHEALTHY_AFTER_ERRORS {
+            health.failed("sync timed out");
+        }
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.status, ChannelStatus::Unhealthy);
+        assert_eq!(snapshot.error_message.as_deref(), Some("sync timed out"));
+
+        health.healthy(Some(Duration::from_millis(42)));
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.status, ChannelStatus::Healthy);
+        assert_eq!(snapshot.latency_ms, Some(42));
+        assert!(snapshot.error_message.is_none());
+    }
 }
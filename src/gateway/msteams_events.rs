@@ -0,0 +1,202 @@
+//! msteams_events — Bot Framework messaging endpoint (`POST /api/messages`).
+//!
+//! Requests are authenticated by the Bot Framework's signed bearer token
+//! rather than gateway tokens. Activities are acked as soon as the token
+//! checks out; messages are answered in the background with a proactive
+//! reply. The bot is configured from `MSTEAMS_APP_ID` and
+//! `MSTEAMS_APP_PASSWORD` (see `MsTeamsConfig`); without them the endpoint
+//! answers 404.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+
+use crate::connectors::msteams::MsTeamsConfig;
+use crate::connectors::msteams_bot::MsTeamsBot;
+use crate::gateway::server::GatewayServer;
+
+pub const MSTEAMS_MESSAGES_PATH: &str = "/api/messages";
+
+pub fn msteams_router() -> Router<Arc<GatewayServer>> {
+    Router::new().route(MSTEAMS_MESSAGES_PATH, post(msteams_messages))
+}
+
+/// The bot built from the environment on first use; it holds the JWKS and
+/// access-token caches.
+fn env_bot() -> Option<Arc<MsTeamsBot>> {
+    static BOT: OnceLock<Option<Arc<MsTeamsBot>>> = OnceLock::new();
+    BOT.get_or_init(|| {
+        let config = MsTeamsConfig::from_env();
+        config.validate_bot().ok()?;
+        match MsTeamsBot::new(&config) {
+            Ok(bot) => Some(Arc::new(bot)),
+            Err(e) => {
+                tracing::warn!("[msteams] bot not configured: {}", e);
+                None
+            }
+        }
+    })
+    .clone()
+}
+
+async fn msteams_messages(
+    State(server): State<Arc<GatewayServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    activity_response(server, env_bot(), &headers, &body).await
+}
+
+async fn activity_response(
+    server: Arc<GatewayServer>,
+    bot: Option<Arc<MsTeamsBot>>,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Response {
+    let Some(bot) = bot else {
+        return (StatusCode::NOT_FOUND, "Teams bot is not configured").into_response();
+    };
+    let Ok(activity) = serde_json::from_slice::<Value>(body) else {
+        return (StatusCode::BAD_REQUEST, "invalid JSON").into_response();
+    };
+    let authorization = headers.get("authorization").and_then(|v| v.to_str().ok());
+    if let Err(e) = bot.authenticate(authorization, &activity).await {
+        tracing::warn!("[msteams] rejected activity: {}", e);
+        return (StatusCode::UNAUTHORIZED, "invalid Bot Framework token").into_response();
+    }
+    if activity.get("type").and_then(|t| t.as_str()) == Some("message") {
+        tokio::spawn(bot.answer(server, activity));
+    }
+    StatusCode::OK.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::msteams_bot::test_support::{jwks, token};
+    use crate::connectors::msteams_bot::BOT_FRAMEWORK_ISSUER;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use axum::extract::Path;
+    use axum::routing::get;
+    use axum::Json;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    type Replies = Arc<Mutex<Vec<(String, String, Option<String>, Value)>>>;
+
+    async fn reply(
+        Path((conversation, activity)): Path<(String, String)>,
+        State(replies): State<Replies>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        let auth = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        replies
+            .lock()
+            .unwrap()
+            .push((conversation, activity, auth, body));
+        Json(json!({ "id": "reply-1" }))
+    }
+
+    #[tokio::test]
+    async fn validated_messages_get_proactive_replies() {
+        // One local server plays the JWKS host, token endpoint and connector.
+        let replies: Replies = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/keys", get(|| async { Json(jwks()) }))
+            .route(
+                "/token",
+                post(|| async { Json(json!({ "access_token": "bot-token", "expires_in": 3600 })) }),
+            )
+            .route(
+                "/v3/conversations/:conversation/activities/:activity",
+                post(reply),
+            )
+            .with_state(replies.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let bot = Arc::new(
+            MsTeamsBot::new(&MsTeamsConfig {
+                webhook_url: String::new(),
+                app_id: Some("app-id".to_string()),
+                app_password: Some("secret".to_string()),
+                tenant_id: None,
+                jwks_url: Some(format!("{}/keys", base)),
+                token_url: Some(format!("{}/token", base)),
+            })
+            .unwrap(),
+        );
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::default());
+        let server = Arc::new(server);
+
+        let activity = json!({
+            "type": "message",
+            "id": "act-1",
+            "serviceUrl": base,
+            "channelId": "msteams",
+            "from": { "id": "29:alice", "name": "Alice" },
+            "recipient": { "id": "28:bot", "name": "Krab" },
+            "conversation": { "id": "a:personal-chat" },
+            "text": "<at>Krab</at> ping"
+        });
+        let body = activity.to_string();
+        let now = chrono::Utc::now().timestamp();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            format!(
+                "Bearer {}",
+                token(json!({
+                    "iss": BOT_FRAMEWORK_ISSUER,
+                    "aud": "app-id",
+                    "exp": now + 600,
+                    "serviceurl": base,
+                }))
+            )
+            .parse()
+            .unwrap(),
+        );
+
+        let response = activity_response(
+            server.clone(),
+            Some(bot.clone()),
+            &HeaderMap::new(),
+            body.as_bytes(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = activity_response(server.clone(), None, &headers, body.as_bytes()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = activity_response(server, Some(bot), &headers, body.as_bytes()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for _ in 0..250 {
+            if !replies.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let replies = replies.lock().unwrap();
+        assert_eq!(replies.len(), 1);
+        let (conversation, activity_id, auth, body) = &replies[0];
+        assert_eq!(conversation, "a:personal-chat");
+        assert_eq!(activity_id, "act-1");
+        assert_eq!(auth.as_deref(), Some("Bearer bot-token"));
+        assert_eq!(body["text"], "re: ping");
+        assert_eq!(body["recipient"]["id"], "29:alice");
+    }
+}
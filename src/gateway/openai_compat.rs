@@ -0,0 +1,670 @@
+//! openai_compat — OpenAI-compatible HTTP API on the gateway.
+//!
+//! `POST /v1/chat/completions` (JSON or SSE) and `GET /v1/models`, so
+//! existing OpenAI SDK tooling can talk to openkrab agents with their memory
+//! and tools.
+//!
+//! - `model` selects the agent: `openkrab/<agent_id>`, a bare agent id, or
+//!   `openkrab` for the default agent.
+//! - The `x-openkrab-session` header (or the `user` field) selects a
+//!   persistent session, namespaced under the selected agent as
+//!   `agent:<id>:openai:<value>` so API clients can never reach another
+//!   channel's sessions. Only the trailing user messages of the request are
+//!   appended to it; the stored transcript is the history. Without either,
+//!   the request's messages are answered in a throwaway session.
+//!
+//! Authentication and token scopes are enforced by `gateway::middleware`.
+
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::agents::chat::{ChatMessage, ContentPart, ImageUrl, UserContent};
+use crate::agents::streaming::{create_stream_pair, StreamEvent, StreamHandler};
+use crate::agents::{Agent, RoutedAgent};
+use crate::gateway::server::{GatewayServer, SessionTurn};
+use crate::routing::resolve_route::ResolvedAgentRoute;
+use crate::routing::session_key::build_agent_main_session_key;
+use crate::sessions::{Session, TranscriptEntry};
+
+/// Header selecting the openkrab session for a request.
+pub const SESSION_HEADER: &str = "x-openkrab-session";
+/// Prefix of the model ids advertised by `/v1/models`.
+pub const MODEL_PREFIX: &str = "openkrab/";
+/// Model id that always resolves to the default agent.
+pub const DEFAULT_MODEL: &str = "openkrab";
+/// Channel recorded on sessions driven through this API.
+pub const OPENAI_CHANNEL: &str = "openai";
+
+pub fn openai_router() -> Router<Arc<GatewayServer>> {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
+}
+
+// ─── Request types ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatCompletionRequest {
+    #[serde(default)]
+    pub model: String,
+    pub messages: Vec<RequestMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StreamOptions {
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// Convert an OpenAI message to a transcript entry. Tool and function
+/// messages are dropped: openkrab runs its own tools server-side.
+fn to_transcript_entry(message: &RequestMessage) -> Option<TranscriptEntry> {
+    match message.role.as_str() {
+        "user" => Some(TranscriptEntry::from_message(&ChatMessage::User {
+            content: user_content(&message.content),
+        })),
+        "assistant" => Some(TranscriptEntry::assistant(content_text(&message.content))),
+        "system" | "developer" => Some(TranscriptEntry::system(content_text(&message.content))),
+        _ => None,
+    }
+}
+
+fn user_content(content: &Value) -> UserContent {
+    let Some(parts) = content.as_array() else {
+        return UserContent::Text(content_text(content));
+    };
+    let parts: Vec<ContentPart> = parts
+        .iter()
+        .filter_map(|part| match part["type"].as_str() {
+            Some("text") => Some(ContentPart::Text {
+                text: part["text"].as_str().unwrap_or_default().to_string(),
+            }),
+            Some("image_url") => {
+                let url = part["image_url"]["url"]
+                    .as_str()
+                    .or_else(|| part["image_url"].as_str())?;
+                Some(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: url.to_string(),
+                    },
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    UserContent::Parts(parts)
+}
+
+/// Text of a string or content-part array.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|p| p["type"] == "text")
+            .filter_map(|p| p["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The user messages after the last assistant message.
+fn trailing_user_messages(messages: &[RequestMessage]) -> &[RequestMessage] {
+    let start = messages
+        .iter()
+        .rposition(|m| m.role != "user")
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &messages[start..]
+}
+
+// ─── Errors ───────────────────────────────────────────────────────────────────
+
+/// Error in OpenAI's `{"error": {...}}` shape.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub kind: &'static str,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub retry_after_ms: Option<u64>,
+}
+
+impl ApiError {
+    pub(crate) fn new(status: StatusCode, kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind,
+            code: None,
+            message: message.into(),
+            retry_after_ms: None,
+        }
+    }
+
+    pub(crate) fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": {
+                "message": self.message,
+                "type": self.kind,
+                "code": self.code,
+            }
+        });
+        let mut response = (self.status, Json(body)).into_response();
+        if let Some(ms) = self.retry_after_ms {
+            let secs = ms.div_ceil(1000).to_string();
+            if let Ok(value) = HeaderValue::from_str(&secs) {
+                response.headers_mut().insert("retry-after", value);
+            }
+        }
+        response
+    }
+}
+
+// ─── Agent and session selection ──────────────────────────────────────────────
+
+fn resolve_agent(server: &GatewayServer, model: &str) -> Result<(String, Arc<Agent>), ApiError> {
+    let wanted = model.trim();
+    let wanted = wanted.strip_prefix(MODEL_PREFIX).unwrap_or(wanted);
+    let found = if wanted.is_empty() || wanted == DEFAULT_MODEL {
+        server.agents.default_agent()
+    } else {
+        server.agents.get(wanted).map(|agent| {
+            (
+                crate::routing::session_key::normalize_agent_id(Some(wanted)),
+                agent,
+            )
+        })
+    };
+    found.ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            format!("The model '{}' does not exist", model),
+        )
+        .with_code("model_not_found")
+    })
+}
+
+/// Persistent session key for a request, if it names one. The header or
+/// `user` value only ever names a session inside the selected agent's
+/// `openai` namespace.
+fn request_session_key(
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+    agent_id: &str,
+) -> Option<String> {
+    let name = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            request
+                .user
+                .as_deref()
+                .map(str::trim)
+                .filter(|user| !user.is_empty())
+        })?;
+    Some(build_agent_main_session_key(
+        agent_id,
+        Some(&format!("{}:{}", OPENAI_CHANNEL, name)),
+    ))
+}
+
+/// Run one agent turn, either on a stored session through the gateway's
+/// session turn path (persisted and serialized like every other channel) or
+/// on a throwaway one.
+async fn run_turn(
+    server: Arc<GatewayServer>,
+    (agent_id, agent): (String, Arc<Agent>),
+    session_key: Option<String>,
+    request: ChatCompletionRequest,
+    handler: Option<StreamHandler>,
+) -> anyhow::Result<SessionTurn> {
+    let Some(key) = session_key else {
+        let mut session = Session::new(format!("openai:{}", uuid::Uuid::new_v4().simple()));
+        for entry in request.messages.iter().filter_map(to_transcript_entry) {
+            session.append_transcript(entry);
+        }
+        let reply = agent.answer_session(&mut session, handler).await?;
+        return Ok(SessionTurn {
+            reply,
+            input_tokens: session.input_tokens as u64,
+            output_tokens: session.output_tokens as u64,
+        });
+    };
+
+    let routed = RoutedAgent {
+        route: ResolvedAgentRoute {
+            main_session_key: build_agent_main_session_key(&agent_id, None),
+            agent_id,
+            channel: OPENAI_CHANNEL.to_string(),
+            account_id: "default".to_string(),
+            session_key: key,
+            matched_by: "openai".to_string(),
+        },
+        agent,
+    };
+    let entries = trailing_user_messages(&request.messages)
+        .iter()
+        .filter_map(to_transcript_entry)
+        .collect();
+    server
+        .answer_entries_streaming(&routed, entries, handler)
+        .await
+}
+
+// ─── Handlers ─────────────────────────────────────────────────────────────────
+
+async fn list_models(State(server): State<Arc<GatewayServer>>) -> Result<Json<Value>, ApiError> {
+    let data: Vec<Value> = server
+        .agents
+        .iter()
+        .map(|(id, agent)| {
+            let root = agent.provider.model_id();
+            let entry = agent.catalog_entry();
+            json!({
+                "id": format!("{}{}", MODEL_PREFIX, id),
+                "object": "model",
+                "created": 0,
+                "owned_by": "openkrab",
+                "root": root,
+                "name": agent.identity.name,
+                "context_window": entry.as_ref().and_then(|e| e.context_window),
+                "input_capabilities": entry.map(|e| e.input_capabilities).unwrap_or_default(),
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+async fn chat_completions(
+    State(server): State<Arc<GatewayServer>>,
+    headers: HeaderMap,
+    payload: Result<Json<ChatCompletionRequest>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(request) = payload.map_err(|e| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            e.body_text(),
+        )
+    })?;
+    if request.messages.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "'messages' must contain at least one message",
+        ));
+    }
+    let agent = resolve_agent(&server, &request.model)?;
+    let session_key = request_session_key(&headers, &request, &agent.0);
+    let model = format!("{}{}", MODEL_PREFIX, agent.0);
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if request.stream {
+        return Ok(stream_completion(
+            server,
+            agent,
+            session_key,
+            request,
+            id,
+            model,
+            created,
+        ));
+    }
+
+    let turn = run_turn(server, agent, session_key, request, None)
+        .await
+        .map_err(|e| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                e.to_string(),
+            )
+        })?;
+    Ok(Json(json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": turn.reply },
+            "finish_reason": "stop",
+        }],
+        "usage": usage_json(&turn),
+    }))
+    .into_response())
+}
+
+fn usage_json(turn: &SessionTurn) -> Value {
+    json!({
+        "prompt_tokens": turn.input_tokens,
+        "completion_tokens": turn.output_tokens,
+        "total_tokens": turn.input_tokens + turn.output_tokens,
+    })
+}
+
+fn stream_completion(
+    server: Arc<GatewayServer>,
+    agent: (String, Arc<Agent>),
+    session_key: Option<String>,
+    request: ChatCompletionRequest,
+    id: String,
+    model: String,
+    created: i64,
+) -> Response {
+    let include_usage = request
+        .stream_options
+        .as_ref()
+        .map(|o| o.include_usage)
+        .unwrap_or(false);
+    let chunk = move |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+        })
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Value>();
+    tokio::spawn(async move {
+        let _ = tx.send(chunk(json!({ "role": "assistant", "content": "" }), None));
+
+        let (handler, mut events) = create_stream_pair(2000);
+        let forward = {
+            let tx = tx.clone();
+            let chunk = chunk.clone();
+            async move {
+                // Reply text since the last tool call, to find any unstreamed tail.
+                let mut streamed = String::new();
+                while let Some(event) = events.rx.recv().await {
+                    match event {
+                        StreamEvent::TextDelta { content, .. } => {
+                            streamed.push_str(&content);
+                            let _ = tx.send(chunk(json!({ "content": content }), None));
+                        }
+                        StreamEvent::ReasoningDelta { content } => {
+                            let _ = tx.send(chunk(json!({ "reasoning_content": content }), None));
+                        }
+                        StreamEvent::ToolCallStart { .. } => streamed.clear(),
+                        _ => {}
+                    }
+                }
+                streamed
+            }
+        };
+        let (result, streamed) = tokio::join!(
+            run_turn(server, agent, session_key, request, Some(handler)),
+            forward
+        );
+
+        match result {
+            Ok(turn) => {
+                // Non-streaming providers and usage footers arrive only here.
+                if let Some(tail) = turn.reply.strip_prefix(streamed.as_str()) {
+                    if !tail.is_empty() {
+                        let _ = tx.send(chunk(json!({ "content": tail }), None));
+                    }
+                } else {
+                    let _ = tx.send(chunk(json!({ "content": turn.reply }), None));
+                }
+                let _ = tx.send(chunk(json!({}), Some("stop")));
+                if include_usage {
+                    let mut usage_chunk = chunk(json!({}), None);
+                    usage_chunk["choices"] = json!([]);
+                    usage_chunk["usage"] = usage_json(&turn);
+                    let _ = tx.send(usage_chunk);
+                }
+            }
+            Err(e) => {
+                tracing::error!("OpenAI-compatible turn failed: {}", e);
+                let _ = tx.send(json!({
+                    "error": { "message": e.to_string(), "type": "server_error", "code": null }
+                }));
+            }
+        }
+    });
+
+    let stream = futures_util::stream::unfold(Some(rx), |state| async move {
+        let mut rx = state?;
+        match rx.recv().await {
+            Some(value) => Some((
+                Ok::<_, Infallible>(Event::default().data(value.to_string())),
+                Some(rx),
+            )),
+            None => Some((Ok(Event::default().data("[DONE]")), None)),
+        }
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+    use std::net::SocketAddr;
+
+    /// Serve the OpenAI routes on an ephemeral port; returns the base URL.
+    async fn test_app() -> (String, Arc<GatewayServer>) {
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents =
+            single_agent_pool(EchoProvider::default().streaming("re:").with_usage(5, 3));
+        let server = Arc::new(server);
+        let app = openai_router().with_state(server.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        (format!("http://{}", addr), server)
+    }
+
+    async fn post_json(base: &str, body: Value, session: Option<&str>) -> (StatusCode, String) {
+        let mut request = reqwest::Client::new()
+            .post(format!("{}/v1/chat/completions", base))
+            .json(&body);
+        if let Some(key) = session {
+            request = request.header(SESSION_HEADER, key);
+        }
+        let response = request.send().await.unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        (status, response.text().await.unwrap())
+    }
+
+    #[test]
+    fn trailing_user_messages_skip_history() {
+        let msg = |role: &str| RequestMessage {
+            role: role.to_string(),
+            content: json!("x"),
+        };
+        let messages = vec![
+            msg("system"),
+            msg("user"),
+            msg("assistant"),
+            msg("user"),
+            msg("user"),
+        ];
+        assert_eq!(trailing_user_messages(&messages).len(), 2);
+        assert_eq!(trailing_user_messages(&messages[..2]).len(), 1);
+    }
+
+    #[test]
+    fn user_content_parts_are_converted() {
+        let content = json!([
+            { "type": "text", "text": "what is this?" },
+            { "type": "image_url", "image_url": { "url": "data:image/png;base64,AAAA", "detail": "low" } }
+        ]);
+        let UserContent::Parts(parts) = user_content(&content) else {
+            panic!("expected parts");
+        };
+        assert_eq!(parts.len(), 2);
+        assert!(
+            matches!(&parts[1], ContentPart::ImageUrl { image_url } if image_url.url.starts_with("data:"))
+        );
+        assert_eq!(content_text(&content), "what is this?");
+    }
+
+    #[tokio::test]
+    async fn completion_returns_openai_shape() {
+        let (app, _) = test_app().await;
+        let body = json!({
+            "model": "openkrab",
+            "messages": [{ "role": "user", "content": "hi" }]
+        });
+        let (status, text) = post_json(&app, body, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json["object"], "chat.completion");
+        assert_eq!(json["model"], "openkrab/main");
+        assert_eq!(json["choices"][0]["message"]["content"], "re: hi");
+        assert_eq!(json["usage"]["total_tokens"], 8);
+    }
+
+    #[tokio::test]
+    async fn unknown_model_is_404() {
+        let (app, _) = test_app().await;
+        let body =
+            json!({ "model": "openkrab/nope", "messages": [{ "role": "user", "content": "hi" }] });
+        let (status, text) = post_json(&app, body, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(text.contains("model_not_found"));
+    }
+
+    #[tokio::test]
+    async fn session_header_persists_only_new_messages() {
+        let (app, server) = test_app().await;
+        let body = json!({
+            "model": "openkrab",
+            "messages": [
+                { "role": "user", "content": "old" },
+                { "role": "assistant", "content": "ignored" },
+                { "role": "user", "content": "new" }
+            ]
+        });
+        let (status, _) = post_json(&app, body, Some("ide")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let sessions = server.sessions.read().await;
+        let session = sessions.get("agent:main:openai:ide").unwrap();
+        let texts: Vec<&str> = session.transcript.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["new", "re: new"]);
+        assert_eq!(session.channel.as_deref(), Some(OPENAI_CHANNEL));
+    }
+
+    #[tokio::test]
+    async fn session_header_cannot_reach_other_channels() {
+        let (app, server) = test_app().await;
+        server
+            .sessions
+            .write()
+            .await
+            .get_or_create("agent:main:telegram:dm:42")
+            .append_transcript(TranscriptEntry::user("private"));
+
+        let body = json!({
+            "model": "openkrab",
+            "messages": [{ "role": "user", "content": "hi" }]
+        });
+        let (status, _) = post_json(&app, body, Some("agent:main:telegram:dm:42")).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let sessions = server.sessions.read().await;
+        assert_eq!(
+            sessions
+                .get("agent:main:telegram:dm:42")
+                .unwrap()
+                .transcript
+                .len(),
+            1
+        );
+        assert!(sessions
+            .get("agent:main:openai:agent:main:telegram:dm:42")
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn streaming_sends_sse_chunks_and_done() {
+        let (app, _) = test_app().await;
+        let body = json!({
+            "model": "openkrab",
+            "stream": true,
+            "stream_options": { "include_usage": true },
+            "messages": [{ "role": "user", "content": "stream me" }]
+        });
+        let (status, text) = post_json(&app, body, None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let chunks: Vec<&str> = text
+            .lines()
+            .filter_map(|l| l.strip_prefix("data: "))
+            .collect();
+        assert_eq!(chunks.last(), Some(&"[DONE]"));
+        let parsed: Vec<Value> = chunks[..chunks.len() - 1]
+            .iter()
+            .map(|c| serde_json::from_str(c).unwrap())
+            .collect();
+        let content: String = parsed
+            .iter()
+            .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+            .collect();
+        assert_eq!(content, "re: stream me");
+        assert!(parsed
+            .iter()
+            .any(|c| c["choices"][0]["finish_reason"] == "stop"));
+        assert_eq!(parsed.last().unwrap()["usage"]["completion_tokens"], 3);
+    }
+
+    #[tokio::test]
+    async fn models_lists_gateway_agents() {
+        let (app, _) = test_app().await;
+        let response = reqwest::get(format!("{}/v1/models", app)).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let json: Value = response.json().await.unwrap();
+        assert_eq!(json["object"], "list");
+        assert_eq!(json["data"][0]["id"], "openkrab/main");
+    }
+}
@@ -0,0 +1,276 @@
+//! scheduler — runs cron jobs inside the gateway.
+//!
+//! Each tick takes the due runs from the shared cron store, runs each job's
+//! task on its agent in a fresh `agent:<id>:cron:<job>:run:<run>` session,
+//! sends the reply to the job's delivery target (or `cron.delivery`), and
+//! records the run in the job's history. A job still running from an earlier
+//! tick is left alone until it finishes; whatever it missed meanwhile is
+//! handled by its catch-up policy.
+
+use chrono::Utc;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::agents::RoutedAgent;
+use crate::cron::{CronService, DueRun, JobRun};
+use crate::gateway::server::GatewayServer;
+use crate::routing::resolve_route::ResolvedAgentRoute;
+use crate::routing::session_key::{build_agent_main_session_key, normalize_agent_id};
+use crate::routing::DeliveryTarget;
+use crate::OPENKRAB_CONFIG::OpenKrabConfig;
+
+/// Channel name on routes of cron runs.
+pub const CRON_CHANNEL: &str = "cron";
+
+#[derive(Debug, Clone)]
+pub struct CronSettings {
+    pub store_path: PathBuf,
+    pub tick: Duration,
+    /// Target for jobs without their own.
+    pub delivery: Option<DeliveryTarget>,
+}
+
+impl CronSettings {
+    /// Settings from `cron`; `None` when the scheduler is disabled.
+    pub fn from_config(cfg: &OpenKrabConfig) -> Option<Self> {
+        let cron = cfg.cron.clone().unwrap_or_default();
+        if !cron.enabled {
+            return None;
+        }
+        Some(Self {
+            store_path: crate::cron::store_path(cfg),
+            tick: Duration::from_secs(cron.tick_secs.unwrap_or(1).max(1)),
+            delivery: cron.delivery,
+        })
+    }
+}
+
+/// Start the scheduler loop. It runs until the runtime shuts down.
+pub fn spawn_cron_scheduler(
+    server: Arc<GatewayServer>,
+    settings: CronSettings,
+) -> tokio::task::JoinHandle<()> {
+    tracing::info!(
+        "Cron scheduler running jobs from {}",
+        settings.store_path.display()
+    );
+    tokio::spawn(async move {
+        let scheduler = Scheduler {
+            server,
+            service: Arc::new(tokio::sync::Mutex::new(None)),
+            running: Arc::new(Mutex::new(HashSet::new())),
+            settings,
+        };
+        let mut ticker = tokio::time::interval(scheduler.settings.tick);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            scheduler.tick().await;
+        }
+    })
+}
+
+#[derive(Clone)]
+struct Scheduler {
+    server: Arc<GatewayServer>,
+    /// Opened lazily so a broken store file is retried on the next tick.
+    service: Arc<tokio::sync::Mutex<Option<CronService>>>,
+    /// Jobs with a run in flight.
+    running: Arc<Mutex<HashSet<String>>>,
+    settings: CronSettings,
+}
+
+impl Scheduler {
+    async fn tick(&self) {
+        let busy = self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let due = {
+            let mut service = self.service.lock().await;
+            if service.is_none() {
+                match CronService::new(&self.settings.store_path) {
+                    Ok(opened) => *service = Some(opened),
+                    Err(e) => {
+                        tracing::warn!("Cron store unreadable: {}", e);
+                        return;
+                    }
+                }
+            }
+            match service.as_mut().map(|s| s.take_due(Utc::now(), &busy)) {
+                Some(Ok(due)) => due,
+                Some(Err(e)) => {
+                    tracing::warn!("Cron tick failed: {}", e);
+                    return;
+                }
+                None => return,
+            }
+        };
+
+        // Runs of one job (several under `CatchUp::All`) go in order.
+        let mut by_job: BTreeMap<String, Vec<DueRun>> = BTreeMap::new();
+        for run in due {
+            by_job.entry(run.job.id.clone()).or_default().push(run);
+        }
+        for (job_id, runs) in by_job {
+            self.running
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(job_id.clone());
+            let scheduler = self.clone();
+            tokio::spawn(async move {
+                for due in runs {
+                    let run = scheduler.execute(&due).await;
+                    scheduler.record(&job_id, run).await;
+                }
+                scheduler
+                    .running
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&job_id);
+            });
+        }
+    }
+
+    async fn record(&self, job_id: &str, run: JobRun) {
+        let mut service = self.service.lock().await;
+        if let Some(Err(e)) = service.as_mut().map(|s| s.record_run(job_id, run)) {
+            tracing::warn!("Failed to record cron run of {}: {}", job_id, e);
+        }
+    }
+
+    /// Run one job and deliver its reply.
+    async fn execute(&self, due: &DueRun) -> JobRun {
+        let job = &due.job;
+        let mut run = JobRun::start(due.scheduled_for, due.catch_up);
+        let agents = &self.server.agents;
+        let agent = match job.agent_id.as_deref() {
+            Some(id) => agents
+                .get(id)
+                .map(|agent| (normalize_agent_id(Some(id)), agent)),
+            None => agents.default_agent(),
+        };
+        let Some((agent_id, agent)) = agent else {
+            let missing = job.agent_id.as_deref().unwrap_or("default");
+            return run.finish(Err(format!("agent '{}' is not loaded", missing)));
+        };
+
+        let session_key = crate::cron::run_session_key(&agent_id, &job.id, &run.id);
+        run.session_key = Some(session_key.clone());
+        let routed = RoutedAgent {
+            route: ResolvedAgentRoute {
+                main_session_key: build_agent_main_session_key(&agent_id, None),
+                agent_id,
+                channel: CRON_CHANNEL.to_string(),
+                account_id: "default".to_string(),
+                session_key: session_key.clone(),
+                matched_by: "cron".to_string(),
+            },
+            agent,
+        };
+        tracing::info!("Cron job {} running in {}", job.id, session_key);
+        let reply = self.server.answer_in_session(&routed, &job.task).await;
+        // Each run gets a throwaway session; the history keeps the reply.
+        self.server.sessions.write().await.remove(&session_key);
+
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!("Cron job {} failed: {}", job.id, e);
+                return run.finish(Err(e.to_string()));
+            }
+        };
+        let target = job.delivery.as_ref().or(self.settings.delivery.as_ref());
+        let delivery_error = match target {
+            Some(target) => deliver(target, &reply).await.err(),
+            None => None,
+        };
+        let mut run = run.finish(Ok(reply));
+        if let Some(e) = delivery_error {
+            tracing::warn!("Cron job {} delivery failed: {}", job.id, e);
+            run.error = Some(format!("delivery failed: {}", e));
+        }
+        run
+    }
+}
+
+async fn deliver(target: &DeliveryTarget, text: &str) -> anyhow::Result<()> {
+    crate::commands::message_send_command(crate::commands::MessageSendOptions {
+        channel: target.connector.clone(),
+        to: target.to.clone(),
+        text: text.to_string(),
+        reply_to: target.thread_id.clone(),
+        silent: false,
+    })
+    .await
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::{CronJob, JobFrequency, RunStatus};
+    use crate::providers::test_support::{single_agent_pool, EchoProvider};
+
+    #[tokio::test]
+    async fn due_jobs_run_on_their_agent_and_record_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let store_path = dir.path().join("jobs.json");
+        let mut service = CronService::new(&store_path).unwrap();
+        service
+            .add_job(CronJob::new(
+                "check",
+                "Check",
+                "status report",
+                JobFrequency::Interval { secs: 3600 },
+            ))
+            .unwrap();
+        service
+            .add_job(
+                CronJob::new("lost", "Lost", "x", JobFrequency::Interval { secs: 3600 })
+                    .with_agent("nobody"),
+            )
+            .unwrap();
+
+        let mut server = GatewayServer::new(0, "127.0.0.1".to_string());
+        server.agents = single_agent_pool(EchoProvider::fixed("all systems nominal"));
+        let scheduler = Scheduler {
+            server: Arc::new(server),
+            service: Arc::new(tokio::sync::Mutex::new(None)),
+            running: Arc::new(Mutex::new(HashSet::new())),
+            settings: CronSettings {
+                store_path: store_path.clone(),
+                tick: Duration::from_secs(1),
+                delivery: None,
+            },
+        };
+        scheduler.tick().await;
+        for _ in 0..100 {
+            if scheduler.running.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let service = CronService::new(&store_path).unwrap();
+        let run = &service.get_job("check").unwrap().history[0];
+        assert_eq!(run.status, RunStatus::Ok);
+        assert_eq!(run.output.as_deref(), Some("all systems nominal"));
+        let key = run.session_key.as_deref().unwrap();
+        assert!(key.starts_with("agent:main:cron:check:run:"));
+        assert!(crate::sessions::session_key_utils::is_cron_run_session_key(
+            Some(key)
+        ));
+
+        let lost = &service.get_job("lost").unwrap().history[0];
+        assert_eq!(lost.status, RunStatus::Error);
+        assert!(lost.error.as_deref().unwrap().contains("nobody"));
+
+        // Nothing is due again within the hour.
+        scheduler.tick().await;
+        assert!(scheduler.running.lock().unwrap().is_empty());
+    }
+}
@@ -3,7 +3,7 @@ use axum::{
     extract::{ws::Message, ws::WebSocketUpgrade, State},
     response::Response,
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
@@ -12,19 +12,28 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 
+use crate::gateway::middleware::AuthPrincipal;
+use crate::gateway::session_queue::{batch_connections, QueuedMessage};
+use crate::gateway::tokens::TokenScope;
 use crate::gateway::types::*;
 
 pub type ClientId = String;
 pub type ConnectionId = u64;
 
+/// Channel name WebSocket chat messages are routed under.
+const WEBCHAT_CHANNEL: &str = "webchat";
+
+/// Block size for `chat.event` block flushes.
+const STREAM_BLOCK_FLUSH_THRESHOLD: usize = 2000;
+
 #[derive(Clone)]
 pub struct GatewayServer {
     pub port: u16,
     pub bind_host: String,
     pub clients: Arc<RwLock<HashMap<ConnectionId, ClientConnection>>>,
     pub next_connection_id: Arc<RwLock<ConnectionId>>,
-    // Agent for processing messages
-    pub agent: Option<Arc<crate::agents::Agent>>,
+    /// Agents keyed by id; bindings pick one per inbound message
+    pub agents: crate::agents::AgentPool,
     pub memory: Option<Arc<crate::memory::MemoryManager>>,
     /// Thread-safe session registry
     pub sessions: Arc<RwLock<crate::sessions::SessionRegistry>>,
@@ -34,6 +43,15 @@ pub struct GatewayServer {
     pub config_reloader: Arc<RwLock<Option<crate::gateway::config_reload::ConfigReloaderHandle>>>,
     /// ACP runtime (HTTP endpoint)
     pub acp_runtime: Arc<crate::acp::AcpRuntime>,
+    /// Per-session lanes for chat messages
+    pub session_queue: Arc<crate::gateway::session_queue::SessionQueue>,
+    /// Authentication for every route except `/health`
+    pub auth: Arc<crate::gateway::auth::AuthManager>,
+    /// Matrix and Signal monitors and their channel health
+    pub monitors: Arc<RwLock<crate::gateway::monitor_manager::MonitorManager>>,
+    /// Answers `/slack/events`; built on the first event and kept so event
+    /// ids are remembered across requests
+    pub slack_events: Arc<std::sync::OnceLock<crate::connectors::slack_monitor::SlackMonitor>>,
 }
 
 impl std::fmt::Debug for GatewayServer {
@@ -42,7 +60,7 @@ impl std::fmt::Debug for GatewayServer {
             .field("port", &self.port)
             .field("bind_host", &self.bind_host)
             .field("clients", &"<async RwLock>")
-            .field("agent", &self.agent.is_some())
+            .field("agents", &self.agents.len())
             .field("memory", &self.memory.is_some())
             .finish()
     }
@@ -57,6 +75,14 @@ pub struct ClientConnection {
     pub tx: tokio::sync::mpsc::UnboundedSender<Message>,
 }
 
+/// Reply to one session turn and the tokens it used.
+#[derive(Debug, Clone)]
+pub struct SessionTurn {
+    pub reply: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
 #[derive(Debug)]
 pub struct GatewayServerOptions {
     pub port: Option<u16>,
@@ -81,15 +107,33 @@ impl GatewayServer {
             bind_host,
             clients: Arc::new(RwLock::new(HashMap::new())),
             next_connection_id: Arc::new(RwLock::new(1)),
-            agent: None,
+            agents: crate::agents::AgentPool::default(),
             memory: None,
             sessions: Arc::new(RwLock::new(crate::sessions::SessionRegistry::new())),
             heartbeat_runner: Arc::new(RwLock::new(None)),
             config_reloader: Arc::new(RwLock::new(None)),
             acp_runtime: Arc::new(crate::acp::AcpRuntime::default()),
+            session_queue: Arc::new(crate::gateway::session_queue::SessionQueue::default()),
+            auth: Arc::new(crate::gateway::auth::AuthManager::default()),
+            monitors: Arc::new(RwLock::new(Default::default())),
+            slack_events: Arc::new(std::sync::OnceLock::new()),
         }
     }
 
+    pub fn with_auth(mut self, auth: crate::gateway::auth::AuthManager) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    /// Replace the burst policy for chat messages (collect/interrupt/queue).
+    pub fn with_queue_settings(
+        mut self,
+        settings: crate::gateway::session_queue::QueueSettings,
+    ) -> Self {
+        self.session_queue = Arc::new(crate::gateway::session_queue::SessionQueue::new(settings));
+        self
+    }
+
     pub async fn broadcast(
         &self,
         message: GatewayMessage,
@@ -141,6 +185,95 @@ impl GatewayServer {
         Ok(())
     }
 
+    /// Answer `text` with a routed agent in the route's session. Turns on
+    /// the same session are serialized with the WebSocket lanes.
+    ///
+    /// Sessions are loaded from and saved to the agent's memory store, so
+    /// conversations survive gateway restarts. One-off cron run sessions
+    /// are not persisted.
+    pub async fn answer_in_session(
+        &self,
+        routed: &crate::agents::RoutedAgent,
+        text: &str,
+    ) -> anyhow::Result<String> {
+        self.answer_entry_in_session(routed, crate::sessions::TranscriptEntry::user(text))
+            .await
+    }
+
+    /// Like [`Self::answer_in_session`] for a prepared user entry, e.g. one
+    /// with image parts.
+    pub async fn answer_entry_in_session(
+        &self,
+        routed: &crate::agents::RoutedAgent,
+        entry: crate::sessions::TranscriptEntry,
+    ) -> anyhow::Result<String> {
+        self.answer_entry_streaming(routed, entry, None).await
+    }
+
+    /// Like [`Self::answer_entry_in_session`], sending stream events to
+    /// `stream` while the reply is generated.
+    pub async fn answer_entry_streaming(
+        &self,
+        routed: &crate::agents::RoutedAgent,
+        entry: crate::sessions::TranscriptEntry,
+        stream: Option<crate::agents::streaming::StreamHandler>,
+    ) -> anyhow::Result<String> {
+        self.answer_entries_streaming(routed, vec![entry], stream)
+            .await
+            .map(|turn| turn.reply)
+    }
+
+    /// Append `entries` to the route's session and answer them in one turn,
+    /// reporting the tokens the turn used.
+    pub async fn answer_entries_streaming(
+        &self,
+        routed: &crate::agents::RoutedAgent,
+        entries: Vec<crate::sessions::TranscriptEntry>,
+        stream: Option<crate::agents::streaming::StreamHandler>,
+    ) -> anyhow::Result<SessionTurn> {
+        let key = &routed.route.session_key;
+        let store = routed
+            .agent
+            .memory
+            .as_ref()
+            .filter(|_| !crate::sessions::session_key_utils::is_cron_run_session_key(Some(key)))
+            .map(|memory| &memory.store);
+        let turn_lock = self.session_queue.turn_lock(key);
+        let _turn = turn_lock.lock().await;
+        let mut session = {
+            let mut sessions = self.sessions.write().await;
+            if sessions.get(key).is_none() {
+                match store.map(|store| store.load_session(key)) {
+                    Some(Ok(Some(saved))) => sessions.insert(saved),
+                    Some(Err(e)) => tracing::warn!("Failed to load session {}: {}", key, e),
+                    _ => {}
+                }
+            }
+            let session = sessions.get_or_create(key);
+            if session.channel.is_none() {
+                session.channel = Some(routed.route.channel.clone());
+            }
+            session.last_channel = Some(routed.route.channel.clone());
+            for entry in entries {
+                session.append_transcript(entry);
+            }
+            session.clone()
+        };
+        let (input_before, output_before) = (session.input_tokens, session.output_tokens);
+        let reply = routed.agent.answer_session(&mut session, stream).await;
+        let input_tokens = session.input_tokens.saturating_sub(input_before) as u64;
+        let output_tokens = session.output_tokens.saturating_sub(output_before) as u64;
+        if let Some(Err(e)) = store.map(|store| store.save_session(&session)) {
+            tracing::warn!("Failed to save session {}: {}", key, e);
+        }
+        self.sessions.write().await.insert(session);
+        Ok(SessionTurn {
+            reply: reply?,
+            input_tokens,
+            output_tokens,
+        })
+    }
+
     pub async fn close(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         tracing::info!("Closing gateway server");
         // Close all client connections
@@ -154,14 +287,25 @@ async fn handle_websocket(
     ws: WebSocketUpgrade,
     State(server): State<Arc<GatewayServer>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(principal): Extension<AuthPrincipal>,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, server, addr))
+    ws.on_upgrade(move |socket| handle_socket(socket, server, addr, principal))
+}
+
+/// Scope a WebSocket message needs; `None` for messages anyone may send.
+fn message_scope(message: &GatewayMessage) -> Option<TokenScope> {
+    match message {
+        GatewayMessage::Chat { .. } | GatewayMessage::ChatCancel { .. } => Some(TokenScope::Chat),
+        GatewayMessage::Status { .. } => Some(TokenScope::Status),
+        _ => None,
+    }
 }
 
 async fn handle_socket(
     socket: axum::extract::ws::WebSocket,
     server: Arc<GatewayServer>,
     addr: SocketAddr,
+    principal: AuthPrincipal,
 ) {
     let connection_id = {
         let mut next_id = server.next_connection_id.write().await;
@@ -200,7 +344,12 @@ async fn handle_socket(
     let hello = GatewayMessage::Hello {
         client_id: format!("client-{}", connection_id),
         version: env!("CARGO_PKG_VERSION").to_string(),
-        capabilities: vec!["chat".to_string(), "status".to_string()],
+        capabilities: vec![
+            "chat".to_string(),
+            "chat.stream".to_string(),
+            "chat.cancel".to_string(),
+            "status".to_string(),
+        ],
     };
 
     if let Ok(json) = serde_json::to_string(&hello) {
@@ -223,7 +372,22 @@ async fn handle_socket(
                 tracing::debug!("Received message from {}: {}", connection_id, text);
 
                 // Parse incoming message
-                match serde_json::from_str::<GatewayMessage>(&text) {
+                let parsed = serde_json::from_str::<GatewayMessage>(&text);
+                if let Some(required) = parsed.as_ref().ok().and_then(message_scope) {
+                    if !principal.scope.allows(required) {
+                        let denied = GatewayMessage::Error {
+                            code: "forbidden".to_string(),
+                            message: format!(
+                                "Token scope '{}' does not allow '{}' messages",
+                                principal.scope.as_str(),
+                                required.as_str()
+                            ),
+                        };
+                        send_to_connection(&server, connection_id, &denied).await;
+                        continue;
+                    }
+                }
+                match parsed {
                     Ok(GatewayMessage::Chat {
                         session_key,
                         message,
@@ -238,66 +402,38 @@ async fn handle_socket(
                         payload.set("connection_id", connection_id as i64);
                         crate::hooks::emit(crate::hooks::events::MESSAGE_INBOUND, &payload);
 
-                        if let Some(agent) = &server.agent {
-                            // Get or create session
-                            let mut sessions_lock = server.sessions.write().await;
-                            let session = sessions_lock.get_or_create(&session_key);
-                            session.append_transcript(crate::sessions::TranscriptEntry::user(
-                                &message,
-                            ));
-
-                            // Drop lock before async call
-                            drop(sessions_lock);
-
-                            // Process message
-                            let mut sessions_lock = server.sessions.write().await;
-                            let reply = agent
-                                .answer_session(sessions_lock.get_or_create(&session_key), None)
-                                .await;
-                            drop(sessions_lock);
-
-                            match reply {
-                                Ok(text) => {
-                                    // Emit Outbound Hook
-                                    let mut out_payload = crate::hooks::HookPayload::new();
-                                    out_payload.set("session_key", session_key.clone());
-                                    out_payload.set("reply", text.clone());
-                                    crate::hooks::emit(
-                                        crate::hooks::events::MESSAGE_OUTBOUND,
-                                        &out_payload,
-                                    );
-
-                                    let response = GatewayMessage::Chat {
-                                        session_key: session_key.clone(),
-                                        message: text,
-                                        attachments: None,
-                                    };
-                                    if let Ok(json) = serde_json::to_string(&response) {
-                                        let tx_opt = {
-                                            let clients = server.clients.read().await;
-                                            clients.get(&connection_id).map(|c| c.tx.clone())
-                                        };
-                                        if let Some(tx) = tx_opt {
-                                            let _ = tx.send(Message::Text(json.into()));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Agent error: {}", e);
-                                    let error_msg = GatewayMessage::Error {
-                                        code: "agent_error".to_string(),
-                                        message: e.to_string(),
-                                    };
-                                    if let Ok(json) = serde_json::to_string(&error_msg) {
-                                        let tx_opt = {
-                                            let clients = server.clients.read().await;
-                                            clients.get(&connection_id).map(|c| c.tx.clone())
-                                        };
-                                        if let Some(tx) = tx_opt {
-                                            let _ = tx.send(Message::Text(json.into()));
-                                        }
-                                    }
+                        if !server.agents.is_empty() {
+                            let queued = QueuedMessage {
+                                text: message,
+                                connection_id,
+                            };
+                            if server.session_queue.enqueue(&session_key, queued) {
+                                tokio::spawn(run_session_lane(server.clone(), session_key));
+                            }
+                        }
+                    }
+                    Ok(GatewayMessage::ChatCancel { session_key }) => {
+                        let cancelled = GatewayMessage::Error {
+                            code: "cancelled".to_string(),
+                            message: format!("Turn cancelled for session {}", session_key),
+                        };
+                        match server.session_queue.cancel(&session_key) {
+                            Some(mut waiting) => {
+                                tracing::info!("Cancelled turn for session {}", session_key);
+                                if !waiting.contains(&connection_id) {
+                                    waiting.push(connection_id);
                                 }
+                                send_to_connections(&server, &waiting, &cancelled).await;
+                            }
+                            None => {
+                                let idle = GatewayMessage::Error {
+                                    code: "not_running".to_string(),
+                                    message: format!(
+                                        "No turn in flight for session {}",
+                                        session_key
+                                    ),
+                                };
+                                send_to_connection(&server, connection_id, &idle).await;
                             }
                         }
                     }
@@ -308,9 +444,11 @@ async fn handle_socket(
                             .map(|(_, s)| GatewaySessionRow::from(s))
                             .collect();
 
-                        let agents = if let Some(agent) = &server.agent {
-                            vec![GatewayAgentRow {
-                                id: agent.identity.name.clone().to_lowercase(),
+                        let agents: Vec<GatewayAgentRow> = server
+                            .agents
+                            .iter()
+                            .map(|(id, agent)| GatewayAgentRow {
+                                id: id.to_string(),
                                 name: Some(agent.identity.name.clone()),
                                 identity: Some(crate::gateway::types::AgentIdentity {
                                     name: Some(agent.identity.name.clone()),
@@ -319,10 +457,8 @@ async fn handle_socket(
                                     avatar: None,
                                     avatar_url: None,
                                 }),
-                            }]
-                        } else {
-                            vec![]
-                        };
+                            })
+                            .collect();
 
                         let response = GatewayMessage::Status { sessions, agents };
                         if let Ok(json) = serde_json::to_string(&response) {
@@ -362,6 +498,170 @@ async fn handle_socket(
     tracing::info!("WebSocket connection cleaned up: {}", connection_id);
 }
 
+/// Worker for one session lane: appends each batch to the transcript and
+/// runs the agent on it, streaming progress to every connection that sent
+/// part of the batch. The registry lock is only held to copy the session in
+/// and out, so other sessions are never blocked behind an LLM call.
+async fn run_session_lane(server: Arc<GatewayServer>, session_key: String) {
+    let queue = server.session_queue.clone();
+    let superseded = |waiting: Vec<ConnectionId>| {
+        let server = server.clone();
+        let interrupted = GatewayMessage::Error {
+            code: "interrupted".to_string(),
+            message: format!(
+                "Turn for session {} was superseded by a newer message",
+                session_key
+            ),
+        };
+        async move { send_to_connections(&server, &waiting, &interrupted).await }
+    };
+    let Some(agent) = lane_agent(&server, &session_key) else {
+        // Nothing can answer: drain the lane so it is released, and tell
+        // every sender instead of leaving them waiting for a reply.
+        tracing::warn!(
+            "No agent for session {}; rejecting queued messages",
+            session_key
+        );
+        queue
+            .drain(
+                session_key.clone(),
+                |batch| {
+                    let server = server.clone();
+                    async move {
+                        let error = GatewayMessage::Error {
+                            code: "no_agent".to_string(),
+                            message: "No agent is configured for this session".to_string(),
+                        };
+                        send_to_connections(&server, &batch_connections(&batch), &error).await;
+                    }
+                },
+                superseded,
+            )
+            .await;
+        return;
+    };
+    let run = |batch: Vec<QueuedMessage>| {
+        let server = server.clone();
+        let agent = agent.clone();
+        let session_key = session_key.clone();
+        async move {
+            let reply_to = batch_connections(&batch);
+            if reply_to.is_empty() {
+                return;
+            }
+            let turn_lock = server.session_queue.turn_lock(&session_key);
+            let _turn = turn_lock.lock().await;
+            let mut session = {
+                let mut sessions = server.sessions.write().await;
+                let session = sessions.get_or_create(&session_key);
+                for queued in &batch {
+                    session.append_transcript(crate::sessions::TranscriptEntry::user(&queued.text));
+                }
+                session.clone()
+            };
+
+            let (input_before, output_before) = (session.input_tokens, session.output_tokens);
+
+            let (handler, mut events) =
+                crate::agents::streaming::create_stream_pair(STREAM_BLOCK_FLUSH_THRESHOLD);
+            let forwarder = {
+                let server = server.clone();
+                let session_key = session_key.clone();
+                let reply_to = reply_to.clone();
+                tokio::spawn(async move {
+                    while let Some(event) = events.rx.recv().await {
+                        let message = GatewayMessage::ChatEvent {
+                            session_key: session_key.clone(),
+                            event,
+                        };
+                        send_to_connections(&server, &reply_to, &message).await;
+                    }
+                })
+            };
+
+            let reply = agent.answer_session(&mut session, Some(handler)).await;
+            // The handler is gone with the turn; let the forwarder drain
+            // so every event precedes the final reply.
+            let _ = forwarder.await;
+
+            let finish = GatewayMessage::ChatFinish {
+                session_key: session_key.clone(),
+                input_tokens: session.input_tokens.saturating_sub(input_before) as u64,
+                output_tokens: session.output_tokens.saturating_sub(output_before) as u64,
+            };
+            server.sessions.write().await.insert(session);
+
+            let response = match reply {
+                Ok(text) => {
+                    send_to_connections(&server, &reply_to, &finish).await;
+
+                    // Emit Outbound Hook
+                    let mut out_payload = crate::hooks::HookPayload::new();
+                    out_payload.set("session_key", session_key.clone());
+                    out_payload.set("reply", text.clone());
+                    crate::hooks::emit(crate::hooks::events::MESSAGE_OUTBOUND, &out_payload);
+
+                    GatewayMessage::Chat {
+                        session_key: session_key.clone(),
+                        message: text,
+                        attachments: None,
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Agent error: {}", e);
+                    GatewayMessage::Error {
+                        code: "agent_error".to_string(),
+                        message: e.to_string(),
+                    }
+                }
+            };
+            send_to_connections(&server, &reply_to, &response).await;
+        }
+    };
+    queue.drain(session_key.clone(), run, superseded).await;
+}
+
+/// Agent for a WebSocket session: the one named by an `agent:<id>:` key,
+/// otherwise whichever agent the `webchat` bindings select.
+fn lane_agent(server: &GatewayServer, session_key: &str) -> Option<Arc<crate::agents::Agent>> {
+    if let Some((_, agent)) = server.agents.for_session_key(session_key) {
+        return Some(agent);
+    }
+    server
+        .agents
+        .route(crate::agents::RouteQuery {
+            channel: WEBCHAT_CHANNEL,
+            ..Default::default()
+        })
+        .map(|routed| routed.agent)
+}
+
+async fn send_to_connections(
+    server: &GatewayServer,
+    connection_ids: &[ConnectionId],
+    message: &GatewayMessage,
+) {
+    for &connection_id in connection_ids {
+        send_to_connection(server, connection_id, message).await;
+    }
+}
+
+async fn send_to_connection(
+    server: &GatewayServer,
+    connection_id: ConnectionId,
+    message: &GatewayMessage,
+) {
+    if let Ok(json) = serde_json::to_string(message) {
+        let tx_opt = {
+            let clients = server.clients.read().await;
+            clients.get(&connection_id).map(|c| c.tx.clone())
+        };
+        if let Some(tx) = tx_opt {
+            let _ = tx.send(Message::Text(json));
+        }
+    }
+}
+
 async fn handle_acp(
     State(server): State<Arc<GatewayServer>>,
     Json(request): Json<crate::acp::AcpRequest>,
@@ -376,19 +676,7 @@ pub async fn start_gateway_server(
     let bind_host = opts.bind_host.unwrap_or_else(|| "127.0.0.1".to_string());
 
     let server = Arc::new(GatewayServer::new(port, bind_host.clone()));
-
-    let app = Router::new()
-        .route("/ws", get(handle_websocket))
-        .route(crate::acp::ACP_DEFAULT_PATH, post(handle_acp))
-        .route("/health", get(|| async { "OK" }))
-        .nest("/webrtc", crate::webrtc::webrtc_router())
-        .with_state(server.clone());
-
-    let app = if opts.enable_cors {
-        app.layer(CorsLayer::permissive())
-    } else {
-        app
-    };
+    let app = build_gateway_router(server.clone(), opts.enable_cors);
 
     let addr: SocketAddr = format!("{}:{}", bind_host, port).parse()?;
     tracing::info!("Starting gateway server on {}", addr);
@@ -407,6 +695,46 @@ pub async fn start_gateway_server(
     Ok((*server).clone())
 }
 
+/// All gateway routes: WebSocket, ACP, OpenAI-compatible API, Slack
+/// events, Teams activities, WebRTC. Everything except `/health` and the
+/// Slack- and Bot Framework-signed requests goes through the auth middleware.
+pub fn build_gateway_router(server: Arc<GatewayServer>, enable_cors: bool) -> Router {
+    let app = Router::new()
+        .route("/ws", get(handle_websocket))
+        .route(crate::acp::ACP_DEFAULT_PATH, post(handle_acp))
+        .route("/health", get(|| async { "OK" }))
+        .merge(crate::gateway::openai_compat::openai_router())
+        .merge(crate::gateway::slack_events::slack_events_router())
+        .merge(crate::gateway::msteams_events::msteams_router())
+        .nest("/webrtc", crate::webrtc::webrtc_router())
+        .layer(axum::middleware::from_fn_with_state(
+            server.clone(),
+            crate::gateway::middleware::require_auth,
+        ))
+        .with_state(server);
+
+    if enable_cors {
+        app.layer(CorsLayer::permissive())
+    } else {
+        app
+    }
+}
+
+/// Serve a configured gateway (agent attached) until the listener fails.
+pub async fn serve_gateway(server: GatewayServer, enable_cors: bool) -> anyhow::Result<()> {
+    let addr: SocketAddr = format!("{}:{}", server.bind_host, server.port).parse()?;
+    let app = build_gateway_router(Arc::new(server), enable_cors);
+    tracing::info!("Starting gateway server on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -429,4 +757,80 @@ mod tests {
         let result = server.broadcast(message).await;
         assert!(result.is_ok());
     }
+
+    async fn connect(
+        server: &GatewayServer,
+        id: ConnectionId,
+    ) -> tokio::sync::mpsc::UnboundedReceiver<Message> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        server.clients.write().await.insert(
+            id,
+            ClientConnection {
+                id,
+                client_id: None,
+                addr: "127.0.0.1:1".parse().unwrap(),
+                connected_at: chrono::Utc::now(),
+                tx,
+            },
+        );
+        rx
+    }
+
+    fn frames(rx: &mut tokio::sync::mpsc::UnboundedReceiver<Message>) -> Vec<String> {
+        let mut frames = Vec::new();
+        while let Ok(Message::Text(json)) = rx.try_recv() {
+            frames.push(json);
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn lane_without_agent_is_released_with_an_error() {
+        let server = Arc::new(GatewayServer::new(18789, "127.0.0.1".to_string()));
+        let mut rx = connect(&server, 7).await;
+        let queued = QueuedMessage {
+            text: "hello".to_string(),
+            connection_id: 7,
+        };
+        assert!(server.session_queue.enqueue("webchat:u1", queued));
+
+        run_session_lane(server.clone(), "webchat:u1".to_string()).await;
+
+        assert!(!server.session_queue.is_active("webchat:u1"));
+        let Some(Message::Text(json)) = rx.recv().await else {
+            panic!("expected a text frame");
+        };
+        assert!(json.contains("no_agent"), "{}", json);
+    }
+
+    #[tokio::test]
+    async fn collected_batch_replies_to_every_sender() {
+        let mut server = GatewayServer::new(18789, "127.0.0.1".to_string());
+        server.agents = crate::providers::test_support::single_agent_pool(
+            crate::providers::test_support::EchoProvider::fixed("hello both"),
+        );
+        let server = Arc::new(server);
+        let mut first = connect(&server, 7).await;
+        let mut second = connect(&server, 8).await;
+        for (text, connection_id) in [("hi", 7), ("me too", 8)] {
+            let queued = QueuedMessage {
+                text: text.to_string(),
+                connection_id,
+            };
+            server.session_queue.enqueue("webchat:u1", queued);
+        }
+
+        run_session_lane(server.clone(), "webchat:u1".to_string()).await;
+
+        for rx in [&mut first, &mut second] {
+            let frames = frames(rx);
+            assert!(
+                frames
+                    .iter()
+                    .any(|f| f.contains("\"chat\"") && f.contains("hello both")),
+                "{:?}",
+                frames
+            );
+        }
+    }
 }
@@ -0,0 +1,468 @@
+//! session_queue — Per-session lanes for gateway chat.
+//!
+//! Each session key gets its own lane: at most one agent run is in flight
+//! per session, while different sessions run in parallel. Messages that
+//! arrive while a run is in flight are handled according to `QueueMode`:
+//!
+//! - `collect`   — buffer them and answer them together in one follow-up run
+//! - `interrupt` — cancel the in-flight run and answer everything pending
+//! - `queue`     — answer them one by one, in arrival order
+
+use crate::gateway::server::ConnectionId;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// ─── Settings ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueMode {
+    #[default]
+    Collect,
+    Interrupt,
+    Queue,
+}
+
+impl QueueMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "collect" => Some(Self::Collect),
+            "interrupt" => Some(Self::Interrupt),
+            "queue" | "followup" => Some(Self::Queue),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Collect => "collect",
+            Self::Interrupt => "interrupt",
+            Self::Queue => "queue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueueSettings {
+    pub mode: QueueMode,
+    /// How long a lane waits for more messages before starting a run.
+    pub debounce: Duration,
+}
+
+impl QueueSettings {
+    pub fn from_config(cfg: Option<&crate::OPENKRAB_CONFIG::MessageQueueConfig>) -> Self {
+        let Some(cfg) = cfg else {
+            return Self::default();
+        };
+        let mode = match cfg.mode.as_deref() {
+            Some(raw) => QueueMode::parse(raw).unwrap_or_else(|| {
+                tracing::warn!("Unknown messages.queue.mode '{}', using collect", raw);
+                QueueMode::Collect
+            }),
+            None => QueueMode::Collect,
+        };
+        Self {
+            mode,
+            debounce: Duration::from_millis(cfg.debounce_ms.unwrap_or(0)),
+        }
+    }
+}
+
+// ─── Lanes ────────────────────────────────────────────────────────────────────
+
+/// A chat message waiting for its session's lane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedMessage {
+    pub text: String,
+    /// Connection that sent it; a batch's reply goes to every connection
+    /// with a message in it.
+    pub connection_id: ConnectionId,
+}
+
+/// Distinct senders of a batch, in arrival order.
+pub fn batch_connections(batch: &[QueuedMessage]) -> Vec<ConnectionId> {
+    let mut connections = Vec::new();
+    for message in batch {
+        if !connections.contains(&message.connection_id) {
+            connections.push(message.connection_id);
+        }
+    }
+    connections
+}
+
+#[derive(Default)]
+struct Lane {
+    pending: Vec<QueuedMessage>,
+    /// A worker task owns this lane.
+    active: bool,
+    /// The run in flight, if any.
+    in_flight: Option<InFlight>,
+}
+
+struct InFlight {
+    /// Cancellation signal. A fresh `Notify` per run, so a late interrupt
+    /// can never cancel the next run.
+    signal: Arc<Notify>,
+    waiting: Vec<ConnectionId>,
+    /// Stopped by `chat.cancel`, which answers the waiting connections
+    /// itself.
+    cancelled: bool,
+}
+
+/// Outcome of one run in a lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Completed,
+    Interrupted,
+}
+
+#[derive(Default)]
+pub struct SessionQueue {
+    settings: QueueSettings,
+    lanes: Mutex<HashMap<String, Lane>>,
+    /// Held for the duration of a turn, by lanes and by other entry points
+    /// (HTTP) that run turns on the same session.
+    turn_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl SessionQueue {
+    pub fn new(settings: QueueSettings) -> Self {
+        Self {
+            settings,
+            lanes: Mutex::new(HashMap::new()),
+            turn_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn settings(&self) -> &QueueSettings {
+        &self.settings
+    }
+
+    /// Add a message to its session lane. Returns `true` when the lane was
+    /// idle and the caller must start a worker with [`SessionQueue::drain`].
+    pub fn enqueue(&self, session_key: &str, message: QueuedMessage) -> bool {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let lane = lanes.entry(session_key.to_string()).or_default();
+        lane.pending.push(message);
+        if self.settings.mode == QueueMode::Interrupt {
+            if let Some(run) = &lane.in_flight {
+                run.signal.notify_one();
+            }
+        }
+        if lane.active {
+            false
+        } else {
+            lane.active = true;
+            true
+        }
+    }
+
+    /// Lock serializing turns on one session across entry points.
+    pub fn turn_lock(&self, session_key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.turn_locks.lock().unwrap_or_else(|e| e.into_inner());
+        // Drop locks nobody holds or waits on.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(session_key.to_string()).or_default().clone()
+    }
+
+    /// Abort the run in flight for a session (`chat.cancel`). Returns the
+    /// connections that were waiting for its reply, or `None` if the session
+    /// was idle. Pending messages stay queued.
+    pub fn cancel(&self, session_key: &str) -> Option<Vec<ConnectionId>> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let run = lanes.get_mut(session_key)?.in_flight.as_mut()?;
+        run.cancelled = true;
+        run.signal.notify_one();
+        Some(run.waiting.clone())
+    }
+
+    /// Number of messages waiting in a session lane.
+    pub fn pending(&self, session_key: &str) -> usize {
+        let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.get(session_key).map(|l| l.pending.len()).unwrap_or(0)
+    }
+
+    /// Whether a worker currently owns the session lane.
+    pub fn is_active(&self, session_key: &str) -> bool {
+        let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.get(session_key).map(|l| l.active).unwrap_or(false)
+    }
+
+    /// Take the next batch, or release the lane when nothing is pending.
+    fn next_batch(&self, session_key: &str) -> Option<(Vec<QueuedMessage>, Arc<Notify>)> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        let lane = lanes.get_mut(session_key)?;
+        if lane.pending.is_empty() {
+            lanes.remove(session_key);
+            return None;
+        }
+        let batch = match self.settings.mode {
+            QueueMode::Queue => vec![lane.pending.remove(0)],
+            QueueMode::Collect | QueueMode::Interrupt => std::mem::take(&mut lane.pending),
+        };
+        let signal = Arc::new(Notify::new());
+        lane.in_flight = Some(InFlight {
+            signal: signal.clone(),
+            waiting: batch_connections(&batch),
+            cancelled: false,
+        });
+        Some((batch, signal))
+    }
+
+    fn finish_run(&self, session_key: &str) -> Option<InFlight> {
+        let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.get_mut(session_key)?.in_flight.take()
+    }
+
+    /// Worker loop for one session lane: runs batches until the lane is
+    /// empty, then releases it. Only one `drain` runs per lane at a time.
+    ///
+    /// When a newer message interrupts a run, `superseded` is called with
+    /// the connections that were waiting for it before the next run starts.
+    pub async fn drain<F, Fut, S, SFut>(
+        self: Arc<Self>,
+        session_key: String,
+        mut run: F,
+        mut superseded: S,
+    ) where
+        F: FnMut(Vec<QueuedMessage>) -> Fut,
+        Fut: Future<Output = ()>,
+        S: FnMut(Vec<ConnectionId>) -> SFut,
+        SFut: Future<Output = ()>,
+    {
+        loop {
+            if !self.settings.debounce.is_zero() {
+                tokio::time::sleep(self.settings.debounce).await;
+            }
+            let Some((batch, signal)) = self.next_batch(&session_key) else {
+                return;
+            };
+            let outcome = tokio::select! {
+                _ = run(batch) => RunOutcome::Completed,
+                _ = signal.notified() => RunOutcome::Interrupted,
+            };
+            let finished = self.finish_run(&session_key);
+            if outcome == RunOutcome::Interrupted {
+                tracing::info!("Interrupted in-flight run for session {}", session_key);
+                if let Some(run) = finished.filter(|run| !run.cancelled) {
+                    superseded(run.waiting).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(text: &str) -> QueuedMessage {
+        msg_from(text, 1)
+    }
+
+    fn msg_from(text: &str, connection_id: ConnectionId) -> QueuedMessage {
+        QueuedMessage {
+            text: text.to_string(),
+            connection_id,
+        }
+    }
+
+    fn texts(batch: &[QueuedMessage]) -> Vec<String> {
+        batch.iter().map(|m| m.text.clone()).collect()
+    }
+
+    async fn run_lane(
+        queue: Arc<SessionQueue>,
+        key: &str,
+        log: Arc<Mutex<Vec<Vec<String>>>>,
+        delay: Duration,
+    ) {
+        let superseded = Arc::new(Mutex::new(Vec::new()));
+        run_lane_noting_superseded(queue, key, log, superseded, delay).await;
+    }
+
+    async fn run_lane_noting_superseded(
+        queue: Arc<SessionQueue>,
+        key: &str,
+        log: Arc<Mutex<Vec<Vec<String>>>>,
+        superseded: Arc<Mutex<Vec<Vec<ConnectionId>>>>,
+        delay: Duration,
+    ) {
+        queue
+            .drain(
+                key.to_string(),
+                move |batch| {
+                    let log = log.clone();
+                    async move {
+                        tokio::time::sleep(delay).await;
+                        log.lock().unwrap().push(texts(&batch));
+                    }
+                },
+                move |waiting| {
+                    superseded.lock().unwrap().push(waiting);
+                    async {}
+                },
+            )
+            .await;
+    }
+
+    #[test]
+    fn only_the_first_enqueue_starts_a_worker() {
+        let queue = SessionQueue::new(QueueSettings::default());
+        assert!(queue.enqueue("a", msg("1")));
+        assert!(!queue.enqueue("a", msg("2")));
+        assert!(queue.enqueue("b", msg("x")));
+        assert_eq!(queue.pending("a"), 2);
+    }
+
+    #[tokio::test]
+    async fn collect_batches_messages_that_arrive_during_a_run() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings::default()));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        assert!(queue.enqueue("s", msg("1")));
+        let worker = tokio::spawn(run_lane(
+            queue.clone(),
+            "s",
+            log.clone(),
+            Duration::from_millis(50),
+        ));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!queue.enqueue("s", msg("2")));
+        assert!(!queue.enqueue("s", msg("3")));
+        worker.await.unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                vec!["1".to_string()],
+                vec!["2".to_string(), "3".to_string()]
+            ]
+        );
+        assert!(!queue.is_active("s"));
+    }
+
+    #[tokio::test]
+    async fn queue_mode_answers_one_message_per_run() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings {
+            mode: QueueMode::Queue,
+            debounce: Duration::ZERO,
+        }));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        queue.enqueue("s", msg("1"));
+        queue.enqueue("s", msg("2"));
+        run_lane(queue.clone(), "s", log.clone(), Duration::ZERO).await;
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![vec!["1".to_string()], vec!["2".to_string()]]
+        );
+    }
+
+    #[tokio::test]
+    async fn interrupt_cancels_the_run_in_flight() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings {
+            mode: QueueMode::Interrupt,
+            debounce: Duration::ZERO,
+        }));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        queue.enqueue("s", msg("slow"));
+        let worker = tokio::spawn(run_lane(
+            queue.clone(),
+            "s",
+            log.clone(),
+            Duration::from_millis(200),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.enqueue("s", msg("newer"));
+        worker.await.unwrap();
+
+        // The first run never completed; only the newer batch was answered.
+        assert_eq!(*log.lock().unwrap(), vec![vec!["newer".to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn superseded_runs_report_their_waiting_connections() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings {
+            mode: QueueMode::Interrupt,
+            debounce: Duration::ZERO,
+        }));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let superseded = Arc::new(Mutex::new(Vec::new()));
+        queue.enqueue("s", msg_from("slow", 1));
+        queue.enqueue("s", msg_from("also slow", 2));
+        let worker = tokio::spawn(run_lane_noting_superseded(
+            queue.clone(),
+            "s",
+            log.clone(),
+            superseded.clone(),
+            Duration::from_millis(200),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.enqueue("s", msg_from("newer", 3));
+        worker.await.unwrap();
+
+        assert_eq!(*superseded.lock().unwrap(), vec![vec![1, 2]]);
+        assert_eq!(*log.lock().unwrap(), vec![vec!["newer".to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn collected_batches_wait_on_every_sender() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings::default()));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        queue.enqueue("s", msg_from("1", 1));
+        let worker = tokio::spawn(run_lane(
+            queue.clone(),
+            "s",
+            log.clone(),
+            Duration::from_millis(100),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.enqueue("s", msg_from("2", 2));
+        queue.enqueue("s", msg_from("3", 3));
+        queue.enqueue("s", msg_from("4", 2));
+        // Let the first run finish and the collected batch start.
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(queue.cancel("s"), Some(vec![2, 3]));
+        worker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancel_aborts_the_run_but_keeps_pending_messages() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings::default()));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let superseded = Arc::new(Mutex::new(Vec::new()));
+        assert_eq!(queue.cancel("s"), None);
+        queue.enqueue("s", msg("slow"));
+        let worker = tokio::spawn(run_lane_noting_superseded(
+            queue.clone(),
+            "s",
+            log.clone(),
+            superseded.clone(),
+            Duration::from_millis(200),
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.enqueue("s", msg("next"));
+        assert_eq!(queue.cancel("s"), Some(vec![1]));
+        worker.await.unwrap();
+
+        assert_eq!(*log.lock().unwrap(), vec![vec!["next".to_string()]]);
+        // `chat.cancel` answers the waiting connections itself.
+        assert!(superseded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sessions_run_in_parallel() {
+        let queue = Arc::new(SessionQueue::new(QueueSettings::default()));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        queue.enqueue("a", msg("a1"));
+        queue.enqueue("b", msg("b1"));
+        let started = std::time::Instant::now();
+        tokio::join!(
+            run_lane(queue.clone(), "a", log.clone(), Duration::from_millis(100)),
+            run_lane(queue.clone(), "b", log.clone(), Duration::from_millis(100)),
+        );
+        assert!(started.elapsed() < Duration::from_millis(190));
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+}
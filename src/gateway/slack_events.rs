@@ -0,0 +1,187 @@
+//! slack_events — Slack Events API endpoint (`POST /slack/events`).
+//!
+//! Requests are authenticated by their Slack signature (`SLACK_SIGNING_SECRET`)
+//! rather than gateway tokens. The URL verification challenge is answered
+//! inline; events are acked immediately and answered in the background with
+//! `SLACK_BOT_TOKEN`. Retries (`X-Slack-Retry-Num`) are acked without being
+//! handled again, and one `SlackMonitor` in gateway state drops events whose
+//! id it has already seen.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::connectors::slack_client::SlackApi;
+use crate::connectors::slack_monitor::{verify_signature, SlackMonitor};
+use crate::gateway::server::GatewayServer;
+
+pub const SLACK_EVENTS_PATH: &str = "/slack/events";
+
+pub fn slack_events_router() -> Router<Arc<GatewayServer>> {
+    Router::new().route(SLACK_EVENTS_PATH, post(slack_events))
+}
+
+fn env_secret(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+async fn slack_events(
+    State(server): State<Arc<GatewayServer>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let signing_secret = env_secret("SLACK_SIGNING_SECRET");
+    let bot_token = env_secret("SLACK_BOT_TOKEN");
+    events_response(
+        server,
+        signing_secret.as_deref(),
+        bot_token,
+        &headers,
+        &body,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+fn events_response(
+    server: Arc<GatewayServer>,
+    signing_secret: Option<&str>,
+    bot_token: Option<String>,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> Response {
+    let Some(signing_secret) = signing_secret else {
+        return (StatusCode::NOT_FOUND, "Slack events are not configured").into_response();
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    if !verify_signature(
+        signing_secret,
+        header("x-slack-request-timestamp"),
+        body,
+        header("x-slack-signature"),
+        now,
+    ) {
+        return (StatusCode::UNAUTHORIZED, "invalid Slack signature").into_response();
+    }
+    let Ok(payload) = serde_json::from_slice::<Value>(body) else {
+        return (StatusCode::BAD_REQUEST, "invalid JSON").into_response();
+    };
+
+    match payload.get("type").and_then(|t| t.as_str()) {
+        Some("url_verification") => {
+            Json(json!({ "challenge": payload.get("challenge") })).into_response()
+        }
+        Some("event_callback") if headers.get("x-slack-retry-num").is_none() => {
+            match bot_token {
+                Some(token) => {
+                    let monitor = server
+                        .slack_events
+                        .get_or_init(|| SlackMonitor::new(server.clone(), SlackApi::new(token)))
+                        .clone();
+                    tokio::spawn(async move { monitor.handle_payload(payload).await });
+                }
+                None => tracing::warn!("[slack] event received but SLACK_BOT_TOKEN is not set"),
+            }
+            StatusCode::OK.into_response()
+        }
+        _ => StatusCode::OK.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hmac::{Hmac, Mac};
+
+    fn signed_headers(secret: &str, timestamp: i64, body: &[u8]) -> HeaderMap {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("v0:{}:", timestamp).as_bytes());
+        mac.update(body);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-slack-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+        headers.insert(
+            "x-slack-signature",
+            format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+                .parse()
+                .unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn url_verification_needs_a_valid_signature() {
+        let server = Arc::new(GatewayServer::new(0, "127.0.0.1".to_string()));
+        let body = br#"{"type":"url_verification","challenge":"3eZbrw1aBm"}"#;
+        let now = 1_760_000_000;
+
+        let headers = signed_headers("s3cret", now, body);
+        let response = events_response(server.clone(), Some("s3cret"), None, &headers, body, now);
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        let reply: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(reply["challenge"], "3eZbrw1aBm");
+
+        let forged = signed_headers("wrong", now, body);
+        let response = events_response(server.clone(), Some("s3cret"), None, &forged, body, now);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = events_response(server, None, None, &headers, body, now);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn event_ids_are_remembered_across_requests() {
+        let server = Arc::new(GatewayServer::new(0, "127.0.0.1".to_string()));
+        let now = 1_760_000_000;
+        for id in ["Ev1", "Ev2"] {
+            let body = json!({
+                "type": "event_callback",
+                "event_id": id,
+                "event": {"type": "reaction_added", "user": "U1"},
+            })
+            .to_string();
+            let headers = signed_headers("s3cret", now, body.as_bytes());
+            let response = events_response(
+                server.clone(),
+                Some("s3cret"),
+                Some("xoxb-test".to_string()),
+                &headers,
+                body.as_bytes(),
+                now,
+            );
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Events are handled in the background; one monitor saw both.
+        let seen_both = || {
+            server
+                .slack_events
+                .get()
+                .is_some_and(|m| m.has_seen("Ev1") && m.has_seen("Ev2"))
+        };
+        for _ in 0..100 {
+            if seen_both() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(seen_both());
+    }
+}
@@ -0,0 +1,237 @@
+//! tokens — scoped API tokens for the gateway.
+//!
+//! Tokens are issued and revoked from the CLI (`krabkrab gateway token ...`)
+//! and stored hashed in `<state dir>/gateway/tokens.json`. The gateway
+//! re-reads the file when it changes, so revocation applies without a
+//! restart.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Prefix of every issued token secret.
+pub const TOKEN_PREFIX: &str = "okt_";
+
+/// What a token may do on the gateway. Scopes are ordered: each one
+/// includes everything the scopes before it allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// Read-only status (`status` over WS, `GET /v1/models`).
+    Status,
+    /// Chat turns (WS `chat`/`chat.cancel`, `/v1/chat/completions`, ACP,
+    /// WebRTC), plus status.
+    Chat,
+    /// Everything.
+    Admin,
+}
+
+impl TokenScope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "status" | "read" | "readonly" | "read-only" => Some(Self::Status),
+            "chat" => Some(Self::Chat),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Chat => "chat",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Whether a principal with this scope may perform `required`.
+    pub fn allows(&self, required: TokenScope) -> bool {
+        *self >= required
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    /// Hex SHA-256 of the secret; the secret itself is never stored.
+    pub token_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ApiToken {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenFile {
+    #[serde(default)]
+    tokens: Vec<ApiToken>,
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// File mtime and size, used to notice changes made by the CLI.
+type FileStamp = (Option<SystemTime>, u64);
+
+/// File-backed token store.
+pub struct TokenStore {
+    path: PathBuf,
+    cache: Mutex<Option<(FileStamp, Vec<ApiToken>)>>,
+}
+
+impl TokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Store at the default location under the openkrab state dir.
+    pub fn open_default() -> Self {
+        Self::new(default_tokens_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn list(&self) -> Result<Vec<ApiToken>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let raw = std::fs::read_to_string(&self.path)?;
+        let file: TokenFile = serde_json::from_str(&raw)?;
+        Ok(file.tokens)
+    }
+
+    fn save(&self, tokens: Vec<ApiToken>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&TokenFile { tokens })?;
+        std::fs::write(&self.path, json)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600));
+        }
+        Ok(())
+    }
+
+    /// Issue a new token. Returns the record and the secret, which is only
+    /// shown once.
+    pub fn issue(&self, name: &str, scope: TokenScope) -> Result<(ApiToken, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("token name is required");
+        }
+        let mut tokens = self.list()?;
+        let secret = format!(
+            "{}{}",
+            TOKEN_PREFIX,
+            hex::encode(rand::random::<[u8; 24]>())
+        );
+        let token = ApiToken {
+            id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+            name: name.to_string(),
+            scope,
+            token_hash: hash_secret(&secret),
+            created_at: chrono::Utc::now(),
+            revoked_at: None,
+        };
+        tokens.push(token.clone());
+        self.save(tokens)?;
+        Ok((token, secret))
+    }
+
+    /// Revoke a token by id or name. Returns `false` if nothing active matched.
+    pub fn revoke(&self, id_or_name: &str) -> Result<bool> {
+        let mut tokens = self.list()?;
+        let mut revoked = false;
+        for token in tokens
+            .iter_mut()
+            .filter(|t| t.is_active() && (t.id == id_or_name || t.name == id_or_name))
+        {
+            token.revoked_at = Some(chrono::Utc::now());
+            revoked = true;
+        }
+        if revoked {
+            self.save(tokens)?;
+        }
+        Ok(revoked)
+    }
+
+    /// Look up an active token by its secret.
+    pub fn verify(&self, secret: &str) -> Option<ApiToken> {
+        if !secret.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let stamp = std::fs::metadata(&self.path)
+            .map(|m| (m.modified().ok(), m.len()))
+            .unwrap_or((None, 0));
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if !matches!(cache.as_ref(), Some((seen, _)) if *seen == stamp) {
+            *cache = Some((stamp, self.list().unwrap_or_default()));
+        }
+        let hash = hash_secret(secret);
+        cache.as_ref().and_then(|(_, tokens)| {
+            tokens
+                .iter()
+                .find(|t| t.is_active() && t.token_hash == hash)
+                .cloned()
+        })
+    }
+}
+
+pub fn default_tokens_path() -> PathBuf {
+    PathBuf::from(crate::utils::resolve_config_dir())
+        .join("gateway")
+        .join("tokens.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_gate_by_level() {
+        assert!(TokenScope::Admin.allows(TokenScope::Chat));
+        assert!(TokenScope::Chat.allows(TokenScope::Chat));
+        assert!(TokenScope::Chat.allows(TokenScope::Status));
+        assert!(!TokenScope::Chat.allows(TokenScope::Admin));
+        assert!(!TokenScope::Status.allows(TokenScope::Chat));
+        assert_eq!(TokenScope::parse("read-only"), Some(TokenScope::Status));
+    }
+
+    #[test]
+    fn issue_verify_and_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TokenStore::new(dir.path().join("tokens.json"));
+        let (token, secret) = store.issue("ci", TokenScope::Chat).unwrap();
+        assert!(secret.starts_with(TOKEN_PREFIX));
+
+        let found = store.verify(&secret).unwrap();
+        assert_eq!(found.id, token.id);
+        assert_eq!(found.scope, TokenScope::Chat);
+        assert!(store.verify("okt_nope").is_none());
+
+        assert!(store.revoke(&token.id).unwrap());
+        assert!(!store.revoke(&token.id).unwrap());
+        assert!(store.verify(&secret).is_none());
+        // A second store sees the revocation through the file.
+        let fresh = TokenStore::new(store.path());
+        assert!(fresh.verify(&secret).is_none());
+    }
+}
@@ -195,6 +195,27 @@ pub enum GatewayMessage {
         attachments: Option<Vec<Attachment>>,
     },
 
+    /// Progress of a chat turn, sent to the requesting connection before
+    /// the final `chat` reply.
+    #[serde(rename = "chat.event")]
+    ChatEvent {
+        session_key: String,
+        event: crate::agents::streaming::StreamEvent,
+    },
+
+    /// End of a chat turn with its token counts; followed by the `chat` reply.
+    #[serde(rename = "chat.finish")]
+    ChatFinish {
+        session_key: String,
+        input_tokens: u64,
+        output_tokens: u64,
+    },
+
+    /// Abort the in-flight turn of a session. The waiting connection gets
+    /// an `error` with code `cancelled`.
+    #[serde(rename = "chat.cancel")]
+    ChatCancel { session_key: String },
+
     #[serde(rename = "status")]
     Status {
         sessions: Vec<GatewaySessionRow>,
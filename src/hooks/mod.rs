@@ -15,6 +15,7 @@ pub mod events {
     pub const AGENT_START: &str = "agent:start";
     pub const AGENT_COMPLETE: &str = "agent:complete";
     pub const AGENT_ERROR: &str = "agent:error";
+    pub const MODEL_FALLBACK: &str = "model:fallback";
     pub const SESSION_CREATED: &str = "session:created";
     pub const SESSION_CLOSED: &str = "session:closed";
     pub const MEMORY_INDEXED: &str = "memory:indexed";
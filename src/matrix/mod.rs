@@ -295,6 +295,37 @@ pub async fn send_message(
     Ok(json["event_id"].as_str().unwrap_or("").to_string())
 }
 
+/// Send a reply to `reply_to_event_id` in a Matrix room.
+pub async fn send_reply(
+    client: &reqwest::Client,
+    cfg: &MatrixConfig,
+    room_id: &str,
+    body: &str,
+    reply_to_event_id: &str,
+) -> Result<String> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let txn_id = format!("krab_reply_{}", now_ms);
+    let url = format!(
+        "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+        cfg.homeserver,
+        encode_room_id(room_id),
+        txn_id
+    );
+    let payload = build_reply_event(body, reply_to_event_id);
+    let resp = client
+        .put(&url)
+        .bearer_auth(&cfg.access_token)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    let json: serde_json::Value = resp.json().await?;
+    Ok(json["event_id"].as_str().unwrap_or("").to_string())
+}
+
 /// Send a formatted text message to a Matrix room.
 pub async fn send_formatted_message(
     client: &reqwest::Client,
@@ -532,6 +563,29 @@ pub async fn get_room_members(
     Ok(members)
 }
 
+/// One `/sync` long-poll; `since` is the previous `next_batch`.
+pub async fn sync_once(
+    client: &reqwest::Client,
+    cfg: &MatrixConfig,
+    since: Option<&str>,
+) -> Result<MatrixSyncResponse> {
+    let timeout = cfg.sync_timeout_ms.unwrap_or(30_000);
+    let mut url = format!(
+        "{}/_matrix/client/v3/sync?timeout={}",
+        cfg.homeserver, timeout
+    );
+    if let Some(since) = since {
+        url.push_str(&format!("&since={}", urlencoding::encode(since)));
+    }
+    let resp = client
+        .get(&url)
+        .bearer_auth(&cfg.access_token)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(resp.json().await?)
+}
+
 // ─── Normalize to common message ─────────────────────────────────────────────
 
 pub fn normalize_inbound(msg: &ParsedMatrixMessage) -> crate::common::Message {
@@ -627,29 +681,10 @@ impl Monitor {
         event_tx: MatrixEventSender,
     ) -> Result<()> {
         let client = reqwest::Client::new();
-        let timeout = config.sync_timeout_ms.unwrap_or(30_000);
 
         loop {
-            let url = if next_batch.is_empty() {
-                format!(
-                    "{}/_matrix/client/v3/sync?timeout={}",
-                    config.homeserver, timeout
-                )
-            } else {
-                format!(
-                    "{}/_matrix/client/v3/sync?timeout={}&since={}",
-                    config.homeserver, timeout, next_batch
-                )
-            };
-
-            let resp = client
-                .get(&url)
-                .bearer_auth(&config.access_token)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let sync: MatrixSyncResponse = resp.json().await?;
+            let since = (!next_batch.is_empty()).then(|| next_batch.clone());
+            let sync = sync_once(&client, config, since.as_deref()).await?;
             *next_batch = sync.next_batch.clone();
 
             // Process messages
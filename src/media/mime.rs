@@ -166,13 +166,13 @@ async fn sniff_mime(buffer: Option<&[u8]>) -> Option<String> {
     if &buf[0..4] == b"RIFF" && buf.len() >= 12 && &buf[8..12] == b"WEBP" {
         return Some("image/webp".to_string());
     }
-    if &buf[4..8] == b"ftyp" {
+    if buf.get(4..8) == Some(b"ftyp".as_slice()) {
         return Some("video/mp4".to_string());
     }
     if &buf[0..4] == b"\x1a\x45\xdf\xa3" {
         return Some("video/webm".to_string());
     }
-    if &buf[0..5] == b"%PDF-" {
+    if buf.starts_with(b"%PDF-") {
         return Some("application/pdf".to_string());
     }
     if buf.starts_with(b"OggS") {
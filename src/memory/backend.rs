@@ -0,0 +1,205 @@
+//! Memory backends — where agent recall is served from.
+//!
+//! The builtin backend searches the SQLite index kept by [`MemoryManager`];
+//! the QMD backend shells out to an external `qmd` sidecar (see
+//! [`crate::memory::qmd`]). Both return [`SearchResult`]s, which
+//! [`render_snippet`] turns into prompt text with or without a citation.
+
+use crate::memory::backend_config::{resolve_memory_backend_config, MemoryBackendConfig};
+use crate::memory::manager::{HybridSearchOptions, MemoryManager};
+use crate::memory::qmd::QmdBackend;
+use crate::memory::scope::ScopeContext;
+use crate::memory::store::SearchResult;
+use crate::sessions::Session;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[async_trait]
+pub trait MemoryBackend: Send + Sync {
+    /// `builtin` or `qmd`.
+    fn id(&self) -> &str;
+
+    fn citations(&self) -> CitationMode;
+
+    /// Starts background maintenance, if the backend has any.
+    async fn start(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Brings the index up to date with the workspace now.
+    async fn sync(&self) -> Result<()>;
+
+    async fn search(&self, query: &str, opts: HybridSearchOptions) -> Result<Vec<SearchResult>>;
+
+    /// Called after each answered turn so the transcript can be recalled later.
+    async fn record_session(&self, session: &Session, agent_id: Option<&str>) -> Result<()>;
+}
+
+// ─── Citations ──────────────────────────────────────────────────────────────
+
+/// `memory.citations`: whether snippets carry a `Source:` footer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitationMode {
+    /// Cite in direct chats only, so group replies don't expose file paths.
+    Auto,
+    Always,
+    Never,
+}
+
+impl CitationMode {
+    pub fn parse(raw: &str) -> Self {
+        match raw.trim().to_lowercase().as_str() {
+            "always" | "on" => Self::Always,
+            "never" | "off" => Self::Never,
+            _ => Self::Auto,
+        }
+    }
+
+    /// Whether results shown to `ctx` should cite their source.
+    pub fn cites(self, ctx: Option<&ScopeContext>) -> bool {
+        use crate::channels::chat_type::{normalize_chat_type, ChatType};
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => !matches!(
+                ctx.and_then(|c| normalize_chat_type(c.chat_type.as_deref())),
+                Some(ChatType::Group | ChatType::Channel)
+            ),
+        }
+    }
+}
+
+/// `path#L<start>-L<end>` for a hit, or `path#L<line>` for a single line.
+pub fn citation(result: &SearchResult) -> String {
+    if result.end_line > result.start_line {
+        format!(
+            "{}#L{}-L{}",
+            result.path, result.start_line, result.end_line
+        )
+    } else {
+        format!("{}#L{}", result.path, result.start_line.max(1))
+    }
+}
+
+/// Snippet text as injected into the prompt, with a `Source:` footer when
+/// `cite` is set.
+pub fn render_snippet(result: &SearchResult, cite: bool) -> String {
+    let text = result.text.trim_end();
+    if cite {
+        format!("{}\n\nSource: {}", text, citation(result))
+    } else {
+        text.to_string()
+    }
+}
+
+// ─── Builtin ────────────────────────────────────────────────────────────────
+
+/// Serves recall from the SQLite index of [`MemoryManager`].
+#[derive(Debug)]
+pub struct BuiltinBackend {
+    manager: Arc<MemoryManager>,
+    workspace_dir: PathBuf,
+    citations: CitationMode,
+}
+
+impl BuiltinBackend {
+    pub fn new(manager: Arc<MemoryManager>, workspace_dir: PathBuf) -> Self {
+        Self {
+            manager,
+            workspace_dir,
+            citations: CitationMode::Auto,
+        }
+    }
+
+    pub fn with_citations(mut self, citations: CitationMode) -> Self {
+        self.citations = citations;
+        self
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for BuiltinBackend {
+    fn id(&self) -> &str {
+        "builtin"
+    }
+
+    fn citations(&self) -> CitationMode {
+        self.citations
+    }
+
+    async fn sync(&self) -> Result<()> {
+        self.manager.sync_workspace(&self.workspace_dir).await
+    }
+
+    async fn search(&self, query: &str, opts: HybridSearchOptions) -> Result<Vec<SearchResult>> {
+        self.manager.search_hybrid(query, opts).await
+    }
+
+    /// Transcripts reach the builtin index only through
+    /// [`MemoryManager::warm_session`], which embeds them; nothing to do per turn.
+    async fn record_session(&self, _session: &Session, _agent_id: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Backend selected by `config` for `agent_id`. QMD falls back to the
+/// builtin index whenever the sidecar fails.
+pub fn create_backend(
+    config: Option<MemoryBackendConfig>,
+    manager: Arc<MemoryManager>,
+    agent_id: &str,
+    workspace_dir: PathBuf,
+) -> Arc<dyn MemoryBackend> {
+    let resolved =
+        resolve_memory_backend_config(config, agent_id, &workspace_dir.to_string_lossy());
+    let citations = CitationMode::parse(&resolved.citations);
+    let builtin =
+        Arc::new(BuiltinBackend::new(manager, workspace_dir.clone()).with_citations(citations));
+    match resolved.qmd {
+        Some(qmd) if resolved.backend == "qmd" => Arc::new(
+            QmdBackend::new(qmd, agent_id, workspace_dir)
+                .with_citations(citations)
+                .with_fallback(builtin),
+        ),
+        _ => builtin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(start_line: i32, end_line: i32) -> SearchResult {
+        SearchResult {
+            id: "1".into(),
+            path: "memory/notes.md".into(),
+            source: "memory".into(),
+            model: "test".into(),
+            start_line,
+            end_line,
+            text: "widgets are blue\n".into(),
+            score: 0.9,
+        }
+    }
+
+    #[test]
+    fn citations_follow_mode_and_chat_type() {
+        let group = ScopeContext {
+            chat_type: Some("group".into()),
+            ..Default::default()
+        };
+        assert!(CitationMode::parse("auto").cites(None));
+        assert!(!CitationMode::parse("auto").cites(Some(&group)));
+        assert!(CitationMode::parse("on").cites(Some(&group)));
+        assert!(!CitationMode::parse("never").cites(None));
+
+        assert_eq!(
+            render_snippet(&hit(3, 5), true),
+            "widgets are blue\n\nSource: memory/notes.md#L3-L5"
+        );
+        assert_eq!(citation(&hit(4, 4)), "memory/notes.md#L4");
+        assert_eq!(render_snippet(&hit(3, 5), false), "widgets are blue");
+    }
+}
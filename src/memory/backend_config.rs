@@ -9,11 +9,13 @@ pub struct MemoryBackendConfig {
     pub qmd: Option<QmdConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct QmdCollection {
     pub name: String,
     pub path: String,
+    #[serde(default)]
     pub pattern: String,
+    #[serde(default)]
     pub kind: String, // "memory" | "custom" | "sessions"
 }
 
@@ -59,13 +61,13 @@ pub struct QmdConfig {
     pub scope: Option<SessionSendPolicyConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionSendPolicyConfig {
     pub default: String, // "allow" | "deny"
     pub rules: Vec<PolicyRule>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PolicyRule {
     pub action: String, // "allow" | "deny"
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -124,7 +126,7 @@ fn sanitize_name(input: &str) -> String {
     }
 }
 
-fn scope_collection_base(base: &str, agent_id: &str) -> String {
+pub(crate) fn scope_collection_base(base: &str, agent_id: &str) -> String {
     format!("{}-{}", base, sanitize_name(agent_id))
 }
 
@@ -144,7 +146,7 @@ fn ensure_unique_name(base: &str, existing: &mut HashMap<String, ()>) -> String
     unreachable!()
 }
 
-fn resolve_path(raw: &str, workspace_dir: &str) -> Result<String, String> {
+pub(crate) fn resolve_path(raw: &str, workspace_dir: &str) -> Result<String, String> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
         return Err("path required".to_string());
@@ -1,11 +1,14 @@
+use crate::memory::backend_config::{QmdCollection, SessionSendPolicyConfig};
 use crate::memory::embeddings::{
     EmbeddingProvider, GeminiProvider, OllamaProvider, OpenAiProvider, VoyageProvider,
 };
-use crate::memory::manager::HybridSearchOptions;
+use crate::memory::local_embeddings::{resolve_model_dir, LocalEmbeddingProvider};
+use crate::memory::manager::{EmbeddingCacheLimits, HybridSearchOptions};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
-pub const SUPPORTED_EMBEDDING_PROVIDERS: &[&str] = &["openai", "gemini", "ollama", "voyage"];
+pub const SUPPORTED_EMBEDDING_PROVIDERS: &[&str] =
+    &["openai", "gemini", "ollama", "voyage", "local"];
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MemoryConfig {
@@ -16,6 +19,20 @@ pub struct MemoryConfig {
     pub base_url: Option<String>,
     pub vector_weight: Option<f64>,
     pub text_weight: Option<f64>,
+    /// Most `embedding_cache` rows kept after a sync (default 50 000).
+    #[serde(default)]
+    pub cache_max_entries: Option<usize>,
+    /// Drop cached embeddings unused for this many days.
+    #[serde(default)]
+    pub cache_max_age_days: Option<u32>,
+    /// Extra directories indexed next to `MEMORY.md`; results carry the
+    /// collection name as their `source`.
+    #[serde(default)]
+    pub collections: Vec<QmdCollection>,
+    /// Which session transcripts are shared with every chat of their agent;
+    /// unset keeps each transcript private to its session.
+    #[serde(default)]
+    pub scope: Option<SessionSendPolicyConfig>,
 }
 
 impl Default for MemoryConfig {
@@ -28,6 +45,10 @@ impl Default for MemoryConfig {
             base_url: None,
             vector_weight: Some(0.7),
             text_weight: Some(0.3),
+            cache_max_entries: None,
+            cache_max_age_days: None,
+            collections: Vec::new(),
+            scope: None,
         }
     }
 }
@@ -84,10 +105,22 @@ impl MemoryConfig {
                     self.model.clone(),
                 )))
             }
+            // `model` is a model directory or a name under the data dir's `models/`.
+            "local" => Ok(Box::new(LocalEmbeddingProvider::load(resolve_model_dir(
+                self.model.as_deref(),
+            ))?)),
             _ => Err(anyhow!("Unsupported embedding provider: {}", self.provider)),
         }
     }
 
+    pub fn cache_limits(&self) -> EmbeddingCacheLimits {
+        let defaults = EmbeddingCacheLimits::default();
+        EmbeddingCacheLimits {
+            max_entries: self.cache_max_entries.or(defaults.max_entries),
+            max_age_days: self.cache_max_age_days.or(defaults.max_age_days),
+        }
+    }
+
     pub fn hybrid_search_options(&self) -> HybridSearchOptions {
         HybridSearchOptions {
             max_results: 10,
@@ -108,6 +141,19 @@ mod tests {
         assert!(p.contains(&"openai"));
         assert!(p.contains(&"gemini"));
         assert!(p.contains(&"ollama"));
+        assert!(p.contains(&"local"));
+    }
+
+    #[test]
+    fn local_provider_reports_missing_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            provider: "local".to_string(),
+            model: Some(dir.path().join("missing").display().to_string()),
+            ..Default::default()
+        };
+        let err = config.create_provider().err().unwrap();
+        assert!(err.to_string().contains("local embedding model not found"));
     }
 
     #[test]
@@ -0,0 +1,526 @@
+//! ingest — turns workspace files into memory chunks.
+//!
+//! Every supported format has an extractor (file → text) and a chunker that
+//! prefers natural boundaries: headings for Markdown and HTML, top-level
+//! definitions for source code, top-level keys for JSON and YAML, and
+//! paragraphs for plain text and PDFs. Chunks carry 1-based line ranges
+//! into the extracted text.
+
+use crate::memory::backend_config::{resolve_path, QmdCollection};
+use crate::memory::manager::{hash_text, MemoryChunk};
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Largest chunk handed to the embedding provider, in bytes.
+pub const CHUNK_MAX_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Markdown,
+    Html,
+    Pdf,
+    Text,
+    Code,
+    Json,
+    Yaml,
+}
+
+impl DocumentFormat {
+    /// Format implied by the file extension; `None` for files we do not index.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match ext.as_str() {
+            "md" | "markdown" | "mdx" => Self::Markdown,
+            "html" | "htm" | "xhtml" => Self::Html,
+            "pdf" => Self::Pdf,
+            "txt" | "text" | "log" | "rst" | "csv" => Self::Text,
+            "json" => Self::Json,
+            "yaml" | "yml" => Self::Yaml,
+            "rs" | "py" | "js" | "jsx" | "mjs" | "ts" | "tsx" | "go" | "java" | "kt" | "c"
+            | "h" | "cc" | "cpp" | "hpp" | "cs" | "rb" | "php" | "swift" | "scala" | "sh"
+            | "bash" | "lua" => Self::Code,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+            Self::Text => "text",
+            Self::Code => "code",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+}
+
+// ─── Extraction ───────────────────────────────────────────────────────────────
+
+/// Text of `path` as it will be chunked and stored.
+pub fn extract_text(path: &Path, format: DocumentFormat) -> Result<String> {
+    match format {
+        DocumentFormat::Pdf => {
+            let bytes = std::fs::read(path)?;
+            extract_pdf_text(&bytes)
+        }
+        DocumentFormat::Html => Ok(html2md::parse_html(&std::fs::read_to_string(path)?)),
+        DocumentFormat::Json => {
+            let raw = std::fs::read_to_string(path)?;
+            // Minified documents are re-indented so keys land on their own lines.
+            if raw.trim().lines().count() <= 1 {
+                if let Ok(value) = serde_json::from_str::<serde_json::Value>(&raw) {
+                    return Ok(serde_json::to_string_pretty(&value)?);
+                }
+            }
+            Ok(raw)
+        }
+        _ => Ok(std::fs::read_to_string(path)?),
+    }
+}
+
+/// Page texts joined by blank lines. The PDF parser can panic on malformed
+/// input, so it runs behind `catch_unwind`.
+pub fn extract_pdf_text(bytes: &[u8]) -> Result<String> {
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| anyhow!("PDF parser panicked"))?
+        .map_err(|e| anyhow!("PDF extraction failed: {}", e))?;
+    Ok(pages
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n"))
+}
+
+// ─── Chunking ─────────────────────────────────────────────────────────────────
+
+pub fn chunk_document(format: DocumentFormat, text: &str, max_chars: usize) -> Vec<MemoryChunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let boundaries = match format {
+        DocumentFormat::Markdown | DocumentFormat::Html => heading_boundaries(&lines),
+        DocumentFormat::Code => code_boundaries(&lines),
+        DocumentFormat::Json => json_boundaries(&lines),
+        DocumentFormat::Yaml => yaml_boundaries(&lines),
+        DocumentFormat::Text | DocumentFormat::Pdf => paragraph_boundaries(&lines),
+    };
+    pack_blocks(&lines, &boundaries, max_chars)
+}
+
+/// ATX headings outside fenced code blocks.
+fn heading_boundaries(lines: &[&str]) -> Vec<usize> {
+    let mut in_fence = false;
+    let mut starts = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence && trimmed.starts_with('#') {
+            starts.push(i);
+        }
+    }
+    starts
+}
+
+static DEFINITION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?: {0,4}|\t)(?:export\s+)?(?:default\s+)?(?:pub(?:\([^)]*\))?\s+)?(?:(?:public|private|protected|internal|static|abstract|final|async|unsafe|extern)\s+)*(?:fn|struct|enum|impl|trait|mod|def|class|function|interface|func|type|module|namespace)\b",
+    )
+    .unwrap()
+});
+
+/// Function, class and type definitions (top level or one indent deep),
+/// pulled up over the doc comments, attributes and decorators above them.
+fn code_boundaries(lines: &[&str]) -> Vec<usize> {
+    let mut starts = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !DEFINITION.is_match(line) {
+            continue;
+        }
+        let mut start = i;
+        while start > 0 && is_attached(lines[start - 1]) {
+            start -= 1;
+        }
+        if starts.last().is_none_or(|&last| start > last) {
+            starts.push(start);
+        }
+    }
+    starts
+}
+
+fn is_attached(line: &str) -> bool {
+    let t = line.trim_start();
+    ["//", "#[", "#!", "@", "/*", "*", "# ", "\"\"\"", "--"]
+        .iter()
+        .any(|p| t.starts_with(p))
+}
+
+/// Members of the outermost object or array, for indented JSON.
+fn json_boundaries(lines: &[&str]) -> Vec<usize> {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut starts = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let t = line.trim();
+        if depth == 1 && !in_string && !t.is_empty() && !t.starts_with(['}', ']']) {
+            starts.push(i);
+        }
+        let mut escaped = false;
+        for c in line.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '{' | '[' if !in_string => depth += 1,
+                '}' | ']' if !in_string => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    starts
+}
+
+/// Top-level keys, list items and document separators, pulled up over the
+/// comments above them.
+fn yaml_boundaries(lines: &[&str]) -> Vec<usize> {
+    let mut starts = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() || line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        let mut start = i;
+        while start > 0 && lines[start - 1].starts_with('#') {
+            start -= 1;
+        }
+        starts.push(start);
+    }
+    starts
+}
+
+/// First line of each paragraph.
+fn paragraph_boundaries(lines: &[&str]) -> Vec<usize> {
+    (0..lines.len())
+        .filter(|&i| !lines[i].trim().is_empty() && (i == 0 || lines[i - 1].trim().is_empty()))
+        .collect()
+}
+
+/// Groups the blocks between `boundaries` into chunks of at most
+/// `max_chars`, so every chunk starts on a boundary. Blocks that are too
+/// large on their own are split by lines.
+fn pack_blocks(lines: &[&str], boundaries: &[usize], max_chars: usize) -> Vec<MemoryChunk> {
+    let mut starts: Vec<usize> = std::iter::once(0)
+        .chain(
+            boundaries
+                .iter()
+                .copied()
+                .filter(|&b| b > 0 && b < lines.len()),
+        )
+        .collect();
+    starts.dedup();
+    let size = |from: usize, to: usize| lines[from..to].iter().map(|l| l.len() + 1).sum::<usize>();
+
+    let mut chunks = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(lines.len());
+        let block = size(start, end);
+        if let Some((from, to)) = current.take() {
+            if size(from, to) + block <= max_chars {
+                current = Some((from, end));
+                continue;
+            }
+            push_chunk(&mut chunks, lines, from, to);
+        }
+        if block <= max_chars {
+            current = Some((start, end));
+            continue;
+        }
+        let mut from = start;
+        let mut chars = 0;
+        for line in start..end {
+            let len = lines[line].len() + 1;
+            if chars + len > max_chars && line > from {
+                push_chunk(&mut chunks, lines, from, line);
+                from = line;
+                chars = 0;
+            }
+            chars += len;
+        }
+        current = Some((from, end));
+    }
+    if let Some((from, to)) = current {
+        push_chunk(&mut chunks, lines, from, to);
+    }
+    chunks
+}
+
+fn push_chunk(chunks: &mut Vec<MemoryChunk>, lines: &[&str], from: usize, to: usize) {
+    let text = lines[from..to].join("\n");
+    if text.trim().is_empty() {
+        return;
+    }
+    chunks.push(MemoryChunk {
+        start_line: from as i32 + 1,
+        end_line: to as i32,
+        hash: hash_text(&text),
+        text,
+    });
+}
+
+// ─── Collections ──────────────────────────────────────────────────────────────
+
+/// Indexable files of `collection` whose path relative to the collection
+/// root matches its pattern, in a stable order.
+pub fn collection_files(collection: &QmdCollection, workspace_dir: &Path) -> Vec<PathBuf> {
+    let Ok(root) = resolve_path(&collection.path, &workspace_dir.to_string_lossy()) else {
+        return Vec::new();
+    };
+    let root = PathBuf::from(root);
+    let pattern = if collection.pattern.trim().is_empty() {
+        "**/*"
+    } else {
+        collection.pattern.trim()
+    };
+    let mut files: Vec<PathBuf> = WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| DocumentFormat::from_path(e.path()).is_some())
+        .filter(|e| {
+            e.path()
+                .strip_prefix(&root)
+                .map(|rel| glob_match(pattern, &rel.to_string_lossy().replace('\\', "/")))
+                .unwrap_or(false)
+        })
+        .map(|e| e.path().to_path_buf())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Glob match with `**` (any directories), `*`, `?` and `{a,b}` alternatives.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let mut re = String::from("^");
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    let mut in_braces = false;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    re.push_str("(?:.*/)?");
+                    i += 1;
+                } else {
+                    re.push_str(".*");
+                }
+                i += 1;
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '{' => {
+                in_braces = true;
+                re.push_str("(?:");
+            }
+            '}' if in_braces => {
+                in_braces = false;
+                re.push(')');
+            }
+            ',' if in_braces => re.push('|'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re.push('$');
+    Regex::new(&re).map(|r| r.is_match(path)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(chunks: &[MemoryChunk]) -> Vec<String> {
+        chunks.iter().map(|c| c.text.clone()).collect()
+    }
+
+    #[test]
+    fn formats_follow_extensions() {
+        let f = |p: &str| DocumentFormat::from_path(Path::new(p));
+        assert_eq!(f("notes/a.MD"), Some(DocumentFormat::Markdown));
+        assert_eq!(f("page.htm"), Some(DocumentFormat::Html));
+        assert_eq!(f("paper.pdf"), Some(DocumentFormat::Pdf));
+        assert_eq!(f("src/lib.rs"), Some(DocumentFormat::Code));
+        assert_eq!(f("conf.yml"), Some(DocumentFormat::Yaml));
+        assert_eq!(f("image.png"), None);
+        assert_eq!(f("Makefile"), None);
+    }
+
+    #[test]
+    fn globs_match_relative_paths() {
+        assert!(glob_match("**/*.md", "a.md"));
+        assert!(glob_match("**/*.md", "x/y/a.md"));
+        assert!(!glob_match("*.md", "x/a.md"));
+        assert!(glob_match("docs/**/*.{html,pdf}", "docs/v1/guide.pdf"));
+        assert!(!glob_match("docs/**/*.{html,pdf}", "docs/guide.txt"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("MEMORY.md", "MEMORY.md"));
+    }
+
+    #[test]
+    fn markdown_chunks_start_at_headings() {
+        let text = format!(
+            "# Title\nintro\n```\n# not a heading\n```\n## Setup\n{}\n## Usage\nrun it",
+            "step\n".repeat(10).trim_end()
+        );
+        let chunks = chunk_document(DocumentFormat::Markdown, &text, 60);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].text.contains("# not a heading"));
+        assert!(chunks[1].text.starts_with("## Setup"));
+        assert_eq!(chunks[2].text, "## Usage\nrun it");
+        assert_eq!((chunks[2].start_line, chunks[2].end_line), (17, 18));
+
+        // Small sections share a chunk.
+        let packed = chunk_document(DocumentFormat::Markdown, "# A\na\n# B\nb", 100);
+        assert_eq!(texts(&packed), vec!["# A\na\n# B\nb"]);
+    }
+
+    #[test]
+    fn code_chunks_follow_definitions() {
+        let rust = "use std::fmt;\n\n/// Adds.\n#[inline]\npub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nimpl Thing {\n    pub fn go(&self) {}\n}\n";
+        let chunks = chunk_document(DocumentFormat::Code, rust, 80);
+        assert!(chunks[1]
+            .text
+            .starts_with("/// Adds.\n#[inline]\npub fn add"));
+        assert!(chunks.iter().any(|c| c.text.starts_with("impl Thing")));
+
+        let python = "import os\n\n@cache\ndef load():\n    return 1\n\nclass Store:\n    def get(self):\n        pass\n";
+        let chunks = chunk_document(DocumentFormat::Code, python, 36);
+        assert_eq!(
+            texts(&chunks),
+            vec![
+                "import os\n",
+                "@cache\ndef load():\n    return 1\n",
+                "class Store:",
+                "    def get(self):\n        pass"
+            ]
+        );
+    }
+
+    #[test]
+    fn structured_and_plain_text_chunk_by_top_level_entries() {
+        let json = "{\n  \"a\": {\n    \"x\": \"}\"\n  },\n  \"b\": [1, 2],\n  \"c\": true\n}";
+        let chunks = chunk_document(DocumentFormat::Json, json, 30);
+        assert_eq!(chunks[0].text, "{\n  \"a\": {\n    \"x\": \"}\"\n  },");
+        assert!(chunks[1].text.starts_with("  \"b\""));
+
+        let yaml = "# config\nserver:\n  port: 80\nusers:\n  - alice\n  - bob\n";
+        let chunks = chunk_document(DocumentFormat::Yaml, yaml, 30);
+        assert_eq!(
+            texts(&chunks),
+            vec![
+                "# config\nserver:\n  port: 80",
+                "users:\n  - alice\n  - bob"
+            ]
+        );
+
+        let text = "first para\nstill first\n\nsecond para\n\nthird";
+        let chunks = chunk_document(DocumentFormat::Text, text, 25);
+        assert_eq!(chunks[0].text, "first para\nstill first\n");
+        assert_eq!(chunks[1].start_line, 4);
+
+        // Oversized blocks fall back to line splitting.
+        let long = "word\n".repeat(30);
+        let chunks = chunk_document(DocumentFormat::Text, &long, 50);
+        assert!(chunks.iter().all(|c| c.text.len() <= 50));
+        assert_eq!(chunks.last().unwrap().end_line, 30);
+    }
+
+    #[test]
+    fn html_and_json_are_normalized_before_chunking() {
+        let dir = tempfile::tempdir().unwrap();
+        let html = dir.path().join("page.html");
+        std::fs::write(&html, "<h1>Widgets</h1><p>Blue <b>widget</b> guide</p>").unwrap();
+        let text = extract_text(&html, DocumentFormat::Html).unwrap();
+        assert!(text.starts_with("Widgets\n=") || text.starts_with("# Widgets"));
+        assert!(text.contains("**widget**"));
+
+        let json = dir.path().join("data.json");
+        std::fs::write(&json, r#"{"a":1,"b":{"c":2}}"#).unwrap();
+        let text = extract_text(&json, DocumentFormat::Json).unwrap();
+        assert_eq!(chunk_document(DocumentFormat::Json, &text, 30).len(), 2);
+    }
+
+    /// A one-page PDF showing `text` in Helvetica, with a valid xref table.
+    fn tiny_pdf(text: &str) -> Vec<u8> {
+        let stream = format!("BT /F1 12 Tf 72 712 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", stream.len(), stream),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, body).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+
+    #[test]
+    fn pdf_text_is_extracted_and_garbage_is_an_error() {
+        let text = extract_pdf_text(&tiny_pdf("Quarterly widget report")).unwrap();
+        assert!(text.contains("Quarterly widget report"), "got {:?}", text);
+        assert!(extract_pdf_text(b"not a pdf").is_err());
+    }
+
+    #[test]
+    fn collection_files_match_pattern_and_format() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs/api")).unwrap();
+        for f in ["docs/a.html", "docs/api/b.html", "docs/c.md", "docs/d.png"] {
+            std::fs::write(dir.path().join(f), "x").unwrap();
+        }
+        let collection = QmdCollection {
+            name: "docs".into(),
+            path: "docs".into(),
+            pattern: "**/*.html".into(),
+            kind: "custom".into(),
+        };
+        let files = collection_files(&collection, dir.path());
+        let names: Vec<_> = files
+            .iter()
+            .map(|p| {
+                p.strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(names, vec!["docs/a.html", "docs/api/b.html"]);
+
+        let all = QmdCollection {
+            pattern: String::new(),
+            ..collection
+        };
+        assert_eq!(collection_files(&all, dir.path()).len(), 3);
+    }
+}
@@ -0,0 +1,551 @@
+//! local_embeddings — in-process sentence embeddings on CPU.
+//!
+//! Loads a BERT-family sentence-embedding model (all-MiniLM-L6-v2,
+//! bge-small-en, e5-small, ...) from a local directory laid out the way the
+//! Hugging Face hub ships it:
+//!
+//! - `config.json` — the standard BERT config (`hidden_size`, `vocab_size`, ...)
+//! - `model.safetensors` — weights (F32, F16 or BF16)
+//! - `tokenizer.json` or `vocab.txt` — WordPiece vocabulary
+//! - `1_Pooling/config.json` (optional) — CLS vs mean pooling
+//! - `sentence_bert_config.json` (optional) — `max_seq_length`
+//!
+//! The forward pass runs on candle's BERT implementation (pure Rust, CPU), so
+//! no network access and no native runtime are needed and memory search keeps
+//! its vector half on air-gapped machines.
+
+use crate::memory::embeddings::{sanitize_and_normalize, EmbeddingProvider};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config as BertConfig};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+/// Environment override for the model directory.
+pub const LOCAL_MODEL_ENV: &str = "OPENKRAB_LOCAL_EMBEDDING_MODEL";
+
+/// Model used when neither the config nor the environment names one.
+pub const DEFAULT_LOCAL_MODEL: &str = "all-MiniLM-L6-v2";
+
+/// Texts handed to one blocking task by `embed_batch`.
+pub const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Where a bare model name is looked up: `<data_dir>/models/<name>`.
+pub fn default_model_dir(name: &str) -> PathBuf {
+    crate::infra::data_dir().join("models").join(name)
+}
+
+/// Resolves the `model` setting to a directory: an existing path is used as
+/// is, anything else is treated as a name under `default_model_dir`.
+pub fn resolve_model_dir(model: Option<&str>) -> PathBuf {
+    let model = model
+        .map(str::to_string)
+        .or_else(|| std::env::var(LOCAL_MODEL_ENV).ok())
+        .unwrap_or_else(|| DEFAULT_LOCAL_MODEL.to_string());
+    let path = PathBuf::from(&model);
+    if path.is_dir() {
+        path
+    } else {
+        default_model_dir(&model)
+    }
+}
+
+// ─── Provider ─────────────────────────────────────────────────────────────────
+
+pub struct LocalEmbeddingProvider {
+    model: Arc<LocalEmbeddingModel>,
+    name: String,
+    batch_size: usize,
+}
+
+impl LocalEmbeddingProvider {
+    /// Loads the model in `dir`; fails if any required file is missing.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| DEFAULT_LOCAL_MODEL.to_string());
+        Ok(Self {
+            model: Arc::new(LocalEmbeddingModel::load(dir)?),
+            name,
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.model.config.hidden_size
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LocalEmbeddingProvider {
+    fn id(&self) -> &str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        &self.name
+    }
+
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        let mut res = self.embed_batch(&[text.to_string()]).await?;
+        Ok(res.pop().unwrap_or_default())
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.batch_size) {
+            let model = self.model.clone();
+            let chunk = chunk.to_vec();
+            let embedded = tokio::task::spawn_blocking(move || model.embed_all(&chunk))
+                .await
+                .map_err(|e| anyhow!("local embedding task failed: {}", e))??;
+            results.extend(embedded);
+        }
+        Ok(results)
+    }
+}
+
+// ─── Model ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pooling {
+    Cls,
+    Mean,
+}
+
+struct LocalEmbeddingModel {
+    tokenizer: WordPieceTokenizer,
+    config: BertConfig,
+    encoder: BertModel,
+    pooling: Pooling,
+    max_tokens: usize,
+}
+
+impl LocalEmbeddingModel {
+    fn load(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            bail!(
+                "local embedding model not found at {} (set {} or memory.model to a model directory)",
+                dir.display(),
+                LOCAL_MODEL_ENV
+            );
+        }
+        let config: BertConfig = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("config.json"))
+                .with_context(|| format!("reading {}", dir.join("config.json").display()))?,
+        )
+        .context("parsing config.json")?;
+        let weights_path = dir.join("model.safetensors");
+        let bytes = std::fs::read(&weights_path)
+            .with_context(|| format!("reading {}", weights_path.display()))?;
+        let encoder = load_encoder(&config, bytes)?;
+        let tokenizer = WordPieceTokenizer::from_dir(dir)?;
+
+        let pooling = match read_json(&dir.join("1_Pooling").join("config.json")) {
+            Some(v) if v["pooling_mode_cls_token"].as_bool() == Some(true) => Pooling::Cls,
+            _ => Pooling::Mean,
+        };
+        let mut max_tokens = config.max_position_embeddings.min(512);
+        if let Some(v) = read_json(&dir.join("sentence_bert_config.json")) {
+            if let Some(n) = v["max_seq_length"].as_u64() {
+                max_tokens = max_tokens.min(n as usize);
+            }
+        }
+
+        Ok(Self {
+            tokenizer,
+            config,
+            encoder,
+            pooling,
+            max_tokens,
+        })
+    }
+
+    /// Embeds `texts` as one padded batch.
+    fn embed_all(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let encoded: Vec<Vec<u32>> = texts
+            .iter()
+            .map(|t| self.tokenizer.encode(t, self.max_tokens))
+            .collect();
+        let width = encoded.iter().map(Vec::len).max().unwrap_or(0);
+        let mut ids = Vec::with_capacity(texts.len() * width);
+        let mut mask = Vec::with_capacity(texts.len() * width);
+        for seq in &encoded {
+            ids.extend(seq.iter().copied().chain(std::iter::repeat(0)).take(width));
+            mask.extend((0..width).map(|i| u32::from(i < seq.len())));
+        }
+
+        let device = &self.encoder.device;
+        let ids = Tensor::from_vec(ids, (texts.len(), width), device)?;
+        let mask = Tensor::from_vec(mask, (texts.len(), width), device)?;
+        let hidden = self
+            .encoder
+            .forward(&ids, &ids.zeros_like()?, Some(&mask))?;
+        let pooled = match self.pooling {
+            Pooling::Cls => hidden.narrow(1, 0, 1)?.squeeze(1)?,
+            Pooling::Mean => {
+                // Padding positions must not pull the average towards zero.
+                let mask = mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+                hidden
+                    .broadcast_mul(&mask)?
+                    .sum(1)?
+                    .broadcast_div(&mask.sum(1)?)?
+            }
+        };
+        let mut vectors = pooled.to_vec2::<f32>()?;
+        vectors.iter_mut().for_each(sanitize_and_normalize);
+        Ok(vectors)
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+// ─── Encoder ──────────────────────────────────────────────────────────────────
+
+/// Builds the encoder on CPU. F16 and BF16 checkpoints are widened to F32
+/// on load; weights under a `bert.` prefix are found via `model_type`.
+fn load_encoder(config: &BertConfig, weights: Vec<u8>) -> Result<BertModel> {
+    let vb = VarBuilder::from_buffered_safetensors(weights, DType::F32, &Device::Cpu)
+        .map_err(|e| anyhow!("parsing model.safetensors: {}", e))?;
+    BertModel::load(vb, config).map_err(|e| anyhow!("loading model.safetensors: {}", e))
+}
+
+// ─── Tokenizer ────────────────────────────────────────────────────────────────
+
+/// BERT's uncased/cased WordPiece tokenizer.
+pub struct WordPieceTokenizer {
+    vocab: HashMap<String, u32>,
+    lowercase: bool,
+    unk: u32,
+    cls: u32,
+    sep: u32,
+}
+
+impl WordPieceTokenizer {
+    pub fn new(vocab: HashMap<String, u32>, lowercase: bool) -> Result<Self> {
+        let id = |token: &str| {
+            vocab
+                .get(token)
+                .copied()
+                .ok_or_else(|| anyhow!("vocabulary has no {} token", token))
+        };
+        Ok(Self {
+            unk: id("[UNK]")?,
+            cls: id("[CLS]")?,
+            sep: id("[SEP]")?,
+            vocab,
+            lowercase,
+        })
+    }
+
+    /// Reads `tokenizer.json`, falling back to `vocab.txt` (lowercased, as
+    /// the uncased models it usually comes with expect).
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        if let Some(json) = read_json(&dir.join("tokenizer.json")) {
+            let vocab: HashMap<String, u32> = json["model"]["vocab"]
+                .as_object()
+                .ok_or_else(|| anyhow!("tokenizer.json has no WordPiece vocab"))?
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_u64()? as u32)))
+                .collect();
+            let lowercase = json["normalizer"]["lowercase"].as_bool().unwrap_or(true);
+            return Self::new(vocab, lowercase);
+        }
+        let path = dir.join("vocab.txt");
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {} (or tokenizer.json)", path.display()))?;
+        let vocab = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (line.trim_end().to_string(), i as u32))
+            .collect();
+        Self::new(vocab, true)
+    }
+
+    /// Token ids wrapped in `[CLS] … [SEP]`, at most `max_tokens` long.
+    pub fn encode(&self, text: &str, max_tokens: usize) -> Vec<u32> {
+        let budget = max_tokens.saturating_sub(2);
+        let mut ids = vec![self.cls];
+        for word in self.basic_tokens(text) {
+            self.word_pieces(&word, &mut ids);
+            if ids.len() > budget {
+                ids.truncate(budget + 1);
+                break;
+            }
+        }
+        ids.push(self.sep);
+        ids
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let by_id: HashMap<u32, &str> = self.vocab.iter().map(|(k, v)| (*v, k.as_str())).collect();
+        let ids = self.encode(text, usize::MAX);
+        ids[1..ids.len() - 1]
+            .iter()
+            .map(|id| by_id.get(id).copied().unwrap_or("[UNK]").to_string())
+            .collect()
+    }
+
+    /// Whitespace and punctuation splitting, with lowercasing and accent
+    /// stripping for uncased vocabularies.
+    fn basic_tokens(&self, text: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut current = String::new();
+        let flush = |current: &mut String, words: &mut Vec<String>| {
+            if !current.is_empty() {
+                words.push(std::mem::take(current));
+            }
+        };
+        let normalized: String = if self.lowercase {
+            text.to_lowercase()
+                .nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .collect()
+        } else {
+            text.to_string()
+        };
+        for ch in normalized.chars() {
+            if ch == '\0' || ch == '\u{fffd}' || (ch.is_control() && !ch.is_whitespace()) {
+                continue;
+            }
+            if ch.is_whitespace() {
+                flush(&mut current, &mut words);
+            } else if is_punctuation(ch) || is_cjk(ch) {
+                flush(&mut current, &mut words);
+                words.push(ch.to_string());
+            } else {
+                current.push(ch);
+            }
+        }
+        flush(&mut current, &mut words);
+        words
+    }
+
+    /// Greedy longest-match-first split into vocabulary pieces.
+    fn word_pieces(&self, word: &str, ids: &mut Vec<u32>) {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() > 100 {
+            ids.push(self.unk);
+            return;
+        }
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut end = chars.len();
+            let mut found = None;
+            while start < end {
+                let mut piece: String = chars[start..end].iter().collect();
+                if start > 0 {
+                    piece.insert_str(0, "##");
+                }
+                if let Some(&id) = self.vocab.get(&piece) {
+                    found = Some(id);
+                    break;
+                }
+                end -= 1;
+            }
+            match found {
+                Some(id) => pieces.push(id),
+                None => {
+                    ids.push(self.unk);
+                    return;
+                }
+            }
+            start = end;
+        }
+        ids.extend(pieces);
+    }
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c as u32, 0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F)
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace() && !is_combining_mark(c))
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF
+        | 0x3400..=0x4DBF
+        | 0x20000..=0x2A6DF
+        | 0x2A700..=0x2B73F
+        | 0x2B740..=0x2B81F
+        | 0x2B820..=0x2CEAF
+        | 0xF900..=0xFAFF
+        | 0x2F800..=0x2FA1F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOCAB: &[&str] = &[
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "un", "##want", "##ed", "want", ",", "runn", "##ing",
+        "the", "cat", "dog", "sat", "on", "mat", "!",
+    ];
+
+    fn vocab() -> HashMap<String, u32> {
+        VOCAB
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect()
+    }
+
+    /// Writes a two-layer, 8-wide BERT with deterministic pseudo-random
+    /// weights to `dir`.
+    fn write_tiny_model(dir: &Path) {
+        let (hidden, inner, layers, positions) = (8usize, 16usize, 2usize, 16usize);
+        std::fs::write(
+            dir.join("config.json"),
+            serde_json::json!({
+                "vocab_size": VOCAB.len(),
+                "hidden_size": hidden,
+                "num_hidden_layers": layers,
+                "num_attention_heads": 2,
+                "intermediate_size": inner,
+                "hidden_act": "gelu",
+                "hidden_dropout_prob": 0.1,
+                "max_position_embeddings": positions,
+                "type_vocab_size": 2,
+                "initializer_range": 0.02,
+                "layer_norm_eps": 1e-12,
+                "pad_token_id": 0
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("vocab.txt"), VOCAB.join("\n")).unwrap();
+
+        let mut seed = 0x2545_f491u32;
+        let mut random = |shape: &[usize]| -> Tensor {
+            let values: Vec<f32> = (0..shape.iter().product::<usize>())
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+                })
+                .collect();
+            Tensor::from_vec(values, shape, &Device::Cpu).unwrap()
+        };
+        let ones = |n: usize| Tensor::ones(n, DType::F32, &Device::Cpu).unwrap();
+        let mut tensors: HashMap<String, Tensor> = HashMap::from([
+            (
+                "embeddings.word_embeddings.weight".into(),
+                random(&[VOCAB.len(), hidden]),
+            ),
+            (
+                "embeddings.position_embeddings.weight".into(),
+                random(&[positions, hidden]),
+            ),
+            (
+                "embeddings.token_type_embeddings.weight".into(),
+                random(&[2, hidden]),
+            ),
+            ("embeddings.LayerNorm.weight".into(), ones(hidden)),
+            ("embeddings.LayerNorm.bias".into(), random(&[hidden])),
+        ]);
+        for i in 0..layers {
+            let p = format!("encoder.layer.{i}");
+            for (name, out, inp) in [
+                ("attention.self.query", hidden, hidden),
+                ("attention.self.key", hidden, hidden),
+                ("attention.self.value", hidden, hidden),
+                ("attention.output.dense", hidden, hidden),
+                ("intermediate.dense", inner, hidden),
+                ("output.dense", hidden, inner),
+            ] {
+                tensors.insert(format!("{p}.{name}.weight"), random(&[out, inp]));
+                tensors.insert(format!("{p}.{name}.bias"), random(&[out]));
+            }
+            for name in ["attention.output.LayerNorm", "output.LayerNorm"] {
+                tensors.insert(format!("{p}.{name}.weight"), ones(hidden));
+                tensors.insert(format!("{p}.{name}.bias"), random(&[hidden]));
+            }
+        }
+        candle_core::safetensors::save(&tensors, dir.join("model.safetensors")).unwrap();
+    }
+
+    #[test]
+    fn wordpiece_splits_words_punctuation_and_accents() {
+        let tokenizer = WordPieceTokenizer::new(vocab(), true).unwrap();
+        assert_eq!(
+            tokenizer.tokenize("UNwant\u{00E9}d,running"),
+            vec!["un", "##want", "##ed", ",", "runn", "##ing"]
+        );
+        assert_eq!(tokenizer.tokenize("the zebra!"), vec!["the", "[UNK]", "!"]);
+
+        let ids = tokenizer.encode("the cat sat on the mat", 5);
+        assert_eq!(ids, vec![2, 11, 12, 14, 3]);
+    }
+
+    #[tokio::test]
+    async fn tiny_model_embeds_normalized_vectors_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        write_tiny_model(dir.path());
+        let provider = LocalEmbeddingProvider::load(dir.path())
+            .unwrap()
+            .with_batch_size(2);
+        assert_eq!(provider.id(), "local");
+        assert_eq!(provider.dimensions(), 8);
+
+        let texts: Vec<String> = ["the cat sat on the mat", "the dog sat", "unwanted running!"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let batch = provider.embed_batch(&texts).await.unwrap();
+        assert_eq!(batch.len(), 3);
+        for (text, vector) in texts.iter().zip(&batch) {
+            assert_eq!(vector.len(), 8);
+            let norm: f32 = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-4);
+            let single = provider.embed_query(text).await.unwrap();
+            assert!(single.iter().zip(vector).all(|(a, b)| (a - b).abs() < 1e-6));
+        }
+        assert_ne!(batch[0], batch[1]);
+
+        // Text longer than the position table is truncated, not rejected.
+        let long = "the cat ".repeat(40);
+        assert_eq!(provider.embed_query(&long).await.unwrap().len(), 8);
+    }
+
+    #[test]
+    fn missing_model_or_tensors_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = LocalEmbeddingProvider::load(dir.path().join("absent"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains(LOCAL_MODEL_ENV));
+
+        write_tiny_model(dir.path());
+        let config: BertConfig =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("config.json")).unwrap())
+                .unwrap();
+        let deeper = BertConfig {
+            num_hidden_layers: 3,
+            ..config
+        };
+        let bytes = std::fs::read(dir.path().join("model.safetensors")).unwrap();
+        let err = load_encoder(&deeper, bytes).err().unwrap();
+        assert!(err.to_string().contains("encoder.layer.2"));
+    }
+}
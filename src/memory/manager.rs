@@ -1,5 +1,8 @@
+use crate::memory::backend_config::{QmdCollection, SessionSendPolicyConfig};
 use crate::memory::embeddings::EmbeddingProvider;
-use crate::memory::store::MemoryStore;
+use crate::memory::ingest::{self, DocumentFormat, CHUNK_MAX_CHARS};
+use crate::memory::scope::{self, MemoryScope, ScopeContext};
+use crate::memory::store::{ChunkFilter, MemoryStore};
 use anyhow::Result;
 use notify::{Config, Event, RecursiveMode, Watcher};
 use sha2::{Digest, Sha256};
@@ -66,9 +69,35 @@ pub fn chunk_markdown(content: &str, max_chars: usize) -> Vec<MemoryChunk> {
     chunks
 }
 
+/// Provider key for `embedding_cache` rows; providers are told apart by id
+/// and model alone.
+const CACHE_PROVIDER_KEY: &str = "";
+
+/// Bounds applied to `embedding_cache` after a workspace sync.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingCacheLimits {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for EmbeddingCacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(50_000),
+            max_age_days: None,
+        }
+    }
+}
+
 pub struct MemoryManager {
     pub store: MemoryStore,
     pub provider: Box<dyn EmbeddingProvider>,
+    pub cache_limits: EmbeddingCacheLimits,
+    /// Indexed by `sync_workspace` under their own `source` tag.
+    pub collections: Vec<QmdCollection>,
+    /// Decides which session transcripts are shared agent-wide; without one
+    /// every transcript stays private to its session.
+    pub scope_policy: Option<SessionSendPolicyConfig>,
 }
 
 impl std::fmt::Debug for MemoryManager {
@@ -81,7 +110,7 @@ impl std::fmt::Debug for MemoryManager {
 }
 
 use crate::memory::store::SearchResult;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::memory::mmr::{apply_mmr_to_results, MMRConfig};
 use crate::memory::temporal_decay::{apply_temporal_decay_to_results, TemporalDecayConfig};
@@ -95,6 +124,12 @@ pub struct HybridSearchOptions {
     pub temporal_decay: Option<TemporalDecayConfig>,
     pub mmr: Option<MMRConfig>,
     pub workspace_dir: PathBuf,
+    /// Only return chunks from these sources (`memory`, `session` or a
+    /// collection name); empty means all.
+    pub sources: Vec<String>,
+    /// Caller the search runs for; only its visible scopes are searched.
+    /// `None` searches every scope, for operator tools such as the CLI.
+    pub scope: Option<ScopeContext>,
 }
 
 impl Default for HybridSearchOptions {
@@ -107,13 +142,21 @@ impl Default for HybridSearchOptions {
             temporal_decay: None,
             mmr: None,
             workspace_dir: PathBuf::new(),
+            sources: Vec::new(),
+            scope: None,
         }
     }
 }
 
 impl MemoryManager {
     pub fn new(store: MemoryStore, provider: Box<dyn EmbeddingProvider>) -> Self {
-        Self { store, provider }
+        Self {
+            store,
+            provider,
+            cache_limits: EmbeddingCacheLimits::default(),
+            collections: Vec::new(),
+            scope_policy: None,
+        }
     }
 
     pub fn from_config(
@@ -121,7 +164,13 @@ impl MemoryManager {
         config: crate::memory::config::MemoryConfig,
     ) -> Result<Self> {
         let provider = config.create_provider()?;
-        Ok(Self { store, provider })
+        Ok(Self {
+            store,
+            provider,
+            cache_limits: config.cache_limits(),
+            collections: config.collections,
+            scope_policy: config.scope,
+        })
     }
 
     pub async fn search_hybrid(
@@ -129,17 +178,21 @@ impl MemoryManager {
         query: &str,
         opts: HybridSearchOptions,
     ) -> Result<Vec<SearchResult>> {
-        let model = self.provider.model();
+        let filter = ChunkFilter {
+            model: self.provider.model().to_string(),
+            sources: opts.sources.clone(),
+            scopes: opts.scope.as_ref().map(ScopeContext::visible_scopes),
+        };
         let candidates = opts.max_results * 2;
 
         // 1. Keyword search (FTS5) - with query expansion (OR)
         let (_original, _keywords, expanded) = crate::memory::query_expansion::expand_query_for_fts(query);
         let search_terms = if expanded.is_empty() { query } else { &expanded };
 
-        // Use an empty string for FTS model if we want to search all models, but for now we limit to current model
+        // Limited to the current model and the caller's sources and scopes
         let keyword_results = self
             .store
-            .search_fts(search_terms, model, candidates)
+            .search_fts(search_terms, &filter, candidates)
             .unwrap_or_default();
 
         // 2. Vector search (using sqlite-vec)
@@ -149,7 +202,7 @@ impl MemoryManager {
                 let has_v = query_vec.iter().any(|&x| x != 0.0);
                 let res = self
                     .store
-                    .search_vector(&query_vec, candidates)
+                    .search_vector(&query_vec, &filter, candidates)
                     .unwrap_or_default();
                 (res, has_v)
             }
@@ -214,36 +267,54 @@ impl MemoryManager {
     }
 
     pub async fn index_file(&self, workspace_dir: &Path, abs_path: &Path) -> Result<()> {
-        let content = std::fs::read_to_string(abs_path)?;
+        self.index_document(workspace_dir, abs_path, "memory", &MemoryScope::Global)
+            .await
+    }
+
+    /// Indexes one file of any supported format under `source` and `scope`.
+    /// Paths are stored relative to `workspace_dir` when the file lives
+    /// inside it.
+    pub async fn index_document(
+        &self,
+        workspace_dir: &Path,
+        abs_path: &Path,
+        source: &str,
+        scope: &MemoryScope,
+    ) -> Result<()> {
+        let scope = scope.tag();
+        let format = DocumentFormat::from_path(abs_path).unwrap_or(DocumentFormat::Text);
+        let content = ingest::extract_text(abs_path, format)?;
         let hash = hash_text(&content);
-        
+
         let rel_path = abs_path
-            .strip_prefix(workspace_dir)?
+            .strip_prefix(workspace_dir)
+            .unwrap_or(abs_path)
             .to_string_lossy()
             .replace("\\", "/");
-            
-        let source = "memory";
+
         let model = self.provider.model().to_string();
 
         // High-Performance Skip: Check if file hash + model + source matches
         if let Some(existing_hash) = self.store.get_file_hash(&rel_path, source) {
             if existing_hash == hash {
-                // Check if we have chunks for this model
-                if self.store.has_chunks_for_path(&rel_path, source, &model)? {
+                // Check if we have chunks for this model and scope
+                if self
+                    .store
+                    .has_chunks_for_path(&rel_path, source, &model, &scope)?
+                {
                     return Ok(());
                 }
             }
         }
 
-        let chunks = chunk_markdown(&content, 2000);
-        
+        let chunks = ingest::chunk_document(format, &content, CHUNK_MAX_CHARS);
+
+        // Only chunks whose text changed reach the provider
+        let embeddings = self.embed_chunks(&chunks).await?;
+
         // Clean up old entries
         self.store.delete_chunks_by_path(&rel_path, source, &model)?;
 
-        // Batch Embedding: 10x faster than sequential (where supported)
-        let chunk_texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
-        let embeddings = self.provider.embed_batch(&chunk_texts).await?;
-
         for (chunk, embedding_vec) in chunks.into_iter().zip(embeddings.into_iter()) {
             // Ensure vector index exists with these dimensions
             self.store.ensure_vector_index(embedding_vec.len())?;
@@ -261,6 +332,7 @@ impl MemoryManager {
                 chunk.end_line,
                 &chunk.hash,
                 &model,
+                &scope,
                 &chunk.text,
                 &embedding_vec,
             )?;
@@ -272,7 +344,13 @@ impl MemoryManager {
         Ok(())
     }
 
-    pub async fn warm_session(&self, session: &crate::sessions::Session) -> Result<()> {
+    /// Indexes a session transcript under the scope `scope_policy` assigns
+    /// it: agent-wide for sessions the policy shares, else the session's own.
+    pub async fn warm_session(
+        &self,
+        session: &crate::sessions::Session,
+        agent_id: Option<&str>,
+    ) -> Result<()> {
         let transcript_text = session
             .transcript
             .iter()
@@ -288,30 +366,36 @@ impl MemoryManager {
         let rel_path = format!("sessions/{}.md", session.id);
         let source = "session";
         let model = self.provider.model().to_string();
+        let scope = scope::transcript_scope(
+            self.scope_policy.as_ref(),
+            &ScopeContext::for_session(agent_id, session),
+        )
+        .tag();
+
+        let embeddings = self.embed_chunks(&chunks).await?;
 
         self.store
             .delete_chunks_by_path(&rel_path, source, &model)?;
 
-        for chunk in chunks {
-            if let Ok(embedding_vec) = self.provider.embed_query(&chunk.text).await {
-                let _ = self.store.ensure_vector_index(embedding_vec.len());
-                let chunk_id = hash_text(&format!(
-                    "{}:{}:{}:{}",
-                    rel_path, chunk.start_line, chunk.end_line, model
-                ));
-
-                let _ = self.store.insert_chunk(
-                    &chunk_id,
-                    &rel_path,
-                    source,
-                    chunk.start_line,
-                    chunk.end_line,
-                    &chunk.hash,
-                    &model,
-                    &chunk.text,
-                    &embedding_vec,
-                );
-            }
+        for (chunk, embedding_vec) in chunks.into_iter().zip(embeddings) {
+            let _ = self.store.ensure_vector_index(embedding_vec.len());
+            let chunk_id = hash_text(&format!(
+                "{}:{}:{}:{}",
+                rel_path, chunk.start_line, chunk.end_line, model
+            ));
+
+            let _ = self.store.insert_chunk(
+                &chunk_id,
+                &rel_path,
+                source,
+                chunk.start_line,
+                chunk.end_line,
+                &chunk.hash,
+                &model,
+                &scope,
+                &chunk.text,
+                &embedding_vec,
+            );
         }
 
         Ok(())
@@ -344,9 +428,81 @@ impl MemoryManager {
             }
         }
 
+        for collection in &self.collections {
+            for file_path in ingest::collection_files(collection, workspace_dir) {
+                println!("Indexing [{}]: {:?}", collection.name, file_path);
+                if let Err(e) = self
+                    .index_document(
+                        workspace_dir,
+                        &file_path,
+                        &collection.name,
+                        &MemoryScope::Global,
+                    )
+                    .await
+                {
+                    eprintln!("Failed to index {:?}: {}", file_path, e);
+                }
+            }
+        }
+
+        self.evict_embedding_cache()?;
+
         Ok(())
     }
 
+    /// Embeddings for `chunks`, served from `embedding_cache` where a chunk
+    /// with the same hash was embedded before. Only unseen text is sent to
+    /// the provider, once per distinct hash.
+    async fn embed_chunks(&self, chunks: &[MemoryChunk]) -> Result<Vec<Vec<f32>>> {
+        let provider = self.provider.id().to_string();
+        let model = self.provider.model().to_string();
+        let hashes: Vec<String> = chunks.iter().map(|c| c.hash.clone()).collect();
+        let mut embeddings =
+            self.store
+                .get_cached_embeddings(&provider, &model, CACHE_PROVIDER_KEY, &hashes)?;
+        let hits = chunks
+            .iter()
+            .filter(|c| embeddings.contains_key(&c.hash))
+            .count();
+
+        let mut seen = HashSet::new();
+        let missing: Vec<&MemoryChunk> = chunks
+            .iter()
+            .filter(|c| !embeddings.contains_key(&c.hash) && seen.insert(c.hash.as_str()))
+            .collect();
+        if !missing.is_empty() {
+            let texts: Vec<String> = missing.iter().map(|c| c.text.clone()).collect();
+            let fresh = self.provider.embed_batch(&texts).await?;
+            if fresh.len() != texts.len() {
+                anyhow::bail!(
+                    "{} returned {} embeddings for {} texts",
+                    provider,
+                    fresh.len(),
+                    texts.len()
+                );
+            }
+            let fresh: Vec<(String, Vec<f32>)> =
+                missing.iter().map(|c| c.hash.clone()).zip(fresh).collect();
+            self.store
+                .put_cached_embeddings(&provider, &model, CACHE_PROVIDER_KEY, &fresh)?;
+            embeddings.extend(fresh);
+        }
+        self.store
+            .record_cache_lookups(hits as u64, (chunks.len() - hits) as u64)?;
+
+        Ok(chunks.iter().map(|c| embeddings[&c.hash].clone()).collect())
+    }
+
+    /// Applies `cache_limits` to `embedding_cache`; returns the rows dropped.
+    pub fn evict_embedding_cache(&self) -> Result<usize> {
+        let cutoff = self.cache_limits.max_age_days.map(|days| {
+            (chrono::Utc::now() - chrono::Duration::days(days as i64)).timestamp_millis()
+        });
+        Ok(self
+            .store
+            .evict_embedding_cache(cutoff, self.cache_limits.max_entries)?)
+    }
+
     pub async fn watch_workspace(self: Arc<Self>, workspace_dir: PathBuf) -> Result<()> {
         let (tx, mut rx) = mpsc::channel(100);
 
@@ -391,3 +547,183 @@ impl MemoryManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records every text it is asked to embed.
+    struct Counting(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for Counting {
+        fn id(&self) -> &str {
+            "counting"
+        }
+
+        fn model(&self) -> &str {
+            "test"
+        }
+
+        async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+            let mut res = self.embed_batch(&[text.to_string()]).await?;
+            Ok(res.pop().unwrap_or_default())
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.0.lock().unwrap().extend(texts.iter().cloned());
+            Ok(texts
+                .iter()
+                .map(|t| vec![t.len() as f32, 1.0, 0.5])
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn reindexing_embeds_only_changed_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("MEMORY.md");
+        let paragraph = |tag: &str| format!("{} {}", tag, "x".repeat(1500));
+        std::fs::write(&file, [paragraph("a"), paragraph("b")].join("\n")).unwrap();
+
+        let embedded = Arc::new(Mutex::new(Vec::new()));
+        let manager = MemoryManager::new(
+            MemoryStore::open_in_memory().unwrap(),
+            Box::new(Counting(embedded.clone())),
+        );
+        manager.index_file(dir.path(), &file).await.unwrap();
+        assert_eq!(embedded.lock().unwrap().len(), 2);
+
+        // Edit the second chunk only; the first comes from the cache.
+        std::fs::write(&file, [paragraph("a"), paragraph("c")].join("\n")).unwrap();
+        manager.index_file(dir.path(), &file).await.unwrap();
+        let texts = embedded.lock().unwrap().clone();
+        assert_eq!(texts.len(), 3);
+        assert!(texts[2].starts_with("c "));
+
+        let stats = manager.store.memory_stats().unwrap();
+        assert_eq!(stats.chunks[0].chunks, 2);
+        assert_eq!(stats.cache_entries, 3);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (1, 3));
+
+        let manager = MemoryManager {
+            cache_limits: EmbeddingCacheLimits {
+                max_entries: Some(2),
+                max_age_days: None,
+            },
+            ..manager
+        };
+        assert_eq!(manager.evict_embedding_cache().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn collections_are_indexed_and_searchable_by_source() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("MEMORY.md"), "# Notes
+widget colours are blue").unwrap();
+        std::fs::create_dir_all(dir.path().join("site")).unwrap();
+        std::fs::write(
+            dir.path().join("site/guide.html"),
+            "<h1>Guide</h1><p>Every widget ships in a box.</p>",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("site/skip.txt"), "widget text").unwrap();
+
+        let manager = MemoryManager {
+            collections: vec![QmdCollection {
+                name: "docs".into(),
+                path: "site".into(),
+                pattern: "**/*.html".into(),
+                kind: "custom".into(),
+            }],
+            ..MemoryManager::new(
+                MemoryStore::open_in_memory().unwrap(),
+                Box::new(Counting(Arc::new(Mutex::new(Vec::new())))),
+            )
+        };
+        manager.sync_workspace(dir.path()).await.unwrap();
+
+        let stats = manager.store.memory_stats().unwrap();
+        let sources: Vec<_> = stats.chunks.iter().map(|c| c.source.as_str()).collect();
+        assert_eq!(sources, vec!["docs", "memory"]);
+
+        let search = |sources: Vec<String>| {
+            manager.search_hybrid(
+                "widget",
+                HybridSearchOptions {
+                    min_score: 0.0,
+                    sources,
+                    ..Default::default()
+                },
+            )
+        };
+        let all = search(Vec::new()).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let docs = search(vec!["docs".into()]).await.unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].path, "site/guide.html");
+        assert!(docs[0].text.contains("widget ships"));
+    }
+
+    #[tokio::test]
+    async fn dm_transcripts_do_not_leak_into_group_searches() {
+        use crate::memory::backend_config::PolicyRule;
+        use crate::sessions::{Session, TranscriptEntry};
+
+        let manager = MemoryManager {
+            // Group chats are shared with the whole agent; DMs stay private.
+            scope_policy: Some(SessionSendPolicyConfig {
+                default: "deny".into(),
+                rules: vec![PolicyRule {
+                    action: "allow".into(),
+                    chat_type: Some("group".into()),
+                    channel: None,
+                    session_key: None,
+                }],
+            }),
+            ..MemoryManager::new(
+                MemoryStore::open_in_memory().unwrap(),
+                Box::new(Counting(Arc::new(Mutex::new(Vec::new())))),
+            )
+        };
+        let session = |id: &str, chat_type: &str, text: &str| {
+            let mut session = Session::new(id);
+            session.channel = Some(id.split(':').next().unwrap().to_string());
+            session.chat_type = Some(chat_type.to_string());
+            session.append_transcript(TranscriptEntry::user(text));
+            session
+        };
+        let dm = session("telegram:dm:42", "direct", "my widget pin is 1234");
+        let group = session("slack:group:c1", "group", "widget launch is friday");
+        manager.warm_session(&dm, Some("main")).await.unwrap();
+        manager.warm_session(&group, Some("main")).await.unwrap();
+
+        let search = |scope: Option<ScopeContext>| {
+            manager.search_hybrid(
+                "widget",
+                HybridSearchOptions {
+                    min_score: 0.0,
+                    scope,
+                    ..Default::default()
+                },
+            )
+        };
+        let paths = |results: Vec<SearchResult>| {
+            let mut paths: Vec<String> = results.into_iter().map(|r| r.path).collect();
+            paths.sort();
+            paths
+        };
+
+        let in_group = search(Some(ScopeContext::for_session(Some("main"), &group)));
+        assert_eq!(
+            paths(in_group.await.unwrap()),
+            vec!["sessions/slack:group:c1.md"]
+        );
+        let in_dm = search(Some(ScopeContext::for_session(Some("main"), &dm)));
+        assert_eq!(paths(in_dm.await.unwrap()).len(), 2);
+        let other_agent = search(Some(ScopeContext::for_session(Some("ops"), &group)));
+        assert!(other_agent.await.unwrap().is_empty());
+        assert_eq!(paths(search(None).await.unwrap()).len(), 2);
+    }
+}
\ No newline at end of file
@@ -1,20 +1,30 @@
+pub mod backend;
 pub mod backend_config;
 pub mod config;
 pub mod embeddings;
+pub mod ingest;
+pub mod local_embeddings;
 pub mod manager;
 pub mod mmr;
+pub mod qmd;
 pub mod query_expansion;
 pub mod schema;
+pub mod scope;
 pub mod store;
 pub mod temporal_decay;
 
+pub use backend::{create_backend, BuiltinBackend, CitationMode, MemoryBackend};
 pub use backend_config::{resolve_memory_backend_config, MemoryBackendConfig, QmdConfig};
 pub use config::MemoryConfig;
 pub use embeddings::{
     EmbeddingProvider, GeminiProvider, OllamaProvider, OpenAiProvider, VoyageProvider,
 };
-pub use manager::{HybridSearchOptions, MemoryManager};
+pub use local_embeddings::LocalEmbeddingProvider;
+pub use ingest::DocumentFormat;
+pub use manager::{EmbeddingCacheLimits, HybridSearchOptions, MemoryManager};
 pub use mmr::{apply_mmr_to_results, mmr_rerank, MMRConfig, MMRItem};
+pub use qmd::QmdBackend;
 pub use query_expansion::{expand_query_for_fts, extract_keywords};
-pub use store::MemoryStore;
+pub use scope::{MemoryScope, ScopeContext};
+pub use store::{ChunkCount, ChunkFilter, MemoryStats, MemoryStore};
 pub use temporal_decay::{apply_temporal_decay_to_results, TemporalDecayConfig, TemporalDecayItem};
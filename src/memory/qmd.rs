@@ -0,0 +1,685 @@
+//! QMD memory backend — recall served by the external `qmd` sidecar.
+//!
+//! Every agent gets a self-contained QMD home under
+//! `~/.openkrab/agents/<agentId>/qmd/` (`XDG_CONFIG_HOME`/`XDG_CACHE_HOME`
+//! point inside it). Collections are registered with `qmd collection add`,
+//! then `qmd update` and `qmd embed` run on boot and on the configured
+//! intervals. Searches run `qmd <searchMode> --json`, retrying with
+//! `qmd query` when the chosen mode fails, and fall back to the builtin
+//! index when QMD is unavailable.
+
+use crate::memory::backend::{CitationMode, MemoryBackend};
+use crate::memory::backend_config::{scope_collection_base, QmdCollection, QmdConfig};
+use crate::memory::manager::HybridSearchOptions;
+use crate::memory::scope::policy_allows;
+use crate::memory::store::SearchResult;
+use crate::sessions::Session;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Floor for the maintenance tick, whatever the configured intervals.
+const MIN_TICK_MS: u64 = 1_000;
+
+/// `@@ -<line>,<count> @@` header QMD puts in front of snippets.
+static SNIPPET_HEADER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? @@[^\n]*\n?").unwrap());
+
+#[derive(Debug, Default)]
+struct QmdState {
+    collections_ready: bool,
+    last_update: Option<Instant>,
+    last_embed: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub struct QmdBackend {
+    config: Arc<QmdConfig>,
+    workspace_dir: PathBuf,
+    home: PathBuf,
+    citations: CitationMode,
+    fallback: Option<Arc<dyn MemoryBackend>>,
+    /// Held for a whole update or embed run so cycles never overlap.
+    state: Arc<tokio::sync::Mutex<QmdState>>,
+    /// When a transcript was exported that the index hasn't picked up yet.
+    dirty_since: Arc<std::sync::Mutex<Option<Instant>>>,
+    started: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for QmdBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QmdBackend")
+            .field("command", &self.config.command)
+            .field("home", &self.home)
+            .field("collections", &self.config.collections.len())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl QmdBackend {
+    /// `config` as resolved by `resolve_memory_backend_config`. When session
+    /// export is enabled the export directory is added as a collection.
+    pub fn new(config: QmdConfig, agent_id: &str, workspace_dir: PathBuf) -> Self {
+        let home = PathBuf::from(crate::utils::resolve_config_dir())
+            .join("agents")
+            .join(crate::routing::session_key::normalize_agent_id(Some(
+                agent_id,
+            )))
+            .join("qmd");
+        Self::with_home(config, agent_id, workspace_dir, home)
+    }
+
+    pub fn with_home(
+        mut config: QmdConfig,
+        agent_id: &str,
+        workspace_dir: PathBuf,
+        home: PathBuf,
+    ) -> Self {
+        if config.sessions.enabled {
+            let export_dir = config
+                .sessions
+                .export_dir
+                .clone()
+                .unwrap_or_else(|| home.join("sessions").to_string_lossy().to_string());
+            config.collections.push(QmdCollection {
+                name: scope_collection_base("sessions", agent_id),
+                path: export_dir.clone(),
+                pattern: "**/*.md".to_string(),
+                kind: "sessions".to_string(),
+            });
+            config.sessions.export_dir = Some(export_dir);
+        }
+        Self {
+            config: Arc::new(config),
+            workspace_dir,
+            home,
+            citations: CitationMode::Auto,
+            fallback: None,
+            state: Arc::new(tokio::sync::Mutex::new(QmdState::default())),
+            dirty_since: Arc::new(std::sync::Mutex::new(None)),
+            started: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_citations(mut self, citations: CitationMode) -> Self {
+        self.citations = citations;
+        self
+    }
+
+    /// Backend searched when QMD fails or is missing.
+    pub fn with_fallback(mut self, fallback: Arc<dyn MemoryBackend>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    // ─── Commands ───────────────────────────────────────────────────────────
+
+    /// Runs `qmd <args>` in the agent's QMD home, killing it after
+    /// `timeout_ms`. Returns stdout.
+    async fn run(&self, args: &[&str], timeout_ms: u64) -> Result<String> {
+        let config_home = self.home.join("xdg-config");
+        let cache_home = self.home.join("xdg-cache");
+        std::fs::create_dir_all(&config_home)?;
+        std::fs::create_dir_all(&cache_home)?;
+
+        let label = format!("{} {}", self.config.command, args.first().unwrap_or(&""));
+        let child = tokio::process::Command::new(&self.config.command)
+            .args(args)
+            .current_dir(&self.workspace_dir)
+            .env("XDG_CONFIG_HOME", &config_home)
+            .env("XDG_CACHE_HOME", &cache_home)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(Duration::from_millis(timeout_ms), child)
+            .await
+            .map_err(|_| anyhow!("`{}` timed out after {}ms", label, timeout_ms))?
+            .with_context(|| format!("failed to run `{}`", label))?;
+        if !output.status.success() {
+            bail!(
+                "`{}` exited with {}: {}",
+                label,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Registers every collection with QMD. Already-known collections are
+    /// fine; other failures are retried on the next cycle.
+    async fn ensure_collections(&self, state: &mut QmdState) {
+        if state.collections_ready {
+            return;
+        }
+        let mut ready = true;
+        for collection in &self.config.collections {
+            if collection.kind == "sessions" {
+                let _ = std::fs::create_dir_all(&collection.path);
+            }
+            let args = [
+                "collection",
+                "add",
+                collection.path.as_str(),
+                "--name",
+                collection.name.as_str(),
+                "--mask",
+                collection.pattern.as_str(),
+            ];
+            match self.run(&args, self.config.update.command_timeout_ms).await {
+                Ok(_) => {}
+                Err(e) if e.to_string().contains("exists") => {}
+                Err(e) => {
+                    tracing::warn!("qmd: could not add collection {}: {}", collection.name, e);
+                    ready = false;
+                }
+            }
+        }
+        state.collections_ready = ready;
+    }
+
+    /// Re-scans collections (`qmd update`) after pruning expired transcripts.
+    pub async fn update(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        self.prune_sessions()?;
+        self.ensure_collections(&mut state).await;
+        self.run(&["update"], self.config.update.update_timeout_ms)
+            .await?;
+        state.last_update = Some(Instant::now());
+        *self.dirty_since.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Computes missing embeddings (`qmd embed`).
+    pub async fn embed(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        self.run(&["embed"], self.config.update.embed_timeout_ms)
+            .await?;
+        state.last_embed = Some(Instant::now());
+        Ok(())
+    }
+
+    /// One maintenance tick: update once the interval has passed or an
+    /// exported transcript has settled for `debounce_ms`, embed once the
+    /// embed interval has passed.
+    async fn maintain(&self) {
+        let update = &self.config.update;
+        let (last_update, last_embed) = {
+            let state = self.state.lock().await;
+            (state.last_update, state.last_embed)
+        };
+        let elapsed = |at: Option<Instant>, ms: u64| {
+            at.is_none_or(|t| t.elapsed() >= Duration::from_millis(ms))
+        };
+        let settled = self
+            .dirty_since
+            .lock()
+            .unwrap()
+            .is_some_and(|t| t.elapsed() >= Duration::from_millis(update.debounce_ms));
+        if settled || elapsed(last_update, update.interval_ms) {
+            if let Err(e) = self.update().await {
+                tracing::warn!("qmd update failed: {}", e);
+            }
+        }
+        if elapsed(last_embed, update.embed_interval_ms) {
+            if let Err(e) = self.embed().await {
+                tracing::warn!("qmd embed failed: {}", e);
+            }
+        }
+    }
+
+    // ─── Sessions ───────────────────────────────────────────────────────────
+
+    fn export_dir(&self) -> Option<PathBuf> {
+        self.config
+            .sessions
+            .export_dir
+            .as_ref()
+            .filter(|_| self.config.sessions.enabled)
+            .map(PathBuf::from)
+    }
+
+    /// Deletes exported transcripts untouched for `retention_days`.
+    fn prune_sessions(&self) -> Result<()> {
+        let (Some(dir), Some(days)) = (self.export_dir(), self.config.sessions.retention_days)
+        else {
+            return Ok(());
+        };
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Ok(());
+        };
+        let cutoff = SystemTime::now() - Duration::from_secs(u64::from(days) * 86_400);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let expired = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified < cutoff);
+            if expired && path.extension().is_some_and(|ext| ext == "md") {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // ─── Results ────────────────────────────────────────────────────────────
+
+    /// Workspace-relative path for hits inside the workspace, otherwise
+    /// `qmd/<collection>/<relative-path>`.
+    fn display_path(&self, collection: Option<&QmdCollection>, name: &str, rel: &str) -> String {
+        if let Some(collection) = collection {
+            let abs = Path::new(&collection.path).join(rel);
+            if let Ok(inside) = abs.strip_prefix(&self.workspace_dir) {
+                return inside.to_string_lossy().replace('\\', "/");
+            }
+        }
+        format!("qmd/{}/{}", name, rel)
+    }
+
+    fn parse_results(&self, stdout: &str) -> Result<Vec<SearchResult>> {
+        let value: serde_json::Value =
+            serde_json::from_str(stdout.trim()).context("qmd returned invalid JSON")?;
+        let hits = match value {
+            serde_json::Value::Object(mut obj) => obj.remove("results").unwrap_or_default(),
+            other => other,
+        };
+        let hits: Vec<QmdHit> = serde_json::from_value(hits).context("unexpected qmd output")?;
+
+        Ok(hits
+            .into_iter()
+            .map(|hit| {
+                let file = hit.file.trim();
+                let (name, rel) = file
+                    .strip_prefix("qmd://")
+                    .and_then(|rest| rest.split_once('/'))
+                    .unwrap_or(("qmd", file));
+                let collection = self.config.collections.iter().find(|c| c.name == name);
+                let (start_line, end_line, text) = split_snippet(&hit.snippet);
+                SearchResult {
+                    id: hit.docid.unwrap_or_else(|| file.to_string()),
+                    path: self.display_path(collection, name, rel),
+                    source: name.to_string(),
+                    model: "qmd".to_string(),
+                    start_line,
+                    end_line,
+                    text,
+                    score: hit.score,
+                }
+            })
+            .collect())
+    }
+
+    async fn run_search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let limit = limit.to_string();
+        let timeout = self.config.limits.timeout_ms;
+        let mode = self.config.search_mode.as_str();
+        let stdout = match self
+            .run(&[mode, query, "--json", "-n", &limit], timeout)
+            .await
+        {
+            Ok(stdout) => stdout,
+            Err(e) if mode != "query" => {
+                tracing::debug!("qmd {} failed, retrying with query: {}", mode, e);
+                self.run(&["query", query, "--json", "-n", &limit], timeout)
+                    .await?
+            }
+            Err(e) => return Err(e),
+        };
+        self.parse_results(&stdout)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct QmdHit {
+    #[serde(default)]
+    docid: Option<String>,
+    #[serde(default)]
+    score: f64,
+    #[serde(default, alias = "path")]
+    file: String,
+    #[serde(default, alias = "body", alias = "content")]
+    snippet: String,
+}
+
+/// Line range and text of a snippet, without its `@@` header.
+fn split_snippet(snippet: &str) -> (i32, i32, String) {
+    match SNIPPET_HEADER.captures(snippet) {
+        Some(caps) => {
+            let start: i32 = caps[1].parse().unwrap_or(1);
+            let text = snippet[caps[0].len()..].to_string();
+            let count = caps
+                .get(2)
+                .and_then(|m| m.as_str().parse::<i32>().ok())
+                .unwrap_or_else(|| text.lines().count() as i32);
+            (start, start + count.max(1) - 1, text)
+        }
+        None => (
+            1,
+            snippet.lines().count().max(1) as i32,
+            snippet.to_string(),
+        ),
+    }
+}
+
+fn clip(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Plain-text transcript of the user and assistant turns of `session`.
+fn render_transcript(session: &Session) -> Option<String> {
+    let turns: Vec<String> = session
+        .transcript
+        .iter()
+        .filter(|entry| !entry.text.trim().is_empty())
+        .filter_map(|entry| {
+            let speaker = match entry.role.as_str() {
+                "user" => "User",
+                "assistant" => "Assistant",
+                _ => return None,
+            };
+            Some(format!(
+                "{}: {}",
+                speaker,
+                entry.text.trim().replace('\r', "")
+            ))
+        })
+        .collect();
+    if turns.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "# Session {}\n\n{}\n",
+        session.id,
+        turns.join("\n\n")
+    ))
+}
+
+#[async_trait]
+impl MemoryBackend for QmdBackend {
+    fn id(&self) -> &str {
+        "qmd"
+    }
+
+    fn citations(&self) -> CitationMode {
+        self.citations
+    }
+
+    /// Arms the maintenance loop. With `wait_for_boot_sync` the boot refresh
+    /// finishes before this returns; otherwise it runs in the background.
+    async fn start(&self) -> Result<()> {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let update = &self.config.update;
+        if !update.on_boot {
+            let mut state = self.state.lock().await;
+            state.last_update = Some(Instant::now());
+            state.last_embed = Some(Instant::now());
+        } else if update.wait_for_boot_sync {
+            if let Err(e) = self.sync().await {
+                tracing::warn!("qmd boot sync failed: {}", e);
+            }
+        }
+
+        let tick = update.debounce_ms.min(update.interval_ms).max(MIN_TICK_MS);
+        let backend = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_millis(tick));
+            loop {
+                ticker.tick().await;
+                backend.maintain().await;
+            }
+        });
+        Ok(())
+    }
+
+    async fn sync(&self) -> Result<()> {
+        self.update().await?;
+        self.embed().await
+    }
+
+    async fn search(&self, query: &str, opts: HybridSearchOptions) -> Result<Vec<SearchResult>> {
+        if let (Some(policy), Some(ctx)) = (&self.config.scope, &opts.scope) {
+            if !policy_allows(policy, ctx) {
+                tracing::warn!(
+                    "qmd search denied by memory scope (channel={:?}, chat_type={:?})",
+                    ctx.channel,
+                    ctx.chat_type
+                );
+                return Ok(Vec::new());
+            }
+        }
+
+        let limits = &self.config.limits;
+        let limit = opts.max_results.min(limits.max_results).max(1);
+        let mut results = match self.run_search(query, limit).await {
+            Ok(results) => results,
+            Err(e) => match &self.fallback {
+                Some(fallback) => {
+                    tracing::warn!("qmd search failed, using {} index: {}", fallback.id(), e);
+                    return fallback.search(query, opts).await;
+                }
+                None => return Err(e),
+            },
+        };
+
+        results.retain(|r| r.score >= opts.min_score);
+        if !opts.sources.is_empty() {
+            results.retain(|r| opts.sources.contains(&r.source));
+        }
+        results.truncate(limit);
+
+        let mut injected = 0;
+        let mut kept = Vec::with_capacity(results.len());
+        for mut result in results {
+            result.text = clip(&result.text, limits.max_snippet_chars);
+            injected += result.text.chars().count();
+            if injected > limits.max_injected_chars && !kept.is_empty() {
+                break;
+            }
+            kept.push(result);
+        }
+        Ok(kept)
+    }
+
+    /// Exports the transcript into the sessions collection; the next
+    /// maintenance tick indexes it once `debounce_ms` has passed.
+    async fn record_session(&self, session: &Session, _agent_id: Option<&str>) -> Result<()> {
+        let Some(dir) = self.export_dir() else {
+            return Ok(());
+        };
+        let Some(transcript) = render_transcript(session) else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(&dir)?;
+        let name: String = session
+            .id
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        std::fs::write(dir.join(format!("{}.md", name)), transcript)?;
+        self.dirty_since
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::memory::backend_config::{
+        resolve_memory_backend_config, MemoryBackendConfig, PolicyRule, SessionSendPolicyConfig,
+    };
+    use crate::memory::scope::ScopeContext;
+    use crate::sessions::TranscriptEntry;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A fake `qmd` that logs its arguments. `search` is rejected so the
+    /// `query` retry is exercised.
+    fn fake_qmd(dir: &Path) -> String {
+        let script = dir.join("qmd");
+        std::fs::write(
+            &script,
+            r##"#!/bin/sh
+echo "$@" >> "$XDG_CACHE_HOME/calls.log"
+case "$1" in
+  search) echo "unknown flag --json" >&2; exit 2 ;;
+  query) cat <<'EOF'
+[{"docid":"#a1","score":0.8,"file":"qmd://notes/ideas.md","snippet":"@@ -3,2 @@ (2 before)\nwidgets\nare blue"},
+ {"docid":"#b2","score":0.6,"file":"qmd://archive/old.md","snippet":"widget history"},
+ {"docid":"#c3","score":0.01,"file":"qmd://notes/noise.md","snippet":"noise"}]
+EOF
+  ;;
+esac
+"##,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script.to_string_lossy().to_string()
+    }
+
+    fn backend(dir: &Path, edit: impl FnOnce(&mut QmdConfig)) -> QmdBackend {
+        let workspace = dir.join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let resolved = resolve_memory_backend_config(
+            Some(MemoryBackendConfig {
+                backend: "qmd".into(),
+                citations: "auto".into(),
+                qmd: None,
+            }),
+            "main",
+            &workspace.to_string_lossy(),
+        );
+        let mut config = resolved.qmd.unwrap();
+        config.command = fake_qmd(dir);
+        config.collections.push(QmdCollection {
+            name: "notes".into(),
+            path: workspace.join("notes").to_string_lossy().to_string(),
+            pattern: "**/*.md".into(),
+            kind: "custom".into(),
+        });
+        edit(&mut config);
+        QmdBackend::with_home(config, "main", workspace, dir.join("home"))
+    }
+
+    fn calls(dir: &Path) -> String {
+        std::fs::read_to_string(dir.join("home/xdg-cache/calls.log")).unwrap_or_default()
+    }
+
+    #[test]
+    fn snippet_headers_give_line_ranges() {
+        assert_eq!(
+            split_snippet("@@ -3,2 @@ (2 before)\na\nb"),
+            (3, 4, "a\nb".into())
+        );
+        assert_eq!(split_snippet("one\ntwo"), (1, 2, "one\ntwo".into()));
+    }
+
+    #[tokio::test]
+    async fn search_retries_with_query_and_maps_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let qmd = backend(dir.path(), |c| c.limits.max_snippet_chars = 10);
+        let opts = HybridSearchOptions {
+            min_score: 0.1,
+            ..Default::default()
+        };
+        let results = qmd.search("widget", opts).await.unwrap();
+
+        assert_eq!(
+            calls(dir.path()),
+            "search widget --json -n 6\nquery widget --json -n 6\n"
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].path, "notes/ideas.md");
+        assert_eq!((results[0].start_line, results[0].end_line), (3, 4));
+        assert_eq!(results[0].text, "widgets\nar…");
+        assert_eq!(results[1].path, "qmd/archive/old.md");
+        assert_eq!(results[1].source, "archive");
+    }
+
+    #[tokio::test]
+    async fn scope_policy_blocks_group_searches() {
+        let dir = tempfile::tempdir().unwrap();
+        let qmd = backend(dir.path(), |c| {
+            c.scope = Some(SessionSendPolicyConfig {
+                default: "deny".into(),
+                rules: vec![PolicyRule {
+                    action: "allow".into(),
+                    chat_type: Some("direct".into()),
+                    channel: None,
+                    session_key: None,
+                }],
+            })
+        });
+        let opts = |chat_type: &str| HybridSearchOptions {
+            min_score: 0.0,
+            scope: Some(ScopeContext {
+                chat_type: Some(chat_type.into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(qmd
+            .search("widget", opts("group"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(calls(dir.path()).is_empty());
+        assert_eq!(qmd.search("widget", opts("direct")).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn sync_registers_collections_and_exports_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let qmd = backend(dir.path(), |c| {
+            c.sessions.enabled = true;
+            c.sessions.retention_days = Some(7);
+        });
+        let export_dir = dir.path().join("home/sessions");
+        std::fs::create_dir_all(&export_dir).unwrap();
+        let stale = std::fs::File::create(export_dir.join("stale.md")).unwrap();
+        stale
+            .set_modified(SystemTime::now() - Duration::from_secs(30 * 86_400))
+            .unwrap();
+
+        let mut session = Session::new("telegram:dm:42");
+        session.append_transcript(TranscriptEntry::user("remember the widget"));
+        session.append_transcript(TranscriptEntry::assistant("noted"));
+        session.append_transcript(TranscriptEntry::system("internal"));
+        qmd.record_session(&session, Some("main")).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(export_dir.join("telegram_dm_42.md")).unwrap(),
+            "# Session telegram:dm:42\n\nUser: remember the widget\n\nAssistant: noted\n"
+        );
+
+        qmd.sync().await.unwrap();
+        assert!(!export_dir.join("stale.md").exists());
+        let log = calls(dir.path());
+        assert!(log.contains(&format!(
+            "collection add {} --name sessions-main --mask **/*.md",
+            export_dir.display()
+        )));
+        assert!(log.contains("--name notes --mask **/*.md"));
+        assert!(log.ends_with("update\nembed\n"));
+        assert!(qmd.dirty_since.lock().unwrap().is_none());
+    }
+}
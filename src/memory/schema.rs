@@ -31,10 +31,12 @@ pub fn ensure_schema(conn: &Connection) -> Result<()> {
             model TEXT NOT NULL,
             text TEXT NOT NULL,
             embedding TEXT NOT NULL,
-            updated_at INTEGER NOT NULL
+            updated_at INTEGER NOT NULL,
+            scope TEXT NOT NULL DEFAULT 'global'
         )",
         [],
     )?;
+    migrate_chunk_scope(conn)?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS embedding_cache (
@@ -65,6 +67,11 @@ pub fn ensure_schema(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chunks_scope ON chunks(scope)",
+        [],
+    )?;
+
     conn.execute(
         "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
             text,
@@ -118,9 +125,117 @@ pub fn ensure_schema(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    migrate_session_transcripts(conn)?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS usage_records (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            agent TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL,
+            cost_usd REAL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_usage_records_created_at ON usage_records(created_at)",
+        [],
+    )?;
+
     Ok(())
 }
 
+/// Add `chunks.scope` to databases created before memory scopes; existing
+/// chunks become `global`, which is what every search saw until then.
+fn migrate_chunk_scope(conn: &Connection) -> Result<()> {
+    let has_scope = conn
+        .prepare("SELECT 1 FROM pragma_table_info('chunks') WHERE name = 'scope'")?
+        .exists([])?;
+    if !has_scope {
+        conn.execute(
+            "ALTER TABLE chunks ADD COLUMN scope TEXT NOT NULL DEFAULT 'global'",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Transcript format written by this build, tracked in `meta`.
+pub const SESSION_TRANSCRIPT_VERSION: u32 = 2;
+const SESSION_TRANSCRIPT_VERSION_KEY: &str = "sessions.transcript_version";
+
+/// Upgrade stored `sessions.transcript` JSON to the structured format.
+///
+/// Version 1 rows hold plain `{role, text, timestamp}` entries. They are
+/// rewritten as structured entries with tool calls and results re-paired;
+/// entries missing fields are rebuilt from whatever `role`/`text` they have.
+fn migrate_session_transcripts(conn: &Connection) -> Result<()> {
+    use crate::sessions::{repair_transcript, TranscriptEntry};
+
+    let current: u32 = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = ?1",
+            [SESSION_TRANSCRIPT_VERSION_KEY],
+            |row| row.get::<_, String>(0),
+        )
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+    if current >= SESSION_TRANSCRIPT_VERSION {
+        return Ok(());
+    }
+
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, transcript FROM sessions")?;
+        let mapped = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        mapped.collect::<Result<_>>()?
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    for (id, transcript_json) in rows {
+        let raw: Vec<serde_json::Value> =
+            serde_json::from_str(&transcript_json).unwrap_or_default();
+        let entries: Vec<TranscriptEntry> = raw
+            .into_iter()
+            .filter_map(|value| {
+                serde_json::from_value(value.clone()).ok().or_else(|| {
+                    let text = value["text"].as_str()?;
+                    let mut entry = match value["role"].as_str().unwrap_or("user") {
+                        "assistant" => TranscriptEntry::assistant(text),
+                        "system" => TranscriptEntry::system(text),
+                        _ => TranscriptEntry::user(text),
+                    };
+                    if let Some(ts) = value["timestamp"]
+                        .as_str()
+                        .and_then(|t| t.parse::<chrono::DateTime<chrono::Utc>>().ok())
+                    {
+                        entry.timestamp = ts;
+                    }
+                    Some(entry)
+                })
+            })
+            .collect();
+        let migrated =
+            serde_json::to_string(&repair_transcript(entries)).unwrap_or_else(|_| "[]".to_string());
+        tx.execute(
+            "UPDATE sessions SET transcript = ?1 WHERE id = ?2",
+            rusqlite::params![migrated, id],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        rusqlite::params![
+            SESSION_TRANSCRIPT_VERSION_KEY,
+            SESSION_TRANSCRIPT_VERSION.to_string()
+        ],
+    )?;
+    tx.commit()
+}
+
 pub fn ensure_vector_table(conn: &Connection, dimensions: usize) -> Result<()> {
     conn.execute(
         &format!(
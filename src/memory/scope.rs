@@ -0,0 +1,288 @@
+//! Memory scopes — which chats may recall which chunks.
+//!
+//! Every chunk is indexed under one scope tag (`global`, `agent:<id>`,
+//! `channel:<name>` or `session:<key>`). Searches run on behalf of a
+//! [`ScopeContext`] and only see the tags that context owns, so a private
+//! DM transcript never surfaces in a group chat. How widely a session
+//! transcript is shared is decided by a [`SessionSendPolicyConfig`]: sessions
+//! the policy allows are indexed for their whole agent, the rest stay
+//! private to the session.
+
+use crate::channels::chat_type::{normalize_chat_type, ChatType};
+use crate::memory::backend_config::{PolicyRule, SessionSendPolicyConfig};
+use crate::sessions::Session;
+
+pub const GLOBAL_SCOPE: &str = "global";
+
+// ─── Scope tags ─────────────────────────────────────────────────────────────
+
+/// Namespace a chunk is indexed under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryScope {
+    Global,
+    Agent(String),
+    Channel(String),
+    Session(String),
+}
+
+impl MemoryScope {
+    /// Value stored in `chunks.scope`.
+    pub fn tag(&self) -> String {
+        match self {
+            Self::Global => GLOBAL_SCOPE.to_string(),
+            Self::Agent(id) => format!("agent:{}", normalize(id)),
+            Self::Channel(name) => format!("channel:{}", normalize(name)),
+            Self::Session(key) => format!("session:{}", normalize(key)),
+        }
+    }
+
+    /// Inverse of [`MemoryScope::tag`]; unknown tags read as `None`.
+    pub fn parse(tag: &str) -> Option<Self> {
+        if tag == GLOBAL_SCOPE {
+            return Some(Self::Global);
+        }
+        let (kind, value) = tag.split_once(':')?;
+        let value = value.to_string();
+        match kind {
+            "agent" => Some(Self::Agent(value)),
+            "channel" => Some(Self::Channel(value)),
+            "session" => Some(Self::Session(value)),
+            _ => None,
+        }
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(normalize).filter(|v| !v.is_empty())
+}
+
+// ─── Search context ─────────────────────────────────────────────────────────
+
+/// Who a memory search or index run is acting for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScopeContext {
+    pub agent_id: Option<String>,
+    pub session_key: Option<String>,
+    pub channel: Option<String>,
+    pub chat_type: Option<String>,
+}
+
+impl ScopeContext {
+    /// Context with no chat attached, e.g. a tool call: sees global and
+    /// agent-wide memory only.
+    pub fn for_agent(agent_id: Option<&str>) -> Self {
+        Self {
+            agent_id: non_empty(agent_id),
+            ..Default::default()
+        }
+    }
+
+    pub fn for_session(agent_id: Option<&str>, session: &Session) -> Self {
+        Self {
+            agent_id: non_empty(agent_id),
+            session_key: non_empty(Some(&session.id)),
+            channel: non_empty(
+                session
+                    .channel
+                    .as_deref()
+                    .or(session.last_channel.as_deref()),
+            ),
+            chat_type: non_empty(session.chat_type.as_deref()),
+        }
+    }
+
+    /// Scope tags this context may read: `global` plus its own agent,
+    /// channel and session.
+    pub fn visible_scopes(&self) -> Vec<String> {
+        let mut scopes = vec![MemoryScope::Global.tag()];
+        if let Some(agent) = &self.agent_id {
+            scopes.push(MemoryScope::Agent(agent.clone()).tag());
+        }
+        if let Some(channel) = &self.channel {
+            scopes.push(MemoryScope::Channel(channel.clone()).tag());
+        }
+        if let Some(key) = &self.session_key {
+            scopes.push(MemoryScope::Session(key.clone()).tag());
+        }
+        scopes
+    }
+
+    fn resolved_chat_type(&self) -> Option<ChatType> {
+        normalize_chat_type(self.chat_type.as_deref()).or_else(|| {
+            let key = self.session_key.as_deref()?;
+            if key.contains(":group:") {
+                Some(ChatType::Group)
+            } else if key.contains(":channel:") {
+                Some(ChatType::Channel)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// ─── Policy ─────────────────────────────────────────────────────────────────
+
+/// Whether `policy` lets `ctx` share its memory beyond its own session.
+/// A matching deny rule wins, then any matching allow rule, then the
+/// policy default.
+pub fn policy_allows(policy: &SessionSendPolicyConfig, ctx: &ScopeContext) -> bool {
+    let mut allowed = false;
+    for rule in policy.rules.iter().filter(|rule| rule_matches(rule, ctx)) {
+        if rule.action.trim().eq_ignore_ascii_case("deny") {
+            return false;
+        }
+        allowed = true;
+    }
+    allowed || policy.default.trim().eq_ignore_ascii_case("allow")
+}
+
+fn rule_matches(rule: &PolicyRule, ctx: &ScopeContext) -> bool {
+    if let Some(expected) = normalize_chat_type(rule.chat_type.as_deref()) {
+        if ctx.resolved_chat_type() != Some(expected) {
+            return false;
+        }
+    }
+    if let Some(expected) = non_empty(rule.channel.as_deref()) {
+        if ctx.channel.as_deref() != Some(expected.as_str()) {
+            return false;
+        }
+    }
+    if let Some(prefix) = non_empty(rule.session_key.as_deref()) {
+        if !ctx
+            .session_key
+            .as_deref()
+            .is_some_and(|key| key.starts_with(&prefix))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Scope a session transcript is indexed under: agent-wide (or global
+/// without an agent) when the policy allows sharing, otherwise private to
+/// the session. No policy keeps every transcript private.
+pub fn transcript_scope(
+    policy: Option<&SessionSendPolicyConfig>,
+    ctx: &ScopeContext,
+) -> MemoryScope {
+    if policy.is_some_and(|p| policy_allows(p, ctx)) {
+        return match &ctx.agent_id {
+            Some(agent) => MemoryScope::Agent(agent.clone()),
+            None => MemoryScope::Global,
+        };
+    }
+    match &ctx.session_key {
+        Some(key) => MemoryScope::Session(key.clone()),
+        None => MemoryScope::Global,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(action: &str, chat_type: Option<&str>, channel: Option<&str>) -> PolicyRule {
+        PolicyRule {
+            action: action.to_string(),
+            chat_type: chat_type.map(str::to_string),
+            channel: channel.map(str::to_string),
+            session_key: None,
+        }
+    }
+
+    fn ctx(key: &str, channel: &str, chat_type: &str) -> ScopeContext {
+        ScopeContext {
+            agent_id: Some("main".to_string()),
+            session_key: Some(key.to_string()),
+            channel: Some(channel.to_string()),
+            chat_type: Some(chat_type.to_string()),
+        }
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        for scope in [
+            MemoryScope::Global,
+            MemoryScope::Agent("main".to_string()),
+            MemoryScope::Channel("slack".to_string()),
+            MemoryScope::Session("telegram:dm:42".to_string()),
+        ] {
+            assert_eq!(MemoryScope::parse(&scope.tag()), Some(scope));
+        }
+        assert_eq!(MemoryScope::parse("team:x"), None);
+    }
+
+    #[test]
+    fn visible_scopes_cover_own_namespaces_only() {
+        let dm = ctx("telegram:dm:42", "Telegram", "direct");
+        assert_eq!(
+            dm.visible_scopes(),
+            vec![
+                "global",
+                "agent:main",
+                "channel:telegram",
+                "session:telegram:dm:42"
+            ]
+        );
+        assert_eq!(
+            ScopeContext::for_agent(None).visible_scopes(),
+            vec!["global"]
+        );
+    }
+
+    #[test]
+    fn policy_deny_rules_win_and_default_applies() {
+        let policy = SessionSendPolicyConfig {
+            default: "deny".to_string(),
+            rules: vec![
+                rule("allow", Some("group"), None),
+                rule("deny", None, Some("discord")),
+            ],
+        };
+        assert!(policy_allows(
+            &policy,
+            &ctx("slack:group:c1", "slack", "group")
+        ));
+        assert!(!policy_allows(
+            &policy,
+            &ctx("discord:group:c2", "discord", "group")
+        ));
+        assert!(!policy_allows(
+            &policy,
+            &ctx("slack:dm:u1", "slack", "direct")
+        ));
+        // chat type falls back to the session key
+        let keyed = ScopeContext {
+            chat_type: None,
+            ..ctx("slack:group:c1", "slack", "")
+        };
+        assert!(policy_allows(&policy, &keyed));
+    }
+
+    #[test]
+    fn transcripts_stay_private_unless_shared() {
+        let dm = ctx("telegram:dm:42", "telegram", "direct");
+        assert_eq!(
+            transcript_scope(None, &dm),
+            MemoryScope::Session("telegram:dm:42".to_string())
+        );
+        let share_all = SessionSendPolicyConfig {
+            default: "allow".to_string(),
+            rules: vec![rule("deny", Some("direct"), None)],
+        };
+        assert_eq!(
+            transcript_scope(Some(&share_all), &dm),
+            MemoryScope::Session("telegram:dm:42".to_string())
+        );
+        assert_eq!(
+            transcript_scope(Some(&share_all), &ctx("slack:group:c1", "slack", "group")),
+            MemoryScope::Agent("main".to_string())
+        );
+    }
+}
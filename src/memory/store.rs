@@ -1,9 +1,19 @@
+use crate::agents::usage::UsageRecord;
 use crate::memory::schema;
 use crate::sessions::{Session, VerbosityLevel};
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
+const CACHE_HITS_KEY: &str = "embedding_cache.hits";
+const CACHE_MISSES_KEY: &str = "embedding_cache.misses";
+/// KNN candidates fetched per requested vector hit; the filter is applied
+/// after the nearest-neighbour scan, so scoped searches need headroom.
+const VECTOR_OVERSAMPLE: usize = 8;
+/// Largest `k` sqlite-vec accepts for a KNN query.
+const VECTOR_MAX_K: usize = 4096;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub id: String,
@@ -134,6 +144,11 @@ impl MemoryStore {
         Ok(())
     }
 
+    fn has_table(conn: &Connection, name: &str) -> Result<bool> {
+        conn.prepare("SELECT 1 FROM sqlite_master WHERE name = ?1")?
+            .exists([name])
+    }
+
     pub fn ensure_vector_index(&self, dimensions: usize) -> Result<()> {
         schema::ensure_vector_table(&self.conn.lock().unwrap(), dimensions)
     }
@@ -179,12 +194,19 @@ impl MemoryStore {
         Ok(())
     }
 
-    pub fn has_chunks_for_path(&self, path: &str, source: &str, model: &str) -> Result<bool> {
+    pub fn has_chunks_for_path(
+        &self,
+        path: &str,
+        source: &str,
+        model: &str,
+        scope: &str,
+    ) -> Result<bool> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT 1 FROM chunks WHERE path = ?1 AND source = ?2 AND model = ?3 LIMIT 1",
+            "SELECT 1 FROM chunks
+             WHERE path = ?1 AND source = ?2 AND model = ?3 AND scope = ?4 LIMIT 1",
         )?;
-        Ok(stmt.exists([path, source, model])?)
+        stmt.exists([path, source, model, scope])
     }
 
     pub fn build_fts_query(&self, raw: &str) -> Option<String> {
@@ -203,10 +225,13 @@ impl MemoryStore {
 
     pub fn delete_chunks_by_path(&self, path: &str, source: &str, model: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "DELETE FROM chunks_vec WHERE id IN (SELECT id FROM chunks WHERE path = ?1 AND source = ?2 AND model = ?3)",
-            [path, source, model],
-        )?;
+        // chunks_vec only exists once the first embedding fixed its dimensions
+        if Self::has_table(&conn, "chunks_vec")? {
+            conn.execute(
+                "DELETE FROM chunks_vec WHERE id IN (SELECT id FROM chunks WHERE path = ?1 AND source = ?2 AND model = ?3)",
+                [path, source, model],
+            )?;
+        }
         conn.execute(
             "DELETE FROM chunks_fts WHERE path = ?1 AND source = ?2 AND model = ?3",
             [path, source, model],
@@ -227,6 +252,7 @@ impl MemoryStore {
         end_line: i32,
         hash: &str,
         model: &str,
+        scope: &str,
         text: &str,
         embedding: &[f32],
     ) -> Result<()> {
@@ -236,15 +262,16 @@ impl MemoryStore {
 
         // 1. Insert/Update chunks table
         conn.execute(
-            "INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at, scope)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
              ON CONFLICT(id) DO UPDATE SET
                hash=excluded.hash,
                model=excluded.model,
                text=excluded.text,
                embedding=excluded.embedding,
-               updated_at=excluded.updated_at",
-            rusqlite::params![id, path, source, start_line, end_line, hash, model, text, embedding_json, now],
+               updated_at=excluded.updated_at,
+               scope=excluded.scope",
+            rusqlite::params![id, path, source, start_line, end_line, hash, model, text, embedding_json, now, scope],
         )?;
 
         // 2. Insert into FTS table
@@ -259,15 +286,167 @@ impl MemoryStore {
         for &f in embedding {
             blob.extend_from_slice(&f.to_le_bytes());
         }
+        // vec0 tables do not support UPSERT
+        conn.execute("DELETE FROM chunks_vec WHERE id = ?1", [id])?;
         conn.execute(
-            "INSERT INTO chunks_vec (id, embedding) VALUES (?1, ?2)
-             ON CONFLICT(id) DO UPDATE SET embedding=excluded.embedding",
+            "INSERT INTO chunks_vec (id, embedding) VALUES (?1, ?2)",
             rusqlite::params![id, blob],
         )?;
 
         Ok(())
     }
 
+    /// Cached embeddings for those of `hashes` embedded before. Hits are
+    /// stamped with the current time so eviction drops the least recently
+    /// used entries first.
+    pub fn get_cached_embeddings(
+        &self,
+        provider: &str,
+        model: &str,
+        provider_key: &str,
+        hashes: &[String],
+    ) -> Result<HashMap<String, Vec<f32>>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let conn = self.conn.lock().unwrap();
+        let mut select = conn.prepare(
+            "SELECT embedding FROM embedding_cache
+             WHERE provider = ?1 AND model = ?2 AND provider_key = ?3 AND hash = ?4",
+        )?;
+        let mut touch = conn.prepare(
+            "UPDATE embedding_cache SET updated_at = ?5
+             WHERE provider = ?1 AND model = ?2 AND provider_key = ?3 AND hash = ?4",
+        )?;
+        let mut found = HashMap::new();
+        for hash in hashes {
+            if found.contains_key(hash) {
+                continue;
+            }
+            let embedding: Option<String> = select
+                .query_row(
+                    rusqlite::params![provider, model, provider_key, hash],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(vec) = embedding.and_then(|e| serde_json::from_str::<Vec<f32>>(&e).ok()) {
+                touch.execute(rusqlite::params![provider, model, provider_key, hash, now])?;
+                found.insert(hash.clone(), vec);
+            }
+        }
+        Ok(found)
+    }
+
+    pub fn put_cached_embeddings(
+        &self,
+        provider: &str,
+        model: &str,
+        provider_key: &str,
+        entries: &[(String, Vec<f32>)],
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        for (hash, embedding) in entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO embedding_cache
+                   (provider, model, provider_key, hash, embedding, dims, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    provider,
+                    model,
+                    provider_key,
+                    hash,
+                    serde_json::to_string(embedding).unwrap_or_default(),
+                    embedding.len() as i64,
+                    now
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Drops cache entries last used before `cutoff_ms`, then the least
+    /// recently used ones beyond `max_entries`. Returns the rows removed.
+    pub fn evict_embedding_cache(
+        &self,
+        cutoff_ms: Option<i64>,
+        max_entries: Option<usize>,
+    ) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let mut removed = 0;
+        if let Some(cutoff) = cutoff_ms {
+            removed += conn.execute(
+                "DELETE FROM embedding_cache WHERE updated_at < ?1",
+                [cutoff],
+            )?;
+        }
+        if let Some(max) = max_entries {
+            removed += conn.execute(
+                "DELETE FROM embedding_cache WHERE rowid IN (
+                   SELECT rowid FROM embedding_cache
+                   ORDER BY updated_at DESC LIMIT -1 OFFSET ?1
+                 )",
+                [max as i64],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    /// Adds to the running cache hit/miss counters kept in `meta`.
+    pub fn record_cache_lookups(&self, hits: u64, misses: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        for (key, n) in [(CACHE_HITS_KEY, hits), (CACHE_MISSES_KEY, misses)] {
+            conn.execute(
+                "INSERT INTO meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET
+                   value = CAST(CAST(value AS INTEGER) + CAST(excluded.value AS INTEGER) AS TEXT)",
+                rusqlite::params![key, n.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn memory_stats(&self) -> Result<MemoryStats> {
+        let conn = self.conn.lock().unwrap();
+        let count = |sql: &str| -> Result<u64> {
+            conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+                .map(|n| n as u64)
+        };
+        let counter = |key: &str| -> Result<u64> {
+            Ok(conn
+                .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                    row.get::<_, String>(0)
+                })
+                .optional()?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0))
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT source, model, COUNT(*) FROM chunks
+             GROUP BY source, model ORDER BY source, model",
+        )?;
+        let chunks = stmt
+            .query_map([], |row| {
+                Ok(ChunkCount {
+                    source: row.get(0)?,
+                    model: row.get(1)?,
+                    chunks: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(MemoryStats {
+            files: count("SELECT COUNT(*) FROM files")?,
+            chunks,
+            cache_entries: count("SELECT COUNT(*) FROM embedding_cache")?,
+            cache_hits: counter(CACHE_HITS_KEY)?,
+            cache_misses: counter(CACHE_MISSES_KEY)?,
+            db_bytes: count(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            )?,
+        })
+    }
+
     pub fn bm25_to_score(&self, rank: f64) -> f64 {
         let normalized = if rank.is_finite() {
             rank.max(0.0)
@@ -303,23 +482,42 @@ impl MemoryStore {
         Ok(results)
     }
 
-    pub fn search_vector(&self, query_vec: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+    pub fn search_vector(
+        &self,
+        query_vec: &[f32],
+        filter: &ChunkFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
         let mut blob = Vec::with_capacity(query_vec.len() * 4);
         for &f in query_vec {
             blob.extend_from_slice(&f.to_le_bytes());
         }
+        let k = (limit * VECTOR_OVERSAMPLE).clamp(1, VECTOR_MAX_K);
 
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT c.id, c.path, c.source, c.model, c.start_line, c.end_line, c.text, v.distance
-             FROM chunks_vec v
-             JOIN chunks c ON c.id = v.id
-             WHERE v.embedding MATCH ?1 AND k = ?2
-             ORDER BY distance ASC",
-        )?;
+        if !Self::has_table(&conn, "chunks_vec")? {
+            return Ok(Vec::new());
+        }
+        let (conditions, filter_params) = filter.where_clause(4);
+        let mut stmt = conn.prepare(&format!(
+            "WITH knn AS (
+               SELECT id, distance FROM chunks_vec WHERE embedding MATCH ?1 AND k = ?2
+             )
+             SELECT c.id, c.path, c.source, c.model, c.start_line, c.end_line, c.text, knn.distance
+             FROM knn
+             JOIN chunks c ON c.id = knn.id
+             WHERE {}
+             ORDER BY knn.distance ASC
+             LIMIT ?3",
+            conditions
+        ))?;
+
+        let mut params: Vec<rusqlite::types::Value> =
+            vec![blob.into(), (k as i64).into(), (limit as i64).into()];
+        params.extend(filter_params);
 
         // Note: sqlite-vec uses distance, lower is better. We convert it to a score.
-        let rows = stmt.query_map(rusqlite::params![blob, limit as i64], |row| {
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
             let distance: f64 = row.get(7)?;
             Ok(SearchResult {
                 id: row.get(0)?,
@@ -343,7 +541,7 @@ impl MemoryStore {
     pub fn search_fts(
         &self,
         query_str: &str,
-        model: &str,
+        filter: &ChunkFilter,
         limit: usize,
     ) -> Result<Vec<SearchResult>> {
         let fts_query = match self.build_fts_query(query_str) {
@@ -352,16 +550,23 @@ impl MemoryStore {
         };
 
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, path, source, model, start_line, end_line, text, bm25(chunks_fts) as rank
-             FROM chunks_fts 
-             WHERE chunks_fts MATCH ?1 
-               AND (model = ?2 OR model IS NULL)
-             ORDER BY rank ASC 
-             LIMIT ?3",
-        )?;
+        let (conditions, filter_params) = filter.where_clause(3);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT c.id, c.path, c.source, c.model, c.start_line, c.end_line, c.text,
+                    bm25(chunks_fts) as rank
+             FROM chunks_fts
+             JOIN chunks c ON c.id = chunks_fts.id
+             WHERE chunks_fts MATCH ?1
+               AND {}
+             ORDER BY rank ASC
+             LIMIT ?2",
+            conditions
+        ))?;
 
-        let rows = stmt.query_map(rusqlite::params![fts_query, model, limit as i64], |row| {
+        let mut params: Vec<rusqlite::types::Value> = vec![fts_query.into(), (limit as i64).into()];
+        params.extend(filter_params);
+
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
             let rank: f64 = row.get(7)?;
             Ok(SearchResult {
                 id: row.get(0)?,
@@ -476,7 +681,9 @@ impl MemoryStore {
                 delivery_mode: serde_json::from_str(&delivery_mode_json).unwrap_or_default(),
                 send_policy: serde_json::from_str(&send_policy_json).unwrap_or_default(),
                 elevated: row.get::<_, i32>(7)? != 0,
-                transcript: serde_json::from_str(&transcript_json).unwrap_or_default(),
+                transcript: crate::sessions::repair_transcript(
+                    serde_json::from_str(&transcript_json).unwrap_or_default(),
+                ),
                 max_transcript: row.get::<_, i64>(9)? as usize,
                 created_at: chrono::DateTime::from_timestamp(created_at_ts, 0).unwrap_or_default(),
                 last_active: chrono::DateTime::from_timestamp(last_active_ts, 0)
@@ -542,7 +749,9 @@ impl MemoryStore {
                 delivery_mode: serde_json::from_str(&delivery_mode_json).unwrap_or_default(),
                 send_policy: serde_json::from_str(&send_policy_json).unwrap_or_default(),
                 elevated: row.get::<_, i32>(7)? != 0,
-                transcript: serde_json::from_str(&transcript_json).unwrap_or_default(),
+                transcript: crate::sessions::repair_transcript(
+                    serde_json::from_str(&transcript_json).unwrap_or_default(),
+                ),
                 max_transcript: row.get::<_, i64>(9)? as usize,
                 created_at: chrono::DateTime::from_timestamp(created_at_ts, 0).unwrap_or_default(),
                 last_active: chrono::DateTime::from_timestamp(last_active_ts, 0)
@@ -580,6 +789,98 @@ impl MemoryStore {
         conn.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
         Ok(())
     }
+
+    pub fn insert_usage_record(&self, record: &UsageRecord) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO usage_records (
+                session_id, agent, provider, model, input_tokens, output_tokens, cost_usd, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                record.session_id,
+                record.agent,
+                record.provider,
+                record.model,
+                record.input_tokens as i64,
+                record.output_tokens as i64,
+                record.cost_usd,
+                record.created_at.timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Usage records, oldest first, optionally only those at or after `since`.
+    pub fn list_usage_records(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<UsageRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT session_id, agent, provider, model, input_tokens, output_tokens, cost_usd, created_at
+             FROM usage_records WHERE created_at >= ?1 ORDER BY created_at, id",
+        )?;
+        let since_ts = since.map(|t| t.timestamp()).unwrap_or(i64::MIN);
+        let rows = stmt.query_map([since_ts], |row| {
+            Ok(UsageRecord {
+                session_id: row.get(0)?,
+                agent: row.get(1)?,
+                provider: row.get(2)?,
+                model: row.get(3)?,
+                input_tokens: row.get::<_, i64>(4)? as u64,
+                output_tokens: row.get::<_, i64>(5)? as u64,
+                cost_usd: row.get(6)?,
+                created_at: chrono::DateTime::from_timestamp(row.get(7)?, 0).unwrap_or_default(),
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+/// Which chunks a keyword or vector search may return.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkFilter {
+    pub model: String,
+    /// Empty means every source.
+    pub sources: Vec<String>,
+    /// Scope tags the caller may read; `None` searches every scope.
+    pub scopes: Option<Vec<String>>,
+}
+
+impl ChunkFilter {
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// SQL conditions on the `chunks` alias `c`, with placeholders
+    /// numbered from `?{first}`, and the values they bind.
+    fn where_clause(&self, first: usize) -> (String, Vec<rusqlite::types::Value>) {
+        let mut params: Vec<rusqlite::types::Value> = vec![self.model.clone().into()];
+        let mut conditions = vec![format!("c.model = ?{}", first)];
+        let lists = [
+            ("c.source", Some(&self.sources).filter(|s| !s.is_empty())),
+            ("c.scope", self.scopes.as_ref()),
+        ];
+        for (column, values) in lists {
+            let Some(values) = values else { continue };
+            if values.is_empty() {
+                conditions.push("0".to_string());
+                continue;
+            }
+            let placeholders: Vec<String> = values
+                .iter()
+                .map(|value| {
+                    params.push(value.clone().into());
+                    format!("?{}", first + params.len() - 1)
+                })
+                .collect();
+            conditions.push(format!("{} IN ({})", column, placeholders.join(", ")));
+        }
+        (conditions.join(" AND "), params)
+    }
 }
 
 #[derive(Debug)]
@@ -594,6 +895,32 @@ pub struct ChunkData {
     pub embedding_json: String,
 }
 
+/// Index and cache figures for `openkrab memory stats`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MemoryStats {
+    pub files: u64,
+    pub chunks: Vec<ChunkCount>,
+    pub cache_entries: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub db_bytes: u64,
+}
+
+impl MemoryStats {
+    /// Share of chunk embeddings served from the cache, if any were looked up.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkCount {
+    pub source: String,
+    pub model: String,
+    pub chunks: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -604,4 +931,212 @@ mod tests {
         store.save_meta("version", "1").unwrap();
         assert_eq!(store.read_meta("version").unwrap(), Some("1".to_string()));
     }
+
+    #[test]
+    fn session_transcript_roundtrips_tool_calls() {
+        use crate::agents::chat::{ChatMessage, TokenUsage};
+        use crate::agents::tool::ToolCall;
+        use crate::sessions::TranscriptEntry;
+
+        let store = MemoryStore::open_in_memory().unwrap();
+        let mut session = Session::new("s1");
+        session.append_transcript(TranscriptEntry::user("list files"));
+        session.append_transcript(
+            TranscriptEntry::from_message(&ChatMessage::Assistant {
+                content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "c1".to_string(),
+                    name: "list_files".to_string(),
+                    arguments: "{}".to_string(),
+                }]),
+            })
+            .with_usage(Some(TokenUsage::new(10, 3))),
+        );
+        session.append_transcript(TranscriptEntry::tool("c1", "a.txt"));
+        store.save_session(&session).unwrap();
+
+        let loaded = store.load_session("s1").unwrap().unwrap();
+        assert_eq!(loaded.transcript.len(), 3);
+        assert_eq!(
+            loaded.transcript[1].tool_calls.as_ref().unwrap()[0].id,
+            "c1"
+        );
+        assert_eq!(loaded.transcript[1].usage, Some(TokenUsage::new(10, 3)));
+        assert_eq!(loaded.transcript[2].tool_call_id.as_deref(), Some("c1"));
+    }
+
+    #[test]
+    fn usage_records_roundtrip() {
+        use crate::agents::chat::TokenUsage;
+
+        let store = MemoryStore::open_in_memory().unwrap();
+        let record = UsageRecord::new("s1", "krab", "openai", "gpt-4o", &TokenUsage::new(100, 20));
+        store.insert_usage_record(&record).unwrap();
+
+        let all = store.list_usage_records(None).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].input_tokens, 100);
+        assert_eq!(all[0].cost_usd, record.cost_usd);
+
+        let later = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert!(store.list_usage_records(Some(later)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_session_transcripts_are_migrated() {
+        let store = MemoryStore::open_in_memory().unwrap();
+        store.save_session(&Session::new("old")).unwrap();
+        {
+            let conn = store.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE sessions SET transcript = ?1 WHERE id = 'old'",
+                [r#"[{"role":"user","text":"hi","timestamp":"2024-01-01T00:00:00Z"},{"role":"assistant","text":"yo"}]"#],
+            )
+            .unwrap();
+            conn.execute(
+                "DELETE FROM meta WHERE key = 'sessions.transcript_version'",
+                [],
+            )
+            .unwrap();
+            schema::ensure_schema(&conn).unwrap();
+        }
+
+        let loaded = store.load_session("old").unwrap().unwrap();
+        assert_eq!(loaded.transcript.len(), 2);
+        assert_eq!(loaded.transcript[1].text, "yo");
+        assert_eq!(
+            store.read_meta("sessions.transcript_version").unwrap(),
+            Some(schema::SESSION_TRANSCRIPT_VERSION.to_string())
+        );
+    }
+
+    #[test]
+    fn embedding_cache_roundtrips_and_evicts_least_recent() {
+        let store = MemoryStore::open_in_memory().unwrap();
+        let entries: Vec<(String, Vec<f32>)> = (0..3)
+            .map(|i| (format!("h{i}"), vec![i as f32, 1.0]))
+            .collect();
+        store
+            .put_cached_embeddings("openai", "small", "", &entries)
+            .unwrap();
+
+        let hashes = vec!["h1".to_string(), "h9".to_string()];
+        let found = store
+            .get_cached_embeddings("openai", "small", "", &hashes)
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found["h1"], vec![1.0, 1.0]);
+        assert!(store
+            .get_cached_embeddings("ollama", "small", "", &hashes)
+            .unwrap()
+            .is_empty());
+
+        // h1 was just used; h0 and h2 share an older stamp.
+        {
+            let conn = store.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE embedding_cache SET updated_at = 1 WHERE hash != 'h1'",
+                [],
+            )
+            .unwrap();
+        }
+        assert_eq!(store.evict_embedding_cache(None, Some(1)).unwrap(), 2);
+        let left = store
+            .get_cached_embeddings("openai", "small", "", &["h0".into(), "h1".into()])
+            .unwrap();
+        assert_eq!(left.keys().collect::<Vec<_>>(), vec!["h1"]);
+        let future = chrono::Utc::now().timestamp_millis() + 1_000;
+        assert_eq!(store.evict_embedding_cache(Some(future), None).unwrap(), 1);
+    }
+
+    #[test]
+    fn memory_stats_count_chunks_cache_and_size() {
+        let store = MemoryStore::open_in_memory().unwrap();
+        store.ensure_vector_index(2).unwrap();
+        for (id, source) in [("a", "memory"), ("b", "memory"), ("c", "session")] {
+            store
+                .insert_chunk(
+                    id,
+                    "MEMORY.md",
+                    source,
+                    1,
+                    1,
+                    id,
+                    "small",
+                    "global",
+                    "text",
+                    &[1.0, 0.0],
+                )
+                .unwrap();
+        }
+        store
+            .put_cached_embeddings("openai", "small", "", &[("a".into(), vec![1.0, 0.0])])
+            .unwrap();
+        store.record_cache_lookups(3, 1).unwrap();
+        store.record_cache_lookups(1, 0).unwrap();
+
+        let stats = store.memory_stats().unwrap();
+        assert_eq!(stats.chunks.len(), 2);
+        assert_eq!(stats.chunks[0].source, "memory");
+        assert_eq!(stats.chunks[0].chunks, 2);
+        assert_eq!(stats.cache_entries, 1);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (4, 1));
+        assert_eq!(stats.cache_hit_rate(), Some(0.8));
+        assert!(stats.db_bytes > 0);
+    }
+
+    #[test]
+    fn searches_only_return_chunks_in_scope() {
+        let store = MemoryStore::open_in_memory().unwrap();
+        store.ensure_vector_index(2).unwrap();
+        let chunks = [
+            ("g", "global", "small", [1.0, 0.0]),
+            ("dm", "session:telegram:dm:42", "small", [1.0, 0.1]),
+            ("old", "global", "large", [1.0, 0.0]),
+        ];
+        for (id, scope, model, embedding) in chunks {
+            store
+                .insert_chunk(
+                    id,
+                    &format!("{id}.md"),
+                    "memory",
+                    1,
+                    1,
+                    id,
+                    model,
+                    scope,
+                    "shared widget notes",
+                    &embedding,
+                )
+                .unwrap();
+        }
+
+        let ids = |results: Vec<SearchResult>| {
+            let mut ids: Vec<String> = results.into_iter().map(|r| r.id).collect();
+            ids.sort();
+            ids
+        };
+        let mut filter = ChunkFilter::for_model("small");
+        assert_eq!(
+            ids(store.search_fts("widget", &filter, 10).unwrap()),
+            vec!["dm", "g"]
+        );
+        assert_eq!(
+            ids(store.search_vector(&[1.0, 0.0], &filter, 10).unwrap()),
+            vec!["dm", "g"]
+        );
+
+        filter.scopes = Some(vec!["global".to_string()]);
+        assert_eq!(
+            ids(store.search_fts("widget", &filter, 10).unwrap()),
+            vec!["g"]
+        );
+        assert_eq!(
+            ids(store.search_vector(&[1.0, 0.0], &filter, 10).unwrap()),
+            vec!["g"]
+        );
+
+        filter.sources = vec!["docs".to_string()];
+        assert!(store.search_fts("widget", &filter, 10).unwrap().is_empty());
+    }
 }
@@ -329,6 +329,52 @@ pub struct NodeHostConfig {
 pub struct AgentsConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub defaults: Option<AgentDefaults>,
+    /// Agents served by the gateway; `bindings` route messages to them by id.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub list: Vec<AgentEntry>,
+}
+
+/// One agent in `agents.list`. Unset fields fall back to `agents.defaults`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentEntry {
+    pub id: String,
+    /// Answers messages no binding matches (first entry if none is marked).
+    #[serde(default)]
+    pub default: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub emoji: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub personality: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub model: Option<ModelSelection>,
+    /// Root for file and exec tools (default: current directory).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub workspace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tools: Option<AgentToolPolicy>,
+}
+
+/// Tool allow/deny lists for an agent, by tool name. An empty `allow`
+/// permits every tool; `deny` wins over `allow`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentToolPolicy {
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub allow: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub deny: Vec<String>,
+}
+
+impl AgentToolPolicy {
+    pub fn permits(&self, tool_name: &str) -> bool {
+        if self.deny.iter().any(|t| t == tool_name) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|t| t == tool_name)
+    }
 }
 
 /// Agent defaults
@@ -338,6 +384,18 @@ pub struct AgentDefaults {
     pub model: Option<ModelSelection>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sandbox: Option<SandboxConfig>,
+    /// Model calls allowed per turn (default 16).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_iterations: Option<usize>,
+    /// Wall-clock budget per turn in seconds (default 300).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub turn_timeout_seconds: Option<u64>,
+    /// Tool calls run concurrently per assistant message (default 4).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tool_concurrency: Option<usize>,
+    /// Model used to summarize compacted history (e.g. a cheaper one).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub compaction_model: Option<String>,
 }
 
 /// Model selection
@@ -416,6 +474,18 @@ pub struct MessagesConfig {
     pub max_length: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<MessageQueueConfig>,
+}
+
+/// How bursts of messages to one session are handled while a reply is running
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MessageQueueConfig {
+    /// `collect` (default), `interrupt` or `queue`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debounce_ms: Option<u64>,
 }
 
 /// Commands configuration
@@ -447,6 +517,10 @@ pub struct SessionConfig {
     pub maintenance: Option<SessionMaintenanceConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub identity_links: Option<Vec<IdentityLinkConfig>>,
+    /// How direct messages map to sessions (`main`, `per-peer`,
+    /// `per-channel-peer`, `per-account-channel-peer`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dm_scope: Option<crate::routing::session_key::DmScope>,
 }
 
 /// Send policy configuration
@@ -676,7 +750,9 @@ pub struct ChannelsConfig {
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub whatsapp: HashMap<String, ChannelConfig>,
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
-    pub signal: HashMap<String, ChannelConfig>,
+    pub signal: HashMap<String, SignalAccountConfig>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub matrix: HashMap<String, MatrixAccountConfig>,
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub imessage: HashMap<String, ChannelConfig>,
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
@@ -700,6 +776,7 @@ impl Default for ChannelsConfig {
             slack: HashMap::new(),
             whatsapp: HashMap::new(),
             signal: HashMap::new(),
+            matrix: HashMap::new(),
             imessage: HashMap::new(),
             irc: HashMap::new(),
             web: HashMap::new(),
@@ -734,11 +811,61 @@ pub struct ChannelConfig {
     pub webhook_secret_encrypted: Option<EncryptedValue>,
 }
 
+/// Signal account configuration (served by a signal-cli HTTP daemon)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SignalAccountConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Number registered with signal-cli, e.g. "+15550123456"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    /// Daemon base URL (default "http://localhost:8080")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    /// Spawn signal-cli (default: when `api_base` is local)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_start: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cli_path: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub allowlist: Vec<String>,
+}
+
+/// Matrix account configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MatrixAccountConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Homeserver URL (default "https://matrix.org")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homeserver: Option<String>,
+    /// Bot user, e.g. "@krabbot:matrix.org"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub allowlist: Vec<String>,
+}
+
 /// Cron configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CronConfig {
+    /// Run the scheduler inside the gateway (opt-in).
     #[serde(default)]
     pub enabled: bool,
+    /// Job store path (default `<state dir>/cron/jobs.json`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<String>,
+    /// IANA time zone for new jobs that don't name one (default UTC)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Where replies go for jobs without their own delivery target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<crate::routing::DeliveryTarget>,
+    /// Seconds between scheduler ticks (default 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_secs: Option<u64>,
 }
 
 /// Hooks configuration
@@ -778,6 +905,24 @@ pub struct GatewayConfig {
     pub port: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bind_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<GatewayAuthConfig>,
+}
+
+/// Gateway authentication (`gateway.auth`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GatewayAuthConfig {
+    /// `none` (default), `token`, `password` or `trusted-proxy`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_user: Option<u32>,
 }
 
 /// Memory configuration
@@ -791,6 +936,28 @@ pub struct MemoryConfig {
     pub embedding_provider: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vector_store: Option<String>,
+    /// `builtin` (default) or `qmd`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// `auto` (default), `always` or `never`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub citations: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qmd: Option<crate::memory::QmdConfig>,
+}
+
+impl MemoryConfig {
+    /// Backend settings for `memory::create_backend`, if any are set.
+    pub fn backend_config(&self) -> Option<crate::memory::MemoryBackendConfig> {
+        if self.backend.is_none() && self.citations.is_none() {
+            return None;
+        }
+        Some(crate::memory::MemoryBackendConfig {
+            backend: self.backend.clone().unwrap_or_else(|| "builtin".to_string()),
+            citations: self.citations.clone().unwrap_or_else(|| "auto".to_string()),
+            qmd: self.qmd.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -804,6 +971,13 @@ mod tests {
         assert!(cfg.auth.is_none());
     }
 
+    #[test]
+    fn cron_scheduler_is_opt_in() {
+        assert!(!CronConfig::default().enabled);
+        let cron: CronConfig = serde_json::from_str(r#"{"timezone":"UTC"}"#).unwrap();
+        assert!(!cron.enabled);
+    }
+
     #[test]
     fn OPENKRAB_CONFIG_SERIALIZE() {
         let cfg = OpenKrabConfig {
@@ -815,6 +989,7 @@ mod tests {
                 enabled: true,
                 port: Some(18789),
                 bind_address: Some("0.0.0.0".to_string()),
+                auth: None,
             }),
             ..Default::default()
         };
@@ -0,0 +1,871 @@
+//! Anthropic provider — native Messages API chat with tool use, streaming and
+//! extended thinking.
+//!
+//! Unlike going through an OpenAI-compatible shim, this maps `ChatMessage::Tool`
+//! and assistant `tool_calls` onto `tool_result` / `tool_use` content blocks and
+//! forwards thinking deltas to the `StreamHandler` as reasoning.
+
+use super::LlmProvider;
+use crate::agents::chat::{
+    ChatCompletionResponse, ChatMessage, ChatOptions, ChatProvider, ContentPart, TokenUsage,
+    UserContent,
+};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::{ToolCall, ToolDefinition};
+use crate::sessions::ThinkingLevel;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+pub const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
+pub const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-5";
+pub const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 8192;
+
+// ─── Provider implementation ──────────────────────────────────────────────────
+
+/// Anthropic Messages API chat provider.
+pub struct AnthropicChatProvider {
+    api_key: String,
+    model: String,
+    base_url: String,
+    max_tokens: u32,
+    client: reqwest::Client,
+}
+
+impl AnthropicChatProvider {
+    pub fn new(api_key: String, base_url: Option<String>, model: Option<String>) -> Self {
+        Self {
+            api_key,
+            base_url: base_url
+                .unwrap_or_else(|| DEFAULT_ANTHROPIC_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model: model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string()),
+            max_tokens: DEFAULT_ANTHROPIC_MAX_TOKENS,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Build from `ANTHROPIC_API_KEY` (plus optional `ANTHROPIC_BASE_URL` and
+    /// `ANTHROPIC_MODEL`).
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var("ANTHROPIC_API_KEY").ok()?;
+        Some(Self::new(
+            api_key,
+            std::env::var("ANTHROPIC_BASE_URL").ok(),
+            std::env::var("ANTHROPIC_MODEL").ok(),
+        ))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Build the JSON request body for `/messages`.
+    pub fn build_request_body(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[ToolDefinition]>,
+        options: &ChatOptions,
+        stream: bool,
+    ) -> Value {
+        let (system, converted) = convert_messages(messages);
+
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": converted,
+        });
+
+        if !system.is_empty() {
+            body["system"] = json!(system);
+        }
+
+        if let Some(t) = tools {
+            if !t.is_empty() {
+                body["tools"] = json!(t
+                    .iter()
+                    .map(|td| {
+                        json!({
+                            "name": td.name,
+                            "description": td.description,
+                            "input_schema": td.parameters,
+                        })
+                    })
+                    .collect::<Vec<_>>());
+            }
+        }
+
+        // Thinking blocks cannot be replayed (ChatMessage has no slot for their
+        // signatures), and the API rejects a tool-use continuation whose
+        // assistant turn lacks one. Only enable thinking at the start of a turn.
+        let budget = options.thinking_level.and_then(thinking_budget_tokens);
+        if let Some(budget) = budget {
+            if !is_tool_use_continuation(messages) {
+                body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+                body["max_tokens"] = json!(self.max_tokens.max(budget + 1024));
+            }
+        }
+
+        if stream {
+            body["stream"] = json!(true);
+        }
+
+        body
+    }
+
+    async fn send(&self, body: &Value) -> Result<reqwest::Response> {
+        let url = format!("{}/messages", self.base_url);
+        let res = self
+            .client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_API_VERSION)
+            .json(body)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().await?;
+            return Err(anyhow!("Anthropic API error: {} - {}", status, error_text));
+        }
+        Ok(res)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for AnthropicChatProvider {
+    fn model_id(&self) -> Option<String> {
+        Some(self.model.clone())
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        Some("anthropic".to_string())
+    }
+
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<ChatCompletionResponse> {
+        self.complete_with_options(messages, tools, &ChatOptions::default())
+            .await
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> Result<ChatCompletionResponse> {
+        self.stream_with_options(messages, tools, handler, &ChatOptions::default())
+            .await
+    }
+
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let body = self.build_request_body(&messages, tools, options, false);
+        let json: Value = self.send(&body).await?.json().await?;
+        parse_message_response(&json)
+    }
+
+    async fn stream_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let body = self.build_request_body(&messages, tools, options, true);
+        let res = self.send(&body).await?;
+
+        let mut stream = res.bytes_stream();
+        let mut buffer = String::new();
+        let mut state = SseState::default();
+
+        while let Some(item) = stream.next().await {
+            let chunk = item?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer.drain(..newline_pos + 1).collect::<String>();
+                let line = line.trim();
+
+                // `event:` lines duplicate the `type` field inside `data:`.
+                if let Some(data) = line.strip_prefix("data:") {
+                    let event: Value = serde_json::from_str(data.trim())?;
+                    state.apply(&event, &handler)?;
+                }
+            }
+        }
+
+        // The handler may be shared across several model calls in one turn,
+        // so the reply is built from what this stream produced.
+        let content = if state.text.is_empty() {
+            None
+        } else {
+            Some(state.text.clone())
+        };
+
+        let tool_calls = if state.tool_calls.is_empty() {
+            None
+        } else {
+            Some(
+                state
+                    .tool_calls
+                    .iter()
+                    .map(|tc| ToolCall {
+                        id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        arguments: normalize_tool_arguments(&tc.arguments),
+                    })
+                    .collect(),
+            )
+        };
+
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content,
+                tool_calls,
+            },
+            finish_reason: state
+                .stop_reason
+                .as_deref()
+                .map(map_stop_reason)
+                .unwrap_or("stop")
+                .to_string(),
+            fallback: None,
+            usage: Some(TokenUsage::new(
+                state.input_tokens as u64,
+                state.output_tokens as u64,
+            )),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicChatProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let response = ChatProvider::complete(
+            self,
+            vec![ChatMessage::User {
+                content: UserContent::Text(prompt.to_string()),
+            }],
+            None,
+        )
+        .await?;
+        match response.message {
+            ChatMessage::Assistant { content, .. } => Ok(content.unwrap_or_default()),
+            _ => Ok(String::new()),
+        }
+    }
+
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Err(anyhow!("Anthropic does not provide an embeddings API"))
+    }
+}
+
+// ─── Streaming state ──────────────────────────────────────────────────────────
+
+/// Tracks content-block indices across SSE events so argument deltas can be
+/// attributed to the right tool call, and collects this stream's text and
+/// tool calls.
+#[derive(Default)]
+struct SseState {
+    tool_ids_by_index: HashMap<u64, String>,
+    text: String,
+    tool_calls: Vec<ToolCall>,
+    input_tokens: usize,
+    output_tokens: usize,
+    stop_reason: Option<String>,
+}
+
+impl SseState {
+    fn apply(&mut self, event: &Value, handler: &StreamHandler) -> Result<()> {
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &event["message"]["usage"];
+                self.input_tokens = usage["input_tokens"].as_u64().unwrap_or(0) as usize;
+                self.output_tokens = usage["output_tokens"].as_u64().unwrap_or(0) as usize;
+            }
+            "content_block_start" => {
+                let index = event["index"].as_u64().unwrap_or(0);
+                let block = &event["content_block"];
+                match block["type"].as_str().unwrap_or_default() {
+                    "tool_use" => {
+                        let id = block["id"].as_str().unwrap_or_default().to_string();
+                        let name = block["name"].as_str().unwrap_or_default();
+                        handler.start_tool_call(&id, name)?;
+                        self.tool_calls.push(ToolCall {
+                            id: id.clone(),
+                            name: name.to_string(),
+                            arguments: String::new(),
+                        });
+                        self.tool_ids_by_index.insert(index, id);
+                    }
+                    "text" => {
+                        if let Some(text) = block["text"].as_str().filter(|t| !t.is_empty()) {
+                            handler.push_text(text)?;
+                            self.text.push_str(text);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_delta" => {
+                let index = event["index"].as_u64().unwrap_or(0);
+                let delta = &event["delta"];
+                match delta["type"].as_str().unwrap_or_default() {
+                    "text_delta" => {
+                        if let Some(text) = delta["text"].as_str() {
+                            handler.push_text(text)?;
+                            self.text.push_str(text);
+                        }
+                    }
+                    "thinking_delta" => {
+                        if let Some(thinking) = delta["thinking"].as_str() {
+                            handler.push_reasoning(thinking)?;
+                        }
+                    }
+                    "input_json_delta" => {
+                        if let (Some(id), Some(partial)) = (
+                            self.tool_ids_by_index.get(&index),
+                            delta["partial_json"].as_str(),
+                        ) {
+                            handler.push_tool_arguments(id, partial)?;
+                            if let Some(tc) = self.tool_calls.iter_mut().find(|tc| &tc.id == id) {
+                                tc.arguments.push_str(partial);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                let index = event["index"].as_u64().unwrap_or(0);
+                if let Some(id) = self.tool_ids_by_index.get(&index) {
+                    handler.end_tool_call(id)?;
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                    self.stop_reason = Some(reason.to_string());
+                }
+                if let Some(out) = event["usage"]["output_tokens"].as_u64() {
+                    self.output_tokens = out as usize;
+                }
+            }
+            "message_stop" => {
+                let reason = self
+                    .stop_reason
+                    .as_deref()
+                    .map(map_stop_reason)
+                    .unwrap_or("stop");
+                handler.finish(reason, Some(self.input_tokens + self.output_tokens))?;
+            }
+            "error" => {
+                let message = event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string();
+                handler.error(&message)?;
+                return Err(anyhow!("Anthropic stream error: {}", message));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// ─── Message conversion ───────────────────────────────────────────────────────
+
+/// Split out system prompts and convert the rest into Messages API turns.
+///
+/// Tool results become `tool_result` blocks inside a user turn; consecutive
+/// results (and a user message that follows them) share one turn, since the
+/// API requires strictly alternating roles after a `tool_use`.
+pub fn convert_messages(messages: &[ChatMessage]) -> (String, Vec<Value>) {
+    let mut system_parts: Vec<&str> = Vec::new();
+    let mut out: Vec<Value> = Vec::new();
+
+    for msg in messages {
+        match msg {
+            ChatMessage::System { content } => {
+                if !content.trim().is_empty() {
+                    system_parts.push(content);
+                }
+            }
+            ChatMessage::User { content } => {
+                push_blocks(&mut out, "user", user_content_blocks(content));
+            }
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                let mut blocks = Vec::new();
+                if let Some(text) = content.as_deref().filter(|t| !t.is_empty()) {
+                    blocks.push(json!({ "type": "text", "text": text }));
+                }
+                for call in tool_calls.iter().flatten() {
+                    let input: Value =
+                        serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.name,
+                        "input": input,
+                    }));
+                }
+                if !blocks.is_empty() {
+                    push_blocks(&mut out, "assistant", blocks);
+                }
+            }
+            ChatMessage::Tool {
+                tool_call_id,
+                content,
+            } => {
+                push_blocks(
+                    &mut out,
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool_call_id,
+                        "content": content,
+                    })],
+                );
+            }
+        }
+    }
+
+    (system_parts.join("\n\n"), out)
+}
+
+fn push_blocks(out: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if let Some(last) = out.last_mut() {
+        if last["role"] == role {
+            if let Some(existing) = last["content"].as_array_mut() {
+                existing.extend(blocks);
+                return;
+            }
+        }
+    }
+    out.push(json!({ "role": role, "content": blocks }));
+}
+
+fn user_content_blocks(content: &UserContent) -> Vec<Value> {
+    match content {
+        UserContent::Text(text) => vec![json!({ "type": "text", "text": text })],
+        UserContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+                ContentPart::ImageUrl { image_url } => image_block(&image_url.url),
+            })
+            .collect(),
+    }
+}
+
+/// Anthropic takes either base64 `data:` payloads or plain URLs as image sources.
+fn image_block(url: &str) -> Value {
+    if let Some(rest) = url.strip_prefix("data:") {
+        if let Some((meta, data)) = rest.split_once(',') {
+            let media_type = meta.trim_end_matches(";base64");
+            return json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": media_type, "data": data },
+            });
+        }
+    }
+    json!({
+        "type": "image",
+        "source": { "type": "url", "url": url },
+    })
+}
+
+fn is_tool_use_continuation(messages: &[ChatMessage]) -> bool {
+    matches!(messages.last(), Some(ChatMessage::Tool { .. }))
+}
+
+/// Thinking budget for a session `ThinkingLevel`; `None` disables thinking.
+pub fn thinking_budget_tokens(level: ThinkingLevel) -> Option<u32> {
+    match level {
+        ThinkingLevel::Off => None,
+        ThinkingLevel::Minimal => Some(1024),
+        ThinkingLevel::Low => Some(4096),
+        ThinkingLevel::Medium => Some(10_000),
+        ThinkingLevel::High => Some(24_000),
+        ThinkingLevel::Xhigh => Some(32_000),
+    }
+}
+
+/// Map Anthropic `stop_reason` values onto the OpenAI-style finish reasons the
+/// agent loop understands.
+pub fn map_stop_reason(reason: &str) -> &'static str {
+    match reason {
+        "tool_use" => "tool_calls",
+        "max_tokens" => "length",
+        "refusal" => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn normalize_tool_arguments(raw: &str) -> String {
+    if raw.trim().is_empty() {
+        "{}".to_string()
+    } else {
+        raw.to_string()
+    }
+}
+
+fn parse_message_response(json: &Value) -> Result<ChatCompletionResponse> {
+    let blocks = json["content"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Anthropic response missing content: {}", json))?;
+
+    let mut text = String::new();
+    let mut tool_calls = Vec::new();
+
+    for block in blocks {
+        match block["type"].as_str().unwrap_or_default() {
+            "text" => text.push_str(block["text"].as_str().unwrap_or_default()),
+            "tool_use" => tool_calls.push(ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                name: block["name"].as_str().unwrap_or_default().to_string(),
+                arguments: block["input"].to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    Ok(ChatCompletionResponse {
+        message: ChatMessage::Assistant {
+            content: if text.is_empty() { None } else { Some(text) },
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+        },
+        finish_reason: map_stop_reason(json["stop_reason"].as_str().unwrap_or("end_turn"))
+            .to_string(),
+        fallback: None,
+        usage: json["usage"]["input_tokens"].as_u64().map(|input| {
+            TokenUsage::new(input, json["usage"]["output_tokens"].as_u64().unwrap_or(0))
+        }),
+    })
+}
+
+// ─── Model list helpers ───────────────────────────────────────────────────────
+
+pub const KNOWN_ANTHROPIC_CHAT_MODELS: &[&str] = &[
+    "claude-opus-4-1",
+    "claude-opus-4-0",
+    "claude-sonnet-4-5",
+    "claude-sonnet-4-0",
+    "claude-3-7-sonnet-latest",
+    "claude-3-5-haiku-latest",
+];
+
+pub fn is_known_anthropic_model(model: &str) -> bool {
+    KNOWN_ANTHROPIC_CHAT_MODELS.contains(&model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::chat::ImageUrl;
+    use crate::agents::streaming::{create_stream_pair, StreamEvent};
+    use std::sync::{Arc, Mutex};
+
+    async fn spawn_mock(
+        reply: &'static str,
+        content_type: &'static str,
+    ) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let (base, seen) = crate::providers::test_support::spawn_mock(reply, content_type).await;
+        (format!("{}/v1", base), seen)
+    }
+
+    fn tool_def() -> ToolDefinition {
+        ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Read a file".to_string(),
+            parameters: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+        }
+    }
+
+    #[test]
+    fn converts_tool_calls_and_results_to_blocks() {
+        let messages = vec![
+            ChatMessage::System {
+                content: "be brief".to_string(),
+            },
+            ChatMessage::User {
+                content: UserContent::Text("read a.txt".to_string()),
+            },
+            ChatMessage::Assistant {
+                content: Some("Reading.".to_string()),
+                tool_calls: Some(vec![ToolCall {
+                    id: "toolu_1".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: r#"{"path":"a.txt"}"#.to_string(),
+                }]),
+            },
+            ChatMessage::Tool {
+                tool_call_id: "toolu_1".to_string(),
+                content: "hello".to_string(),
+            },
+            ChatMessage::User {
+                content: UserContent::Text("thanks".to_string()),
+            },
+        ];
+
+        let (system, converted) = convert_messages(&messages);
+        assert_eq!(system, "be brief");
+        assert_eq!(converted.len(), 3);
+        assert_eq!(converted[1]["content"][1]["type"], "tool_use");
+        assert_eq!(converted[1]["content"][1]["input"]["path"], "a.txt");
+        assert_eq!(converted[2]["role"], "user");
+        assert_eq!(converted[2]["content"][0]["type"], "tool_result");
+        assert_eq!(converted[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(converted[2]["content"][1]["text"], "thanks");
+    }
+
+    #[test]
+    fn converts_data_url_images_to_base64_source() {
+        let content = UserContent::Parts(vec![ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: "data:image/png;base64,AAAA".to_string(),
+            },
+        }]);
+        let blocks = user_content_blocks(&content);
+        assert_eq!(blocks[0]["source"]["type"], "base64");
+        assert_eq!(blocks[0]["source"]["media_type"], "image/png");
+        assert_eq!(blocks[0]["source"]["data"], "AAAA");
+    }
+
+    #[test]
+    fn thinking_level_sets_budget_except_on_tool_continuation() {
+        let provider = AnthropicChatProvider::new("k".to_string(), None, None);
+        let options = ChatOptions {
+            thinking_level: Some(ThinkingLevel::Medium),
+        };
+        let user = vec![ChatMessage::User {
+            content: UserContent::Text("hi".to_string()),
+        }];
+        let body = provider.build_request_body(&user, None, &options, false);
+        assert_eq!(body["thinking"]["budget_tokens"], 10_000);
+        assert!(body["max_tokens"].as_u64().unwrap() > 10_000);
+
+        let mut continuation = user.clone();
+        continuation.push(ChatMessage::Tool {
+            tool_call_id: "t".to_string(),
+            content: "x".to_string(),
+        });
+        let body = provider.build_request_body(&continuation, None, &options, false);
+        assert!(body.get("thinking").is_none());
+
+        let off = ChatOptions {
+            thinking_level: Some(ThinkingLevel::Off),
+        };
+        let body = provider.build_request_body(&user, None, &off, false);
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[tokio::test]
+    async fn complete_parses_tool_use_against_mock_server() {
+        let reply = r#"{
+            "id": "msg_1", "type": "message", "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "hmm", "signature": "sig"},
+                {"type": "text", "text": "Let me look."},
+                {"type": "tool_use", "id": "toolu_9", "name": "read_file", "input": {"path": "a.txt"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 12, "output_tokens": 7}
+        }"#;
+        let (base_url, seen) = spawn_mock(reply, "application/json").await;
+        let provider = AnthropicChatProvider::new("test-key".to_string(), Some(base_url), None);
+
+        let tools = vec![tool_def()];
+        let response = ChatProvider::complete(
+            &provider,
+            vec![ChatMessage::User {
+                content: UserContent::Text("read a.txt".to_string()),
+            }],
+            Some(&tools),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        match response.message {
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                assert_eq!(content.as_deref(), Some("Let me look."));
+                let calls = tool_calls.unwrap();
+                assert_eq!(calls[0].id, "toolu_9");
+                let args: Value = serde_json::from_str(&calls[0].arguments).unwrap();
+                assert_eq!(args["path"], "a.txt");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let body = seen.lock().unwrap()[0].clone();
+        assert_eq!(body["tools"][0]["name"], "read_file");
+        assert!(body["tools"][0]["input_schema"].is_object());
+    }
+
+    #[tokio::test]
+    async fn stream_forwards_text_reasoning_and_tool_deltas() {
+        let reply = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":20,\"output_tokens\":1}}}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"plan\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"Checking\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":2,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_2\",\"name\":\"read_file\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":2,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":2,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"b.txt\\\"}\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":2}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":15}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let (base_url, _seen) = spawn_mock(reply, "text/event-stream").await;
+        let provider = AnthropicChatProvider::new("test-key".to_string(), Some(base_url), None);
+        let (handler, mut receiver) = create_stream_pair(2000);
+
+        let response = ChatProvider::stream(
+            &provider,
+            vec![ChatMessage::User {
+                content: UserContent::Text("read b.txt".to_string()),
+            }],
+            None,
+            handler,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        match response.message {
+            ChatMessage::Assistant { tool_calls, .. } => {
+                let calls = tool_calls.unwrap();
+                assert_eq!(calls[0].arguments, r#"{"path":"b.txt"}"#);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.rx.try_recv() {
+            events.push(event);
+        }
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, StreamEvent::ReasoningDelta { content } if content == "plan")));
+        assert!(events.iter().any(
+            |e| matches!(e, StreamEvent::ToolCallEnd { tool_name, .. } if tool_name == "read_file")
+        ));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            StreamEvent::MessageEnd { total_tokens_used: Some(35), finish_reason } if finish_reason == "tool_calls"
+        )));
+        assert_eq!(receiver.text(), "Checking");
+    }
+
+    #[tokio::test]
+    async fn each_stream_replies_with_its_own_content() {
+        let tool_round = concat!(
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":20,\"output_tokens\":1}}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"read_file\",\"input\":{}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"path\\\":\\\"a.txt\\\"}\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":9}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let text_round = concat!(
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":30,\"output_tokens\":1}}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"It says hi.\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":4}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let (base, seen) = crate::providers::test_support::spawn_mock_sequence(
+            vec![tool_round, text_round],
+            "text/event-stream",
+        )
+        .await;
+        let provider =
+            AnthropicChatProvider::new("test-key".to_string(), Some(format!("{}/v1", base)), None);
+        // One handler for both calls, as the agent loop uses it.
+        let (handler, _receiver) = create_stream_pair(2000);
+        let ask = || {
+            vec![ChatMessage::User {
+                content: UserContent::Text("what is in a.txt?".to_string()),
+            }]
+        };
+
+        let first = ChatProvider::stream(&provider, ask(), Some(&[tool_def()]), handler.clone())
+            .await
+            .unwrap();
+        let second = ChatProvider::stream(&provider, ask(), Some(&[tool_def()]), handler)
+            .await
+            .unwrap();
+
+        assert_eq!(first.finish_reason, "tool_calls");
+        assert_eq!(second.finish_reason, "stop");
+        match second.message {
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                assert_eq!(content.as_deref(), Some("It says hi."));
+                assert!(tool_calls.is_none());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn malformed_responses_are_errors() {
+        let (base_url, _seen) = spawn_mock(
+            r#"{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}"#,
+            "application/json",
+        )
+        .await;
+        let provider = AnthropicChatProvider::new("k".to_string(), Some(base_url), None);
+        let err = ChatProvider::complete(&provider, Vec::new(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing content"));
+    }
+
+    #[test]
+    fn known_models() {
+        assert!(is_known_anthropic_model("claude-sonnet-4-5"));
+        assert!(!is_known_anthropic_model("gpt-4o"));
+    }
+}
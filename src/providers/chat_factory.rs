@@ -0,0 +1,416 @@
+//! Chat provider factory — builds an `agents::chat::ChatProvider` from config.
+//!
+//! Model references use the `provider/model` form (e.g. `anthropic/claude-sonnet-4-5`,
+//! `ollama/llama3.1`). Bare model ids are matched against the known model lists.
+//! Credentials come from `models.providers.<id>` first, then from the usual
+//! environment variables / auth profiles (`agents::provider_auth`).
+
+use super::ProviderKind;
+use crate::agents::chat::{ChatProvider, OpenAiChatProvider};
+use crate::agents::fallback::FallbackChatProvider;
+use crate::agents::provider_auth::{resolve_api_key_for_provider, resolve_base_url_for_provider};
+use crate::OPENKRAB_CONFIG::{ModelSelection, ModelsConfig, OpenKrabConfig, ProviderConfig};
+use anyhow::{anyhow, bail, Result};
+
+/// A `provider/model` pair resolved from a model reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRef {
+    pub provider: String,
+    pub model: String,
+}
+
+impl ModelRef {
+    pub fn as_string(&self) -> String {
+        format!("{}/{}", self.provider, self.model)
+    }
+}
+
+/// Parse a model reference, inferring the provider for bare model ids.
+pub fn parse_model_ref(raw: &str) -> Option<ModelRef> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+
+    if let Some((provider, model)) = raw.split_once('/') {
+        if !provider.is_empty() && !model.is_empty() {
+            return Some(ModelRef {
+                provider: ProviderKind::from_str(provider).as_str().to_string(),
+                model: model.to_string(),
+            });
+        }
+    }
+
+    let provider = if super::anthropic::is_known_anthropic_model(raw) || raw.starts_with("claude") {
+        ProviderKind::Anthropic
+    } else if super::gemini::is_known_gemini_model(raw) || raw.starts_with("gemini") {
+        ProviderKind::Gemini
+    } else if super::openai::is_known_openai_model(raw) {
+        ProviderKind::OpenAi
+    } else {
+        let catalog = crate::agents::model_catalog::load_model_catalog();
+        match catalog.iter().find(|entry| entry.id == raw) {
+            Some(entry) => ProviderKind::from_str(&entry.provider),
+            None => ProviderKind::OpenAi,
+        }
+    };
+
+    Some(ModelRef {
+        provider: provider.as_str().to_string(),
+        model: raw.to_string(),
+    })
+}
+
+/// Resolve `models.aliases` (one level) before parsing.
+pub fn resolve_model_ref(raw: &str, models: Option<&ModelsConfig>) -> Option<ModelRef> {
+    let aliased = models
+        .and_then(|m| m.aliases.get(raw.trim()))
+        .map(|s| s.as_str())
+        .unwrap_or(raw);
+    parse_model_ref(aliased)
+}
+
+/// The primary model reference from `agents.defaults.model`, falling back to
+/// the first provider that has credentials configured, then to local Ollama.
+pub fn resolve_primary_model_ref(config: &OpenKrabConfig) -> ModelRef {
+    let models = config.models.as_ref();
+    let configured = config
+        .agents
+        .as_ref()
+        .and_then(|a| a.defaults.as_ref())
+        .and_then(|d| d.model.as_ref())
+        .and_then(|m| resolve_model_ref(&m.primary, models));
+    if let Some(model_ref) = configured {
+        return model_ref;
+    }
+
+    let fallback = if resolve_api_key_for_provider("openai").is_some() {
+        "openai/gpt-4o"
+    } else if resolve_api_key_for_provider("anthropic").is_some() {
+        "anthropic/claude-sonnet-4-5"
+    } else if resolve_api_key_for_provider("gemini").is_some() {
+        "gemini/gemini-2.0-flash"
+    } else {
+        "ollama/llama3.1"
+    };
+    parse_model_ref(fallback).expect("static model ref")
+}
+
+/// The configured fallback model references (`agents.defaults.model.fallbacks`).
+pub fn resolve_fallback_model_refs(config: &OpenKrabConfig) -> Vec<ModelRef> {
+    let models = config.models.as_ref();
+    config
+        .agents
+        .as_ref()
+        .and_then(|a| a.defaults.as_ref())
+        .and_then(|d| d.model.as_ref())
+        .map(|m| {
+            m.fallbacks
+                .iter()
+                .filter_map(|f| resolve_model_ref(f, models))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn provider_entry<'a>(
+    models: Option<&'a ModelsConfig>,
+    provider: &str,
+) -> Option<&'a ProviderConfig> {
+    models
+        .and_then(|m| m.providers.as_ref())
+        .and_then(|p| p.get(provider))
+}
+
+/// Build a chat provider for one model reference.
+pub fn build_chat_provider(
+    model_ref: &ModelRef,
+    models: Option<&ModelsConfig>,
+) -> Result<Box<dyn ChatProvider>> {
+    let entry = provider_entry(models, &model_ref.provider);
+    let api_key = entry
+        .and_then(|e| e.api_key.clone())
+        .filter(|k| !k.trim().is_empty())
+        .or_else(|| resolve_api_key_for_provider(&model_ref.provider));
+    let base_url = entry
+        .and_then(|e| e.base_url.clone())
+        .or_else(|| resolve_base_url_for_provider(&model_ref.provider));
+    let model = model_ref.model.clone();
+
+    let require_key = |key: Option<String>| {
+        key.ok_or_else(|| {
+            anyhow!(
+                "Missing API key for provider '{}' (set models.providers.{}.api_key or the provider's environment variable)",
+                model_ref.provider,
+                model_ref.provider
+            )
+        })
+    };
+
+    let provider: Box<dyn ChatProvider> = match ProviderKind::from_str(&model_ref.provider) {
+        ProviderKind::OpenAi => Box::new(OpenAiChatProvider::new(
+            require_key(api_key)?,
+            base_url,
+            Some(model),
+        )),
+        ProviderKind::Anthropic => Box::new(super::anthropic::AnthropicChatProvider::new(
+            require_key(api_key)?,
+            base_url,
+            Some(model),
+        )),
+        ProviderKind::Gemini => {
+            let mut p = super::gemini::GeminiProvider::with_models(
+                require_key(api_key)?,
+                model,
+                "text-embedding-004",
+            );
+            if let Some(url) = base_url {
+                p = p.with_base_url(url);
+            }
+            Box::new(p)
+        }
+        ProviderKind::Ollama => {
+            let host = base_url
+                .or_else(|| std::env::var("OLLAMA_HOST").ok())
+                .unwrap_or_else(|| "http://localhost:11434".to_string());
+            Box::new(super::ollama::OllamaProvider::with_models(
+                host,
+                model,
+                "nomic-embed-text",
+            ))
+        }
+        ProviderKind::LlamaCpp => {
+            let host = base_url
+                .or_else(|| std::env::var("LLAMA_CPP_HOST").ok())
+                .unwrap_or_else(|| "http://localhost:8080/v1".to_string());
+            Box::new(super::llama_cpp::LlamaCppProvider::with_models(
+                host,
+                model.clone(),
+                model,
+            ))
+        }
+        // Anything else with an explicit base URL is treated as OpenAI-compatible.
+        ProviderKind::Custom(_) | ProviderKind::CopilotProxy | ProviderKind::QwenPortal => {
+            let Some(url) = base_url else {
+                bail!(
+                    "Provider '{}' needs models.providers.{}.base_url to be used for chat",
+                    model_ref.provider,
+                    model_ref.provider
+                );
+            };
+            Box::new(
+                OpenAiChatProvider::new(api_key.unwrap_or_default(), Some(url), Some(model))
+                    .with_provider_name(model_ref.provider.clone()),
+            )
+        }
+        ProviderKind::Copilot => bail!(
+            "Provider 'copilot' is not supported for agent chat yet; use copilot-proxy instead"
+        ),
+    };
+
+    Ok(provider)
+}
+
+/// Build the chat provider for the configured primary model. When fallbacks
+/// are configured the result is a `FallbackChatProvider` over all of them;
+/// fallbacks that cannot be built (e.g. missing key) are skipped.
+pub fn build_default_chat_provider(config: &OpenKrabConfig) -> Result<Box<dyn ChatProvider>> {
+    build_provider_chain(
+        config,
+        resolve_primary_model_ref(config),
+        resolve_fallback_model_refs(config),
+    )
+}
+
+/// Build the provider for an `agents.list` entry's model selection, or the
+/// defaults when the entry has none.
+pub fn build_agent_chat_provider(
+    config: &OpenKrabConfig,
+    selection: Option<&ModelSelection>,
+) -> Result<Box<dyn ChatProvider>> {
+    let Some(selection) = selection else {
+        return build_default_chat_provider(config);
+    };
+    let models = config.models.as_ref();
+    let primary = resolve_model_ref(&selection.primary, models)
+        .ok_or_else(|| anyhow!("Invalid model reference '{}'", selection.primary))?;
+    let fallbacks = selection
+        .fallbacks
+        .iter()
+        .filter_map(|f| resolve_model_ref(f, models))
+        .collect();
+    build_provider_chain(config, primary, fallbacks)
+}
+
+fn build_provider_chain(
+    config: &OpenKrabConfig,
+    model_ref: ModelRef,
+    fallback_refs: Vec<ModelRef>,
+) -> Result<Box<dyn ChatProvider>> {
+    let models = config.models.as_ref();
+    let primary = build_chat_provider(&model_ref, models)?;
+
+    let mut fallbacks = Vec::new();
+    for fallback_ref in fallback_refs {
+        if fallback_ref == model_ref {
+            continue;
+        }
+        match build_chat_provider(&fallback_ref, models) {
+            Ok(provider) => fallbacks.push((fallback_ref.as_string(), provider)),
+            Err(e) => tracing::warn!(
+                "Skipping fallback model {}: {}",
+                fallback_ref.as_string(),
+                e
+            ),
+        }
+    }
+    if fallbacks.is_empty() {
+        return Ok(primary);
+    }
+
+    let chain = fallbacks.into_iter().fold(
+        FallbackChatProvider::new(model_ref.as_string(), primary),
+        |chain, (label, provider)| chain.with_fallback(label, provider),
+    );
+    Ok(Box::new(chain))
+}
+
+/// Build the summarizer for LLM compaction from `agents.defaults.compaction_model`.
+/// Returns `None` (heuristic compaction) when unset or when it cannot be built.
+pub fn build_compaction_provider(config: &OpenKrabConfig) -> Option<Box<dyn ChatProvider>> {
+    let models = config.models.as_ref();
+    let raw = config
+        .agents
+        .as_ref()
+        .and_then(|a| a.defaults.as_ref())
+        .and_then(|d| d.compaction_model.as_deref())?;
+    let model_ref = resolve_model_ref(raw, models)?;
+    match build_chat_provider(&model_ref, models) {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            tracing::warn!(
+                "Compaction model {} unavailable, using heuristic summaries: {}",
+                model_ref.as_string(),
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OPENKRAB_CONFIG::{AgentDefaults, AgentsConfig};
+    use std::collections::HashMap;
+
+    fn config_with_primary(primary: &str) -> OpenKrabConfig {
+        OpenKrabConfig {
+            agents: Some(AgentsConfig {
+                defaults: Some(AgentDefaults {
+                    model: Some(ModelSelection {
+                        primary: primary.to_string(),
+                        fallbacks: vec!["ollama/llama3.1".to_string()],
+                    }),
+                    sandbox: None,
+                    max_iterations: None,
+                    turn_timeout_seconds: None,
+                    tool_concurrency: None,
+                    compaction_model: None,
+                }),
+                list: vec![],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_explicit_and_bare_refs() {
+        assert_eq!(
+            parse_model_ref("anthropic/claude-sonnet-4-5").unwrap(),
+            ModelRef {
+                provider: "anthropic".to_string(),
+                model: "claude-sonnet-4-5".to_string()
+            }
+        );
+        assert_eq!(
+            parse_model_ref("google/gemini-1.5-pro").unwrap().provider,
+            "gemini"
+        );
+        assert_eq!(
+            parse_model_ref("gemini-1.5-flash").unwrap().provider,
+            "gemini"
+        );
+        assert_eq!(parse_model_ref("codellama").unwrap().provider, "ollama");
+        assert_eq!(parse_model_ref("gpt-4o").unwrap().provider, "openai");
+        assert!(parse_model_ref("  ").is_none());
+    }
+
+    #[test]
+    fn aliases_are_resolved() {
+        let mut aliases = HashMap::new();
+        aliases.insert("fast".to_string(), "ollama/qwen2.5".to_string());
+        let models = ModelsConfig {
+            providers: None,
+            aliases,
+        };
+        let r = resolve_model_ref("fast", Some(&models)).unwrap();
+        assert_eq!(r.as_string(), "ollama/qwen2.5");
+    }
+
+    #[test]
+    fn primary_and_fallbacks_come_from_agent_defaults() {
+        let cfg = config_with_primary("ollama/mistral");
+        assert_eq!(
+            resolve_primary_model_ref(&cfg).as_string(),
+            "ollama/mistral"
+        );
+        assert_eq!(
+            resolve_fallback_model_refs(&cfg)
+                .iter()
+                .map(|r| r.as_string())
+                .collect::<Vec<_>>(),
+            vec!["ollama/llama3.1"]
+        );
+    }
+
+    #[test]
+    fn builds_local_providers_without_credentials() {
+        let cfg = config_with_primary("ollama/mistral");
+        assert!(build_default_chat_provider(&cfg).is_ok());
+        let llama = parse_model_ref("llama-cpp/local-model").unwrap();
+        assert!(build_chat_provider(&llama, None).is_ok());
+    }
+
+    #[test]
+    fn provider_config_supplies_key_and_custom_base_url() {
+        let mut providers = HashMap::new();
+        providers.insert(
+            "anthropic".to_string(),
+            ProviderConfig {
+                api_key: Some("sk-ant-test".to_string()),
+                base_url: None,
+                enabled: true,
+            },
+        );
+        providers.insert(
+            "openrouter".to_string(),
+            ProviderConfig {
+                api_key: Some("or-key".to_string()),
+                base_url: Some("https://openrouter.ai/api/v1".to_string()),
+                enabled: true,
+            },
+        );
+        let models = ModelsConfig {
+            providers: Some(providers),
+            aliases: HashMap::new(),
+        };
+
+        let anthropic = parse_model_ref("anthropic/claude-sonnet-4-5").unwrap();
+        assert!(build_chat_provider(&anthropic, Some(&models)).is_ok());
+        let custom = parse_model_ref("openrouter/some-model").unwrap();
+        assert!(build_chat_provider(&custom, Some(&models)).is_ok());
+        let unknown = parse_model_ref("nowhere/some-model").unwrap();
+        assert!(build_chat_provider(&unknown, Some(&models)).is_err());
+    }
+}
@@ -2,10 +2,19 @@
 //! Mirrors OpenKrab's google-shared provider behaviour.
 
 use super::LlmProvider;
-use anyhow::Result;
+use crate::agents::chat::{
+    ChatCompletionResponse, ChatMessage, ChatProvider, ContentPart, TokenUsage, UserContent,
+};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::{ToolCall, ToolDefinition};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+pub const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
 
 // ─── Response types ───────────────────────────────────────────────────────────
 
@@ -43,6 +52,7 @@ struct GeminiEmbedding {
 
 pub struct GeminiProvider {
     api_key: String,
+    base_url: String,
     chat_model: String,
     embedding_model: String,
     client: reqwest_middleware::ClientWithMiddleware,
@@ -52,6 +62,7 @@ impl GeminiProvider {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             api_key: api_key.into(),
+            base_url: DEFAULT_GEMINI_BASE_URL.to_string(),
             chat_model: "gemini-1.5-flash".to_string(),
             embedding_model: "text-embedding-004".to_string(),
             client: crate::infra::retry_http::build_retrying_client(),
@@ -65,6 +76,7 @@ impl GeminiProvider {
     ) -> Self {
         Self {
             api_key: api_key.into(),
+            base_url: DEFAULT_GEMINI_BASE_URL.to_string(),
             chat_model: chat_model.into(),
             embedding_model: embedding_model.into(),
             client: crate::infra::retry_http::build_retrying_client(),
@@ -78,6 +90,41 @@ impl GeminiProvider {
             .ok()?;
         Some(Self::new(key))
     }
+
+    /// Override the API base URL (e.g. a proxy or a local mock server).
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn chat_model(&self) -> &str {
+        &self.chat_model
+    }
+
+    fn build_chat_body(&self, messages: &[ChatMessage], tools: Option<&[ToolDefinition]>) -> Value {
+        let (system, contents) = convert_messages(messages);
+        let mut body = json!({ "contents": contents });
+        if let Some(system) = system {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+        if let Some(t) = tools {
+            if !t.is_empty() {
+                body["tools"] = json!([{
+                    "functionDeclarations": t
+                        .iter()
+                        .map(|td| {
+                            json!({
+                                "name": td.name,
+                                "description": td.description,
+                                "parameters": sanitize_schema(&td.parameters),
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                }]);
+            }
+        }
+        body
+    }
 }
 
 #[async_trait]
@@ -88,8 +135,8 @@ impl LlmProvider for GeminiProvider {
 
     async fn complete(&self, prompt: &str) -> Result<String> {
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.chat_model, self.api_key
+            "{}/models/{}:generateContent?key={}",
+            self.base_url, self.chat_model, self.api_key
         );
         let body = json!({
             "contents": [{"parts": [{"text": prompt}]}]
@@ -116,8 +163,8 @@ impl LlmProvider for GeminiProvider {
 
     async fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:embedContent?key={}",
-            self.embedding_model, self.api_key
+            "{}/models/{}:embedContent?key={}",
+            self.base_url, self.embedding_model, self.api_key
         );
         let body = json!({
             "model": format!("models/{}", self.embedding_model),
@@ -137,6 +184,334 @@ impl LlmProvider for GeminiProvider {
     }
 }
 
+#[async_trait]
+impl ChatProvider for GeminiProvider {
+    fn model_id(&self) -> Option<String> {
+        Some(self.chat_model.clone())
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        Some("gemini".to_string())
+    }
+
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<ChatCompletionResponse> {
+        let url = format!(
+            "{}/models/{}:generateContent?key={}",
+            self.base_url, self.chat_model, self.api_key
+        );
+        let body = self.build_chat_body(&messages, tools);
+
+        let res = self.client.post(&url).json(&body).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().await?;
+            return Err(anyhow!("Gemini API error: {} - {}", status, error_text));
+        }
+
+        let json: Value = res.json().await?;
+        let candidate = &json["candidates"][0];
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if part["thought"].as_bool() == Some(true) {
+                continue;
+            }
+            if let Some(t) = part["text"].as_str() {
+                text.push_str(t);
+            }
+            if let Some(call) = parse_function_call(part) {
+                tool_calls.push(call);
+            }
+        }
+
+        let finish_reason = map_finish_reason(
+            candidate["finishReason"].as_str().unwrap_or("STOP"),
+            !tool_calls.is_empty(),
+        );
+
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content: if text.is_empty() { None } else { Some(text) },
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+            },
+            finish_reason: finish_reason.to_string(),
+            fallback: None,
+            usage: parse_usage(&json["usageMetadata"]),
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> Result<ChatCompletionResponse> {
+        let url = format!(
+            "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+            self.base_url, self.chat_model, self.api_key
+        );
+        let body = self.build_chat_body(&messages, tools);
+
+        let res = self.client.post(&url).json(&body).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().await?;
+            return Err(anyhow!("Gemini API error: {} - {}", status, error_text));
+        }
+
+        let mut stream = res.bytes_stream();
+        let mut buffer = String::new();
+        let mut raw_finish: Option<String> = None;
+        let mut total_tokens: Option<usize> = None;
+        let mut usage = None;
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+
+        while let Some(item) = stream.next().await {
+            let chunk = item?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer.drain(..newline_pos + 1).collect::<String>();
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let json: Value = serde_json::from_str(data.trim())?;
+                let candidate = &json["candidates"][0];
+
+                for part in candidate["content"]["parts"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(t) = part["text"].as_str() {
+                        if part["thought"].as_bool() == Some(true) {
+                            handler.push_reasoning(t)?;
+                        } else {
+                            handler.push_text(t)?;
+                            text.push_str(t);
+                        }
+                    }
+                    // Gemini streams function calls whole rather than as deltas.
+                    if let Some(call) = parse_function_call(part) {
+                        handler.start_tool_call(&call.id, &call.name)?;
+                        handler.push_tool_arguments(&call.id, &call.arguments)?;
+                        handler.end_tool_call(&call.id)?;
+                        tool_calls.push(call);
+                    }
+                }
+
+                if let Some(reason) = candidate["finishReason"].as_str() {
+                    raw_finish = Some(reason.to_string());
+                }
+                if let Some(total) = json["usageMetadata"]["totalTokenCount"].as_u64() {
+                    total_tokens = Some(total as usize);
+                }
+                if let Some(u) = parse_usage(&json["usageMetadata"]) {
+                    usage = Some(u);
+                }
+            }
+        }
+
+        let finish_reason = map_finish_reason(
+            raw_finish.as_deref().unwrap_or("STOP"),
+            !tool_calls.is_empty(),
+        );
+        handler.finish(finish_reason, total_tokens)?;
+
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content: if text.is_empty() { None } else { Some(text) },
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+            },
+            finish_reason: finish_reason.to_string(),
+            fallback: None,
+            usage,
+        })
+    }
+}
+
+// ─── Chat message conversion ──────────────────────────────────────────────────
+
+/// Convert chat messages into Gemini `contents`, returning the joined system
+/// prompt separately (it goes into `systemInstruction`).
+///
+/// Gemini's `functionResponse` is keyed by function name rather than call id,
+/// so the name is recovered from the assistant turn that issued the call.
+pub fn convert_messages(messages: &[ChatMessage]) -> (Option<String>, Vec<Value>) {
+    let mut system_parts: Vec<&str> = Vec::new();
+    let mut contents: Vec<Value> = Vec::new();
+    let mut names_by_call_id: HashMap<&str, &str> = HashMap::new();
+
+    for msg in messages {
+        match msg {
+            ChatMessage::System { content } => system_parts.push(content),
+            ChatMessage::User { content } => {
+                let parts = match content {
+                    UserContent::Text(text) => vec![json!({ "text": text })],
+                    UserContent::Parts(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ContentPart::Text { text } => json!({ "text": text }),
+                            ContentPart::ImageUrl { image_url } => image_part(&image_url.url),
+                        })
+                        .collect(),
+                };
+                push_parts(&mut contents, "user", parts);
+            }
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                let mut parts = Vec::new();
+                if let Some(text) = content.as_deref().filter(|t| !t.is_empty()) {
+                    parts.push(json!({ "text": text }));
+                }
+                for call in tool_calls.iter().flatten() {
+                    names_by_call_id.insert(&call.id, &call.name);
+                    let args: Value =
+                        serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                    parts.push(json!({ "functionCall": { "name": call.name, "args": args } }));
+                }
+                if !parts.is_empty() {
+                    push_parts(&mut contents, "model", parts);
+                }
+            }
+            ChatMessage::Tool {
+                tool_call_id,
+                content,
+            } => {
+                let name = names_by_call_id
+                    .get(tool_call_id.as_str())
+                    .copied()
+                    .unwrap_or(tool_call_id.as_str());
+                push_parts(
+                    &mut contents,
+                    "user",
+                    vec![json!({
+                        "functionResponse": {
+                            "name": name,
+                            "response": { "content": content },
+                        }
+                    })],
+                );
+            }
+        }
+    }
+
+    let system = if system_parts.is_empty() {
+        None
+    } else {
+        Some(system_parts.join("\n\n"))
+    };
+    (system, contents)
+}
+
+fn push_parts(contents: &mut Vec<Value>, role: &str, parts: Vec<Value>) {
+    if let Some(last) = contents.last_mut() {
+        if last["role"] == role {
+            if let Some(existing) = last["parts"].as_array_mut() {
+                existing.extend(parts);
+                return;
+            }
+        }
+    }
+    contents.push(json!({ "role": role, "parts": parts }));
+}
+
+fn image_part(url: &str) -> Value {
+    if let Some(rest) = url.strip_prefix("data:") {
+        if let Some((meta, data)) = rest.split_once(',') {
+            return json!({
+                "inlineData": {
+                    "mimeType": meta.trim_end_matches(";base64"),
+                    "data": data,
+                }
+            });
+        }
+    }
+    let lower = url.to_lowercase();
+    let mime = if lower.ends_with(".png") {
+        "image/png"
+    } else if lower.ends_with(".gif") {
+        "image/gif"
+    } else if lower.ends_with(".webp") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    };
+    json!({ "fileData": { "mimeType": mime, "fileUri": url } })
+}
+
+fn parse_function_call(part: &Value) -> Option<ToolCall> {
+    let call = part.get("functionCall")?;
+    let name = call["name"].as_str()?.to_string();
+    let id = call["id"]
+        .as_str()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+    let arguments = if call["args"].is_null() {
+        "{}".to_string()
+    } else {
+        call["args"].to_string()
+    };
+    Some(ToolCall {
+        id,
+        name,
+        arguments,
+    })
+}
+
+/// Gemini accepts an OpenAPI subset; drop JSON-Schema keywords it rejects.
+fn sanitize_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(k, _)| !matches!(k.as_str(), "$schema" | "additionalProperties"))
+                .map(|(k, v)| (k.clone(), sanitize_schema(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(sanitize_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+fn parse_usage(meta: &Value) -> Option<TokenUsage> {
+    let input = meta["promptTokenCount"].as_u64()?;
+    Some(TokenUsage::new(
+        input,
+        meta["candidatesTokenCount"].as_u64().unwrap_or(0)
+            + meta["thoughtsTokenCount"].as_u64().unwrap_or(0),
+    ))
+}
+
+fn map_finish_reason(reason: &str, has_tool_calls: bool) -> &'static str {
+    if has_tool_calls {
+        return "tool_calls";
+    }
+    match reason {
+        "MAX_TOKENS" => "length",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" => "content_filter",
+        _ => "stop",
+    }
+}
+
 // ─── Known models ─────────────────────────────────────────────────────────────
 
 pub const KNOWN_GEMINI_CHAT_MODELS: &[&str] = &[
@@ -170,5 +545,134 @@ mod tests {
         let p = GeminiProvider::new("test-key");
         assert_eq!(p.name(), "gemini");
     }
-}
 
+    #[test]
+    fn convert_messages_maps_roles_and_function_responses() {
+        let messages = vec![
+            ChatMessage::System {
+                content: "sys".to_string(),
+            },
+            ChatMessage::User {
+                content: UserContent::Text("weather?".to_string()),
+            },
+            ChatMessage::Assistant {
+                content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "get_weather".to_string(),
+                    arguments: r#"{"city":"Paris"}"#.to_string(),
+                }]),
+            },
+            ChatMessage::Tool {
+                tool_call_id: "call_1".to_string(),
+                content: "sunny".to_string(),
+            },
+        ];
+        let (system, contents) = convert_messages(&messages);
+        assert_eq!(system.as_deref(), Some("sys"));
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["args"]["city"],
+            "Paris"
+        );
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["name"],
+            "get_weather"
+        );
+    }
+
+    #[test]
+    fn sanitize_schema_drops_unsupported_keys() {
+        let schema = json!({
+            "$schema": "x",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {"a": {"type": "string", "additionalProperties": true}}
+        });
+        let cleaned = sanitize_schema(&schema);
+        assert!(cleaned.get("$schema").is_none());
+        assert!(cleaned.get("additionalProperties").is_none());
+        assert!(cleaned["properties"]["a"]
+            .get("additionalProperties")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn chat_complete_parses_function_call() {
+        let reply = r#"{"candidates":[{"content":{"role":"model","parts":[
+            {"functionCall":{"name":"get_weather","args":{"city":"Paris"}}}
+        ]},"finishReason":"STOP"}]}"#;
+        let (base_url, seen) =
+            crate::providers::test_support::spawn_mock(reply, "application/json").await;
+        let provider = GeminiProvider::new("k").with_base_url(base_url);
+        let tools = vec![ToolDefinition {
+            name: "get_weather".to_string(),
+            description: "Weather".to_string(),
+            parameters: json!({"type": "object"}),
+        }];
+
+        let response = ChatProvider::complete(
+            &provider,
+            vec![ChatMessage::User {
+                content: UserContent::Text("weather?".to_string()),
+            }],
+            Some(&tools),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        match response.message {
+            ChatMessage::Assistant { tool_calls, .. } => {
+                let calls = tool_calls.unwrap();
+                assert_eq!(calls[0].name, "get_weather");
+                assert_eq!(calls[0].arguments, r#"{"city":"Paris"}"#);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        let body = seen.lock().unwrap()[0].clone();
+        assert_eq!(
+            body["tools"][0]["functionDeclarations"][0]["name"],
+            "get_weather"
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_stream_emits_text_and_thoughts() {
+        let reply = concat!(
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"hmm\",\"thought\":true}]}}]}\n\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hello\"}]}}]}\n\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\" there\"}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"totalTokenCount\":9}}\n\n",
+        );
+        let (base_url, _seen) =
+            crate::providers::test_support::spawn_mock(reply, "text/event-stream").await;
+        let provider = GeminiProvider::new("k").with_base_url(base_url);
+        let (handler, receiver) = crate::agents::streaming::create_stream_pair(2000);
+        let ask = || {
+            vec![ChatMessage::User {
+                content: UserContent::Text("hi".to_string()),
+            }]
+        };
+
+        let response = ChatProvider::stream(&provider, ask(), None, handler.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(response.finish_reason, "stop");
+        assert_eq!(receiver.text(), "Hello there");
+        assert!(receiver.is_finished());
+
+        // A second call on the same handler (the next agent-loop round)
+        // replies with its own text only.
+        let again = ChatProvider::stream(&provider, ask(), None, handler)
+            .await
+            .unwrap();
+        match again.message {
+            ChatMessage::Assistant { content, .. } => {
+                assert_eq!(content.as_deref(), Some("Hello there"))
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+}
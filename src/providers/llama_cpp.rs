@@ -1,6 +1,9 @@
 //! Llama.cpp local provider — chat completion + text embeddings via OpenAI-compatible API.
 
 use super::LlmProvider;
+use crate::agents::chat::{ChatProvider, OpenAiChatProvider};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::ToolDefinition;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -97,6 +100,18 @@ impl LlamaCppProvider {
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    /// llama-server speaks the OpenAI chat API (including `tools` when started
+    /// with `--jinja`), so chat requests go through the OpenAI client.
+    fn chat_client(&self) -> OpenAiChatProvider {
+        OpenAiChatProvider::new(
+            String::new(),
+            Some(self.base_url.trim_end_matches('/').to_string()),
+            Some(self.chat_model.clone()),
+        )
+        .with_provider_name("llama-cpp")
+        .with_stream_usage(false)
+    }
 }
 
 #[async_trait]
@@ -158,6 +173,34 @@ impl LlmProvider for LlamaCppProvider {
     }
 }
 
+#[async_trait]
+impl ChatProvider for LlamaCppProvider {
+    fn model_id(&self) -> Option<String> {
+        Some(self.chat_model.clone())
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        Some("llama-cpp".to_string())
+    }
+
+    async fn complete(
+        &self,
+        messages: Vec<crate::agents::chat::ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<crate::agents::chat::ChatCompletionResponse> {
+        self.chat_client().complete(messages, tools).await
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<crate::agents::chat::ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> Result<crate::agents::chat::ChatCompletionResponse> {
+        self.chat_client().stream(messages, tools, handler).await
+    }
+}
+
 /// Check if a llama.cpp server is reachable at the given base URL.
 pub async fn probe_llama_cpp(base_url: &str) -> bool {
     let client = reqwest::Client::new();
@@ -185,4 +228,34 @@ mod tests {
         let p = LlamaCppProvider::from_env();
         assert!(p.base_url().starts_with("http://"));
     }
+
+    #[tokio::test]
+    async fn chat_stream_tracks_tool_calls_by_index() {
+        let reply = concat!(
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"function\":{\"name\":\"ls\",\"arguments\":\"\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\".\\\"}\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let (base_url, seen) =
+            crate::providers::test_support::spawn_mock(reply, "text/event-stream").await;
+        let provider = LlamaCppProvider::with_models(format!("{}/v1", base_url), "qwen", "qwen");
+        let (handler, _receiver) = crate::agents::streaming::create_stream_pair(2000);
+
+        let response = ChatProvider::stream(&provider, Vec::new(), None, handler)
+            .await
+            .unwrap();
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        match response.message {
+            crate::agents::chat::ChatMessage::Assistant { tool_calls, .. } => {
+                let calls = tool_calls.unwrap();
+                assert_eq!(calls[0].id, "call_a");
+                assert_eq!(calls[0].arguments, r#"{"path":"."}"#);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(seen.lock().unwrap()[0]["model"], "qwen");
+    }
 }
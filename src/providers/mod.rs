@@ -4,6 +4,8 @@
 //! Provides a unified `LlmProvider` trait and a `ProviderRegistry` for
 //! selecting chat / embedding backends by name at runtime.
 
+pub mod anthropic;
+pub mod chat_factory;
 pub mod copilot_models;
 pub mod copilot_proxy;
 pub mod copilot_token;
@@ -13,7 +15,10 @@ pub mod llama_cpp;
 pub mod minimax_oauth;
 pub mod ollama;
 pub mod openai;
+pub mod pricing;
 pub mod qwen_oauth;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -75,6 +80,7 @@ impl Default for ProviderRegistry {
 /// Build a default runtime registry from environment variables.
 ///
 /// - Registers `openai` when `OPENAI_API_KEY` is set.
+/// - Registers `anthropic` when `ANTHROPIC_API_KEY` is set.
 /// - Registers `gemini` when `GEMINI_API_KEY` or `GOOGLE_AI_API_KEY` is set.
 /// - Registers `copilot` when `GITHUB_TOKEN` is set.
 /// - Registers `qwen-portal` when `QWEN_ACCESS_TOKEN` is set.
@@ -85,6 +91,9 @@ pub fn default_registry_from_env() -> ProviderRegistry {
     if let Some(p) = crate::providers::openai::OpenAiProvider::from_env() {
         registry.register(Box::new(p));
     }
+    if let Some(p) = crate::providers::anthropic::AnthropicChatProvider::from_env() {
+        registry.register(Box::new(p));
+    }
     if let Some(p) = crate::providers::gemini::GeminiProvider::from_env() {
         registry.register(Box::new(p));
     }
@@ -115,6 +124,10 @@ pub fn known_model_ids(provider: &str) -> Vec<String> {
             .chain(crate::providers::openai::KNOWN_OPENAI_EMBEDDING_MODELS.iter())
             .map(|m| (*m).to_string())
             .collect(),
+        ProviderKind::Anthropic => crate::providers::anthropic::KNOWN_ANTHROPIC_CHAT_MODELS
+            .iter()
+            .map(|m| (*m).to_string())
+            .collect(),
         ProviderKind::Gemini => crate::providers::gemini::KNOWN_GEMINI_CHAT_MODELS
             .iter()
             .chain(crate::providers::gemini::KNOWN_GEMINI_EMBEDDING_MODELS.iter())
@@ -151,6 +164,7 @@ pub async fn resolve_qwen_credentials(
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ProviderKind {
     OpenAi,
+    Anthropic,
     Gemini,
     Ollama,
     LlamaCpp,
@@ -164,6 +178,7 @@ impl ProviderKind {
     pub fn from_str(s: &str) -> Self {
         match s.trim().to_lowercase().as_str() {
             "openai" | "gpt" => ProviderKind::OpenAi,
+            "anthropic" | "claude" => ProviderKind::Anthropic,
             "gemini" | "google" => ProviderKind::Gemini,
             "ollama" => ProviderKind::Ollama,
             "llama-cpp" | "llamacpp" => ProviderKind::LlamaCpp,
@@ -177,6 +192,7 @@ impl ProviderKind {
     pub fn as_str(&self) -> &str {
         match self {
             ProviderKind::OpenAi => "openai",
+            ProviderKind::Anthropic => "anthropic",
             ProviderKind::Gemini => "gemini",
             ProviderKind::Ollama => "ollama",
             ProviderKind::LlamaCpp => "llama-cpp",
@@ -196,6 +212,7 @@ mod tests {
     fn provider_kind_from_str() {
         assert_eq!(ProviderKind::from_str("openai"), ProviderKind::OpenAi);
         assert_eq!(ProviderKind::from_str("GPT"), ProviderKind::OpenAi);
+        assert_eq!(ProviderKind::from_str("claude"), ProviderKind::Anthropic);
         assert_eq!(ProviderKind::from_str("gemini"), ProviderKind::Gemini);
         assert_eq!(ProviderKind::from_str("ollama"), ProviderKind::Ollama);
         assert_eq!(ProviderKind::from_str("copilot"), ProviderKind::Copilot);
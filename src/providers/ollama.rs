@@ -1,10 +1,19 @@
 //! Ollama local provider — chat completion + text embeddings via Ollama API.
 
 use super::LlmProvider;
-use anyhow::Result;
+use crate::agents::chat::{
+    ChatCompletionResponse, ChatMessage, ChatOptions, ChatProvider, ContentPart, TokenUsage,
+    UserContent,
+};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::{ToolCall, ToolDefinition};
+use crate::sessions::ThinkingLevel;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 
 // ─── Response types ───────────────────────────────────────────────────────────
 
@@ -71,6 +80,49 @@ impl OllamaProvider {
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    pub fn chat_model(&self) -> &str {
+        &self.chat_model
+    }
+
+    fn build_chat_body(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[ToolDefinition]>,
+        options: &ChatOptions,
+        stream: bool,
+    ) -> Value {
+        let mut body = json!({
+            "model": self.chat_model,
+            "messages": convert_messages(messages),
+            "stream": stream,
+        });
+        if let Some(t) = tools {
+            if !t.is_empty() {
+                body["tools"] = json!(t
+                    .iter()
+                    .map(|td| json!({ "type": "function", "function": td }))
+                    .collect::<Vec<_>>());
+            }
+        }
+        // Only send `think` when the session asked for it; models without
+        // thinking support reject the flag.
+        if let Some(level) = options.thinking_level {
+            body["think"] = json!(level != ThinkingLevel::Off);
+        }
+        body
+    }
+
+    async fn post_chat(&self, body: &Value) -> Result<reqwest::Response> {
+        let url = format!("{}/api/chat", self.base_url);
+        let res = self.client.post(&url).json(body).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error_text = res.text().await?;
+            return Err(anyhow!("Ollama API error: {} - {}", status, error_text));
+        }
+        Ok(res)
+    }
 }
 
 #[async_trait]
@@ -117,6 +169,283 @@ impl LlmProvider for OllamaProvider {
     }
 }
 
+#[async_trait]
+impl ChatProvider for OllamaProvider {
+    fn model_id(&self) -> Option<String> {
+        Some(self.chat_model.clone())
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        Some("ollama".to_string())
+    }
+
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+    ) -> Result<ChatCompletionResponse> {
+        self.complete_with_options(messages, tools, &ChatOptions::default())
+            .await
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> Result<ChatCompletionResponse> {
+        self.stream_with_options(messages, tools, handler, &ChatOptions::default())
+            .await
+    }
+
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let body = self.build_chat_body(&messages, tools, options, false);
+        let json: Value = self.post_chat(&body).await?.json().await?;
+
+        let message = &json["message"];
+        let content = message["content"]
+            .as_str()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_string());
+        let tool_calls = parse_tool_calls(message);
+        let finish_reason = map_done_reason(
+            json["done_reason"].as_str().unwrap_or("stop"),
+            !tool_calls.is_empty(),
+        );
+
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content,
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+            },
+            finish_reason: finish_reason.to_string(),
+            fallback: None,
+            usage: parse_usage(&json),
+        })
+    }
+
+    async fn stream_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+        options: &ChatOptions,
+    ) -> Result<ChatCompletionResponse> {
+        let body = self.build_chat_body(&messages, tools, options, true);
+        let res = self.post_chat(&body).await?;
+
+        // Ollama streams newline-delimited JSON objects rather than SSE.
+        let mut stream = res.bytes_stream();
+        let mut buffer = String::new();
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        let mut done_reason = "stop".to_string();
+        let mut total_tokens: Option<usize> = None;
+        let mut usage = None;
+
+        while let Some(item) = stream.next().await {
+            let chunk = item?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_pos) = buffer.find('\n') {
+                let line = buffer.drain(..newline_pos + 1).collect::<String>();
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let json: Value = serde_json::from_str(line)?;
+                if let Some(err) = json["error"].as_str() {
+                    handler.error(err)?;
+                    return Err(anyhow!("Ollama stream error: {}", err));
+                }
+
+                let message = &json["message"];
+                if let Some(thinking) = message["thinking"].as_str().filter(|t| !t.is_empty()) {
+                    handler.push_reasoning(thinking)?;
+                }
+                if let Some(content) = message["content"].as_str().filter(|c| !c.is_empty()) {
+                    handler.push_text(content)?;
+                    text.push_str(content);
+                }
+                for call in parse_tool_calls(message) {
+                    handler.start_tool_call(&call.id, &call.name)?;
+                    handler.push_tool_arguments(&call.id, &call.arguments)?;
+                    handler.end_tool_call(&call.id)?;
+                    tool_calls.push(call);
+                }
+
+                if json["done"].as_bool() == Some(true) {
+                    if let Some(reason) = json["done_reason"].as_str() {
+                        done_reason = reason.to_string();
+                    }
+                    usage = parse_usage(&json);
+                    total_tokens = usage.map(|u| u.total_tokens() as usize);
+                }
+            }
+        }
+
+        let finish_reason = map_done_reason(&done_reason, !tool_calls.is_empty());
+        handler.finish(finish_reason, total_tokens)?;
+
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content: if text.is_empty() { None } else { Some(text) },
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+            },
+            finish_reason: finish_reason.to_string(),
+            fallback: None,
+            usage,
+        })
+    }
+}
+
+// ─── Chat message conversion ──────────────────────────────────────────────────
+
+/// Convert chat messages into Ollama `/api/chat` messages.
+///
+/// Images travel as bare base64 in a per-message `images` array, and tool
+/// results carry the tool name since Ollama does not use call ids.
+pub fn convert_messages(messages: &[ChatMessage]) -> Vec<Value> {
+    let mut names_by_call_id: HashMap<&str, &str> = HashMap::new();
+    let mut out = Vec::with_capacity(messages.len());
+
+    for msg in messages {
+        match msg {
+            ChatMessage::System { content } => {
+                out.push(json!({ "role": "system", "content": content }));
+            }
+            ChatMessage::User { content } => match content {
+                UserContent::Text(text) => {
+                    out.push(json!({ "role": "user", "content": text }));
+                }
+                UserContent::Parts(parts) => {
+                    let mut text = String::new();
+                    let mut images = Vec::new();
+                    for part in parts {
+                        match part {
+                            ContentPart::Text { text: t } => {
+                                if !text.is_empty() {
+                                    text.push('\n');
+                                }
+                                text.push_str(t);
+                            }
+                            ContentPart::ImageUrl { image_url } => {
+                                if let Some((_, data)) = image_url
+                                    .url
+                                    .strip_prefix("data:")
+                                    .and_then(|rest| rest.split_once(','))
+                                {
+                                    images.push(data.to_string());
+                                }
+                            }
+                        }
+                    }
+                    let mut entry = json!({ "role": "user", "content": text });
+                    if !images.is_empty() {
+                        entry["images"] = json!(images);
+                    }
+                    out.push(entry);
+                }
+            },
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                let mut entry = json!({
+                    "role": "assistant",
+                    "content": content.clone().unwrap_or_default(),
+                });
+                if let Some(calls) = tool_calls {
+                    entry["tool_calls"] = json!(calls
+                        .iter()
+                        .map(|call| {
+                            names_by_call_id.insert(&call.id, &call.name);
+                            let args: Value =
+                                serde_json::from_str(&call.arguments).unwrap_or_else(|_| json!({}));
+                            json!({ "function": { "name": call.name, "arguments": args } })
+                        })
+                        .collect::<Vec<_>>());
+                }
+                out.push(entry);
+            }
+            ChatMessage::Tool {
+                tool_call_id,
+                content,
+            } => {
+                let mut entry = json!({ "role": "tool", "content": content });
+                if let Some(name) = names_by_call_id.get(tool_call_id.as_str()) {
+                    entry["tool_name"] = json!(name);
+                }
+                out.push(entry);
+            }
+        }
+    }
+
+    out
+}
+
+fn parse_tool_calls(message: &Value) -> Vec<ToolCall> {
+    message["tool_calls"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|call| {
+            let function = &call["function"];
+            let name = function["name"].as_str()?.to_string();
+            // Arguments arrive as a JSON object, not a string.
+            let arguments = match &function["arguments"] {
+                Value::String(s) => s.clone(),
+                Value::Null => "{}".to_string(),
+                other => other.to_string(),
+            };
+            Some(ToolCall {
+                id: call["id"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+                name,
+                arguments,
+            })
+        })
+        .collect()
+}
+
+/// Token counts from the final (`done: true`) object; `prompt_eval_count` is
+/// omitted when the prompt was served from Ollama's cache.
+fn parse_usage(json: &Value) -> Option<TokenUsage> {
+    if json["done"].as_bool() != Some(true) {
+        return None;
+    }
+    Some(TokenUsage::new(
+        json["prompt_eval_count"].as_u64().unwrap_or(0),
+        json["eval_count"].as_u64().unwrap_or(0),
+    ))
+}
+
+fn map_done_reason(reason: &str, has_tool_calls: bool) -> &'static str {
+    if has_tool_calls {
+        return "tool_calls";
+    }
+    match reason {
+        "length" => "length",
+        _ => "stop",
+    }
+}
+
 /// Check if an Ollama server is reachable at the given base URL.
 pub async fn probe_ollama(base_url: &str) -> bool {
     let client = reqwest::Client::new();
@@ -144,4 +473,98 @@ mod tests {
         let p = OllamaProvider::from_env();
         assert!(p.base_url().starts_with("http://"));
     }
+
+    #[test]
+    fn convert_messages_attaches_tool_names_and_images() {
+        let messages = vec![
+            ChatMessage::User {
+                content: UserContent::Parts(vec![
+                    ContentPart::Text {
+                        text: "what is this?".to_string(),
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: crate::agents::chat::ImageUrl {
+                            url: "data:image/png;base64,QUJD".to_string(),
+                        },
+                    },
+                ]),
+            },
+            ChatMessage::Assistant {
+                content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "c1".to_string(),
+                    name: "lookup".to_string(),
+                    arguments: r#"{"q":"x"}"#.to_string(),
+                }]),
+            },
+            ChatMessage::Tool {
+                tool_call_id: "c1".to_string(),
+                content: "result".to_string(),
+            },
+        ];
+        let out = convert_messages(&messages);
+        assert_eq!(out[0]["images"][0], "QUJD");
+        assert_eq!(out[1]["tool_calls"][0]["function"]["arguments"]["q"], "x");
+        assert_eq!(out[2]["role"], "tool");
+        assert_eq!(out[2]["tool_name"], "lookup");
+    }
+
+    #[tokio::test]
+    async fn chat_stream_parses_ndjson_and_tool_calls() {
+        let reply = concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Let me check\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"lookup\",\"arguments\":{\"q\":\"rust\"}}}]},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":10,\"eval_count\":5}\n",
+        );
+        let (base_url, seen) =
+            crate::providers::test_support::spawn_mock(reply, "application/x-ndjson").await;
+        let provider = OllamaProvider::new(base_url);
+        let (handler, mut receiver) = crate::agents::streaming::create_stream_pair(2000);
+        let ask = || {
+            vec![ChatMessage::User {
+                content: UserContent::Text("search rust".to_string()),
+            }]
+        };
+
+        let response = ChatProvider::stream(&provider, ask(), None, handler.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        match response.message {
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => {
+                assert_eq!(content.as_deref(), Some("Let me check"));
+                assert_eq!(tool_calls.unwrap()[0].arguments, r#"{"q":"rust"}"#);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(seen.lock().unwrap()[0]["stream"], true);
+
+        let mut saw_end = false;
+        while let Ok(event) = receiver.rx.try_recv() {
+            if let crate::agents::streaming::StreamEvent::MessageEnd {
+                total_tokens_used, ..
+            } = event
+            {
+                assert_eq!(total_tokens_used, Some(15));
+                saw_end = true;
+            }
+        }
+        assert!(saw_end);
+
+        // A second call on the same handler (the next agent-loop round)
+        // replies with its own text only.
+        let again = ChatProvider::stream(&provider, ask(), None, handler)
+            .await
+            .unwrap();
+        match again.message {
+            ChatMessage::Assistant { content, .. } => {
+                assert_eq!(content.as_deref(), Some("Let me check"))
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,97 @@
+//! Per-model token pricing, in the shape of `copilot_models::ModelCost`.
+//!
+//! Prices are USD per million tokens. Local providers (Ollama, llama.cpp)
+//! are free; unknown hosted models have no price and are reported without
+//! a cost rather than with a guessed one.
+
+use serde::{Deserialize, Serialize};
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_read: 0.0,
+            cache_write: 0.0,
+        }
+    }
+
+    /// Cost in USD for the given token counts.
+    pub fn cost_usd(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input + output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// (provider, model id prefix, pricing). Longest matching prefix wins, so
+/// dated snapshots (`gpt-4o-2024-08-06`) resolve to their family.
+const PRICING_TABLE: &[(&str, &str, ModelPricing)] = &[
+    ("openai", "gpt-4o", ModelPricing::new(2.5, 10.0)),
+    ("openai", "gpt-4o-mini", ModelPricing::new(0.15, 0.6)),
+    ("openai", "gpt-4.1", ModelPricing::new(2.0, 8.0)),
+    ("openai", "gpt-4.1-mini", ModelPricing::new(0.4, 1.6)),
+    ("openai", "o1", ModelPricing::new(15.0, 60.0)),
+    ("openai", "o3-mini", ModelPricing::new(1.1, 4.4)),
+    ("anthropic", "claude-opus-4", ModelPricing::new(15.0, 75.0)),
+    ("anthropic", "claude-sonnet-4", ModelPricing::new(3.0, 15.0)),
+    (
+        "anthropic",
+        "claude-3-7-sonnet",
+        ModelPricing::new(3.0, 15.0),
+    ),
+    ("anthropic", "claude-3-5-haiku", ModelPricing::new(0.8, 4.0)),
+    ("gemini", "gemini-1.5-flash", ModelPricing::new(0.075, 0.3)),
+    ("gemini", "gemini-1.5-pro", ModelPricing::new(1.25, 5.0)),
+    ("gemini", "gemini-2.0-flash", ModelPricing::new(0.1, 0.4)),
+];
+
+/// Look up pricing for a model. Local providers are always free.
+pub fn lookup_model_pricing(provider: &str, model: &str) -> Option<ModelPricing> {
+    if matches!(provider, "ollama" | "llama-cpp") {
+        return Some(ModelPricing::default());
+    }
+    PRICING_TABLE
+        .iter()
+        .filter(|(p, prefix, _)| *p == provider && model.starts_with(prefix))
+        .max_by_key(|(_, prefix, _)| prefix.len())
+        .map(|(_, _, pricing)| *pricing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mini = lookup_model_pricing("openai", "gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.input, 0.15);
+        let full = lookup_model_pricing("openai", "gpt-4o-2024-08-06").unwrap();
+        assert_eq!(full.input, 2.5);
+    }
+
+    #[test]
+    fn local_models_are_free_and_unknown_are_unpriced() {
+        assert_eq!(
+            lookup_model_pricing("ollama", "llama3.1")
+                .unwrap()
+                .cost_usd(1000, 1000),
+            0.0
+        );
+        assert!(lookup_model_pricing("openrouter", "some-model").is_none());
+    }
+
+    #[test]
+    fn cost_is_per_million_tokens() {
+        let p = lookup_model_pricing("anthropic", "claude-sonnet-4-5").unwrap();
+        let cost = p.cost_usd(1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+    }
+}
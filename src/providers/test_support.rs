@@ -0,0 +1,138 @@
+//! Shared helpers for provider and agent tests: a local HTTP server that
+//! replays a canned response and records the JSON bodies it received, and a
+//! scripted chat provider for tests that drive a whole agent.
+
+use crate::agents::chat::{
+    ChatCompletionResponse, ChatMessage, ChatProvider, TokenUsage, UserContent,
+};
+use crate::agents::streaming::StreamHandler;
+use crate::agents::tool::ToolDefinition;
+use crate::agents::{Agent, AgentIdentity, AgentPool};
+use async_trait::async_trait;
+use axum::{routing::post, Router};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Serve `reply` with `content_type` for POSTs to any path under `/`.
+/// Returns the server base URL (`http://127.0.0.1:<port>`) and the recorded bodies.
+pub async fn spawn_mock(
+    reply: &'static str,
+    content_type: &'static str,
+) -> (String, Arc<Mutex<Vec<Value>>>) {
+    spawn_mock_sequence(vec![reply], content_type).await
+}
+
+/// Like `spawn_mock`, but answers the n-th request with `replies[n]`; the
+/// last reply repeats once the list runs out.
+pub async fn spawn_mock_sequence(
+    replies: Vec<&'static str>,
+    content_type: &'static str,
+) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_handler = seen.clone();
+    let handler = move |axum::Json(body): axum::Json<Value>| {
+        let seen = seen_handler.clone();
+        let replies = replies.clone();
+        async move {
+            let mut seen = seen.lock().unwrap();
+            let reply = replies[seen.len().min(replies.len() - 1)];
+            seen.push(body);
+            ([(axum::http::header::CONTENT_TYPE, content_type)], reply)
+        }
+    };
+    let app = Router::new().route("/*path", post(handler));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), seen)
+}
+
+/// Replies `re: <last user text>`, or a fixed text. Optionally streams a
+/// prefix of the reply first and reports token usage.
+#[derive(Debug, Clone, Default)]
+pub struct EchoProvider {
+    reply: Option<String>,
+    streamed: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+impl EchoProvider {
+    /// Always answers `reply`, whatever was asked.
+    pub fn fixed(reply: impl Into<String>) -> Self {
+        Self {
+            reply: Some(reply.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Push `delta` to the stream handler before returning the reply.
+    pub fn streaming(mut self, delta: impl Into<String>) -> Self {
+        self.streamed = Some(delta.into());
+        self
+    }
+
+    pub fn with_usage(mut self, input_tokens: u64, output_tokens: u64) -> Self {
+        self.usage = Some(TokenUsage::new(input_tokens, output_tokens));
+        self
+    }
+
+    fn reply_to(&self, messages: &[ChatMessage]) -> String {
+        if let Some(reply) = &self.reply {
+            return reply.clone();
+        }
+        let asked = messages
+            .iter()
+            .rev()
+            .find_map(|m| match m {
+                ChatMessage::User {
+                    content: UserContent::Text(text),
+                } => Some(text.as_str()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        format!("re: {}", asked)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for EchoProvider {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        _tools: Option<&[ToolDefinition]>,
+    ) -> anyhow::Result<ChatCompletionResponse> {
+        Ok(ChatCompletionResponse {
+            message: ChatMessage::Assistant {
+                content: Some(self.reply_to(&messages)),
+                tool_calls: None,
+            },
+            finish_reason: "stop".to_string(),
+            fallback: None,
+            usage: self.usage,
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Option<&[ToolDefinition]>,
+        handler: StreamHandler,
+    ) -> anyhow::Result<ChatCompletionResponse> {
+        if let Some(delta) = &self.streamed {
+            handler.push_text(delta)?;
+        }
+        self.complete(messages, tools).await
+    }
+}
+
+/// A pool holding one default agent backed by `provider`.
+pub fn single_agent_pool(provider: impl ChatProvider + 'static) -> AgentPool {
+    AgentPool::single(Arc::new(Agent::new(
+        AgentIdentity::default(),
+        Box::new(provider),
+        None,
+        vec![],
+    )))
+}
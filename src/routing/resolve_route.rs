@@ -1,10 +1,11 @@
 //! Ported from `OpenKrab/src/routing/resolve-route.ts`
 
 use crate::channels::chat_type::{normalize_chat_type, ChatType};
-use crate::OPENKRAB_CONFIG::{AgentBinding, BindingMatch, OpenKrabConfig};
+use crate::OPENKRAB_CONFIG::{AgentBinding, AgentEntry, BindingMatch, OpenKrabConfig};
 use crate::routing::session_key::{
     build_agent_main_session_key, build_agent_peer_session_key, normalize_account_id,
-    normalize_agent_id, DmScope, PeerSessionKeyParams, DEFAULT_ACCOUNT_ID, DEFAULT_MAIN_KEY,
+    normalize_agent_id, PeerSessionKeyParams, DEFAULT_ACCOUNT_ID, DEFAULT_AGENT_ID,
+    DEFAULT_MAIN_KEY,
 };
 use std::collections::HashSet;
 
@@ -55,28 +56,41 @@ fn matches_account_id(match_val: Option<&str>, actual: &str) -> bool {
     trimmed == actual
 }
 
+fn agent_list(cfg: &OpenKrabConfig) -> &[AgentEntry] {
+    cfg.agents
+        .as_ref()
+        .map(|a| a.list.as_slice())
+        .unwrap_or(&[])
+}
+
+/// The requested agent if `agents.list` has it (or there is no list),
+/// otherwise the default agent.
 fn pick_first_existing_agent_id(cfg: &OpenKrabConfig, agent_id: &str) -> String {
     let trimmed = agent_id.trim();
     if trimmed.is_empty() {
         return resolve_default_agent_id(cfg);
     }
 
-    let _normalized = normalize_agent_id(Some(trimmed));
-
-    if let Some(agents_cfg) = &cfg.agents {
-        if let Some(ref _list) = agents_cfg.defaults {
-            // Note: In OpenKrab, it walks the `cfg.agents.list`. However OpenKrabConfig
-            // doesn't have a list of agents inside `agents` right now. It just has defaults.
-            // So we'll skip the actual checking against list, and just return what was requested.
-            // When openkrab's config supports agent lists, we can refine this.
-        }
+    let normalized = normalize_agent_id(Some(trimmed));
+    let list = agent_list(cfg);
+    if list.is_empty()
+        || list
+            .iter()
+            .any(|entry| normalize_agent_id(Some(&entry.id)) == normalized)
+    {
+        return normalized;
     }
-
-    normalize_agent_id(Some(trimmed))
+    resolve_default_agent_id(cfg)
 }
 
-fn resolve_default_agent_id(_cfg: &OpenKrabConfig) -> String {
-    "main".to_string()
+/// The entry marked `default` in `agents.list`, else its first entry, else `main`.
+pub fn resolve_default_agent_id(cfg: &OpenKrabConfig) -> String {
+    let list = agent_list(cfg);
+    list.iter()
+        .find(|entry| entry.default)
+        .or_else(|| list.first())
+        .map(|entry| normalize_agent_id(Some(&entry.id)))
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string())
 }
 
 fn matches_channel(match_val: &str, channel: &str) -> bool {
@@ -238,14 +252,16 @@ pub fn resolve_agent_route(input: ResolveAgentRouteInput) -> ResolvedAgentRoute
     }
 
     let identity_links = None;
-    if let Some(_session_cfg) = &input.cfg.session {
-        // Build identity links dictionary if we had the struct fields...
-    }
+    let dm_scope = input
+        .cfg
+        .session
+        .as_ref()
+        .and_then(|s| s.dm_scope.clone())
+        .unwrap_or_default();
 
     let choose = |agent_id: &str, matched_by: &str| -> ResolvedAgentRoute {
         let resolved_agent_id = pick_first_existing_agent_id(input.cfg, agent_id);
 
-        // This is a simplified DmScope to keep compilation simple initially
         let session_key = build_agent_peer_session_key(PeerSessionKeyParams {
             agent_id: resolved_agent_id.clone(),
             main_key: Some(DEFAULT_MAIN_KEY.to_string()),
@@ -254,7 +270,7 @@ pub fn resolve_agent_route(input: ResolveAgentRouteInput) -> ResolvedAgentRoute
             peer_kind: peer.as_ref().map(|p| p.kind.clone()),
             peer_id: peer.as_ref().map(|p| p.id.clone()),
             identity_links: identity_links.clone(),
-            dm_scope: Some(DmScope::Main), // Fallback
+            dm_scope: Some(dm_scope.clone()),
         })
         .to_lowercase();
 
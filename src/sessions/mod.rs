@@ -80,35 +80,144 @@ pub enum SendPolicy {
 
 // ─── Transcript entry ─────────────────────────────────────────────────────────
 
+/// One transcript message. `role`/`text` are always filled so plain-text
+/// consumers keep working; the optional fields carry what a `ChatMessage`
+/// needs to be rebuilt exactly (tool calls, tool results, image parts) plus
+/// the token usage of the model call that produced it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptEntry {
     pub role: String,
     pub text: String,
     pub timestamp: DateTime<Utc>,
+    /// Multi-part user content (text + images); `text` holds the text parts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Vec<crate::agents::chat::ContentPart>>,
+    /// Tool calls requested by an assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<crate::agents::tool::ToolCall>>,
+    /// The call a `tool` entry is the result of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<crate::agents::chat::TokenUsage>,
 }
 
 impl TranscriptEntry {
-    pub fn user(text: impl Into<String>) -> Self {
+    fn plain(role: &str, text: String) -> Self {
         Self {
-            role: "user".to_string(),
-            text: text.into(),
+            role: role.to_string(),
+            text,
             timestamp: Utc::now(),
+            parts: None,
+            tool_calls: None,
+            tool_call_id: None,
+            usage: None,
         }
     }
+
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::plain("user", text.into())
+    }
     pub fn assistant(text: impl Into<String>) -> Self {
-        Self {
-            role: "assistant".to_string(),
-            text: text.into(),
-            timestamp: Utc::now(),
-        }
+        Self::plain("assistant", text.into())
     }
     pub fn system(text: impl Into<String>) -> Self {
+        Self::plain("system", text.into())
+    }
+    pub fn tool(tool_call_id: impl Into<String>, output: impl Into<String>) -> Self {
         Self {
-            role: "system".to_string(),
-            text: text.into(),
-            timestamp: Utc::now(),
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::plain("tool", output.into())
         }
     }
+
+    /// Capture a chat message without losing tool calls or content parts.
+    pub fn from_message(message: &crate::agents::chat::ChatMessage) -> Self {
+        use crate::agents::chat::{ChatMessage, ContentPart, UserContent};
+        match message {
+            ChatMessage::System { content } => Self::system(content.clone()),
+            ChatMessage::User {
+                content: UserContent::Text(text),
+            } => Self::user(text.clone()),
+            ChatMessage::User {
+                content: UserContent::Parts(parts),
+            } => {
+                let text = parts
+                    .iter()
+                    .filter_map(|p| match p {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::ImageUrl { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Self {
+                    parts: Some(parts.clone()),
+                    ..Self::user(text)
+                }
+            }
+            ChatMessage::Assistant {
+                content,
+                tool_calls,
+            } => Self {
+                tool_calls: tool_calls.clone(),
+                ..Self::assistant(content.clone().unwrap_or_default())
+            },
+            ChatMessage::Tool {
+                tool_call_id,
+                content,
+            } => Self::tool(tool_call_id.clone(), content.clone()),
+        }
+    }
+
+    pub fn with_usage(mut self, usage: Option<crate::agents::chat::TokenUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+/// Run `repair_tool_use_result_pairing` over a stored transcript, keeping the
+/// timestamps and usage of every surviving entry. Synthetic tool results take
+/// the timestamp of the entry before them.
+pub fn repair_transcript(entries: Vec<TranscriptEntry>) -> Vec<TranscriptEntry> {
+    let messages: Vec<crate::agents::chat::ChatMessage> =
+        entries.iter().cloned().map(Into::into).collect();
+    let report = crate::agents::session_repair::repair_tool_use_result_pairing(&messages);
+    if !report.moved {
+        return entries;
+    }
+
+    // The repair only moves, drops or adds tool results; every other message
+    // keeps its relative order, so walk the originals alongside the output.
+    let mut tool_entries: HashMap<String, TranscriptEntry> = HashMap::new();
+    let mut other_entries = std::collections::VecDeque::new();
+    for entry in entries {
+        match entry.tool_call_id.clone() {
+            Some(id) if entry.role == "tool" => {
+                tool_entries.entry(id).or_insert(entry);
+            }
+            _ => other_entries.push_back(entry),
+        }
+    }
+
+    let mut repaired: Vec<TranscriptEntry> = Vec::with_capacity(report.messages.len());
+    for message in &report.messages {
+        let entry = match message {
+            crate::agents::chat::ChatMessage::Tool { tool_call_id, .. } => {
+                tool_entries.remove(tool_call_id).unwrap_or_else(|| {
+                    let mut synthetic = TranscriptEntry::from_message(message);
+                    if let Some(prev) = repaired.last() {
+                        synthetic.timestamp = prev.timestamp;
+                    }
+                    synthetic
+                })
+            }
+            _ => other_entries
+                .pop_front()
+                .unwrap_or_else(|| TranscriptEntry::from_message(message)),
+        };
+        repaired.push(entry);
+    }
+    repaired
 }
 
 // ─── Thinking level ───────────────────────────────────────────────────────────
@@ -149,18 +258,28 @@ pub enum UsageDisplayLevel {
 
 impl From<TranscriptEntry> for crate::agents::chat::ChatMessage {
     fn from(entry: TranscriptEntry) -> Self {
+        use crate::agents::chat::{ChatMessage, UserContent};
         match entry.role.as_str() {
-            "user" => crate::agents::chat::ChatMessage::User {
-                content: crate::agents::chat::UserContent::Text(entry.text),
+            "user" => ChatMessage::User {
+                content: match entry.parts {
+                    Some(parts) => UserContent::Parts(parts),
+                    None => UserContent::Text(entry.text),
+                },
             },
-            "assistant" => crate::agents::chat::ChatMessage::Assistant {
-                content: Some(entry.text),
-                tool_calls: None,
+            "assistant" => ChatMessage::Assistant {
+                // A tool-call-only turn has no text content.
+                content: if entry.text.is_empty() && entry.tool_calls.is_some() {
+                    None
+                } else {
+                    Some(entry.text)
+                },
+                tool_calls: entry.tool_calls,
             },
-            "system" => crate::agents::chat::ChatMessage::System {
+            "tool" => ChatMessage::Tool {
+                tool_call_id: entry.tool_call_id.unwrap_or_default(),
                 content: entry.text,
             },
-            _ => crate::agents::chat::ChatMessage::System {
+            _ => ChatMessage::System {
                 content: entry.text,
             },
         }
@@ -290,6 +409,20 @@ impl Session {
             .collect()
     }
 
+    /// Add one model call's usage to the session counters. `context_tokens`
+    /// tracks the prompt size of the most recent call.
+    pub fn record_usage(&mut self, usage: &crate::agents::chat::TokenUsage) {
+        let clamp = |n: u64| n.min(u32::MAX as u64) as u32;
+        self.input_tokens = self.input_tokens.saturating_add(clamp(usage.input_tokens));
+        self.output_tokens = self
+            .output_tokens
+            .saturating_add(clamp(usage.output_tokens));
+        self.total_tokens = self
+            .total_tokens
+            .saturating_add(clamp(usage.total_tokens()));
+        self.context_tokens = clamp(usage.input_tokens);
+    }
+
     pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.metadata.insert(key.into(), value.into());
     }
@@ -329,6 +462,11 @@ impl SessionRegistry {
         self.sessions.get_mut(id)
     }
 
+    /// Insert or replace a session, keyed by its id.
+    pub fn insert(&mut self, session: Session) {
+        self.sessions.insert(session.id.clone(), session);
+    }
+
     pub fn remove(&mut self, id: &str) -> Option<Session> {
         self.sessions.remove(id)
     }
@@ -372,6 +510,92 @@ mod tests {
         assert_eq!(s.transcript[0].text, "msg 2");
     }
 
+    #[test]
+    fn transcript_entry_roundtrips_chat_messages() {
+        use crate::agents::chat::{ChatMessage, ContentPart, ImageUrl, UserContent};
+        use crate::agents::tool::ToolCall;
+
+        let messages = vec![
+            ChatMessage::User {
+                content: UserContent::Parts(vec![
+                    ContentPart::Text {
+                        text: "what is this?".to_string(),
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "data:image/png;base64,AAAA".to_string(),
+                        },
+                    },
+                ]),
+            },
+            ChatMessage::Assistant {
+                content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: "{\"path\":\"a.txt\"}".to_string(),
+                }]),
+            },
+            ChatMessage::Tool {
+                tool_call_id: "call_1".to_string(),
+                content: "hello".to_string(),
+            },
+            ChatMessage::Assistant {
+                content: Some("It says hello.".to_string()),
+                tool_calls: None,
+            },
+        ];
+
+        let entries: Vec<TranscriptEntry> =
+            messages.iter().map(TranscriptEntry::from_message).collect();
+        let json = serde_json::to_string(&entries).unwrap();
+        let restored: Vec<TranscriptEntry> = serde_json::from_str(&json).unwrap();
+        let rebuilt: Vec<ChatMessage> = restored.into_iter().map(Into::into).collect();
+
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(&messages).unwrap()
+        );
+        assert_eq!(entries[0].text, "what is this?");
+    }
+
+    #[test]
+    fn legacy_transcript_json_still_loads() {
+        let legacy = r#"[{"role":"user","text":"hi","timestamp":"2024-01-01T00:00:00Z"}]"#;
+        let entries: Vec<TranscriptEntry> = serde_json::from_str(legacy).unwrap();
+        assert_eq!(entries[0].text, "hi");
+        assert!(entries[0].tool_calls.is_none());
+    }
+
+    #[test]
+    fn repair_transcript_drops_orphans_and_keeps_timestamps() {
+        use crate::agents::tool::ToolCall;
+
+        let mut call = TranscriptEntry::assistant("");
+        call.tool_calls = Some(vec![ToolCall {
+            id: "c1".to_string(),
+            name: "search".to_string(),
+            arguments: "{}".to_string(),
+        }]);
+        let user = TranscriptEntry::user("next");
+        let stamp = user.timestamp - chrono::Duration::seconds(60);
+        let mut user = user;
+        user.timestamp = stamp;
+        let entries = vec![
+            TranscriptEntry::tool("orphan", "stale"),
+            call,
+            user,
+            TranscriptEntry::tool("c1", "found"),
+        ];
+
+        let repaired = repair_transcript(entries);
+
+        let roles: Vec<&str> = repaired.iter().map(|e| e.role.as_str()).collect();
+        assert_eq!(roles, vec!["assistant", "tool", "user"]);
+        assert_eq!(repaired[1].text, "found");
+        assert_eq!(repaired[2].timestamp, stamp);
+    }
+
     #[test]
     fn session_registry_get_or_create() {
         let mut reg = SessionRegistry::new();
@@ -169,6 +169,51 @@ pub fn chunk_by_sentences(text: &str, max_chunk_size: usize) -> Vec<String> {
     chunks
 }
 
+/// Chunk text into pieces of at most `max_bytes` bytes without overlap,
+/// breaking between words where possible and never inside a character.
+/// Whitespace at a break is dropped; runs of whitespace collapse to one space.
+pub fn chunk_by_words(text: &str, max_bytes: usize) -> Vec<String> {
+    let max_bytes = max_bytes.max(4);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let needed = if current.is_empty() {
+            word.len()
+        } else {
+            current.len() + 1 + word.len()
+        };
+        if needed <= max_bytes {
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        // A word longer than a chunk is split at character boundaries.
+        let mut rest = word;
+        while rest.len() > max_bytes {
+            let mut cut = max_bytes;
+            while !rest.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            chunks.push(rest[..cut].to_string());
+            rest = &rest[cut..];
+        }
+        current.push_str(rest);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
 /// Estimate token count (rough approximation: 1 token ≈ 4 characters)
 pub fn estimate_token_count(text: &str) -> usize {
     text.len().div_ceil(4)
@@ -219,6 +264,17 @@ mod tests {
         assert_eq!(chunks[0], text);
     }
 
+    #[test]
+    fn test_chunk_by_words() {
+        let chunks = chunk_by_words("one two  three\nfour", 9);
+        assert_eq!(chunks, vec!["one two", "three", "four"]);
+
+        let long = "é".repeat(10);
+        let chunks = chunk_by_words(&long, 5);
+        assert!(chunks.iter().all(|c| c.len() <= 5));
+        assert_eq!(chunks.concat(), long);
+    }
+
     #[test]
     fn test_estimate_token_count() {
         assert_eq!(estimate_token_count(""), 0);
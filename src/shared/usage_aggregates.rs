@@ -122,6 +122,14 @@ impl TokenUsage {
         }
     }
 
+    /// Record token usage with an already-computed cost (in USD)
+    pub fn record_with_cost(&mut self, input: u64, output: u64, cost_usd: f64) {
+        self.input += input;
+        self.output += output;
+        self.total += input + output;
+        self.estimated_cost += cost_usd;
+    }
+
     /// Get total tokens
     pub fn total_tokens(&self) -> u64 {
         self.total
@@ -163,6 +171,22 @@ impl UsageAggregates {
         self.update_activity();
     }
 
+    /// Record token usage priced from a model pricing table
+    pub fn record_priced_tokens(
+        &mut self,
+        key: impl Into<String>,
+        input: u64,
+        output: u64,
+        cost_usd: f64,
+    ) {
+        let key = key.into();
+        self.tokens
+            .entry(key)
+            .or_default()
+            .record_with_cost(input, output, cost_usd);
+        self.update_activity();
+    }
+
     /// Record API call
     pub fn record_api_call(&mut self, key: impl Into<String>) {
         let key = key.into();
@@ -1,10 +1,13 @@
 //! Interactive shell - simple REPL over gateway WebSocket.
+//!
+//! Each line is sent as a chat message to the configured session; replies
+//! stream in as they are generated. Ctrl-C cancels the turn in flight.
 
+use crate::agents::streaming::StreamEvent;
+use crate::gateway::client::GatewayClient;
 use anyhow::{anyhow, Context};
-use futures_util::{SinkExt, StreamExt};
-use serde::{Deserialize, Serialize};
+use std::io::Write;
 use tokio::io::{self, AsyncBufReadExt, BufReader};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 #[derive(Debug, Clone)]
 pub struct ShellConfig {
@@ -15,8 +18,11 @@ pub struct ShellConfig {
 
 pub async fn run_interactive_shell(config: ShellConfig) -> anyhow::Result<()> {
     let ws_url = to_ws_url(&config.url)?;
-    let (mut ws, _) = connect_async(&ws_url)
+    let client = GatewayClient::new(url::Url::parse(&ws_url)?);
+    let mut conn = client
+        .connect()
         .await
+        .map_err(|e| anyhow!("{e}"))
         .with_context(|| format!("failed to connect to gateway websocket: {ws_url}"))?;
 
     println!("openkrab Shell");
@@ -26,41 +32,87 @@ pub async fn run_interactive_shell(config: ShellConfig) -> anyhow::Result<()> {
     if config.token.is_some() {
         println!("Token: provided");
     }
-    println!("Type text to run memory/search, /exit to quit.");
+    println!("Type a message to chat, Ctrl-C to cancel a reply, /exit to quit.");
     println!();
 
     let stdin = BufReader::new(io::stdin());
     let mut lines = stdin.lines();
 
     while let Some(line) = lines.next_line().await? {
-        let query = line.trim();
-        if query.is_empty() {
+        let text = line.trim();
+        if text.is_empty() {
             continue;
         }
-        if query.eq_ignore_ascii_case("/exit") || query.eq_ignore_ascii_case("/quit") {
+        if text.eq_ignore_ascii_case("/exit") || text.eq_ignore_ascii_case("/quit") {
             break;
         }
 
-        let req = GatewaySearchRequest {
-            msg_type: "memory/search".to_string(),
-            query: query.to_string(),
-        };
-        let payload = serde_json::to_string(&req)?;
-        ws.send(Message::Text(payload)).await?;
-
-        match ws.next().await {
-            Some(Ok(Message::Text(text))) => render_response(&text),
-            Some(Ok(_)) => println!("[gateway] unexpected non-text response"),
-            Some(Err(err)) => return Err(anyhow!("gateway websocket error: {err}")),
-            None => return Err(anyhow!("gateway websocket closed")),
+        let mut printed = String::new();
+        let result = conn
+            .send_chat_streaming(
+                config.session.clone(),
+                text.to_string(),
+                None,
+                async {
+                    let _ = tokio::signal::ctrl_c().await;
+                },
+                |event| render_event(event, &mut printed),
+            )
+            .await;
+
+        match result {
+            Ok(turn) => {
+                // Anything not streamed (non-streaming providers, usage footer).
+                let rest = turn.message.strip_prefix(printed.as_str()).unwrap_or(&turn.message);
+                println!("{rest}");
+                if turn.input_tokens + turn.output_tokens > 0 {
+                    println!(
+                        "[{} in / {} out tokens]",
+                        turn.input_tokens, turn.output_tokens
+                    );
+                }
+            }
+            Err(err) => {
+                if !printed.is_empty() {
+                    println!();
+                }
+                println!("[gateway error] {err}");
+            }
         }
+        println!();
     }
 
-    let _ = ws.close(None).await;
+    let _ = conn.close().await;
     Ok(())
 }
 
-fn to_ws_url(input: &str) -> anyhow::Result<String> {
+/// Print a streamed event; `printed` tracks the reply text shown so far.
+fn render_event(event: &StreamEvent, printed: &mut String) {
+    match event {
+        StreamEvent::TextDelta { content, .. } => {
+            print!("{content}");
+            printed.push_str(content);
+        }
+        StreamEvent::ToolCallStart { tool_name, .. } => {
+            // The reply is only the text after the last tool call.
+            if !printed.is_empty() && !printed.ends_with('\n') {
+                println!();
+            }
+            printed.clear();
+            println!("[tool: {tool_name}]");
+        }
+        StreamEvent::ToolResult {
+            is_error: true,
+            output,
+            ..
+        } => println!("[tool error] {}", single_line_preview(output)),
+        StreamEvent::Error { message } => println!("[stream error] {message}"),
+        _ => {}
+    }
+    let _ = std::io::stdout().flush();
+}
+
+pub(crate) fn to_ws_url(input: &str) -> anyhow::Result<String> {
     let base = input.trim();
     if base.is_empty() {
         return Err(anyhow!("shell url is required"));
@@ -94,35 +146,6 @@ fn to_ws_url(input: &str) -> anyhow::Result<String> {
     Ok(url.to_string())
 }
 
-fn render_response(text: &str) {
-    let parsed: Result<GatewaySearchResponse, _> = serde_json::from_str(text);
-    match parsed {
-        Ok(GatewaySearchResponse::MemoryResults { results }) => {
-            if results.is_empty() {
-                println!("(no results)");
-                return;
-            }
-            for (idx, item) in results.iter().enumerate() {
-                println!(
-                    "{}. {}:{}-{} score={:.3}\n   {}",
-                    idx + 1,
-                    item.path,
-                    item.start_line,
-                    item.end_line,
-                    item.score,
-                    single_line_preview(&item.text)
-                );
-            }
-        }
-        Ok(GatewaySearchResponse::Error { message }) => {
-            println!("[gateway error] {message}");
-        }
-        Err(_) => {
-            println!("[gateway raw] {text}");
-        }
-    }
-}
-
 fn single_line_preview(input: &str) -> String {
     let mut out = input.lines().next().unwrap_or_default().trim().to_string();
     if out.len() > 180 {
@@ -132,31 +155,6 @@ fn single_line_preview(input: &str) -> String {
     out
 }
 
-#[derive(Debug, Serialize)]
-struct GatewaySearchRequest {
-    #[serde(rename = "type")]
-    msg_type: String,
-    query: String,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-enum GatewaySearchResponse {
-    #[serde(rename = "memory/results")]
-    MemoryResults { results: Vec<SearchItem> },
-    #[serde(rename = "error")]
-    Error { message: String },
-}
-
-#[derive(Debug, Deserialize)]
-struct SearchItem {
-    path: String,
-    start_line: i32,
-    end_line: i32,
-    text: String,
-    score: f64,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +181,27 @@ mod tests {
         assert_eq!(u, "wss://example.com/custom");
     }
 
+    #[test]
+    fn tool_calls_reset_the_streamed_reply() {
+        let mut printed = String::new();
+        render_event(
+            &StreamEvent::TextDelta {
+                content: "Let me check".to_string(),
+                accumulated: "Let me check".to_string(),
+            },
+            &mut printed,
+        );
+        assert_eq!(printed, "Let me check");
+        render_event(
+            &StreamEvent::ToolCallStart {
+                tool_call_id: "c1".to_string(),
+                tool_name: "read_file".to_string(),
+            },
+            &mut printed,
+        );
+        assert!(printed.is_empty());
+    }
+
     #[test]
     fn ws_url_rejects_unsupported_scheme() {
         let err = to_ws_url("ftp://example.com").expect_err("must fail");
@@ -16,6 +16,52 @@ pub struct SseEvent {
     pub id: Option<String>,
 }
 
+/// Splits a signal-cli `/api/v1/events` byte stream into SSE events.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buf: String,
+}
+
+impl SseDecoder {
+    /// Feed a chunk; returns the events it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buf
+            .push_str(&String::from_utf8_lossy(chunk).replace("\r\n", "\n"));
+        let mut events = Vec::new();
+        while let Some(end) = self.buf.find("\n\n") {
+            let block: String = self.buf.drain(..end + 2).collect();
+            if let Some(event) = parse_sse_block(&block) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_sse_block(block: &str) -> Option<SseEvent> {
+    let mut event = None;
+    let mut data: Vec<&str> = Vec::new();
+    let mut id = None;
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = Some(value.to_string()),
+            "data" => data.push(value),
+            "id" => id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+    Some(SseEvent {
+        event: event.unwrap_or_else(|| "message".to_string()),
+        data: data.join("\n"),
+        id,
+    })
+}
+
 /// Health check response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthCheckResponse {
@@ -38,3 +84,22 @@ pub async fn health_check(url: &str) -> anyhow::Result<HealthCheckResponse> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_events_span_chunks_and_skip_keepalives() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder
+            .push(b":keepalive\n\nevent:receive\ndata:{\"a\"")
+            .is_empty());
+        let events = decoder.push(b":1}\r\n\r\ndata: x\n\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "receive");
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert_eq!(events[1].event, "message");
+        assert_eq!(events[1].data, "x");
+    }
+}
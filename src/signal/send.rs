@@ -1,3 +1,88 @@
-pub fn send_signal_message(_recipient: &str, _message: &str) -> Result<(), String> {
-    Ok(())
+//! signal::send — outbound messages through the signal-cli JSON-RPC `send` method.
+
+use anyhow::{bail, Result};
+
+use super::SignalConfig;
+
+/// Who a message goes to: a number (or UUID) or a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignalTarget {
+    Recipient(String),
+    Group(String),
+}
+
+/// JSON-RPC request for `send`.
+pub fn build_send_request(
+    account: &str,
+    target: &SignalTarget,
+    message: &str,
+    id: &str,
+) -> serde_json::Value {
+    let mut params = serde_json::json!({
+        "account": account,
+        "message": message,
+    });
+    match target {
+        SignalTarget::Recipient(recipient) => params["recipient"] = serde_json::json!([recipient]),
+        SignalTarget::Group(group_id) => params["groupId"] = group_id.clone().into(),
+    }
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "send",
+        "params": params,
+        "id": id,
+    })
+}
+
+/// Send `message` via the daemon at `cfg.api_base`; returns the sent timestamp.
+pub async fn send_message(
+    client: &reqwest::Client,
+    cfg: &SignalConfig,
+    target: &SignalTarget,
+    message: &str,
+) -> Result<i64> {
+    let id = format!(
+        "krab_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+    );
+    let request = build_send_request(cfg.resolve_account(), target, message, &id);
+    let resp: serde_json::Value = client
+        .post(format!("{}/api/v1/rpc", cfg.api_base))
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(error) = resp.get("error") {
+        bail!(
+            "signal-cli send failed: {}",
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+        );
+    }
+    Ok(resp["result"]["timestamp"].as_i64().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_request_targets_recipient_or_group() {
+        let direct = build_send_request("+1", &SignalTarget::Recipient("+2".into()), "hi", "7");
+        assert_eq!(direct["method"], "send");
+        assert_eq!(direct["params"]["account"], "+1");
+        assert_eq!(direct["params"]["recipient"][0], "+2");
+        assert!(direct["params"].get("groupId").is_none());
+
+        let group = build_send_request("+1", &SignalTarget::Group("g==".into()), "hi", "8");
+        assert_eq!(group["params"]["groupId"], "g==");
+        assert!(group["params"].get("recipient").is_none());
+    }
 }
@@ -1,5 +1,8 @@
 //! TUI Application - Main application state and event handling
 
+use crate::tui::gateway::{
+    apply_update, spawn_gateway_link, GatewayLink, GatewayRequest, StreamingReply, REPLY_PREFIX,
+};
 use crate::tui::TuiConfig;
 use crossterm::{
     event::{self, Event, KeyCode, KeyModifiers},
@@ -15,11 +18,14 @@ use ratatui::{
     Terminal,
 };
 use std::io;
+use std::time::Duration;
 
 pub struct TuiApp {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
-    #[allow(dead_code)]
     config: TuiConfig,
+    gateway: Option<GatewayLink>,
+    /// Reply being streamed for the turn in flight.
+    streaming: Option<StreamingReply>,
     messages: Vec<String>,
     input: String,
     history: Vec<String>,
@@ -36,6 +42,8 @@ impl TuiApp {
         Ok(Self {
             terminal,
             config,
+            gateway: None,
+            streaming: None,
             messages: vec![],
             input: String::new(),
             history: vec![],
@@ -68,15 +76,26 @@ impl TuiApp {
             .push("🦀 openkrab TUI - Type /help for commands".to_string());
         self.messages.push("─".repeat(50));
 
+        match spawn_gateway_link(&self.config.gateway_url, &self.config.session) {
+            Ok(link) => self.gateway = Some(link),
+            Err(e) => self.messages.push(format!("[error] Gateway unavailable: {}", e)),
+        }
+
         loop {
             self.draw()?;
 
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
-                    break;
+            // Poll so streamed replies render while waiting for keys.
+            if event::poll(Duration::from_millis(50))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        break;
+                    }
+                    self.handle_key_event(key);
                 }
-                self.handle_key_event(key);
             }
+            self.drain_gateway_updates();
 
             if self.should_quit() {
                 break;
@@ -171,6 +190,9 @@ impl TuiApp {
                 }
             }
             KeyCode::Esc => {
+                if self.streaming.is_some() && self.input.is_empty() {
+                    self.cancel_turn();
+                }
                 self.input.clear();
             }
             _ => {}
@@ -193,6 +215,55 @@ impl TuiApp {
             self.handle_command(&text);
         } else {
             self.messages.push(format!("\x1b[36m> {}\x1b[0m", text));
+            self.send_chat(text);
+        }
+    }
+
+    fn send_chat(&mut self, text: String) {
+        if self.streaming.is_some() {
+            self.messages
+                .push("Still answering - press Esc to cancel".to_string());
+            return;
+        }
+        let Some(gateway) = &self.gateway else {
+            return;
+        };
+        if gateway.requests.send(GatewayRequest::Chat(text)).is_err() {
+            self.messages
+                .push("[error] Gateway connection closed".to_string());
+            return;
+        }
+        self.messages.push(REPLY_PREFIX.to_string());
+        self.streaming = Some(StreamingReply {
+            line: self.messages.len() - 1,
+            text: String::new(),
+        });
+    }
+
+    fn cancel_turn(&mut self) {
+        if let Some(gateway) = &self.gateway {
+            let _ = gateway.requests.send(GatewayRequest::Cancel);
+        }
+    }
+
+    fn drain_gateway_updates(&mut self) {
+        let Some(gateway) = &self.gateway else {
+            return;
+        };
+        while let Ok(update) = gateway.updates.try_recv() {
+            match self.streaming.as_mut() {
+                Some(reply) => {
+                    if !apply_update(&mut self.messages, reply, update) {
+                        self.streaming = None;
+                    }
+                }
+                // Connection errors can arrive before any turn starts.
+                None => {
+                    if let crate::tui::gateway::GatewayUpdate::Error(e) = update {
+                        self.messages.push(format!("[error] {}", e));
+                    }
+                }
+            }
         }
     }
 
@@ -207,6 +278,8 @@ impl TuiApp {
                     .push("  /sessions - List sessions".to_string());
                 self.messages
                     .push("  /memory   - Search memory".to_string());
+                self.messages
+                    .push("  /cancel   - Cancel the reply in progress (or Esc)".to_string());
                 self.messages.push("  /quit     - Exit".to_string());
             }
             "/clear" | "/c" => {
@@ -221,6 +294,13 @@ impl TuiApp {
                 self.messages
                     .push("Use: openkrab memory search <query>".to_string());
             }
+            "/cancel" => {
+                if self.streaming.is_some() {
+                    self.cancel_turn();
+                } else {
+                    self.messages.push("Nothing to cancel".to_string());
+                }
+            }
             "/quit" | "/exit" | "/q" => {
                 self.messages.push("Goodbye! 🦀".to_string());
             }
@@ -0,0 +1,208 @@
+//! TUI gateway link - runs chat turns on a background thread
+//!
+//! The TUI event loop is synchronous, so the gateway connection lives on its
+//! own thread with a single-threaded runtime. Requests go in over a tokio
+//! channel; streamed events come back over a std channel the UI polls.
+
+use crate::agents::streaming::StreamEvent;
+use crate::gateway::client::GatewayClient;
+use std::sync::mpsc;
+use tokio::sync::mpsc as async_mpsc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GatewayRequest {
+    Chat(String),
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum GatewayUpdate {
+    Event(StreamEvent),
+    Reply {
+        message: String,
+        input_tokens: u64,
+        output_tokens: u64,
+    },
+    Error(String),
+}
+
+pub struct GatewayLink {
+    pub requests: async_mpsc::UnboundedSender<GatewayRequest>,
+    pub updates: mpsc::Receiver<GatewayUpdate>,
+}
+
+/// Start the background gateway thread for a session.
+pub fn spawn_gateway_link(gateway_url: &str, session: &str) -> anyhow::Result<GatewayLink> {
+    let ws_url = url::Url::parse(&crate::shell::to_ws_url(gateway_url)?)?;
+    let session = session.to_string();
+    let (req_tx, mut req_rx) = async_mpsc::unbounded_channel::<GatewayRequest>();
+    let (upd_tx, upd_rx) = mpsc::channel::<GatewayUpdate>();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    std::thread::spawn(move || {
+        runtime.block_on(async move {
+            let mut conn = match GatewayClient::new(ws_url.clone()).connect().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    let _ = upd_tx.send(GatewayUpdate::Error(format!(
+                        "Failed to connect to {}: {}",
+                        ws_url, e
+                    )));
+                    return;
+                }
+            };
+
+            while let Some(request) = req_rx.recv().await {
+                let GatewayRequest::Chat(text) = request else {
+                    continue;
+                };
+                let events = upd_tx.clone();
+                let cancel = async {
+                    while let Some(request) = req_rx.recv().await {
+                        if request == GatewayRequest::Cancel {
+                            return;
+                        }
+                    }
+                    std::future::pending::<()>().await
+                };
+                let update = match conn
+                    .send_chat_streaming(session.clone(), text, None, cancel, |event| {
+                        let _ = events.send(GatewayUpdate::Event(event.clone()));
+                    })
+                    .await
+                {
+                    Ok(turn) => GatewayUpdate::Reply {
+                        message: turn.message,
+                        input_tokens: turn.input_tokens,
+                        output_tokens: turn.output_tokens,
+                    },
+                    Err(e) => GatewayUpdate::Error(e.to_string()),
+                };
+                if upd_tx.send(update).is_err() {
+                    break;
+                }
+            }
+            let _ = conn.close().await;
+        });
+    });
+
+    Ok(GatewayLink {
+        requests: req_tx,
+        updates: upd_rx,
+    })
+}
+
+/// The reply line currently being streamed into the chat log.
+#[derive(Debug, Default)]
+pub struct StreamingReply {
+    /// Index of the reply line in the message list.
+    pub line: usize,
+    /// Reply text since the last tool call.
+    pub text: String,
+}
+
+pub const REPLY_PREFIX: &str = "🦀 ";
+
+/// Apply a gateway update to the chat log. Returns `false` once the turn
+/// has ended.
+pub fn apply_update(
+    messages: &mut Vec<String>,
+    reply: &mut StreamingReply,
+    update: GatewayUpdate,
+) -> bool {
+    match update {
+        GatewayUpdate::Event(StreamEvent::TextDelta { content, .. }) => {
+            reply.text.push_str(&content);
+            messages[reply.line] = format!("{}{}", REPLY_PREFIX, reply.text);
+            true
+        }
+        GatewayUpdate::Event(StreamEvent::ToolCallStart { tool_name, .. }) => {
+            // Keep earlier text and start a fresh reply line after the tool.
+            if reply.text.is_empty() {
+                messages[reply.line] = format!("  [tool: {}]", tool_name);
+            } else {
+                messages.push(format!("  [tool: {}]", tool_name));
+            }
+            messages.push(REPLY_PREFIX.to_string());
+            reply.line = messages.len() - 1;
+            reply.text.clear();
+            true
+        }
+        GatewayUpdate::Event(_) => true,
+        GatewayUpdate::Reply {
+            message,
+            input_tokens,
+            output_tokens,
+        } => {
+            messages[reply.line] = format!("{}{}", REPLY_PREFIX, message);
+            if input_tokens + output_tokens > 0 {
+                messages.push(format!(
+                    "  [{} in / {} out tokens]",
+                    input_tokens, output_tokens
+                ));
+            }
+            false
+        }
+        GatewayUpdate::Error(message) => {
+            if reply.text.is_empty() {
+                messages[reply.line] = format!("[error] {}", message);
+            } else {
+                messages.push(format!("[error] {}", message));
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(text: &str) -> GatewayUpdate {
+        GatewayUpdate::Event(StreamEvent::TextDelta {
+            content: text.to_string(),
+            accumulated: String::new(),
+        })
+    }
+
+    #[test]
+    fn deltas_tools_and_reply_update_the_log() {
+        let mut messages = vec!["> hi".to_string(), REPLY_PREFIX.to_string()];
+        let mut reply = StreamingReply {
+            line: 1,
+            text: String::new(),
+        };
+
+        assert!(apply_update(&mut messages, &mut reply, delta("Check")));
+        assert!(apply_update(&mut messages, &mut reply, delta("ing")));
+        assert_eq!(messages[1], "🦀 Checking");
+
+        let tool = GatewayUpdate::Event(StreamEvent::ToolCallStart {
+            tool_call_id: "c1".to_string(),
+            tool_name: "list_files".to_string(),
+        });
+        assert!(apply_update(&mut messages, &mut reply, tool));
+        assert_eq!(messages[2], "  [tool: list_files]");
+        assert_eq!(reply.line, 3);
+
+        let done = GatewayUpdate::Reply {
+            message: "Done".to_string(),
+            input_tokens: 12,
+            output_tokens: 3,
+        };
+        assert!(!apply_update(&mut messages, &mut reply, done));
+        assert_eq!(messages[3], "🦀 Done");
+        assert_eq!(messages[4], "  [12 in / 3 out tokens]");
+    }
+
+    #[test]
+    fn error_replaces_an_empty_reply_line() {
+        let mut messages = vec![REPLY_PREFIX.to_string()];
+        let mut reply = StreamingReply::default();
+        let err = GatewayUpdate::Error("Gateway error (cancelled): stop".to_string());
+        assert!(!apply_update(&mut messages, &mut reply, err));
+        assert_eq!(messages, vec!["[error] Gateway error (cancelled): stop"]);
+    }
+}
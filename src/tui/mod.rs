@@ -2,6 +2,7 @@
 //! Provides interactive terminal experience with chat, sessions, and commands
 
 pub mod app;
+pub mod gateway;
 
 pub use app::TuiApp;
 